@@ -0,0 +1,306 @@
+//! Derive macros for [`rart`](https://docs.rs/rart) composite keys.
+//!
+//! `#[derive(ArtKey)]` generates an order-preserving encoding of a struct's fields, in declaration
+//! order, into a `rart` key type, along with a decoder and a typed builder for key prefixes made of
+//! leading fields. See `rart::keys::codec` for the encoding of individual field types.
+//!
+//! ```rust,ignore
+//! use rart::keys::codec::ArtKey;
+//!
+//! #[derive(ArtKey)]
+//! #[art(key = rart::VectorKey)]
+//! struct EventKey {
+//!     tenant: String,
+//!     #[art(desc)]
+//!     timestamp: u64,
+//!     #[art(fixed = 8)]
+//!     kind: String,
+//! }
+//!
+//! // Newest events for a tenant come first.
+//! let prefix = EventKey::key_prefix().tenant(&"acme".to_string()).to_key();
+//! ```
+//!
+//! ## Attributes
+//!
+//! - `#[art(key = Type)]` on the struct selects the tree key type (default `rart::VectorKey`).
+//! - `#[art(desc)]` on a field reverses the sort order of that field.
+//! - `#[art(fixed = N)]` on a `String` or `Vec<u8>` field encodes it padded to `N` bytes instead
+//!   of escaped and terminated.
+//! - `#[art(varint)]` on an integer field uses the compact length-prefixed encoding from
+//!   `rart::keys::varint` instead of the integer's full fixed width.
+//! - `#[art(escaped)]` on a field explicitly selects the default variable-length encoding.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    Data, DeriveInput, Fields, Ident, LitInt, Member, Type, parse_macro_input, spanned::Spanned,
+};
+
+#[proc_macro_derive(ArtKey, attributes(art))]
+pub fn derive_art_key(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+enum Encoding {
+    Escaped,
+    Fixed(LitInt),
+    Varint,
+}
+
+struct KeyField {
+    member: Member,
+    builder_name: Ident,
+    ty: Type,
+    desc: bool,
+    encoding: Encoding,
+}
+
+fn parse_struct_attrs(input: &DeriveInput) -> syn::Result<Type> {
+    let mut key_type: Option<Type> = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("art")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("key") {
+                key_type = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported struct attribute, expected `key = Type`"))
+            }
+        })?;
+    }
+    Ok(key_type.unwrap_or_else(|| syn::parse_quote!(::rart::VectorKey)))
+}
+
+fn parse_fields(fields: &Fields) -> syn::Result<Vec<KeyField>> {
+    let mut out = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let (member, builder_name) = match &field.ident {
+            Some(ident) => (Member::Named(ident.clone()), ident.clone()),
+            None => (
+                Member::Unnamed(index.into()),
+                format_ident!("field_{}", index),
+            ),
+        };
+        let mut desc = false;
+        let mut encoding = Encoding::Escaped;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("art")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("desc") {
+                    desc = true;
+                    Ok(())
+                } else if meta.path.is_ident("asc") {
+                    desc = false;
+                    Ok(())
+                } else if meta.path.is_ident("escaped") {
+                    encoding = Encoding::Escaped;
+                    Ok(())
+                } else if meta.path.is_ident("varint") {
+                    encoding = Encoding::Varint;
+                    Ok(())
+                } else if meta.path.is_ident("fixed") {
+                    let width: LitInt = meta.value()?.parse()?;
+                    width.base10_parse::<usize>()?;
+                    encoding = Encoding::Fixed(width);
+                    Ok(())
+                } else {
+                    Err(meta.error(
+                        "unsupported field attribute, expected `desc`, `asc`, `escaped`, `varint`, or `fixed = N`",
+                    ))
+                }
+            })?;
+        }
+        out.push(KeyField {
+            member,
+            builder_name,
+            ty: field.ty.clone(),
+            desc,
+            encoding,
+        });
+    }
+    Ok(out)
+}
+
+fn encode_field(field: &KeyField, value: TokenStream2) -> TokenStream2 {
+    let desc = field.desc;
+    let ty = &field.ty;
+    let write = match &field.encoding {
+        Encoding::Escaped => quote! {
+            <#ty as ::rart::keys::codec::KeySegment>::encode_segment(#value, writer);
+        },
+        Encoding::Fixed(width) => quote! {
+            <#ty as ::rart::keys::codec::FixedWidthSegment>::encode_fixed(#value, #width, writer);
+        },
+        Encoding::Varint => quote! {
+            <::rart::keys::codec::Varint<#ty> as ::rart::keys::codec::KeySegment>::encode_segment(
+                &::rart::keys::codec::Varint(*#value),
+                writer,
+            );
+        },
+    };
+    quote! {
+        writer.set_descending(base ^ #desc);
+        #write
+    }
+}
+
+fn decode_field(field: &KeyField) -> TokenStream2 {
+    let desc = field.desc;
+    let ty = &field.ty;
+    let read = match &field.encoding {
+        Encoding::Escaped => quote! {
+            <#ty as ::rart::keys::codec::KeySegment>::decode_segment(reader)
+        },
+        Encoding::Fixed(width) => quote! {
+            <#ty as ::rart::keys::codec::FixedWidthSegment>::decode_fixed(#width, reader)
+        },
+        Encoding::Varint => quote! {
+            <::rart::keys::codec::Varint<#ty> as ::rart::keys::codec::KeySegment>::decode_segment(
+                reader,
+            )
+            .map(|varint| varint.0)
+        },
+    };
+    quote! {
+        {
+            reader.set_descending(base ^ #desc);
+            match #read {
+                Ok(value) => value,
+                Err(e) => {
+                    reader.set_descending(base);
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let vis = &input.vis;
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "ArtKey cannot be derived for generic structs",
+        ));
+    }
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        _ => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "ArtKey can only be derived for structs",
+            ));
+        }
+    };
+    let key_type = parse_struct_attrs(&input)?;
+    let fields = parse_fields(&data.fields)?;
+
+    let encodes = fields.iter().map(|f| {
+        let member = &f.member;
+        encode_field(f, quote!(&self.#member))
+    });
+
+    let decode_bindings: Vec<Ident> = (0..fields.len())
+        .map(|i| format_ident!("__field_{}", i))
+        .collect();
+    let decodes = fields.iter().zip(&decode_bindings).map(|(f, binding)| {
+        let decode = decode_field(f);
+        quote!(let #binding = #decode;)
+    });
+    let construct = match &data.fields {
+        Fields::Named(_) => {
+            let members = fields.iter().map(|f| &f.member);
+            quote!(#name { #(#members: #decode_bindings),* })
+        }
+        Fields::Unnamed(_) => quote!(#name ( #(#decode_bindings),* )),
+        Fields::Unit => quote!(#name),
+    };
+
+    let builder = format_ident!("{}KeyPrefix", name);
+    let builder_doc = format!(
+        "Builds encoded key prefixes of [`{name}`] from its leading fields, in declaration order."
+    );
+    let steps = fields.iter().enumerate().map(|(i, f)| {
+        let next = i + 1;
+        let method = &f.builder_name;
+        let ty = &f.ty;
+        let encode = encode_field(f, quote!(value));
+        quote! {
+            impl #builder<#i> {
+                pub fn #method(mut self, value: &#ty) -> #builder<#next> {
+                    let writer = &mut self.writer;
+                    let base = false;
+                    #encode
+                    writer.set_descending(base);
+                    #builder { writer: self.writer }
+                }
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl ::rart::keys::codec::ArtKey for #name {
+            type Key = #key_type;
+
+            fn encode_key(&self, writer: &mut ::rart::keys::codec::KeyWriter) {
+                let base = writer.is_descending();
+                #(#encodes)*
+                writer.set_descending(base);
+            }
+
+            fn decode_key(
+                reader: &mut ::rart::keys::codec::KeyReader<'_>,
+            ) -> ::std::result::Result<Self, ::rart::keys::codec::KeyDecodeError> {
+                let base = reader.is_descending();
+                #(#decodes)*
+                reader.set_descending(base);
+                Ok(#construct)
+            }
+        }
+
+        impl ::rart::keys::codec::KeySegment for #name {
+            fn encode_segment(&self, writer: &mut ::rart::keys::codec::KeyWriter) {
+                <Self as ::rart::keys::codec::ArtKey>::encode_key(self, writer)
+            }
+
+            fn decode_segment(
+                reader: &mut ::rart::keys::codec::KeyReader<'_>,
+            ) -> ::std::result::Result<Self, ::rart::keys::codec::KeyDecodeError> {
+                <Self as ::rart::keys::codec::ArtKey>::decode_key(reader)
+            }
+        }
+
+        #[doc = #builder_doc]
+        #[derive(Clone, Debug)]
+        #vis struct #builder<const FIELDS: usize> {
+            writer: ::rart::keys::codec::KeyWriter,
+        }
+
+        impl<const FIELDS: usize> #builder<FIELDS> {
+            /// The encoded prefix bytes.
+            pub fn as_bytes(&self) -> &[u8] {
+                self.writer.as_bytes()
+            }
+
+            /// Build a tree key from the encoded prefix.
+            pub fn to_key(&self) -> #key_type {
+                self.writer.to_key()
+            }
+        }
+
+        #(#steps)*
+
+        impl #name {
+            /// Start building a key prefix from this type's leading fields.
+            pub fn key_prefix() -> #builder<0> {
+                #builder {
+                    writer: ::rart::keys::codec::KeyWriter::new(),
+                }
+            }
+        }
+    })
+}
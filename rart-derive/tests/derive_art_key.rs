@@ -0,0 +1,159 @@
+use rart::keys::codec::{ArtKey, KeyDecodeError};
+use rart::{AdaptiveRadixTree, ArrayKey, VectorKey};
+use rart_derive::ArtKey;
+
+#[derive(ArtKey, Clone, Debug, PartialEq, Eq)]
+struct EventKey {
+    tenant: String,
+    #[art(desc)]
+    timestamp: i64,
+    id: u32,
+}
+
+#[derive(ArtKey, Clone, Debug, PartialEq, Eq)]
+#[art(key = ArrayKey<24>)]
+struct FixedKey {
+    #[art(fixed = 8)]
+    kind: String,
+    #[art(desc, fixed = 4)]
+    code: Vec<u8>,
+    flag: bool,
+}
+
+#[derive(ArtKey, Clone, Debug, PartialEq, Eq)]
+struct CompactKey {
+    #[art(varint)]
+    shard: u64,
+    #[art(varint, desc)]
+    delta: i32,
+}
+
+#[derive(ArtKey, Clone, Debug, PartialEq, Eq)]
+struct Wrapped(u16, #[art(desc)] EventKey);
+
+fn event(tenant: &str, timestamp: i64, id: u32) -> EventKey {
+    EventKey {
+        tenant: tenant.to_string(),
+        timestamp,
+        id,
+    }
+}
+
+fn sort_key(k: &EventKey) -> (String, std::cmp::Reverse<i64>, u32) {
+    (k.tenant.clone(), std::cmp::Reverse(k.timestamp), k.id)
+}
+
+#[test]
+fn encoding_matches_declared_order() {
+    let mut keys = Vec::new();
+    for tenant in ["", "a", "a\0", "ab", "b"] {
+        for timestamp in [i64::MIN, -5, 0, 5, i64::MAX] {
+            for id in [0, 1, u32::MAX] {
+                keys.push(event(tenant, timestamp, id));
+            }
+        }
+    }
+    let mut by_value = keys.clone();
+    by_value.sort_by_key(sort_key);
+    let mut by_bytes = keys.clone();
+    by_bytes.sort_by_key(|k| k.to_key());
+    assert_eq!(by_value, by_bytes);
+
+    for key in &keys {
+        assert_eq!(&EventKey::from_key(&key.to_key()).unwrap(), key);
+    }
+}
+
+#[test]
+fn fixed_width_fields_round_trip() {
+    let key = FixedKey {
+        kind: "user".to_string(),
+        code: vec![1, 2],
+        flag: true,
+    };
+    let encoded: ArrayKey<24> = key.to_key();
+    assert_eq!(encoded.as_ref().len(), 13);
+    assert_eq!(FixedKey::from_key(&encoded).unwrap(), key);
+
+    let smaller = FixedKey {
+        code: vec![1, 3],
+        ..key.clone()
+    };
+    assert!(smaller.to_key() < key.to_key());
+}
+
+#[test]
+fn varint_fields_are_compact_and_ordered() {
+    let key = CompactKey {
+        shard: 3,
+        delta: -2,
+    };
+    assert_eq!(key.to_key().as_ref().len(), 4);
+    assert_eq!(CompactKey::from_key(&key.to_key()).unwrap(), key);
+
+    let mut keys = Vec::new();
+    for shard in [0, 1, 255, 256, u64::MAX] {
+        for delta in [i32::MIN, -1, 0, 1, i32::MAX] {
+            keys.push(CompactKey { shard, delta });
+        }
+    }
+    let mut by_bytes = keys.clone();
+    by_bytes.sort_by_key(|k| k.to_key());
+    keys.sort_by_key(|k| (k.shard, std::cmp::Reverse(k.delta)));
+    assert_eq!(keys, by_bytes);
+}
+
+#[test]
+fn nested_descending_segment() {
+    let a = Wrapped(1, event("a", 1, 1));
+    let b = Wrapped(1, event("a", 2, 1));
+    // The nested key is descending, and its timestamp is descending inside it, so the two
+    // inversions cancel out.
+    assert!(a.to_key() < b.to_key());
+    assert_eq!(Wrapped::from_key(&a.to_key()).unwrap(), a);
+}
+
+#[test]
+fn prefix_builder_scans_leading_fields() {
+    let mut tree = AdaptiveRadixTree::<VectorKey, u32>::new();
+    for (tenant, timestamp, id) in [
+        ("acme", 1, 1),
+        ("acme", 2, 2),
+        ("acme", 2, 3),
+        ("beta", 1, 4),
+    ] {
+        tree.insert_k(&event(tenant, timestamp, id).to_key(), id);
+    }
+
+    let tenant = EventKey::key_prefix().tenant(&"acme".to_string());
+    let ids: Vec<u32> = tree
+        .prefix_iter_k(&tenant.to_key())
+        .map(|(_, v)| *v)
+        .collect();
+    assert_eq!(ids, vec![2, 3, 1]);
+
+    let at_two = tenant.timestamp(&2).to_key();
+    let ids: Vec<u32> = tree.prefix_iter_k(&at_two).map(|(_, v)| *v).collect();
+    assert_eq!(ids, vec![2, 3]);
+
+    let full = EventKey::key_prefix()
+        .tenant(&"beta".to_string())
+        .timestamp(&1)
+        .id(&4);
+    assert_eq!(full.as_bytes(), event("beta", 1, 4).to_key().as_ref());
+}
+
+#[test]
+fn decode_rejects_trailing_and_truncated_input() {
+    let mut bytes = event("a", 1, 1).to_key().as_ref().to_vec();
+    bytes.push(0);
+    assert_eq!(
+        EventKey::from_key_bytes(&bytes),
+        Err(KeyDecodeError::TrailingBytes)
+    );
+    bytes.truncate(bytes.len() - 2);
+    assert_eq!(
+        EventKey::from_key_bytes(&bytes),
+        Err(KeyDecodeError::UnexpectedEnd)
+    );
+}
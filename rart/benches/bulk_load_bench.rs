@@ -53,6 +53,12 @@ fn build_bulk_load_sorted_callback(
     )
 }
 
+fn build_bulk_load_sorted_parallel(
+    items: &[(ArrayKey<16>, u64)],
+) -> AdaptiveRadixTree<ArrayKey<16>, u64> {
+    AdaptiveRadixTree::bulk_load_sorted_parallel(items.iter().copied())
+}
+
 fn sequential_u64_items(size: usize) -> Vec<(ArrayKey<16>, u64)> {
     (0..size).map(|i| ((i as u64).into(), i as u64)).collect()
 }
@@ -137,6 +143,18 @@ pub fn bulk_load_build_u64(c: &mut Criterion) {
                 );
             },
         );
+
+        group.bench_with_input(
+            BenchmarkId::new("sorted_parallel", size),
+            &sorted,
+            |b, items| {
+                b.iter_batched(
+                    || (),
+                    |_| build_bulk_load_sorted_parallel(items),
+                    BatchSize::LargeInput,
+                );
+            },
+        );
     }
 
     group.finish();
@@ -204,6 +222,18 @@ pub fn bulk_load_build_prefixed(c: &mut Criterion) {
                 );
             },
         );
+
+        group.bench_with_input(
+            BenchmarkId::new("sorted_parallel", size),
+            &sorted,
+            |b, items| {
+                b.iter_batched(
+                    || (),
+                    |_| build_bulk_load_sorted_parallel(items),
+                    BatchSize::LargeInput,
+                );
+            },
+        );
     }
 
     group.finish();
@@ -418,7 +418,7 @@ impl<const N: usize> KeyTrait for OverflowVectorPartialKey<N> {
         Self::new_from_slice(&data)
     }
 
-    fn truncate(&self, at_depth: usize) -> Self {
+    fn truncated(&self, at_depth: usize) -> Self {
         Self::new_from_slice(&self.inner.as_ref()[..at_depth])
     }
 
@@ -0,0 +1,1414 @@
+//! An Adaptive Radix Tree that many threads can read and write at once.
+//!
+//! [`ConcurrentAdaptiveRadixTree`] synchronizes with optimistic lock coupling (OLC), as described
+//! by Leis et al. in "The ART of Practical Synchronization". Every node carries a version lock:
+//! a counter that each write bumps, plus a locked bit and an obsolete bit. Readers take no locks.
+//! They note a node's version, read what they need, and check that the version is unchanged
+//! before trusting it or following a child pointer; if it moved, the operation restarts from the
+//! root. Writers descend the same way and upgrade the version they read into a lock only on the
+//! nodes they change, so an upgrade fails, and the writer restarts, if anything wrote the node
+//! in between.
+//!
+//! Children live in the usual `SortedKeyedMapping` / `IndexedMapping` / `DirectMapping` layouts
+//! and are changed in place: adding a child to a node with room for it, or removing one that
+//! leaves the node at its size, locks only that node. Values are boxed behind an atomic pointer,
+//! so inserting over an existing key or removing an inner node's value swaps the pointer under
+//! the node's lock without copying the node. A node is replaced only when its shape changes:
+//! growing a full node, shrinking one below its size class, splitting a prefix, or merging a
+//! node that is left with one child and no value into that child. A replacement takes over the
+//! old node's child pointers and value box, locks the parent as well, and marks the old node
+//! obsolete, which sends readers and writers still on it back to the root.
+//!
+//! Optimistic reads can race with a writer that holds the lock, so they may see a mapping in the
+//! middle of a change. Nothing read that way is used until the version validates, child pointers
+//! are only followed after validation, and index bytes read from a `Node48` are bounds-checked.
+//!
+//! Replaced nodes and values are retired and freed through epoch-based reclamation once no
+//! reader can still be using them.
+
+use std::cell::UnsafeCell;
+use std::cmp::min;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::ptr;
+
+use crate::epoch::{EpochGuard, Epochs, Garbage};
+use crate::keys::KeyTrait;
+use crate::mapping::{
+    NodeMapping,
+    direct_mapping::DirectMapping,
+    indexed_mapping::IndexedMapping,
+    sorted_keyed_mapping::{SortedKeyedMapping, SortedKeyedMappingIter},
+};
+use crate::partials::Partial;
+use crate::range::ByteRange;
+use crate::sync::{AtomicPtr, AtomicUsize, Ordering, yield_now};
+use crate::utils::bitset::{Bitset64, BitsetOnesIter};
+
+type Child<P, V> = AtomicPtr<ConcurrentNode<P, V>>;
+type NodeRef<P, V> = *mut ConcurrentNode<P, V>;
+
+const LOCKED: usize = 0b01;
+const OBSOLETE: usize = 0b10;
+/// What one write adds to a node's version.
+const STEP: usize = 0b100;
+
+/// The node changed under an optimistic read or a lock upgrade; the operation starts over.
+struct Restart;
+
+/// A version lock: a write counter with a locked bit and an obsolete bit, set once the node it
+/// guards has been replaced.
+struct NodeVersion(AtomicUsize);
+
+impl NodeVersion {
+    fn new() -> Self {
+        Self(AtomicUsize::new(0))
+    }
+
+    /// Start an optimistic read, waiting for any writer to finish. Fails if the node has been
+    /// replaced.
+    ///
+    /// Only waits while the caller holds no locks, so writers cannot deadlock.
+    fn read(&self) -> Result<usize, Restart> {
+        loop {
+            let version = self.0.load(Ordering::SeqCst);
+            if version & OBSOLETE != 0 {
+                return Err(Restart);
+            }
+            if version & LOCKED == 0 {
+                return Ok(version);
+            }
+            yield_now();
+        }
+    }
+
+    /// Start an optimistic read without waiting, for a writer that already holds locks.
+    fn try_read(&self) -> Result<usize, Restart> {
+        let version = self.0.load(Ordering::SeqCst);
+        if version & (LOCKED | OBSOLETE) != 0 {
+            return Err(Restart);
+        }
+        Ok(version)
+    }
+
+    /// Check that nothing has written the node since `version` was read.
+    fn validate(&self, version: usize) -> Result<(), Restart> {
+        if self.0.load(Ordering::SeqCst) == version {
+            Ok(())
+        } else {
+            Err(Restart)
+        }
+    }
+
+    /// Lock the node, provided nothing has written it since `version` was read.
+    fn upgrade(&self, version: usize) -> Result<(), Restart> {
+        self.0
+            .compare_exchange(
+                version,
+                version | LOCKED,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            )
+            .map(|_| ())
+            .map_err(|_| Restart)
+    }
+
+    /// Unlock after writing, so optimistic readers of the old version restart.
+    fn unlock(&self) {
+        self.0.fetch_add(STEP - LOCKED, Ordering::SeqCst);
+    }
+
+    /// Unlock a node that has been replaced, so everyone still on it restarts.
+    fn unlock_obsolete(&self) {
+        self.0.fetch_add(STEP - LOCKED + OBSOLETE, Ordering::SeqCst);
+    }
+
+    /// Unlock without having written, leaving the version as it was read.
+    fn release(&self, version: usize) {
+        self.0.store(version, Ordering::SeqCst);
+    }
+}
+
+/// Lock each `(lock, version)` pair in order, provided none has been written since its version
+/// was read. On failure the ones already locked are released unchanged.
+fn lock_all(locks: &[(&NodeVersion, usize)]) -> Result<(), Restart> {
+    for (i, &(lock, version)) in locks.iter().enumerate() {
+        if let Err(restart) = lock.upgrade(version) {
+            for &(lock, version) in &locks[..i] {
+                lock.release(version);
+            }
+            return Err(restart);
+        }
+    }
+    Ok(())
+}
+
+/// A node of a concurrent tree. The prefix never changes after the node is published; the value
+/// and the children change in place under the node's lock.
+struct ConcurrentNode<P: Partial, V> {
+    prefix: P,
+    /// A boxed value, or null.
+    value: AtomicPtr<V>,
+    version: NodeVersion,
+    content: UnsafeCell<ConcurrentContent<P, V>>,
+}
+
+enum ConcurrentContent<P: Partial, V> {
+    Empty,
+    Node4(Box<SortedKeyedMapping<Child<P, V>, 4>>),
+    Node16(Box<SortedKeyedMapping<Child<P, V>, 16>>),
+    Node48(Box<IndexedMapping<Child<P, V>, 48, Bitset64<1>>>),
+    Node256(Box<DirectMapping<Child<P, V>>>),
+}
+
+enum ChildIter<'a, P: Partial, V> {
+    Node4(SortedKeyedMappingIter<'a, Child<P, V>, 4>),
+    Node16(SortedKeyedMappingIter<'a, Child<P, V>, 16>),
+    Node48(
+        BitsetOnesIter<u64, 4>,
+        &'a IndexedMapping<Child<P, V>, 48, Bitset64<1>>,
+    ),
+    Node256(BitsetOnesIter<u64, 4>, &'a DirectMapping<Child<P, V>>),
+    Empty,
+}
+
+impl<P: Partial, V> Iterator for ChildIter<'_, P, V> {
+    type Item = (u8, NodeRef<P, V>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, child) = match self {
+            ChildIter::Node4(iter) => iter.next(),
+            ChildIter::Node16(iter) => iter.next(),
+            ChildIter::Node48(keys, im) => {
+                keys.find_map(|key| Some((key as u8, seek_indexed(im, key as u8)?)))
+            }
+            ChildIter::Node256(keys, dm) => {
+                keys.find_map(|key| Some((key as u8, dm.children.peek(key)?)))
+            }
+            ChildIter::Empty => None,
+        }?;
+        Some((key, child.load(Ordering::SeqCst)))
+    }
+}
+
+/// Look up a `Node48` child, skipping index bytes that a concurrent write has left out of range.
+fn seek_indexed<N>(im: &IndexedMapping<N, 48, Bitset64<1>>, key: u8) -> Option<&N> {
+    let pos = *im.child_ptr_indexes.peek(key as usize)? as usize;
+    if pos < 48 {
+        im.children.peek(pos)
+    } else {
+        None
+    }
+}
+
+impl<P: Partial, V> ConcurrentNode<P, V> {
+    /// Allocate a node. `children` must be sorted by edge byte, and a node without a value
+    /// must have at least two children.
+    fn alloc(prefix: P, value: *mut V, children: &[(u8, NodeRef<P, V>)]) -> NodeRef<P, V> {
+        debug_assert!(!value.is_null() || children.len() >= 2);
+        let content = match children.len() {
+            0 => ConcurrentContent::Empty,
+            1..=4 => {
+                let mut km = SortedKeyedMapping::new();
+                for &(key, child) in children {
+                    km.add_child_sorted(key, AtomicPtr::new(child));
+                }
+                ConcurrentContent::Node4(Box::new(km))
+            }
+            5..=16 => {
+                let mut km = SortedKeyedMapping::new();
+                for &(key, child) in children {
+                    km.add_child_sorted(key, AtomicPtr::new(child));
+                }
+                ConcurrentContent::Node16(Box::new(km))
+            }
+            17..=48 => {
+                let mut im = IndexedMapping::new();
+                for &(key, child) in children {
+                    im.add_child_sorted(key, AtomicPtr::new(child));
+                }
+                ConcurrentContent::Node48(Box::new(im))
+            }
+            _ => {
+                let mut dm = DirectMapping::new();
+                for &(key, child) in children {
+                    dm.add_child(key, AtomicPtr::new(child));
+                }
+                ConcurrentContent::Node256(Box::new(dm))
+            }
+        };
+        Box::into_raw(Box::new(Self {
+            prefix,
+            value: AtomicPtr::new(value),
+            content: UnsafeCell::new(content),
+            version: NodeVersion::new(),
+        }))
+    }
+
+    /// The child mapping. Unless the caller holds the node's lock, a writer may be changing it
+    /// in place, and what is read through it is only good once the node's version validates.
+    fn content(&self) -> &ConcurrentContent<P, V> {
+        // SAFETY: the mapping is only changed under the node's lock, and readers that race
+        // with such a change discard what they read when validation fails. Reads stay in
+        // bounds whatever state they find the mapping in.
+        unsafe { &*self.content.get() }
+    }
+
+    /// The child mapping, for changing in place.
+    ///
+    /// # Safety
+    ///
+    /// The caller must hold the node's lock.
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn content_mut(&self) -> &mut ConcurrentContent<P, V> {
+        // SAFETY: the lock excludes other writers; see `content` for readers.
+        unsafe { &mut *self.content.get() }
+    }
+
+    /// The child under `key`, as currently linked.
+    fn load_child(&self, key: u8) -> Option<NodeRef<P, V>> {
+        self.child_slot(key)
+            .map(|child| child.load(Ordering::SeqCst))
+    }
+
+    fn child_slot(&self, key: u8) -> Option<&Child<P, V>> {
+        match self.content() {
+            ConcurrentContent::Node4(km) => km.seek_child(key),
+            ConcurrentContent::Node16(km) => km.seek_child(key),
+            ConcurrentContent::Node48(im) => seek_indexed(im, key),
+            ConcurrentContent::Node256(dm) => dm.children.peek(key as usize),
+            ConcurrentContent::Empty => None,
+        }
+    }
+
+    /// The children in edge order, as currently linked.
+    fn children(&self) -> ChildIter<'_, P, V> {
+        match self.content() {
+            ConcurrentContent::Node4(km) => ChildIter::Node4(km.iter()),
+            ConcurrentContent::Node16(km) => ChildIter::Node16(km.iter()),
+            ConcurrentContent::Node48(im) => {
+                ChildIter::Node48(im.child_ptr_indexes.bitset.iter(), im)
+            }
+            ConcurrentContent::Node256(dm) => ChildIter::Node256(dm.children.bitset.iter(), dm),
+            ConcurrentContent::Empty => ChildIter::Empty,
+        }
+    }
+
+    fn num_children(&self) -> usize {
+        match self.content() {
+            ConcurrentContent::Node4(km) => km.num_children(),
+            ConcurrentContent::Node16(km) => km.num_children(),
+            ConcurrentContent::Node48(im) => im.num_children(),
+            ConcurrentContent::Node256(dm) => dm.num_children(),
+            ConcurrentContent::Empty => 0,
+        }
+    }
+
+    /// Whether a child can be added in place.
+    fn has_room(&self) -> bool {
+        match self.content() {
+            ConcurrentContent::Node4(km) => km.num_children() < 4,
+            ConcurrentContent::Node16(km) => km.num_children() < 16,
+            ConcurrentContent::Node48(im) => im.num_children() < 48,
+            ConcurrentContent::Node256(_) => true,
+            ConcurrentContent::Empty => false,
+        }
+    }
+
+    /// Whether a child can be removed in place without leaving the node below its size class,
+    /// using the same thresholds as `Node::delete_child`.
+    fn keeps_size_without_child(&self) -> bool {
+        match self.content() {
+            ConcurrentContent::Node4(km) => km.num_children() > 1,
+            ConcurrentContent::Node16(km) => km.num_children() > 5,
+            ConcurrentContent::Node48(im) => im.num_children() > 17,
+            ConcurrentContent::Node256(dm) => dm.num_children() > 49,
+            ConcurrentContent::Empty => false,
+        }
+    }
+
+    /// Add a child in place.
+    ///
+    /// # Safety
+    ///
+    /// The caller must hold the node's lock, and the node must have room.
+    unsafe fn add_child(&self, key: u8, child: NodeRef<P, V>) {
+        let child = AtomicPtr::new(child);
+        // SAFETY: the caller holds the lock.
+        match unsafe { self.content_mut() } {
+            ConcurrentContent::Node4(km) => km.add_child(key, child),
+            ConcurrentContent::Node16(km) => km.add_child(key, child),
+            ConcurrentContent::Node48(im) => im.add_child(key, child),
+            ConcurrentContent::Node256(dm) => dm.add_child(key, child),
+            ConcurrentContent::Empty => unreachable!("leaves are replaced to gain children"),
+        }
+    }
+
+    /// Remove a child in place.
+    ///
+    /// # Safety
+    ///
+    /// The caller must hold the node's lock.
+    unsafe fn delete_child(&self, key: u8) {
+        // SAFETY: the caller holds the lock.
+        match unsafe { self.content_mut() } {
+            ConcurrentContent::Node4(km) => km.delete_child(key),
+            ConcurrentContent::Node16(km) => km.delete_child(key),
+            ConcurrentContent::Node48(im) => im.delete_child(key),
+            ConcurrentContent::Node256(dm) => dm.delete_child(key),
+            ConcurrentContent::Empty => None,
+        };
+    }
+}
+
+/// Borrow a node for as long as `guard` keeps retired nodes from being freed.
+///
+/// # Safety
+///
+/// `node` must have been linked into the tree at a point where `guard` was pinned, as checked
+/// by validating the version of the node (or root) it was loaded from.
+unsafe fn node_ref<'g, P: Partial, V>(
+    node: NodeRef<P, V>,
+    _guard: &'g EpochGuard<'_>,
+) -> &'g ConcurrentNode<P, V> {
+    // SAFETY: nodes are only freed once every guard pinned before they were retired is gone.
+    unsafe { &*node }
+}
+
+/// Free `node`, its value, and everything below it.
+///
+/// # Safety
+///
+/// No other thread may be able to reach `node` or its descendants.
+unsafe fn free_subtree<P: Partial, V>(node: NodeRef<P, V>) {
+    // SAFETY: the caller owns the subtree, and every node was allocated by `alloc`.
+    let node = unsafe { Box::from_raw(node) };
+    let value = node.value.load(Ordering::SeqCst);
+    if !value.is_null() {
+        // SAFETY: values are boxed, and owned by the node that links them.
+        drop(unsafe { Box::from_raw(value) });
+    }
+    for (_, child) in node.children() {
+        // SAFETY: children of an exclusively owned node are exclusively owned too.
+        unsafe { free_subtree(child) };
+    }
+}
+
+/// Something unlinked from the tree, freed once no reader can still see it.
+enum Retired<P: Partial, V> {
+    /// A replaced or removed node. Its value and children are retired separately or live on
+    /// elsewhere, so only the node itself is freed.
+    Node(NodeRef<P, V>),
+    Value(*mut V),
+}
+
+/// Where a node hangs: under `edge` in `parent`, or from the root pointer if there is no
+/// parent, together with the version of the lock guarding that link.
+struct Link<'g, P: Partial, V> {
+    parent: Option<&'g ConcurrentNode<P, V>>,
+    edge: u8,
+    lock: &'g NodeVersion,
+    version: usize,
+}
+
+impl<P: Partial, V> Clone for Link<'_, P, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P: Partial, V> Copy for Link<'_, P, V> {}
+
+impl<'g, P: Partial, V> Link<'g, P, V> {
+    fn below(parent: &'g ConcurrentNode<P, V>, edge: u8, version: usize) -> Self {
+        Self {
+            parent: Some(parent),
+            edge,
+            lock: &parent.version,
+            version,
+        }
+    }
+}
+
+/// An Adaptive Radix Tree that supports concurrent reads and writes through `&self`.
+///
+/// Reads take no locks; they wait out a writer only while it holds a node they need, and
+/// restart if it changed the node. Writers lock only the nodes they change, so writes to
+/// different parts of the tree proceed in parallel. Lookups return clones of stored values, since a value may be
+/// replaced or removed by another thread at any time.
+///
+/// Each operation is atomic on its own. Range scans are not snapshots: a scan sees every entry
+/// that is present for the whole scan, and may or may not see entries written while it runs.
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// use rart::{ArrayKey, ConcurrentAdaptiveRadixTree};
+///
+/// let tree = Arc::new(ConcurrentAdaptiveRadixTree::<ArrayKey<16>, u64>::new());
+/// let handles: Vec<_> = (0..4u64)
+///     .map(|t| {
+///         let tree = Arc::clone(&tree);
+///         thread::spawn(move || {
+///             for i in 0..100 {
+///                 tree.insert(t * 100 + i, i);
+///             }
+///         })
+///     })
+///     .collect();
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+///
+/// assert_eq!(tree.len(), 400);
+/// assert_eq!(tree.get(205u64), Some(5));
+///
+/// let mut keys = Vec::new();
+/// tree.range_for_each(ArrayKey::from(10u64)..ArrayKey::from(13u64), |key, _| {
+///     keys.push(key.to_be_u64())
+/// });
+/// assert_eq!(keys, vec![10, 11, 12]);
+/// ```
+pub struct ConcurrentAdaptiveRadixTree<KeyType, ValueType>
+where
+    KeyType: KeyTrait,
+{
+    root: Child<KeyType::PartialType, ValueType>,
+    /// Guards `root` the way a node's version guards its children; never obsolete.
+    root_version: NodeVersion,
+    len: AtomicUsize,
+    epochs: Epochs,
+    garbage: Garbage<Retired<KeyType::PartialType, ValueType>>,
+    _marker: PhantomData<KeyType>,
+}
+
+// SAFETY: the tree owns its values and partials; any thread with `&self` may clone or drop
+// them, so sharing or sending it needs them to be both `Send` and `Sync`. The raw pointers
+// are owned by the tree and only freed through it.
+unsafe impl<KeyType, ValueType> Send for ConcurrentAdaptiveRadixTree<KeyType, ValueType>
+where
+    KeyType: KeyTrait,
+    KeyType::PartialType: Send + Sync,
+    ValueType: Send + Sync,
+{
+}
+
+// SAFETY: as for `Send`.
+unsafe impl<KeyType, ValueType> Sync for ConcurrentAdaptiveRadixTree<KeyType, ValueType>
+where
+    KeyType: KeyTrait,
+    KeyType::PartialType: Send + Sync,
+    ValueType: Send + Sync,
+{
+}
+
+type TreeLink<'g, K, V> = Link<'g, <K as KeyTrait>::PartialType, V>;
+type TreeRetired<K, V> = Vec<Retired<<K as KeyTrait>::PartialType, V>>;
+
+impl<KeyType, ValueType> ConcurrentAdaptiveRadixTree<KeyType, ValueType>
+where
+    KeyType: KeyTrait,
+    ValueType: Clone,
+{
+    /// Create an empty tree.
+    pub fn new() -> Self {
+        Self {
+            root: AtomicPtr::new(ptr::null_mut()),
+            root_version: NodeVersion::new(),
+            len: AtomicUsize::new(0),
+            epochs: Epochs::new(),
+            garbage: Garbage::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// The number of entries in the tree.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::SeqCst)
+    }
+
+    /// Check if the tree is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get a clone of the value for a key (generic version).
+    #[inline]
+    pub fn get<Key>(&self, key: Key) -> Option<ValueType>
+    where
+        Key: Into<KeyType>,
+    {
+        self.get_k(&key.into())
+    }
+
+    /// Get a clone of the value for a key.
+    pub fn get_k(&self, key: &KeyType) -> Option<ValueType> {
+        let guard = self.epochs.pin();
+        loop {
+            if let Ok(value) = self.try_get(&guard, key) {
+                return value;
+            }
+        }
+    }
+
+    /// Insert a key-value pair (generic version), returning the value it replaced.
+    #[inline]
+    pub fn insert<Key>(&self, key: Key, value: ValueType) -> Option<ValueType>
+    where
+        Key: Into<KeyType>,
+    {
+        self.insert_k(&key.into(), value)
+    }
+
+    /// Insert a key-value pair, returning a clone of the value it replaced.
+    pub fn insert_k(&self, key: &KeyType, value: ValueType) -> Option<ValueType> {
+        let value = Box::into_raw(Box::new(value));
+        let mut retired = Vec::new();
+        let replaced = loop {
+            let guard = self.epochs.pin();
+            if let Ok(replaced) = self.try_insert(&guard, key, value, &mut retired) {
+                break replaced;
+            }
+        };
+        if replaced.is_none() {
+            self.len.fetch_add(1, Ordering::SeqCst);
+        }
+        self.retire(retired);
+        replaced
+    }
+
+    /// Remove a key (generic version), returning its value.
+    #[inline]
+    pub fn remove<Key>(&self, key: Key) -> Option<ValueType>
+    where
+        Key: Into<KeyType>,
+    {
+        self.remove_k(&key.into())
+    }
+
+    /// Remove a key, returning a clone of its value.
+    pub fn remove_k(&self, key: &KeyType) -> Option<ValueType> {
+        let mut retired = Vec::new();
+        let removed = loop {
+            let guard = self.epochs.pin();
+            if let Ok(removed) = self.try_remove(&guard, key, &mut retired) {
+                break removed;
+            }
+        };
+        if removed.is_some() {
+            self.len.fetch_sub(1, Ordering::SeqCst);
+        }
+        self.retire(retired);
+        removed
+    }
+
+    /// Visit every entry within `range`, in key order.
+    ///
+    /// Subtrees outside the range are skipped. Entries written while the scan runs may or may
+    /// not be visited. A scan that runs into a node being replaced picks up again after the
+    /// last key it visited.
+    pub fn range_for_each<R, F>(&self, range: R, mut f: F)
+    where
+        R: RangeBounds<KeyType>,
+        F: FnMut(KeyType, &ValueType),
+    {
+        let guard = self.epochs.pin();
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        let mut last = None::<Vec<u8>>;
+        loop {
+            let resume_after = last.clone();
+            let span = match &resume_after {
+                Some(last) => ByteRange::from_bounds(
+                    Bound::Excluded(last.as_slice()),
+                    range.end_bound().map(|key| key.as_ref()),
+                ),
+                None => ByteRange::new(&range),
+            };
+            key_buf.clear();
+            if self
+                .try_range(&span, &mut key_buf, &mut last, &mut f, &guard)
+                .is_ok()
+            {
+                return;
+            }
+        }
+    }
+
+    fn root_link(&self) -> Result<TreeLink<'_, KeyType, ValueType>, Restart> {
+        Ok(Link {
+            parent: None,
+            edge: 0,
+            lock: &self.root_version,
+            version: self.root_version.read()?,
+        })
+    }
+
+    fn try_get(&self, guard: &EpochGuard<'_>, key: &KeyType) -> Result<Option<ValueType>, Restart> {
+        let version = self.root_version.read()?;
+        let mut cur = self.root.load(Ordering::SeqCst);
+        self.root_version.validate(version)?;
+        let mut depth = 0;
+        while !cur.is_null() {
+            // SAFETY: loaded from the tree while pinned.
+            let node = unsafe { node_ref(cur, guard) };
+            let version = node.version.read()?;
+            if node.prefix.prefix_length_key(key, depth) != node.prefix.len() {
+                return Ok(None);
+            }
+            if node.prefix.len() == key.length_at(depth) {
+                let value = node.value.load(Ordering::SeqCst);
+                node.version.validate(version)?;
+                // SAFETY: the value was linked while pinned, so it outlives `guard`.
+                return Ok(unsafe { value.as_ref() }.cloned());
+            }
+            let edge = key.at(depth + node.prefix.len());
+            let child = node.load_child(edge);
+            node.version.validate(version)?;
+            depth += node.prefix.len();
+            cur = match child {
+                Some(child) => child,
+                None => return Ok(None),
+            };
+        }
+        Ok(None)
+    }
+
+    fn try_range<F>(
+        &self,
+        span: &ByteRange<'_>,
+        key_buf: &mut Vec<u8>,
+        last: &mut Option<Vec<u8>>,
+        f: &mut F,
+        guard: &EpochGuard<'_>,
+    ) -> Result<(), Restart>
+    where
+        F: FnMut(KeyType, &ValueType),
+    {
+        let version = self.root_version.read()?;
+        let root = self.root.load(Ordering::SeqCst);
+        self.root_version.validate(version)?;
+        if root.is_null() {
+            return Ok(());
+        }
+        // SAFETY: loaded from the tree while pinned.
+        let root = unsafe { node_ref(root, guard) };
+        Self::range_recurse(root, span, key_buf, last, f, guard)
+    }
+
+    fn range_recurse<F>(
+        node: &ConcurrentNode<KeyType::PartialType, ValueType>,
+        span: &ByteRange<'_>,
+        key_buf: &mut Vec<u8>,
+        last: &mut Option<Vec<u8>>,
+        f: &mut F,
+        guard: &EpochGuard<'_>,
+    ) -> Result<(), Restart>
+    where
+        F: FnMut(KeyType, &ValueType),
+    {
+        let version = node.version.read()?;
+        let len = key_buf.len();
+        key_buf.extend_from_slice(node.prefix.as_ref());
+        if span.may_contain_prefixed(key_buf) {
+            let value = node.value.load(Ordering::SeqCst);
+            let children: Vec<_> = node.children().map(|(_, child)| child).collect();
+            node.version.validate(version)?;
+            // SAFETY: the value was linked while pinned, so it outlives `guard`.
+            if let Some(value) = unsafe { value.as_ref() }
+                && span.contains(key_buf)
+            {
+                f(KeyType::new_from_slice(key_buf), value);
+                *last = Some(key_buf.clone());
+            }
+            for child in children {
+                // SAFETY: loaded from the tree while pinned.
+                let child = unsafe { node_ref(child, guard) };
+                Self::range_recurse(child, span, key_buf, last, f, guard)?;
+            }
+        }
+        key_buf.truncate(len);
+        Ok(())
+    }
+
+    /// One attempt at an insert. On success `value` is linked into the tree; on a restart it
+    /// still belongs to the caller.
+    fn try_insert<'g>(
+        &'g self,
+        guard: &'g EpochGuard<'_>,
+        key: &KeyType,
+        value: *mut ValueType,
+        retired: &mut TreeRetired<KeyType, ValueType>,
+    ) -> Result<Option<ValueType>, Restart> {
+        let mut link = self.root_link()?;
+        let mut cur = self.root.load(Ordering::SeqCst);
+        self.root_version.validate(link.version)?;
+        let mut depth = 0;
+        loop {
+            if cur.is_null() {
+                // Only the root can be empty.
+                link.lock.upgrade(link.version)?;
+                let leaf = ConcurrentNode::alloc(key.to_partial(depth), value, &[]);
+                self.relink(&link, leaf);
+                link.lock.unlock();
+                return Ok(None);
+            }
+
+            // SAFETY: loaded from the tree while pinned.
+            let node = unsafe { node_ref(cur, guard) };
+            let version = node.version.read()?;
+            let prefix_len = node.prefix.len();
+            let key_len = key.length_at(depth);
+            let common = node.prefix.prefix_length_key(key, depth);
+            let is_prefix_match = min(prefix_len, key_len) == common;
+
+            if is_prefix_match && prefix_len == key_len {
+                node.version.upgrade(version)?;
+                let old = node.value.swap(value, Ordering::SeqCst);
+                node.version.unlock();
+                // SAFETY: the old value was linked until now and is retired only once this
+                // thread unpins.
+                let replaced = unsafe { old.as_ref() }.cloned();
+                if !old.is_null() {
+                    retired.push(Retired::Value(old));
+                }
+                return Ok(replaced);
+            }
+
+            if is_prefix_match && prefix_len < key_len {
+                let edge = key.at(depth + prefix_len);
+                let child = node.load_child(edge);
+                let has_room = node.has_room();
+                node.version.validate(version)?;
+                if let Some(child) = child {
+                    link = Link::below(node, edge, version);
+                    cur = child;
+                    depth += prefix_len;
+                    continue;
+                }
+                let leaf = || ConcurrentNode::alloc(key.to_partial(depth + prefix_len), value, &[]);
+                if has_room {
+                    node.version.upgrade(version)?;
+                    // SAFETY: the node is locked, and had room at the version just locked.
+                    unsafe { node.add_child(edge, leaf()) };
+                    node.version.unlock();
+                    return Ok(None);
+                }
+
+                // Grow the node into a larger replacement.
+                lock_all(&[(link.lock, link.version), (&node.version, version)])?;
+                let mut children: Vec<_> = node.children().collect();
+                let pos = children.partition_point(|&(child_edge, _)| child_edge < edge);
+                children.insert(pos, (edge, leaf()));
+                let new = ConcurrentNode::alloc(
+                    node.prefix.clone(),
+                    node.value.load(Ordering::SeqCst),
+                    &children,
+                );
+                self.publish(&link, new, &[cur], retired);
+                return Ok(None);
+            }
+
+            // Split the prefix where the key leaves it.
+            lock_all(&[(link.lock, link.version), (&node.version, version)])?;
+            let children: Vec<_> = node.children().collect();
+            let existing_edge = node.prefix.at(common);
+            let existing = ConcurrentNode::alloc(
+                node.prefix.partial_after(common),
+                node.value.load(Ordering::SeqCst),
+                &children,
+            );
+            let prefix = node.prefix.partial_before(common);
+            let new = if common == key_len {
+                ConcurrentNode::alloc(prefix, value, &[(existing_edge, existing)])
+            } else {
+                let leaf_edge = key.at(depth + common);
+                let leaf = ConcurrentNode::alloc(key.to_partial(depth + common), value, &[]);
+                let children = if leaf_edge < existing_edge {
+                    [(leaf_edge, leaf), (existing_edge, existing)]
+                } else {
+                    [(existing_edge, existing), (leaf_edge, leaf)]
+                };
+                ConcurrentNode::alloc(prefix, ptr::null_mut(), &children)
+            };
+            self.publish(&link, new, &[cur], retired);
+            return Ok(None);
+        }
+    }
+
+    /// One attempt at a removal.
+    fn try_remove<'g>(
+        &'g self,
+        guard: &'g EpochGuard<'_>,
+        key: &KeyType,
+        retired: &mut TreeRetired<KeyType, ValueType>,
+    ) -> Result<Option<ValueType>, Restart> {
+        let mut parent_link = None;
+        let mut link = self.root_link()?;
+        let mut cur = self.root.load(Ordering::SeqCst);
+        self.root_version.validate(link.version)?;
+        let mut depth = 0;
+        let (node, version, value) = loop {
+            if cur.is_null() {
+                return Ok(None);
+            }
+            // SAFETY: loaded from the tree while pinned.
+            let node = unsafe { node_ref(cur, guard) };
+            let version = node.version.read()?;
+            if node.prefix.prefix_length_key(key, depth) != node.prefix.len() {
+                return Ok(None);
+            }
+            if node.prefix.len() == key.length_at(depth) {
+                let value = node.value.load(Ordering::SeqCst);
+                break (node, version, value);
+            }
+            let edge = key.at(depth + node.prefix.len());
+            let child = node.load_child(edge);
+            node.version.validate(version)?;
+            let Some(child) = child else {
+                return Ok(None);
+            };
+            parent_link = Some(link);
+            link = Link::below(node, edge, version);
+            cur = child;
+            depth += node.prefix.len();
+        };
+        let num_children = node.num_children();
+        let first_child = node.children().next();
+        node.version.validate(version)?;
+        if value.is_null() {
+            return Ok(None);
+        }
+
+        if num_children >= 2 {
+            // Drop the value in place.
+            node.version.upgrade(version)?;
+            node.value.store(ptr::null_mut(), Ordering::SeqCst);
+            node.version.unlock();
+        } else if num_children == 1 {
+            // A node left with one child and no value merges into that child.
+            let (_, child_ref) = first_child.ok_or(Restart)?;
+            // SAFETY: loaded from the node at the version validated above.
+            let child = unsafe { node_ref(child_ref, guard) };
+            let child_version = child.version.try_read()?;
+            lock_all(&[
+                (link.lock, link.version),
+                (&node.version, version),
+                (&child.version, child_version),
+            ])?;
+            let new = Self::merged(&node.prefix, child);
+            self.publish(&link, new, &[cur, child_ref], retired);
+        } else if let Some(parent) = link.parent {
+            self.unlink_leaf(guard, parent_link, link, parent, cur, version, retired)?;
+        } else {
+            lock_all(&[(link.lock, link.version), (&node.version, version)])?;
+            self.publish(&link, ptr::null_mut(), &[cur], retired);
+        }
+        // SAFETY: the value was linked until now and is retired only once this thread unpins.
+        let removed = unsafe { &*value }.clone();
+        retired.push(Retired::Value(value));
+        Ok(Some(removed))
+    }
+
+    /// Remove the leaf `leaf_ref`, linked by `link` from `parent`. The parent loses the child in
+    /// place if it keeps its size class, and is otherwise replaced through `parent_link`,
+    /// merging it into its remaining child if that leaves it with one child and no value.
+    #[allow(clippy::too_many_arguments)]
+    fn unlink_leaf<'g>(
+        &'g self,
+        guard: &'g EpochGuard<'_>,
+        parent_link: Option<TreeLink<'g, KeyType, ValueType>>,
+        link: TreeLink<'g, KeyType, ValueType>,
+        parent: &'g ConcurrentNode<KeyType::PartialType, ValueType>,
+        leaf_ref: NodeRef<KeyType::PartialType, ValueType>,
+        leaf_version: usize,
+        retired: &mut TreeRetired<KeyType, ValueType>,
+    ) -> Result<(), Restart> {
+        // SAFETY: `leaf_ref` was loaded from the tree while pinned.
+        let leaf = unsafe { node_ref(leaf_ref, guard) };
+        let parent_value = parent.value.load(Ordering::SeqCst);
+        let remaining = parent.num_children().saturating_sub(1);
+        let keeps_size = parent.keeps_size_without_child();
+        let children: Vec<_> = parent
+            .children()
+            .filter(|&(edge, _)| edge != link.edge)
+            .collect();
+        parent.version.validate(link.version)?;
+        let keeps_shape = !parent_value.is_null() || remaining >= 2;
+
+        if keeps_shape && keeps_size {
+            lock_all(&[(link.lock, link.version), (&leaf.version, leaf_version)])?;
+            // SAFETY: the parent is locked.
+            unsafe { parent.delete_child(link.edge) };
+            leaf.version.unlock_obsolete();
+            link.lock.unlock();
+            retired.push(Retired::Node(leaf_ref));
+            return Ok(());
+        }
+
+        let parent_link = parent_link.expect("a node with a parent has a link to it");
+        let parent_ref = ptr::from_ref(parent).cast_mut();
+        if keeps_shape {
+            lock_all(&[
+                (parent_link.lock, parent_link.version),
+                (link.lock, link.version),
+                (&leaf.version, leaf_version),
+            ])?;
+            // Children read before locking are still current, since the parent's version held.
+            let new = ConcurrentNode::alloc(parent.prefix.clone(), parent_value, &children);
+            self.publish(&parent_link, new, &[parent_ref, leaf_ref], retired);
+            return Ok(());
+        }
+
+        let &[(_, sibling_ref)] = children.as_slice() else {
+            return Err(Restart);
+        };
+        // SAFETY: loaded from the parent at the version validated above.
+        let sibling = unsafe { node_ref(sibling_ref, guard) };
+        let sibling_version = sibling.version.try_read()?;
+        lock_all(&[
+            (parent_link.lock, parent_link.version),
+            (link.lock, link.version),
+            (&leaf.version, leaf_version),
+            (&sibling.version, sibling_version),
+        ])?;
+        let new = Self::merged(&parent.prefix, sibling);
+        self.publish(
+            &parent_link,
+            new,
+            &[parent_ref, leaf_ref, sibling_ref],
+            retired,
+        );
+        Ok(())
+    }
+
+    /// A node standing in for a valueless node with prefix `prefix` and its only child, which
+    /// the caller has locked.
+    fn merged(
+        prefix: &KeyType::PartialType,
+        child: &ConcurrentNode<KeyType::PartialType, ValueType>,
+    ) -> NodeRef<KeyType::PartialType, ValueType> {
+        let grandchildren: Vec<_> = child.children().collect();
+        ConcurrentNode::alloc(
+            prefix.partial_extended_with(&child.prefix),
+            child.value.load(Ordering::SeqCst),
+            &grandchildren,
+        )
+    }
+
+    /// Point `link` at `node`. The link's lock must be held.
+    fn relink(
+        &self,
+        link: &TreeLink<'_, KeyType, ValueType>,
+        node: NodeRef<KeyType::PartialType, ValueType>,
+    ) {
+        match link.parent {
+            Some(parent) => parent
+                .child_slot(link.edge)
+                .expect("a locked parent keeps its children")
+                .store(node, Ordering::SeqCst),
+            None => self.root.store(node, Ordering::SeqCst),
+        }
+    }
+
+    /// Link `new` in place of the locked nodes in `replaced`, the first of which hangs from
+    /// `link`. The replaced nodes are marked obsolete and retired, and `link` is unlocked.
+    fn publish(
+        &self,
+        link: &TreeLink<'_, KeyType, ValueType>,
+        new: NodeRef<KeyType::PartialType, ValueType>,
+        replaced: &[NodeRef<KeyType::PartialType, ValueType>],
+        retired: &mut TreeRetired<KeyType, ValueType>,
+    ) {
+        self.relink(link, new);
+        for &node in replaced {
+            // SAFETY: the caller holds the node's lock, so it has not been retired.
+            unsafe { &*node }.version.unlock_obsolete();
+            retired.push(Retired::Node(node));
+        }
+        link.lock.unlock();
+    }
+
+    fn retire(&self, retired: TreeRetired<KeyType, ValueType>) {
+        self.garbage
+            .retire(&self.epochs, retired, |item| match item {
+                // SAFETY: the node was unlinked before it was retired and no pinned reader can
+                // still see it. Its value and children were handed on or retired on their own, so
+                // only the node itself is freed.
+                Retired::Node(node) => drop(unsafe { Box::from_raw(node) }),
+                // SAFETY: as for nodes; values are boxed.
+                Retired::Value(value) => drop(unsafe { Box::from_raw(value) }),
+            });
+    }
+}
+
+impl<KeyType, ValueType> Default for ConcurrentAdaptiveRadixTree<KeyType, ValueType>
+where
+    KeyType: KeyTrait,
+    ValueType: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<KeyType, ValueType> Drop for ConcurrentAdaptiveRadixTree<KeyType, ValueType>
+where
+    KeyType: KeyTrait,
+{
+    fn drop(&mut self) {
+        for item in self.garbage.drain() {
+            match item {
+                // SAFETY: `&mut self` rules out readers, and retired nodes are unlinked; their
+                // children and values belong to the live tree or are retired on their own.
+                Retired::Node(node) => drop(unsafe { Box::from_raw(node) }),
+                // SAFETY: as for nodes; values are boxed.
+                Retired::Value(value) => drop(unsafe { Box::from_raw(value) }),
+            }
+        }
+        let root = *self.root.get_mut();
+        if !root.is_null() {
+            // SAFETY: `&mut self` rules out any other access to the tree.
+            unsafe { free_subtree(root) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::vector_key::VectorKey;
+    use proptest::prelude::*;
+    use std::collections::BTreeMap;
+
+    pub(super) fn entries(
+        tree: &ConcurrentAdaptiveRadixTree<VectorKey, u32>,
+    ) -> Vec<(Vec<u8>, u32)> {
+        let mut entries = Vec::new();
+        tree.range_for_each(.., |key, value| {
+            entries.push((key.as_ref().to_vec(), *value))
+        });
+        entries
+    }
+
+    #[test]
+    fn writers_on_std_threads() {
+        let tree = ConcurrentAdaptiveRadixTree::<VectorKey, u32>::new();
+        std::thread::scope(|scope| {
+            for t in 0..4u32 {
+                let tree = &tree;
+                scope.spawn(move || {
+                    for i in 0..200u32 {
+                        let key = VectorKey::new_from_slice(format!("{}{i}", t % 2).as_bytes());
+                        tree.insert_k(&key, t);
+                        if i % 3 == 0 {
+                            tree.remove_k(&key);
+                        }
+                    }
+                });
+            }
+        });
+        let mut expected: Vec<_> = (0..2u32)
+            .flat_map(|t| {
+                (0..200u32)
+                    .filter(|i| i % 3 != 0)
+                    .map(move |i| format!("{t}{i}"))
+            })
+            .collect();
+        expected.sort();
+        let keys: Vec<_> = entries(&tree)
+            .into_iter()
+            .map(|(key, _)| String::from_utf8(key).unwrap())
+            .collect();
+        assert_eq!(keys, expected);
+        assert_eq!(tree.len(), expected.len());
+    }
+
+    #[test]
+    fn writes_with_room_change_nodes_in_place() {
+        let tree = ConcurrentAdaptiveRadixTree::<VectorKey, u32>::new();
+        let key = |last: u8| VectorKey::new_from_slice(&[b'k', last]);
+        tree.insert_k(&key(0), 0);
+        tree.insert_k(&key(1), 1);
+        let root = tree.root.load(Ordering::SeqCst);
+
+        assert_eq!(tree.insert_k(&key(2), 2), None);
+        assert_eq!(tree.insert_k(&key(1), 10), Some(1));
+        assert_eq!(tree.remove_k(&key(2)), Some(2));
+        assert_eq!(tree.root.load(Ordering::SeqCst), root);
+        assert_eq!(
+            entries(&tree),
+            vec![(vec![b'k', 0], 0), (vec![b'k', 1], 10)]
+        );
+    }
+
+    #[test]
+    fn grows_and_shrinks_through_every_node_size() {
+        let tree = ConcurrentAdaptiveRadixTree::<VectorKey, u32>::new();
+        let key = |last: u8| VectorKey::new_from_slice(&[b'k', last]);
+        for i in 0..=255u8 {
+            assert_eq!(tree.insert_k(&key(i), u32::from(i)), None);
+            assert!((0..=i).all(|j| tree.get_k(&key(j)) == Some(u32::from(j))));
+        }
+        assert_eq!(tree.len(), 256);
+        for i in (0..=255u8).rev() {
+            assert_eq!(tree.remove_k(&key(i)), Some(u32::from(i)));
+            assert!((0..i).all(|j| tree.get_k(&key(j)) == Some(u32::from(j))));
+            assert_eq!(entries(&tree).len(), usize::from(i));
+        }
+        assert!(tree.is_empty());
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn prop_single_thread_matches_reference_model(
+            ops in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'c', 0..=4), proptest::option::of(any::<u32>())),
+                0..64
+            )
+        ) {
+            let tree = ConcurrentAdaptiveRadixTree::<VectorKey, u32>::new();
+            let mut map = BTreeMap::new();
+            for (key, value) in &ops {
+                let tree_key = VectorKey::new_from_slice(key);
+                match value {
+                    Some(value) => {
+                        assert_eq!(tree.insert_k(&tree_key, *value), map.insert(key.clone(), *value));
+                    }
+                    None => {
+                        assert_eq!(tree.remove_k(&tree_key), map.remove(key));
+                    }
+                }
+                assert_eq!(tree.get_k(&tree_key), map.get(key).copied());
+            }
+            assert_eq!(tree.len(), map.len());
+            assert_eq!(entries(&tree), map.into_iter().collect::<Vec<_>>());
+        }
+    }
+}
+
+#[cfg(all(test, shuttle))]
+mod shuttle_tests {
+    use super::*;
+    use crate::keys::array_key::ArrayKey;
+    use crate::keys::vector_key::VectorKey;
+    use shuttle::{Config, Runner, sync::Arc as ShuttleArc, thread};
+    use std::sync::atomic::{AtomicIsize, Ordering as StdOrdering};
+
+    fn run_dfs(f: impl Fn() + Send + Sync + 'static) {
+        let runner = Runner::new(
+            shuttle::scheduler::DfsScheduler::new(Some(1000), false),
+            Config::new(),
+        );
+        runner.run(f);
+    }
+
+    #[test]
+    fn shuttle_disjoint_writers_and_readers() {
+        run_dfs(|| {
+            let tree = ShuttleArc::new(ConcurrentAdaptiveRadixTree::<ArrayKey<16>, u32>::new());
+            let writers: Vec<_> = (0..2u64)
+                .map(|t| {
+                    let tree = ShuttleArc::clone(&tree);
+                    thread::spawn(move || {
+                        for i in 0..3u32 {
+                            assert_eq!(tree.insert(t * 10 + u64::from(i), i), None);
+                        }
+                    })
+                })
+                .collect();
+            let reader = {
+                let tree = ShuttleArc::clone(&tree);
+                thread::spawn(move || {
+                    // A key, once seen, keeps its value.
+                    let first = tree.get(1u64);
+                    if first.is_some() {
+                        assert_eq!(tree.get(1u64), first);
+                    }
+                    let mut last = None;
+                    tree.range_for_each(.., |key, _| {
+                        let key = key.to_be_u64();
+                        assert!(last < Some(key));
+                        last = Some(key);
+                    });
+                })
+            };
+            for handle in writers {
+                handle.join().unwrap();
+            }
+            reader.join().unwrap();
+
+            assert_eq!(tree.len(), 6);
+            for t in 0..2u64 {
+                for i in 0..3u32 {
+                    assert_eq!(tree.get(t * 10 + u64::from(i)), Some(i));
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn shuttle_writers_on_shared_prefixes() {
+        // Keys that are prefixes of each other force splits, merges, and value moves between
+        // inner nodes and leaves while both threads work on the same nodes.
+        run_dfs(|| {
+            let tree = ShuttleArc::new(ConcurrentAdaptiveRadixTree::<VectorKey, u32>::new());
+            tree.insert(VectorKey::new_from_slice(b"ab"), 0);
+            tree.insert(VectorKey::new_from_slice(b"abd"), 0);
+
+            let inserter = {
+                let tree = ShuttleArc::clone(&tree);
+                thread::spawn(move || {
+                    tree.insert(VectorKey::new_from_slice(b"a"), 1);
+                    tree.insert(VectorKey::new_from_slice(b"abc"), 1);
+                })
+            };
+            let remover = {
+                let tree = ShuttleArc::clone(&tree);
+                thread::spawn(move || {
+                    assert_eq!(tree.remove(VectorKey::new_from_slice(b"ab")), Some(0));
+                    assert_eq!(tree.remove(VectorKey::new_from_slice(b"abd")), Some(0));
+                })
+            };
+            inserter.join().unwrap();
+            remover.join().unwrap();
+
+            assert_eq!(
+                super::tests::entries(&tree),
+                vec![(b"a".to_vec(), 1), (b"abc".to_vec(), 1)]
+            );
+            assert_eq!(tree.len(), 2);
+        });
+    }
+
+    #[test]
+    fn shuttle_same_key_writers_do_not_lose_entries() {
+        run_dfs(|| {
+            let tree = ShuttleArc::new(ConcurrentAdaptiveRadixTree::<ArrayKey<16>, u32>::new());
+            tree.insert(1u32, 0);
+
+            let handles: Vec<_> = (1..=2u32)
+                .map(|t| {
+                    let tree = ShuttleArc::clone(&tree);
+                    thread::spawn(move || {
+                        let replaced = tree.insert(1u32, t);
+                        tree.insert(2u32 + t, t);
+                        replaced
+                    })
+                })
+                .collect();
+            let mut replaced: Vec<_> = handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap().unwrap())
+                .collect();
+            replaced.sort_unstable();
+
+            // The two inserts took effect one after the other.
+            let last = tree.get(1u32).unwrap();
+            assert!(replaced == vec![0, 3 - last]);
+            assert_eq!(tree.get(3u32), Some(1));
+            assert_eq!(tree.get(4u32), Some(2));
+            assert_eq!(tree.len(), 3);
+        });
+    }
+
+    #[test]
+    fn shuttle_writers_grow_and_shrink_a_shared_node() {
+        // Three children leave room for one more in place; the second insert grows the node
+        // while the other thread removes from it and a reader walks it.
+        run_dfs(|| {
+            let tree = ShuttleArc::new(ConcurrentAdaptiveRadixTree::<VectorKey, u32>::new());
+            let key = |last: u8| VectorKey::new_from_slice(&[b'k', last]);
+            for i in 0..3u8 {
+                tree.insert_k(&key(i), 0);
+            }
+
+            let inserter = {
+                let tree = ShuttleArc::clone(&tree);
+                thread::spawn(move || {
+                    tree.insert_k(&key(3), 1);
+                    tree.insert_k(&key(4), 1);
+                })
+            };
+            let remover = {
+                let tree = ShuttleArc::clone(&tree);
+                thread::spawn(move || assert_eq!(tree.remove_k(&key(0)), Some(0)))
+            };
+            let reader = {
+                let tree = ShuttleArc::clone(&tree);
+                thread::spawn(move || {
+                    assert_eq!(tree.get_k(&key(1)), Some(0));
+                    let keys: Vec<_> = super::tests::entries(&tree)
+                        .into_iter()
+                        .map(|(key, _)| key[1])
+                        .collect();
+                    assert!(keys.contains(&1) && keys.contains(&2));
+                })
+            };
+            inserter.join().unwrap();
+            remover.join().unwrap();
+            reader.join().unwrap();
+
+            let keys: Vec<_> = super::tests::entries(&tree)
+                .into_iter()
+                .map(|(key, _)| key[1])
+                .collect();
+            assert_eq!(keys, vec![1, 2, 3, 4]);
+            assert_eq!(tree.len(), 4);
+        });
+    }
+
+    /// Counts live clones, so leaks and double frees show up as a non-zero balance.
+    struct Tracked(ShuttleArc<AtomicIsize>);
+
+    impl Tracked {
+        fn new(live: &ShuttleArc<AtomicIsize>) -> Self {
+            live.fetch_add(1, StdOrdering::SeqCst);
+            Self(ShuttleArc::clone(live))
+        }
+    }
+
+    impl Clone for Tracked {
+        fn clone(&self) -> Self {
+            Self::new(&self.0)
+        }
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.0.fetch_sub(1, StdOrdering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn shuttle_reclaims_every_retired_node() {
+        run_dfs(|| {
+            let live = ShuttleArc::new(AtomicIsize::new(0));
+            {
+                let tree =
+                    ShuttleArc::new(ConcurrentAdaptiveRadixTree::<ArrayKey<16>, Tracked>::new());
+                let handles: Vec<_> = (0..2u32)
+                    .map(|t| {
+                        let tree = ShuttleArc::clone(&tree);
+                        let live = ShuttleArc::clone(&live);
+                        thread::spawn(move || {
+                            for i in 0..3u32 {
+                                tree.insert(i, Tracked::new(&live));
+                            }
+                            tree.insert(10 + t, Tracked::new(&live));
+                            assert!(tree.remove(10 + t).is_some());
+                            let _ = tree.get(2u32);
+                        })
+                    })
+                    .collect();
+                for handle in handles {
+                    handle.join().unwrap();
+                }
+                assert_eq!(tree.len(), 3);
+            }
+            assert_eq!(live.load(StdOrdering::SeqCst), 0);
+        });
+    }
+}
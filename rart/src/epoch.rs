@@ -0,0 +1,202 @@
+//! Epoch-based reclamation for nodes unlinked from concurrent structures.
+//!
+//! Readers pin the current epoch for as long as they may hold pointers into the structure. A
+//! writer that unlinks a node retires it under the epoch current at that point; it is freed
+//! once the epoch has advanced twice more. The epoch only advances past `e + 1` after every
+//! reader pinned at `e` has left, so a reader that could have seen the node before it was
+//! unlinked (and was therefore pinned at or before the retirement epoch) is gone by then.
+//!
+//! Two reader counters suffice because a pinned reader holds the epoch within one step of its
+//! own: counter `e & 1` is shared by epochs `e` and `e + 2`, and the epoch cannot reach `e + 2`
+//! while anyone is counted there.
+
+use crate::sync::{AtomicUsize, Mutex, MutexGuard, Ordering, yield_now};
+
+pub(crate) struct Epochs {
+    epoch: AtomicUsize,
+    readers: [AtomicUsize; 2],
+}
+
+/// Keeps the epoch it was pinned at from advancing by more than one step until dropped.
+pub(crate) struct EpochGuard<'a> {
+    epochs: &'a Epochs,
+    slot: usize,
+}
+
+impl Epochs {
+    pub(crate) fn new() -> Self {
+        Self {
+            epoch: AtomicUsize::new(0),
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+        }
+    }
+
+    pub(crate) fn pin(&self) -> EpochGuard<'_> {
+        loop {
+            let epoch = self.epoch.load(Ordering::SeqCst);
+            let slot = epoch & 1;
+            self.readers[slot].fetch_add(1, Ordering::SeqCst);
+            // Counting ourselves in an epoch that has already moved on would not hold it back.
+            if self.epoch.load(Ordering::SeqCst) == epoch {
+                return EpochGuard { epochs: self, slot };
+            }
+            self.readers[slot].fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    pub(crate) fn current(&self) -> usize {
+        self.epoch.load(Ordering::SeqCst)
+    }
+
+    /// Advance the epoch if no reader is still pinned at the previous one, returning the
+    /// epoch afterwards.
+    pub(crate) fn try_advance(&self) -> usize {
+        let epoch = self.epoch.load(Ordering::SeqCst);
+        if self.readers[(epoch + 1) & 1].load(Ordering::SeqCst) != 0 {
+            return epoch;
+        }
+        match self
+            .epoch
+            .compare_exchange(epoch, epoch + 1, Ordering::SeqCst, Ordering::SeqCst)
+        {
+            Ok(_) => epoch + 1,
+            Err(current) => current,
+        }
+    }
+
+    /// Wait until every reader pinned before the call has unpinned, by advancing the epoch
+    /// twice. Readers that pin meanwhile do not hold this up.
+    ///
+    /// The caller must not be pinned itself.
+    pub(crate) fn synchronize(&self) {
+        let target = self.current() + 2;
+        while self.try_advance() < target {
+            yield_now();
+        }
+    }
+}
+
+impl Drop for EpochGuard<'_> {
+    fn drop(&mut self) {
+        self.epochs.readers[self.slot].fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// How many items are retired between attempts to advance the epoch and free what it allows.
+const COLLECT_EVERY: usize = 64;
+
+/// Retired items waiting for the epoch to move past them, in one bag per epoch modulo three.
+///
+/// An item retired at epoch `e` can go once the epoch reaches `e + 2`, so at epoch `e` the bags
+/// of `e` and `e - 1` are still filling or waiting, and the third holds only items that can be
+/// freed. Each bag is tagged with the epoch it collects; a retirement that finds an older tag
+/// empties the bag first. Writers only push under a bag's lock, and every few dozen retired
+/// items one of them advances the epoch and frees the expired bag after unlocking it.
+pub(crate) struct Garbage<T> {
+    bags: [Mutex<Bag<T>>; 3],
+    retired: AtomicUsize,
+}
+
+struct Bag<T> {
+    epoch: usize,
+    items: Vec<T>,
+}
+
+impl<T> Garbage<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            bags: [0, 1, 2].map(|epoch| {
+                Mutex::new(Bag {
+                    epoch,
+                    items: Vec::new(),
+                })
+            }),
+            retired: AtomicUsize::new(0),
+        }
+    }
+
+    /// Retire `retired`, which must already be unreachable for readers that pin from now on,
+    /// and hand items that no pinned reader can still see to `free`.
+    ///
+    /// The caller must not be pinned itself, or nothing it retired recently can be freed.
+    pub(crate) fn retire(
+        &self,
+        epochs: &Epochs,
+        retired: impl IntoIterator<Item = T>,
+        mut free: impl FnMut(T),
+    ) {
+        let epoch = epochs.current();
+        let (mut expired, count) = {
+            let mut bag = self.lock_bag(epoch);
+            // A bag tagged three or more epochs back holds nothing a reader can still see.
+            let expired = if bag.epoch < epoch {
+                bag.epoch = epoch;
+                std::mem::take(&mut bag.items)
+            } else {
+                Vec::new()
+            };
+            let before = bag.items.len();
+            bag.items.extend(retired);
+            (expired, bag.items.len() - before)
+        };
+
+        expired.drain(..).for_each(&mut free);
+
+        let total = self.retired.fetch_add(count, Ordering::SeqCst) + count;
+        if total / COLLECT_EVERY != (total - count) / COLLECT_EVERY {
+            // Items retired a step ago can go once the epoch moves twice; try both steps now.
+            epochs.try_advance();
+            let now = epochs.try_advance();
+            let mut bag = self.lock_bag(now + 1);
+            if bag.epoch + 2 <= now {
+                std::mem::swap(&mut expired, &mut bag.items);
+            }
+            drop(bag);
+            expired.into_iter().for_each(free);
+        }
+    }
+
+    fn lock_bag(&self, epoch: usize) -> MutexGuard<'_, Bag<T>> {
+        self.bags[epoch % 3]
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Everything still retired, for freeing once no readers remain.
+    pub(crate) fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        self.bags.iter_mut().flat_map(|bag| {
+            bag.get_mut()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .items
+                .drain(..)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn garbage_waits_for_pinned_readers() {
+        let epochs = Epochs::new();
+        let mut garbage = Garbage::new();
+        let mut freed = Vec::new();
+
+        let guard = epochs.pin();
+        garbage.retire(&epochs, 0..COLLECT_EVERY, |item| freed.push(item));
+        assert!(freed.is_empty());
+        drop(guard);
+
+        // Once the reader is gone, later retirements free the first batch.
+        for round in 1..4 {
+            let items = round * COLLECT_EVERY..(round + 1) * COLLECT_EVERY;
+            garbage.retire(&epochs, items, |item| freed.push(item));
+        }
+        assert!((0..COLLECT_EVERY).all(|item| freed.contains(&item)));
+
+        freed.extend(garbage.drain());
+        freed.sort_unstable();
+        assert_eq!(freed, (0..4 * COLLECT_EVERY).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,941 @@
+//! Paired-descent joins shared by every tree representation.
+//!
+//! Intersections and differences walk two tries in lockstep, matching prefix bytes and
+//! following only the child edges present on the side that decides the result. The walk only
+//! needs read access to prefixes, values and children, so it is written once against
+//! [`NodeView`] and reused by `AdaptiveRadixTree`, `VersionedAdaptiveRadixTree`, and joins
+//! that mix the two.
+//!
+//! Each node is visited together with an offset into its prefix, so two nodes whose prefix
+//! compression boundaries differ can still be compared at the same key depth. A child's prefix
+//! starts with the edge byte that leads to it.
+
+use crate::Side;
+use crate::iter::LendingKeyView;
+use crate::keys::KeyTrait;
+use crate::partials::prefix_length_bytes;
+use crate::range::ByteRange;
+use crate::utils::bitset::Bitset64;
+
+/// Read-only access to a trie node, as needed by the paired-descent joins.
+pub(crate) trait NodeView {
+    type Value;
+
+    fn view_prefix(&self) -> &[u8];
+    fn view_value(&self) -> Option<&Self::Value>;
+    fn view_num_children(&self) -> usize;
+    fn view_child(&self, edge: u8) -> Option<&Self>;
+    /// Children in ascending edge order.
+    fn view_children(&self) -> impl Iterator<Item = (u8, &Self)>;
+    /// The set of edge bytes this node has children under.
+    fn view_child_key_set(&self) -> Bitset64<4>;
+}
+
+/// Recursively intersect two nodes, reporting each shared key with both values.
+pub(crate) fn intersect<'l, 'r, L, R, F>(
+    left: &'l L,
+    mut left_offset: usize,
+    right: &'r R,
+    mut right_offset: usize,
+    key_buf: &mut Vec<u8>,
+    on_match: &mut F,
+) where
+    L: NodeView,
+    R: NodeView,
+    F: FnMut(&[u8], &'l L::Value, &'r R::Value),
+{
+    let restore_len = key_buf.len();
+    let left_prefix = left.view_prefix();
+    let right_prefix = right.view_prefix();
+
+    while left_offset < left_prefix.len() && right_offset < right_prefix.len() {
+        let left_byte = left_prefix[left_offset];
+        if left_byte != right_prefix[right_offset] {
+            key_buf.truncate(restore_len);
+            return;
+        }
+        key_buf.push(left_byte);
+        left_offset += 1;
+        right_offset += 1;
+    }
+
+    // The remaining byte in the longer prefix must transition through a matching
+    // child edge in the shorter side to continue.
+    if left_offset < left_prefix.len() {
+        let edge = left_prefix[left_offset];
+        if let Some(right_child) = right.view_child(edge) {
+            key_buf.push(edge);
+            intersect(left, left_offset + 1, right_child, 1, key_buf, on_match);
+        }
+        key_buf.truncate(restore_len);
+        return;
+    }
+
+    if right_offset < right_prefix.len() {
+        let edge = right_prefix[right_offset];
+        if let Some(left_child) = left.view_child(edge) {
+            key_buf.push(edge);
+            intersect(left_child, 1, right, right_offset + 1, key_buf, on_match);
+        }
+        key_buf.truncate(restore_len);
+        return;
+    }
+
+    if let (Some(left_value), Some(right_value)) = (left.view_value(), right.view_value()) {
+        on_match(key_buf, left_value, right_value);
+    }
+
+    if left.view_num_children() <= right.view_num_children() {
+        for (edge, left_child) in left.view_children() {
+            if let Some(right_child) = right.view_child(edge) {
+                intersect(left_child, 0, right_child, 0, key_buf, on_match);
+            }
+        }
+    } else {
+        for (edge, right_child) in right.view_children() {
+            if let Some(left_child) = left.view_child(edge) {
+                intersect(left_child, 0, right_child, 0, key_buf, on_match);
+            }
+        }
+    }
+
+    key_buf.truncate(restore_len);
+}
+
+/// Recursively intersect two nodes and emit only value pairs (no key reconstruction).
+pub(crate) fn intersect_values<'l, 'r, L, R, F>(
+    left: &'l L,
+    mut left_offset: usize,
+    right: &'r R,
+    mut right_offset: usize,
+    on_match: &mut F,
+) where
+    L: NodeView,
+    R: NodeView,
+    F: FnMut(&'l L::Value, &'r R::Value),
+{
+    let left_prefix = left.view_prefix();
+    let right_prefix = right.view_prefix();
+
+    while left_offset < left_prefix.len() && right_offset < right_prefix.len() {
+        if left_prefix[left_offset] != right_prefix[right_offset] {
+            return;
+        }
+        left_offset += 1;
+        right_offset += 1;
+    }
+
+    if left_offset < left_prefix.len() {
+        if let Some(right_child) = right.view_child(left_prefix[left_offset]) {
+            intersect_values(left, left_offset + 1, right_child, 1, on_match);
+        }
+        return;
+    }
+
+    if right_offset < right_prefix.len() {
+        if let Some(left_child) = left.view_child(right_prefix[right_offset]) {
+            intersect_values(left_child, 1, right, right_offset + 1, on_match);
+        }
+        return;
+    }
+
+    if let (Some(left_value), Some(right_value)) = (left.view_value(), right.view_value()) {
+        on_match(left_value, right_value);
+    }
+
+    if left.view_num_children() <= right.view_num_children() {
+        for (edge, left_child) in left.view_children() {
+            if let Some(right_child) = right.view_child(edge) {
+                intersect_values(left_child, 0, right_child, 0, on_match);
+            }
+        }
+    } else {
+        for (edge, right_child) in right.view_children() {
+            if let Some(left_child) = left.view_child(edge) {
+                intersect_values(left_child, 0, right_child, 0, on_match);
+            }
+        }
+    }
+}
+
+/// Recursively intersect two nodes like [`intersect`], lending each shared key as a view over
+/// the matched prefix segments instead of copying it into a buffer.
+pub(crate) fn intersect_lending<'a, L, R, F>(
+    left: &'a L,
+    mut left_offset: usize,
+    right: &'a R,
+    mut right_offset: usize,
+    key_segments: &mut Vec<&'a [u8]>,
+    key_len: &mut usize,
+    on_match: &mut F,
+) where
+    L: NodeView,
+    R: NodeView,
+    F: for<'view> FnMut(LendingKeyView<'a, 'view>, &'a L::Value, &'a R::Value),
+{
+    let restore_segments = key_segments.len();
+    let restore_len = *key_len;
+    let left_prefix = left.view_prefix();
+    let right_prefix = right.view_prefix();
+    let matched_left_start = left_offset;
+
+    while left_offset < left_prefix.len() && right_offset < right_prefix.len() {
+        if left_prefix[left_offset] != right_prefix[right_offset] {
+            return;
+        }
+        left_offset += 1;
+        right_offset += 1;
+    }
+
+    if left_offset > matched_left_start {
+        let matched = &left_prefix[matched_left_start..left_offset];
+        key_segments.push(matched);
+        *key_len += matched.len();
+    }
+
+    if left_offset < left_prefix.len() {
+        if let Some(right_child) = right.view_child(left_prefix[left_offset]) {
+            key_segments.push(&left_prefix[left_offset..=left_offset]);
+            *key_len += 1;
+            intersect_lending(
+                left,
+                left_offset + 1,
+                right_child,
+                1,
+                key_segments,
+                key_len,
+                on_match,
+            );
+        }
+    } else if right_offset < right_prefix.len() {
+        if let Some(left_child) = left.view_child(right_prefix[right_offset]) {
+            key_segments.push(&right_prefix[right_offset..=right_offset]);
+            *key_len += 1;
+            intersect_lending(
+                left_child,
+                1,
+                right,
+                right_offset + 1,
+                key_segments,
+                key_len,
+                on_match,
+            );
+        }
+    } else {
+        if let (Some(left_value), Some(right_value)) = (left.view_value(), right.view_value()) {
+            on_match(
+                LendingKeyView::new(key_segments, *key_len),
+                left_value,
+                right_value,
+            );
+        }
+
+        if left.view_num_children() <= right.view_num_children() {
+            for (edge, left_child) in left.view_children() {
+                if let Some(right_child) = right.view_child(edge) {
+                    intersect_lending(
+                        left_child,
+                        0,
+                        right_child,
+                        0,
+                        key_segments,
+                        key_len,
+                        on_match,
+                    );
+                }
+            }
+        } else {
+            for (edge, right_child) in right.view_children() {
+                if let Some(left_child) = left.view_child(edge) {
+                    intersect_lending(
+                        left_child,
+                        0,
+                        right_child,
+                        0,
+                        key_segments,
+                        key_len,
+                        on_match,
+                    );
+                }
+            }
+        }
+    }
+
+    key_segments.truncate(restore_segments);
+    *key_len = restore_len;
+}
+
+/// Recursively intersect any number of nodes that sit at the same depth, lending each key
+/// present in all of them. Each cursor is a node and how far into its prefix the traversal
+/// has already matched.
+pub(crate) fn intersect_many<'a, N, F>(
+    cursors: &[(&'a N, usize)],
+    key_segments: &mut Vec<&'a [u8]>,
+    key_len: &mut usize,
+    values: &mut Vec<&'a N::Value>,
+    on_match: &mut F,
+) where
+    N: NodeView,
+    F: for<'view> FnMut(LendingKeyView<'a, 'view>, &[&'a N::Value]),
+{
+    let restore_segments = key_segments.len();
+    let restore_len = *key_len;
+
+    // Match the prefix bytes that every cursor still has to consume.
+    let (lead, lead_offset) = cursors[0];
+    let lead_rest = &lead.view_prefix()[lead_offset..];
+    let mut matched = lead_rest.len();
+    let mut shortest = lead_rest.len();
+    for &(node, offset) in &cursors[1..] {
+        let rest = &node.view_prefix()[offset..];
+        matched = matched.min(prefix_length_bytes(lead_rest, rest));
+        shortest = shortest.min(rest.len());
+    }
+    if matched < shortest {
+        return;
+    }
+    if matched > 0 {
+        key_segments.push(&lead_rest[..matched]);
+        *key_len += matched;
+    }
+
+    // Some prefixes continue past the shortest one. Their next byte is the only edge the
+    // cursors that reached a node boundary can follow.
+    let mut forced_edge = None;
+    for &(node, offset) in cursors {
+        if let Some(&edge) = node.view_prefix().get(offset + matched) {
+            if forced_edge.is_some_and(|forced| forced != edge) {
+                key_segments.truncate(restore_segments);
+                *key_len = restore_len;
+                return;
+            }
+            forced_edge = Some(edge);
+        }
+    }
+
+    let mut next = Vec::with_capacity(cursors.len());
+    if let Some(edge) = forced_edge {
+        for &(node, offset) in cursors {
+            if offset + matched < node.view_prefix().len() {
+                next.push((node, offset + matched));
+            } else if let Some(child) = node.view_child(edge) {
+                next.push((child, 0));
+            } else {
+                key_segments.truncate(restore_segments);
+                *key_len = restore_len;
+                return;
+            }
+        }
+        intersect_many(&next, key_segments, key_len, values, on_match);
+        key_segments.truncate(restore_segments);
+        *key_len = restore_len;
+        return;
+    }
+
+    values.clear();
+    values.extend(cursors.iter().map_while(|(node, _)| node.view_value()));
+    if values.len() == cursors.len() {
+        on_match(LendingKeyView::new(key_segments, *key_len), values);
+    }
+
+    let mut edges = lead.view_child_key_set();
+    for (node, _) in &cursors[1..] {
+        edges.intersect_with(&node.view_child_key_set());
+    }
+    for edge in edges.iter() {
+        next.clear();
+        for (node, _) in cursors {
+            let child = node
+                .view_child(edge as u8)
+                .expect("edge is in every node's child key set");
+            next.push((child, 0));
+        }
+        intersect_many(&next, key_segments, key_len, values, on_match);
+    }
+
+    key_segments.truncate(restore_segments);
+    *key_len = restore_len;
+}
+
+/// Recursively intersect two nodes like [`intersect`], skipping subtrees whose keys all fall
+/// outside `range`.
+pub(crate) fn intersect_in_range<'l, 'r, L, R, F>(
+    left: &'l L,
+    mut left_offset: usize,
+    right: &'r R,
+    mut right_offset: usize,
+    range: &ByteRange<'_>,
+    key_buf: &mut Vec<u8>,
+    on_match: &mut F,
+) where
+    L: NodeView,
+    R: NodeView,
+    F: FnMut(&[u8], &'l L::Value, &'r R::Value),
+{
+    let restore_len = key_buf.len();
+    let left_prefix = left.view_prefix();
+    let right_prefix = right.view_prefix();
+
+    while left_offset < left_prefix.len() && right_offset < right_prefix.len() {
+        let left_byte = left_prefix[left_offset];
+        if left_byte != right_prefix[right_offset] {
+            key_buf.truncate(restore_len);
+            return;
+        }
+        key_buf.push(left_byte);
+        left_offset += 1;
+        right_offset += 1;
+    }
+
+    if !range.may_contain_prefixed(key_buf) {
+        key_buf.truncate(restore_len);
+        return;
+    }
+
+    if left_offset < left_prefix.len() {
+        let edge = left_prefix[left_offset];
+        if let Some(right_child) = right.view_child(edge) {
+            key_buf.push(edge);
+            intersect_in_range(
+                left,
+                left_offset + 1,
+                right_child,
+                1,
+                range,
+                key_buf,
+                on_match,
+            );
+        }
+        key_buf.truncate(restore_len);
+        return;
+    }
+
+    if right_offset < right_prefix.len() {
+        let edge = right_prefix[right_offset];
+        if let Some(left_child) = left.view_child(edge) {
+            key_buf.push(edge);
+            intersect_in_range(
+                left_child,
+                1,
+                right,
+                right_offset + 1,
+                range,
+                key_buf,
+                on_match,
+            );
+        }
+        key_buf.truncate(restore_len);
+        return;
+    }
+
+    if let (Some(left_value), Some(right_value)) = (left.view_value(), right.view_value())
+        && range.contains(key_buf)
+    {
+        on_match(key_buf, left_value, right_value);
+    }
+
+    for (edge, left_child) in left.view_children() {
+        if let Some(right_child) = right.view_child(edge) {
+            intersect_in_range(left_child, 0, right_child, 0, range, key_buf, on_match);
+        }
+    }
+
+    key_buf.truncate(restore_len);
+}
+
+/// An entry that a [`difference`] walk found on only one side.
+pub(crate) enum Only<L, R> {
+    Left(L),
+    Right(R),
+}
+
+impl<T> From<Only<T, T>> for Side<T> {
+    fn from(only: Only<T, T>) -> Self {
+        match only {
+            Only::Left(value) => Side::Left(value),
+            Only::Right(value) => Side::Right(value),
+        }
+    }
+}
+
+/// Recursively report every entry of `left` that `right` does not contain and, when
+/// `include_right` is set, every entry of `right` that `left` does not contain, in key order.
+/// Subtrees with no counterpart on the other side are reported whole without further
+/// comparison.
+pub(crate) fn difference<'l, 'r, L, R, F>(
+    left: &'l L,
+    mut left_offset: usize,
+    right: &'r R,
+    mut right_offset: usize,
+    include_right: bool,
+    key_buf: &mut Vec<u8>,
+    on_entry: &mut F,
+) where
+    L: NodeView,
+    R: NodeView,
+    F: FnMut(&[u8], Only<&'l L::Value, &'r R::Value>),
+{
+    let restore_len = key_buf.len();
+    let left_prefix = left.view_prefix();
+    let right_prefix = right.view_prefix();
+
+    while left_offset < left_prefix.len() && right_offset < right_prefix.len() {
+        let left_byte = left_prefix[left_offset];
+        let right_byte = right_prefix[right_offset];
+        if left_byte != right_byte {
+            // The prefixes diverge, so neither subtree has any key on the other side.
+            if include_right && right_byte < left_byte {
+                for_each_from(right, right_offset, key_buf, &mut |key, value| {
+                    on_entry(key, Only::Right(value))
+                });
+            }
+            for_each_from(left, left_offset, key_buf, &mut |key, value| {
+                on_entry(key, Only::Left(value))
+            });
+            if include_right && right_byte > left_byte {
+                for_each_from(right, right_offset, key_buf, &mut |key, value| {
+                    on_entry(key, Only::Right(value))
+                });
+            }
+            key_buf.truncate(restore_len);
+            return;
+        }
+        key_buf.push(left_byte);
+        left_offset += 1;
+        right_offset += 1;
+    }
+
+    let (left_value, left_children) = side_at(left, left_offset);
+    let (right_value, right_children) = side_at(right, right_offset);
+    match (left_value, right_value) {
+        (Some(value), None) => on_entry(key_buf, Only::Left(value)),
+        (None, Some(value)) if include_right => on_entry(key_buf, Only::Right(value)),
+        _ => {}
+    }
+
+    let mut left_children = left_children.into_iter().peekable();
+    let mut right_children = right_children.into_iter().peekable();
+    loop {
+        match (left_children.peek(), right_children.peek()) {
+            (None, None) => break,
+            (None, Some(_)) if !include_right => break,
+            (Some(l), Some(r)) if l.0 == r.0 => {
+                let (_, left_child, left_child_offset) = left_children.next().unwrap();
+                let (_, right_child, right_child_offset) = right_children.next().unwrap();
+                difference(
+                    left_child,
+                    left_child_offset,
+                    right_child,
+                    right_child_offset,
+                    include_right,
+                    key_buf,
+                    on_entry,
+                );
+            }
+            (Some(l), r) if r.is_none_or(|r| l.0 < r.0) => {
+                let (_, child, offset) = left_children.next().unwrap();
+                for_each_from(child, offset, key_buf, &mut |key, value| {
+                    on_entry(key, Only::Left(value))
+                });
+            }
+            _ => {
+                let (_, child, offset) = right_children.next().unwrap();
+                if include_right {
+                    for_each_from(child, offset, key_buf, &mut |key, value| {
+                        on_entry(key, Only::Right(value))
+                    });
+                }
+            }
+        }
+    }
+
+    key_buf.truncate(restore_len);
+}
+
+/// Report every entry below `node`, starting `offset` bytes into its prefix.
+pub(crate) fn for_each_from<'n, N, F>(
+    node: &'n N,
+    offset: usize,
+    key_buf: &mut Vec<u8>,
+    on_entry: &mut F,
+) where
+    N: NodeView,
+    F: FnMut(&[u8], &'n N::Value),
+{
+    let restore_len = key_buf.len();
+    key_buf.extend_from_slice(&node.view_prefix()[offset..]);
+    if let Some(value) = node.view_value() {
+        on_entry(key_buf, value);
+    }
+    for (_, child) in node.view_children() {
+        for_each_from(child, 0, key_buf, on_entry);
+    }
+    key_buf.truncate(restore_len);
+}
+
+/// A set of keys a paired descent can be limited to.
+pub(crate) trait KeySpan {
+    /// Whether any key starting with `path` can be in the span.
+    fn may_contain_prefixed(&self, path: &[u8]) -> bool;
+    fn contains(&self, key: &[u8]) -> bool;
+}
+
+impl KeySpan for ByteRange<'_> {
+    fn may_contain_prefixed(&self, path: &[u8]) -> bool {
+        ByteRange::may_contain_prefixed(self, path)
+    }
+
+    fn contains(&self, key: &[u8]) -> bool {
+        ByteRange::contains(self, key)
+    }
+}
+
+type SideAt<'n, N> = (Option<&'n <N as NodeView>::Value>, Vec<(u8, &'n N, usize)>);
+
+/// The value and children of `node` as seen from `offset` bytes into its prefix. A node whose
+/// prefix continues past `offset` has no value there and is its own only child.
+fn side_at<N: NodeView>(node: &N, offset: usize) -> SideAt<'_, N> {
+    let prefix = node.view_prefix();
+    if offset < prefix.len() {
+        return (None, vec![(prefix[offset], node, offset)]);
+    }
+    let children = node
+        .view_children()
+        .map(|(edge, child)| (edge, child, 0))
+        .collect();
+    (node.view_value(), children)
+}
+
+/// Whether two versions of a trie, `older` and `newer`, each a node and an offset into its
+/// prefix at the same depth, may hold different entries inside `span`.
+///
+/// Subtrees that are the same node in both versions are skipped without being visited. The
+/// answer is conservative: a key whose node was rewritten in `newer` counts as changed even
+/// if the value it holds compares equal.
+pub(crate) fn changed_within<N, S>(
+    older: Option<(&N, usize)>,
+    newer: Option<(&N, usize)>,
+    span: &S,
+    key_buf: &mut Vec<u8>,
+) -> bool
+where
+    N: NodeView,
+    S: KeySpan,
+{
+    let (lead, lead_offset) = match (older, newer) {
+        (None, None) => return false,
+        (Some((older, older_offset)), Some((newer, newer_offset)))
+            if older_offset == newer_offset && std::ptr::eq(older, newer) =>
+        {
+            return false;
+        }
+        (Some(side), _) | (None, Some(side)) => side,
+    };
+    let lead_rest = &lead.view_prefix()[lead_offset..];
+    let common = [older, newer]
+        .into_iter()
+        .flatten()
+        .map(|(node, offset)| prefix_length_bytes(lead_rest, &node.view_prefix()[offset..]))
+        .min()
+        .unwrap_or(0);
+
+    let restore_len = key_buf.len();
+    key_buf.extend_from_slice(&lead_rest[..common]);
+    if !span.may_contain_prefixed(key_buf) {
+        key_buf.truncate(restore_len);
+        return false;
+    }
+
+    let [(older_value, older_children), (newer_value, newer_children)] =
+        [older, newer].map(|side| {
+            side.map_or((None, Vec::new()), |(node, offset)| {
+                side_at(node, offset + common)
+            })
+        });
+    // The two nodes differ, so a value on either side counts as a change.
+    if (older_value.is_some() || newer_value.is_some()) && span.contains(key_buf) {
+        key_buf.truncate(restore_len);
+        return true;
+    }
+
+    let mut older_children = older_children.into_iter().peekable();
+    let mut newer_children = newer_children.into_iter().peekable();
+    let mut changed = false;
+    while !changed {
+        let edge = match (older_children.peek(), newer_children.peek()) {
+            (Some((older_edge, _, _)), Some((newer_edge, _, _))) => (*older_edge).min(*newer_edge),
+            (Some((edge, _, _)), None) | (None, Some((edge, _, _))) => *edge,
+            (None, None) => break,
+        };
+        let older_child = older_children
+            .next_if(|(child_edge, _, _)| *child_edge == edge)
+            .map(|(_, child, offset)| (child, offset));
+        let newer_child = newer_children
+            .next_if(|(child_edge, _, _)| *child_edge == edge)
+            .map(|(_, child, offset)| (child, offset));
+        changed = changed_within(older_child, newer_child, span, key_buf);
+    }
+    key_buf.truncate(restore_len);
+    changed
+}
+
+/// Recursively report, for every key of `left`, each entry of `right` whose key is a prefix
+/// of it. `passed` holds the key lengths and values of the right-side entries already passed
+/// on the way down; they are prefixes of every left key below this point.
+pub(crate) fn prefix_join<'l, 'r, L, R, F>(
+    left: &'l L,
+    mut left_offset: usize,
+    right: &'r R,
+    mut right_offset: usize,
+    key_buf: &mut Vec<u8>,
+    passed: &mut Vec<(usize, &'r R::Value)>,
+    on_match: &mut F,
+) where
+    L: NodeView,
+    R: NodeView,
+    F: FnMut(&[u8], &[u8], &'l L::Value, &'r R::Value),
+{
+    let restore_len = key_buf.len();
+    let restore_passed = passed.len();
+    let left_prefix = left.view_prefix();
+    let right_prefix = right.view_prefix();
+
+    while left_offset < left_prefix.len() && right_offset < right_prefix.len() {
+        let left_byte = left_prefix[left_offset];
+        if left_byte != right_prefix[right_offset] {
+            for_each_under_passed(left, left_offset, key_buf, passed, on_match);
+            key_buf.truncate(restore_len);
+            return;
+        }
+        key_buf.push(left_byte);
+        left_offset += 1;
+        right_offset += 1;
+    }
+
+    if right_offset == right_prefix.len()
+        && let Some(value) = right.view_value()
+    {
+        passed.push((key_buf.len(), value));
+    }
+
+    if left_offset < left_prefix.len() {
+        let edge = left_prefix[left_offset];
+        match right.view_child(edge) {
+            Some(right_child) => {
+                key_buf.push(edge);
+                prefix_join(
+                    left,
+                    left_offset + 1,
+                    right_child,
+                    1,
+                    key_buf,
+                    passed,
+                    on_match,
+                );
+            }
+            None => for_each_under_passed(left, left_offset, key_buf, passed, on_match),
+        }
+        passed.truncate(restore_passed);
+        key_buf.truncate(restore_len);
+        return;
+    }
+
+    if let Some(value) = left.view_value() {
+        for (len, prefix_value) in passed.iter() {
+            on_match(key_buf, &key_buf[..*len], value, prefix_value);
+        }
+    }
+
+    let matched_len = key_buf.len();
+    for (edge, left_child) in left.view_children() {
+        let right_next = if right_offset < right_prefix.len() {
+            (right_prefix[right_offset] == edge).then_some((right, right_offset + 1))
+        } else {
+            right.view_child(edge).map(|right_child| (right_child, 1))
+        };
+        match right_next {
+            Some((right_next, right_next_offset)) => {
+                key_buf.push(edge);
+                prefix_join(
+                    left_child,
+                    1,
+                    right_next,
+                    right_next_offset,
+                    key_buf,
+                    passed,
+                    on_match,
+                );
+                key_buf.truncate(matched_len);
+            }
+            None => for_each_under_passed(left_child, 0, key_buf, passed, on_match),
+        }
+    }
+
+    passed.truncate(restore_passed);
+    key_buf.truncate(restore_len);
+}
+
+/// Pair every entry below `node` with each of the `passed` right-side prefixes.
+fn for_each_under_passed<'l, 'r, L, V, F>(
+    node: &'l L,
+    offset: usize,
+    key_buf: &mut Vec<u8>,
+    passed: &[(usize, &'r V)],
+    on_match: &mut F,
+) where
+    L: NodeView,
+    F: FnMut(&[u8], &[u8], &'l L::Value, &'r V),
+{
+    if passed.is_empty() {
+        return;
+    }
+    for_each_from(node, offset, key_buf, &mut |key, value| {
+        for (len, prefix_value) in passed {
+            on_match(key, &key[..*len], value, prefix_value);
+        }
+    });
+}
+
+/// A pending node visit for [`FilterJoinIter`]. `right` is `None` once the other tree has no
+/// counterpart for the subtree.
+struct FilterFrame<'l, 'r, L, R> {
+    left: &'l L,
+    left_offset: usize,
+    right: Option<(&'r R, usize)>,
+    key_len: usize,
+}
+
+/// Iterates the entries of one tree whose keys do (semi-join) or do not (anti-join) appear in
+/// another. The walk descends both tries together, so subtrees without a counterpart on the
+/// other side are skipped or yielded whole without further comparison.
+pub(crate) struct FilterJoinIter<'l, 'r, K, L, R> {
+    stack: Vec<FilterFrame<'l, 'r, L, R>>,
+    key_buf: Vec<u8>,
+    keep_present: bool,
+    _phantom: std::marker::PhantomData<K>,
+}
+
+impl<'l, 'r, K, L, R> FilterJoinIter<'l, 'r, K, L, R>
+where
+    K: KeyTrait,
+    L: NodeView,
+    R: NodeView,
+{
+    pub(crate) fn new(left: Option<&'l L>, right: Option<&'r R>, keep_present: bool) -> Self {
+        let stack = left
+            .map(|left| FilterFrame {
+                left,
+                left_offset: 0,
+                right: right.map(|right| (right, 0)),
+                key_len: 0,
+            })
+            .into_iter()
+            .collect();
+        Self {
+            stack,
+            key_buf: Vec::with_capacity(K::MAXIMUM_SIZE.unwrap_or(64)),
+            keep_present,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    fn visit(&mut self, frame: FilterFrame<'l, 'r, L, R>) -> Option<(K, &'l L::Value)> {
+        let left = frame.left;
+        let mut left_offset = frame.left_offset;
+        let Some((right, mut right_offset)) = frame.right else {
+            return self.visit_unmatched(left, left_offset);
+        };
+        let left_prefix = left.view_prefix();
+        let right_prefix = right.view_prefix();
+
+        while left_offset < left_prefix.len() && right_offset < right_prefix.len() {
+            let left_byte = left_prefix[left_offset];
+            if left_byte != right_prefix[right_offset] {
+                return self.visit_unmatched(left, left_offset);
+            }
+            self.key_buf.push(left_byte);
+            left_offset += 1;
+            right_offset += 1;
+        }
+
+        if left_offset < left_prefix.len() {
+            return match right.view_child(left_prefix[left_offset]) {
+                Some(right_child) => {
+                    self.stack.push(FilterFrame {
+                        left,
+                        left_offset,
+                        right: Some((right_child, 0)),
+                        key_len: self.key_buf.len(),
+                    });
+                    None
+                }
+                None => self.visit_unmatched(left, left_offset),
+            };
+        }
+
+        let key_len = self.key_buf.len();
+        let start = self.stack.len();
+        for (edge, left_child) in left.view_children() {
+            let right_next = if right_offset < right_prefix.len() {
+                (right_prefix[right_offset] == edge).then_some((right, right_offset))
+            } else {
+                right.view_child(edge).map(|right_child| (right_child, 0))
+            };
+            if right_next.is_none() && self.keep_present {
+                continue;
+            }
+            self.stack.push(FilterFrame {
+                left: left_child,
+                left_offset: 0,
+                right: right_next,
+                key_len,
+            });
+        }
+        self.stack[start..].reverse();
+
+        let value = left.view_value()?;
+        let right_has_value = right_offset == right_prefix.len() && right.view_value().is_some();
+        (right_has_value == self.keep_present).then(|| (K::new_from_slice(&self.key_buf), value))
+    }
+
+    /// Visit a subtree the other tree has no keys in.
+    fn visit_unmatched(&mut self, left: &'l L, left_offset: usize) -> Option<(K, &'l L::Value)> {
+        if self.keep_present {
+            return None;
+        }
+        self.key_buf
+            .extend_from_slice(&left.view_prefix()[left_offset..]);
+        let key_len = self.key_buf.len();
+        let start = self.stack.len();
+        self.stack
+            .extend(left.view_children().map(|(_, left_child)| FilterFrame {
+                left: left_child,
+                left_offset: 0,
+                right: None,
+                key_len,
+            }));
+        self.stack[start..].reverse();
+
+        let value = left.view_value()?;
+        Some((K::new_from_slice(&self.key_buf), value))
+    }
+}
+
+impl<'l, 'r, K, L, R> Iterator for FilterJoinIter<'l, 'r, K, L, R>
+where
+    K: KeyTrait,
+    L: NodeView,
+    R: NodeView,
+{
+    type Item = (K, &'l L::Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(frame) = self.stack.pop() {
+            self.key_buf.truncate(frame.key_len);
+            if let Some(entry) = self.visit(frame) {
+                return Some(entry);
+            }
+        }
+        None
+    }
+}
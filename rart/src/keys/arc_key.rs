@@ -0,0 +1,268 @@
+use std::sync::Arc;
+
+use crate::keys::KeyTrait;
+use crate::keys::vector_key::VectorKey;
+use crate::partials::arc_partial::ArcPartial;
+
+/// A variable-size key type backed by a shared `Arc<[u8]>`.
+///
+/// `ArcKey` stores the same bytes as [`VectorKey`], but cloning it only bumps a reference count.
+/// Keys returned from iteration, longest-prefix matching, or intersections can be kept in caches
+/// or handed to other threads without copying the key bytes again.
+///
+/// ## Features
+///
+/// - **Variable size**: Can store keys of any length
+/// - **Cheap clones**: Clones share the underlying allocation
+/// - **Thread safe**: `Send + Sync`, suitable for sharing across threads
+/// - **Null termination**: Automatically adds null termination for string keys, like `VectorKey`
+///
+/// ## Examples
+///
+/// ```rust
+/// use rart::{AdaptiveRadixTree, ArcKey};
+///
+/// let mut tree = AdaptiveRadixTree::<ArcKey, u32>::new();
+/// tree.insert("hello", 1);
+///
+/// let (key, _) = tree.iter().next().unwrap();
+/// let shared = key.clone(); // refcount-only
+/// assert_eq!(shared.as_ref(), b"hello\0");
+/// ```
+///
+/// ## When to Use
+///
+/// Use `ArcKey` when keys produced by the tree are retained or cloned frequently. Use
+/// [`VectorKey`] when keys are mostly transient, since creating an `ArcKey` also allocates the
+/// reference counts.
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Debug)]
+pub struct ArcKey {
+    data: Arc<[u8]>,
+}
+
+impl AsRef<[u8]> for ArcKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl ArcKey {
+    pub fn new_from_str(s: &str) -> Self {
+        let mut data = Vec::with_capacity(s.len() + 1);
+        data.extend_from_slice(s.as_bytes());
+        data.push(0);
+        Self::new_from_vec(data)
+    }
+
+    pub fn new_from_vec(data: Vec<u8>) -> Self {
+        Self { data: data.into() }
+    }
+
+    pub fn new_from_arc(data: Arc<[u8]>) -> Self {
+        Self { data }
+    }
+
+    /// Returns the shared byte storage of this key.
+    pub fn as_arc(&self) -> &Arc<[u8]> {
+        &self.data
+    }
+
+    pub fn into_arc(self) -> Arc<[u8]> {
+        self.data
+    }
+}
+
+impl KeyTrait for ArcKey {
+    type PartialType = ArcPartial;
+    const MAXIMUM_SIZE: Option<usize> = None;
+
+    fn new_from_slice(data: &[u8]) -> Self {
+        Self { data: data.into() }
+    }
+
+    fn new_from_partial(partial: &Self::PartialType) -> Self {
+        Self {
+            data: partial.to_arc(),
+        }
+    }
+
+    fn extend_from_partial(&self, partial: &Self::PartialType) -> Self {
+        let mut v = Vec::with_capacity(self.data.len() + partial.to_slice().len());
+        v.extend_from_slice(&self.data);
+        v.extend_from_slice(partial.to_slice());
+        Self::new_from_vec(v)
+    }
+
+    fn truncated(&self, at_depth: usize) -> Self {
+        if at_depth >= self.data.len() {
+            return self.clone();
+        }
+        Self::new_from_slice(&self.data[..at_depth])
+    }
+
+    fn at(&self, pos: usize) -> u8 {
+        self.data[pos]
+    }
+
+    fn length_at(&self, at_depth: usize) -> usize {
+        self.data.len() - at_depth
+    }
+
+    fn to_partial(&self, at_depth: usize) -> ArcPartial {
+        if at_depth == 0 {
+            return ArcPartial::from_arc(self.data.clone());
+        }
+        ArcPartial::from_slice(&self.data[at_depth..])
+    }
+
+    fn matches_slice(&self, slice: &[u8]) -> bool {
+        &self.data[..] == slice
+    }
+}
+
+impl From<String> for ArcKey {
+    fn from(data: String) -> Self {
+        Self::new_from_str(&data)
+    }
+}
+impl From<&String> for ArcKey {
+    fn from(data: &String) -> Self {
+        Self::new_from_str(data)
+    }
+}
+impl From<&str> for ArcKey {
+    fn from(data: &str) -> Self {
+        Self::new_from_str(data)
+    }
+}
+impl From<Arc<[u8]>> for ArcKey {
+    fn from(data: Arc<[u8]>) -> Self {
+        Self::new_from_arc(data)
+    }
+}
+impl From<VectorKey> for ArcKey {
+    fn from(key: VectorKey) -> Self {
+        Self::new_from_slice(key.as_ref())
+    }
+}
+impl From<ArcKey> for VectorKey {
+    fn from(key: ArcKey) -> Self {
+        VectorKey::new_from_slice(key.as_ref())
+    }
+}
+macro_rules! impl_from_unsigned {
+    ( $($t:ty),* ) => {
+    $(
+    impl From< $t > for ArcKey
+    {
+        fn from(data: $t) -> Self {
+            ArcKey::new_from_slice(&data.to_be_bytes())
+        }
+    }
+    impl From< &$t > for ArcKey
+    {
+        fn from(data: &$t) -> Self {
+            (*data).into()
+        }
+    }
+    ) *
+    }
+}
+impl_from_unsigned!(u8, u16, u32, u64, usize, u128);
+
+macro_rules! impl_from_signed {
+    ( $t:ty, $tu:ty ) => {
+        impl From<$t> for ArcKey {
+            fn from(val: $t) -> Self {
+                // Convert signed to unsigned preserving sort order
+                let v: $tu = val as $tu;
+                let sign_bit = 1 << (std::mem::size_of::<$tu>() * 8 - 1);
+                let j = v ^ sign_bit;
+                ArcKey::new_from_slice(&j.to_be_bytes())
+            }
+        }
+
+        impl From<&$t> for ArcKey {
+            fn from(val: &$t) -> Self {
+                (*val).into()
+            }
+        }
+    };
+}
+
+impl_from_signed!(i8, u8);
+impl_from_signed!(i16, u16);
+impl_from_signed!(i32, u32);
+impl_from_signed!(i64, u64);
+impl_from_signed!(i128, u128);
+impl_from_signed!(isize, usize);
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::keys::KeyTrait;
+    use crate::keys::arc_key::ArcKey;
+    use crate::partials::Partial;
+    use crate::partials::arc_partial::ArcPartial;
+    use crate::{AdaptiveRadixTree, VersionedAdaptiveRadixTree};
+
+    #[test]
+    fn make_extend_truncate() {
+        let k = ArcKey::new_from_slice(b"hel");
+        let p = ArcPartial::from_slice(b"lo");
+        let k2 = k.extend_from_partial(&p);
+        assert!(k2.matches_slice(b"hello"));
+        let k3 = k2.truncated(3);
+        assert!(k3.matches_slice(b"hel"));
+    }
+
+    #[test]
+    fn clones_and_whole_key_partials_share_storage() {
+        let k: ArcKey = "shared".into();
+        let clone = k.clone();
+        assert!(Arc::ptr_eq(k.as_arc(), clone.as_arc()));
+
+        let partial = k.to_partial(0);
+        let back = ArcKey::new_from_partial(&partial);
+        assert!(Arc::ptr_eq(k.as_arc(), back.as_arc()));
+        assert_eq!(partial.partial_after(2).to_slice(), b"ared\0");
+    }
+
+    #[test]
+    fn signed_keys_sort_like_integers() {
+        let mut keys: Vec<ArcKey> = [5i32, -3, 0, i32::MIN, i32::MAX]
+            .iter()
+            .map(|v| v.into())
+            .collect();
+        keys.sort();
+        let expected: Vec<ArcKey> = [i32::MIN, -3, 0, 5, i32::MAX]
+            .iter()
+            .map(|v| v.into())
+            .collect();
+        assert_eq!(keys, expected);
+    }
+
+    #[test]
+    fn plugs_into_both_trees() {
+        let mut tree = AdaptiveRadixTree::<ArcKey, usize>::new();
+        let mut versioned = VersionedAdaptiveRadixTree::<ArcKey, usize>::new();
+        let words = ["apple", "application", "apply", "banana", "band"];
+        for (i, word) in words.iter().enumerate() {
+            tree.insert(*word, i);
+            versioned.insert(*word, i);
+        }
+        for (i, word) in words.iter().enumerate() {
+            assert_eq!(tree.get(*word), Some(&i));
+            assert_eq!(versioned.get(*word), Some(&i));
+        }
+
+        let tree_keys: Vec<ArcKey> = tree.iter().map(|(k, _)| k).collect();
+        let versioned_keys: Vec<ArcKey> = versioned.iter().map(|(k, _)| k).collect();
+        assert_eq!(tree_keys, versioned_keys);
+        assert_eq!(tree_keys[0].as_ref(), b"apple\0");
+
+        let held = std::thread::spawn(move || tree_keys.len()).join().unwrap();
+        assert_eq!(held, words.len());
+    }
+}
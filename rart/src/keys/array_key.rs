@@ -147,7 +147,7 @@ impl<const N: usize> KeyTrait for ArrayKey<N> {
         }
     }
 
-    fn truncate(&self, at_depth: usize) -> Self {
+    fn truncated(&self, at_depth: usize) -> Self {
         debug_assert!(at_depth <= self.len, "truncating beyond key length");
         Self {
             data: self.data,
@@ -172,6 +172,18 @@ impl<const N: usize> KeyTrait for ArrayKey<N> {
     }
 }
 
+impl<const N: usize, const M: usize> From<[u8; M]> for ArrayKey<N> {
+    fn from(data: [u8; M]) -> Self {
+        ArrayKey::new_from_slice(&data)
+    }
+}
+
+impl<const N: usize, const M: usize> From<&[u8; M]> for ArrayKey<N> {
+    fn from(data: &[u8; M]) -> Self {
+        ArrayKey::new_from_slice(data)
+    }
+}
+
 impl<const N: usize> From<String> for ArrayKey<N> {
     fn from(data: String) -> Self {
         Self::new_from_string(&data)
@@ -258,10 +270,18 @@ mod test {
         let p = ArrPartial::<8>::from_slice(b"lo");
         let k2 = k.extend_from_partial(&p);
         assert!(k2.matches_slice(b"hello"));
-        let k3 = k2.truncate(3);
+        let k3 = k2.truncated(3);
         assert!(k3.matches_slice(b"hel"));
     }
 
+    #[test]
+    fn from_byte_arrays() {
+        let k: ArrayKey<8> = [1u8, 2, 3].into();
+        assert!(k.matches_slice(&[1, 2, 3]));
+        let k: ArrayKey<8> = (&7u64.to_be_bytes()).into();
+        assert_eq!(k, ArrayKey::<8>::from(7u64));
+    }
+
     #[test]
     fn from_to_u64() {
         let k: ArrayKey<16> = 123u64.into();
@@ -0,0 +1,641 @@
+//! Order-preserving composite key encoding.
+//!
+//! This module provides the building blocks for turning structured values (tuples of integers,
+//! strings, byte strings, ...) into byte keys whose lexicographic order matches the logical order
+//! of the values. It is the runtime half of `#[derive(ArtKey)]` (available with the `derive`
+//! feature), but can also be used directly to hand-write encoders.
+//!
+//! ## Encoding
+//!
+//! Each [`KeySegment`] appends a self-delimiting encoding of itself to a [`KeyWriter`]:
+//!
+//! - unsigned integers are written big-endian
+//! - signed integers are written big-endian with the sign bit flipped
+//! - `bool` is a single `0`/`1` byte, `char` is its scalar value as a `u32`
+//! - `[u8; N]` is written as-is
+//! - `String` and `Vec<u8>` are *escaped*: every `0x00` byte is written as `0x00 0xFF`,
+//!   and the value is terminated by `0x00 0x01`. This keeps the encoding prefix-free, so `"a"`
+//!   sorts before `"ab"` no matter which fields follow
+//! - `Option<T>` is a `0` byte for `None`, or a `1` byte followed by `T`
+//! - [`Varint<T>`] wraps an integer to use the compact length-prefixed encoding from
+//!   [`varint`](crate::keys::varint) instead of its full fixed width
+//!
+//! Strings and byte strings can alternatively be written at a fixed width with
+//! [`FixedWidthSegment`], padding with `0x00` bytes. Fixed-width segments are cheaper to compare
+//! but cannot represent trailing NUL bytes and panic if the value is longer than the width.
+//!
+//! A writer or reader can be switched into descending mode, in which every byte is inverted.
+//! Because every segment encoding is prefix-free, inverting it exactly reverses its order.
+//!
+//! ```rust
+//! use rart::keys::codec::{KeyReader, KeySegment, KeyWriter};
+//!
+//! let mut writer = KeyWriter::new();
+//! "tenant".to_string().encode_segment(&mut writer);
+//! 42u32.encode_segment(&mut writer);
+//! let bytes = writer.into_bytes();
+//!
+//! let mut reader = KeyReader::new(&bytes);
+//! assert_eq!(String::decode_segment(&mut reader).unwrap(), "tenant");
+//! assert_eq!(u32::decode_segment(&mut reader).unwrap(), 42);
+//! assert!(reader.is_empty());
+//! ```
+
+use std::fmt;
+
+use crate::keys::KeyTrait;
+use crate::keys::varint;
+
+/// Escape byte used by variable-length segments.
+const ESCAPE: u8 = 0x00;
+/// Follows [`ESCAPE`] to encode a literal `0x00` byte.
+const ESCAPED_NUL: u8 = 0xFF;
+/// Follows [`ESCAPE`] to terminate a variable-length segment.
+const TERMINATOR: u8 = 0x01;
+
+/// Errors produced when decoding an encoded key.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KeyDecodeError {
+    /// The input ended before the segment was complete.
+    UnexpectedEnd,
+    /// The input contained bytes that are not a valid encoding of the segment.
+    InvalidEncoding,
+    /// Bytes remained after every segment was decoded.
+    TrailingBytes,
+}
+
+impl fmt::Display for KeyDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyDecodeError::UnexpectedEnd => write!(f, "unexpected end of encoded key"),
+            KeyDecodeError::InvalidEncoding => write!(f, "invalid encoded key segment"),
+            KeyDecodeError::TrailingBytes => write!(f, "trailing bytes after encoded key"),
+        }
+    }
+}
+
+impl std::error::Error for KeyDecodeError {}
+
+/// Accumulates the bytes of an encoded key.
+#[derive(Clone, Debug, Default)]
+pub struct KeyWriter {
+    buf: Vec<u8>,
+    descending: bool,
+}
+
+impl KeyWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: Vec::with_capacity(capacity),
+            descending: false,
+        }
+    }
+
+    /// Switch descending mode on or off for subsequently written bytes.
+    pub fn set_descending(&mut self, descending: bool) {
+        self.descending = descending;
+    }
+
+    pub fn is_descending(&self) -> bool {
+        self.descending
+    }
+
+    #[inline]
+    pub fn push(&mut self, byte: u8) {
+        self.buf.push(if self.descending { !byte } else { byte });
+    }
+
+    #[inline]
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) {
+        if self.descending {
+            self.buf.extend(bytes.iter().map(|b| !b));
+        } else {
+            self.buf.extend_from_slice(bytes);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    /// Build a tree key from the bytes written so far.
+    pub fn to_key<K: KeyTrait>(&self) -> K {
+        K::new_from_slice(&self.buf)
+    }
+}
+
+/// Reads segments back out of an encoded key.
+#[derive(Clone, Debug)]
+pub struct KeyReader<'a> {
+    input: &'a [u8],
+    descending: bool,
+}
+
+impl<'a> KeyReader<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        Self {
+            input,
+            descending: false,
+        }
+    }
+
+    /// Switch descending mode on or off for subsequently read bytes.
+    pub fn set_descending(&mut self, descending: bool) {
+        self.descending = descending;
+    }
+
+    pub fn is_descending(&self) -> bool {
+        self.descending
+    }
+
+    #[inline]
+    pub fn next_byte(&mut self) -> Result<u8, KeyDecodeError> {
+        let (&byte, rest) = self
+            .input
+            .split_first()
+            .ok_or(KeyDecodeError::UnexpectedEnd)?;
+        self.input = rest;
+        Ok(if self.descending { !byte } else { byte })
+    }
+
+    #[inline]
+    pub fn next_array<const N: usize>(&mut self) -> Result<[u8; N], KeyDecodeError> {
+        if self.input.len() < N {
+            return Err(KeyDecodeError::UnexpectedEnd);
+        }
+        let (head, rest) = self.input.split_at(N);
+        self.input = rest;
+        let mut out = [0u8; N];
+        out.copy_from_slice(head);
+        if self.descending {
+            out.iter_mut().for_each(|b| *b = !*b);
+        }
+        Ok(out)
+    }
+
+    /// Bytes not yet consumed, as stored (not inverted).
+    pub fn remaining(&self) -> &'a [u8] {
+        self.input
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    /// Fail with [`KeyDecodeError::TrailingBytes`] if any input remains.
+    pub fn finish(&self) -> Result<(), KeyDecodeError> {
+        if self.input.is_empty() {
+            Ok(())
+        } else {
+            Err(KeyDecodeError::TrailingBytes)
+        }
+    }
+}
+
+/// A value with an order-preserving, prefix-free byte encoding.
+///
+/// For any two values `a` and `b`, `a.cmp(&b)` must equal the lexicographic comparison of their
+/// encodings, and no encoding may be a proper prefix of another.
+pub trait KeySegment: Sized {
+    fn encode_segment(&self, writer: &mut KeyWriter);
+    fn decode_segment(reader: &mut KeyReader<'_>) -> Result<Self, KeyDecodeError>;
+}
+
+/// A string-like value that can be encoded at a fixed byte width.
+pub trait FixedWidthSegment: Sized {
+    /// Write `self` padded with `0x00` bytes to exactly `width` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is longer than `width` bytes.
+    fn encode_fixed(&self, width: usize, writer: &mut KeyWriter);
+    /// Read a `width` byte value, stripping trailing `0x00` padding.
+    fn decode_fixed(width: usize, reader: &mut KeyReader<'_>) -> Result<Self, KeyDecodeError>;
+}
+
+/// Composite keys that can be encoded into, and decoded from, a tree key type.
+///
+/// Usually implemented with `#[derive(ArtKey)]`.
+pub trait ArtKey: Sized {
+    /// The tree key type the value is encoded into.
+    type Key: KeyTrait;
+
+    /// Append the encoding of `self` to `writer`.
+    fn encode_key(&self, writer: &mut KeyWriter);
+
+    /// Decode a value previously written with [`ArtKey::encode_key`].
+    fn decode_key(reader: &mut KeyReader<'_>) -> Result<Self, KeyDecodeError>;
+
+    /// Encode `self` into its tree key.
+    fn to_key(&self) -> Self::Key {
+        let mut writer = KeyWriter::new();
+        self.encode_key(&mut writer);
+        writer.to_key()
+    }
+
+    /// Decode a value from a complete tree key.
+    fn from_key(key: &Self::Key) -> Result<Self, KeyDecodeError> {
+        Self::from_key_bytes(key.as_ref())
+    }
+
+    /// Decode a value from complete encoded key bytes.
+    fn from_key_bytes(bytes: &[u8]) -> Result<Self, KeyDecodeError> {
+        let mut reader = KeyReader::new(bytes);
+        let value = Self::decode_key(&mut reader)?;
+        reader.finish()?;
+        Ok(value)
+    }
+}
+
+macro_rules! impl_unsigned_segment {
+    ($($t:ty),*) => {
+        $(
+            impl KeySegment for $t {
+                #[inline]
+                fn encode_segment(&self, writer: &mut KeyWriter) {
+                    writer.extend_from_slice(&self.to_be_bytes());
+                }
+
+                #[inline]
+                fn decode_segment(reader: &mut KeyReader<'_>) -> Result<Self, KeyDecodeError> {
+                    Ok(<$t>::from_be_bytes(reader.next_array()?))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_signed_segment {
+    ($(($t:ty, $u:ty)),*) => {
+        $(
+            impl KeySegment for $t {
+                #[inline]
+                fn encode_segment(&self, writer: &mut KeyWriter) {
+                    let flipped = (*self as $u) ^ (1 << (<$u>::BITS - 1));
+                    writer.extend_from_slice(&flipped.to_be_bytes());
+                }
+
+                #[inline]
+                fn decode_segment(reader: &mut KeyReader<'_>) -> Result<Self, KeyDecodeError> {
+                    let flipped = <$u>::from_be_bytes(reader.next_array()?);
+                    Ok((flipped ^ (1 << (<$u>::BITS - 1))) as $t)
+                }
+            }
+        )*
+    };
+}
+
+impl_unsigned_segment!(u8, u16, u32, u64, u128);
+impl_signed_segment!((i8, u8), (i16, u16), (i32, u32), (i64, u64), (i128, u128));
+
+// `usize` and `isize` are always encoded as 64 bits so keys are portable across targets.
+impl KeySegment for usize {
+    fn encode_segment(&self, writer: &mut KeyWriter) {
+        (*self as u64).encode_segment(writer);
+    }
+
+    fn decode_segment(reader: &mut KeyReader<'_>) -> Result<Self, KeyDecodeError> {
+        usize::try_from(u64::decode_segment(reader)?).map_err(|_| KeyDecodeError::InvalidEncoding)
+    }
+}
+
+impl KeySegment for isize {
+    fn encode_segment(&self, writer: &mut KeyWriter) {
+        (*self as i64).encode_segment(writer);
+    }
+
+    fn decode_segment(reader: &mut KeyReader<'_>) -> Result<Self, KeyDecodeError> {
+        isize::try_from(i64::decode_segment(reader)?).map_err(|_| KeyDecodeError::InvalidEncoding)
+    }
+}
+
+impl KeySegment for bool {
+    fn encode_segment(&self, writer: &mut KeyWriter) {
+        writer.push(*self as u8);
+    }
+
+    fn decode_segment(reader: &mut KeyReader<'_>) -> Result<Self, KeyDecodeError> {
+        match reader.next_byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(KeyDecodeError::InvalidEncoding),
+        }
+    }
+}
+
+impl KeySegment for char {
+    fn encode_segment(&self, writer: &mut KeyWriter) {
+        (*self as u32).encode_segment(writer);
+    }
+
+    fn decode_segment(reader: &mut KeyReader<'_>) -> Result<Self, KeyDecodeError> {
+        char::from_u32(u32::decode_segment(reader)?).ok_or(KeyDecodeError::InvalidEncoding)
+    }
+}
+
+impl<const N: usize> KeySegment for [u8; N] {
+    fn encode_segment(&self, writer: &mut KeyWriter) {
+        writer.extend_from_slice(self);
+    }
+
+    fn decode_segment(reader: &mut KeyReader<'_>) -> Result<Self, KeyDecodeError> {
+        reader.next_array()
+    }
+}
+
+impl<T: KeySegment> KeySegment for Option<T> {
+    fn encode_segment(&self, writer: &mut KeyWriter) {
+        match self {
+            None => writer.push(0),
+            Some(value) => {
+                writer.push(1);
+                value.encode_segment(writer);
+            }
+        }
+    }
+
+    fn decode_segment(reader: &mut KeyReader<'_>) -> Result<Self, KeyDecodeError> {
+        match reader.next_byte()? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode_segment(reader)?)),
+            _ => Err(KeyDecodeError::InvalidEncoding),
+        }
+    }
+}
+
+/// An integer segment using the order-preserving variable-length encoding from
+/// [`varint`](crate::keys::varint).
+///
+/// Small values take fewer bytes (and trie levels) than the fixed-width encoding of the bare
+/// integer, at the cost of one header byte.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Varint<T>(pub T);
+
+macro_rules! impl_varint_segment {
+    ($wide:ty, $encode:path, $decode:path, $encoded_len:path, $($t:ty),*) => {
+        $(
+            impl KeySegment for Varint<$t> {
+                fn encode_segment(&self, writer: &mut KeyWriter) {
+                    let mut buf = [0; varint::MAX_ENCODED_LEN];
+                    let len = $encode(self.0 as $wide, &mut buf);
+                    writer.extend_from_slice(&buf[..len]);
+                }
+
+                fn decode_segment(reader: &mut KeyReader<'_>) -> Result<Self, KeyDecodeError> {
+                    let mut buf = [0; varint::MAX_ENCODED_LEN];
+                    buf[0] = reader.next_byte()?;
+                    let len = $encoded_len(buf[0])?;
+                    for byte in &mut buf[1..len] {
+                        *byte = reader.next_byte()?;
+                    }
+                    let (value, _) = $decode(&buf[..len])?;
+                    <$t>::try_from(value)
+                        .map(Varint)
+                        .map_err(|_| KeyDecodeError::InvalidEncoding)
+                }
+            }
+        )*
+    };
+}
+
+impl_varint_segment!(
+    u128,
+    varint::encode_unsigned_into,
+    varint::decode_unsigned,
+    varint::unsigned_encoded_len,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize
+);
+impl_varint_segment!(
+    i128,
+    varint::encode_signed_into,
+    varint::decode_signed,
+    varint::signed_encoded_len,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize
+);
+
+/// Write `bytes` using the escaped, terminated variable-length encoding.
+pub fn encode_escaped(bytes: &[u8], writer: &mut KeyWriter) {
+    for &byte in bytes {
+        if byte == ESCAPE {
+            writer.push(ESCAPE);
+            writer.push(ESCAPED_NUL);
+        } else {
+            writer.push(byte);
+        }
+    }
+    writer.push(ESCAPE);
+    writer.push(TERMINATOR);
+}
+
+/// Read bytes written with [`encode_escaped`].
+pub fn decode_escaped(reader: &mut KeyReader<'_>) -> Result<Vec<u8>, KeyDecodeError> {
+    let mut out = Vec::new();
+    loop {
+        match reader.next_byte()? {
+            ESCAPE => match reader.next_byte()? {
+                ESCAPED_NUL => out.push(ESCAPE),
+                TERMINATOR => return Ok(out),
+                _ => return Err(KeyDecodeError::InvalidEncoding),
+            },
+            byte => out.push(byte),
+        }
+    }
+}
+
+impl KeySegment for Vec<u8> {
+    fn encode_segment(&self, writer: &mut KeyWriter) {
+        encode_escaped(self, writer);
+    }
+
+    fn decode_segment(reader: &mut KeyReader<'_>) -> Result<Self, KeyDecodeError> {
+        decode_escaped(reader)
+    }
+}
+
+impl KeySegment for String {
+    fn encode_segment(&self, writer: &mut KeyWriter) {
+        encode_escaped(self.as_bytes(), writer);
+    }
+
+    fn decode_segment(reader: &mut KeyReader<'_>) -> Result<Self, KeyDecodeError> {
+        String::from_utf8(decode_escaped(reader)?).map_err(|_| KeyDecodeError::InvalidEncoding)
+    }
+}
+
+fn encode_fixed_bytes(bytes: &[u8], width: usize, writer: &mut KeyWriter) {
+    assert!(
+        bytes.len() <= width,
+        "value of {} bytes does not fit fixed-width key segment of {width} bytes",
+        bytes.len()
+    );
+    writer.extend_from_slice(bytes);
+    for _ in bytes.len()..width {
+        writer.push(0);
+    }
+}
+
+fn decode_fixed_bytes(width: usize, reader: &mut KeyReader<'_>) -> Result<Vec<u8>, KeyDecodeError> {
+    let mut out = Vec::with_capacity(width);
+    for _ in 0..width {
+        out.push(reader.next_byte()?);
+    }
+    let len = out.iter().rposition(|&b| b != 0).map_or(0, |pos| pos + 1);
+    out.truncate(len);
+    Ok(out)
+}
+
+impl FixedWidthSegment for Vec<u8> {
+    fn encode_fixed(&self, width: usize, writer: &mut KeyWriter) {
+        encode_fixed_bytes(self, width, writer);
+    }
+
+    fn decode_fixed(width: usize, reader: &mut KeyReader<'_>) -> Result<Self, KeyDecodeError> {
+        decode_fixed_bytes(width, reader)
+    }
+}
+
+impl FixedWidthSegment for String {
+    fn encode_fixed(&self, width: usize, writer: &mut KeyWriter) {
+        encode_fixed_bytes(self.as_bytes(), width, writer);
+    }
+
+    fn decode_fixed(width: usize, reader: &mut KeyReader<'_>) -> Result<Self, KeyDecodeError> {
+        String::from_utf8(decode_fixed_bytes(width, reader)?)
+            .map_err(|_| KeyDecodeError::InvalidEncoding)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn encode<T: KeySegment>(value: &T, descending: bool) -> Vec<u8> {
+        let mut writer = KeyWriter::new();
+        writer.set_descending(descending);
+        value.encode_segment(&mut writer);
+        writer.into_bytes()
+    }
+
+    fn decode<T: KeySegment>(bytes: &[u8], descending: bool) -> T {
+        let mut reader = KeyReader::new(bytes);
+        reader.set_descending(descending);
+        let value = T::decode_segment(&mut reader).unwrap();
+        reader.finish().unwrap();
+        value
+    }
+
+    #[test]
+    fn signed_order_and_round_trip() {
+        let values = [i64::MIN, -1000, -1, 0, 1, 1000, i64::MAX];
+        for pair in values.windows(2) {
+            assert!(encode(&pair[0], false) < encode(&pair[1], false));
+            assert!(encode(&pair[0], true) > encode(&pair[1], true));
+        }
+        for v in values {
+            assert_eq!(decode::<i64>(&encode(&v, false), false), v);
+            assert_eq!(decode::<i64>(&encode(&v, true), true), v);
+        }
+    }
+
+    #[test]
+    fn escaped_strings_are_prefix_free() {
+        let values = ["", "\0", "\0\0", "a", "a\0", "a\0b", "ab", "b"];
+        for pair in values.windows(2) {
+            let (a, b) = (pair[0].to_string(), pair[1].to_string());
+            assert!(encode(&a, false) < encode(&b, false), "{a:?} < {b:?}");
+            assert!(encode(&a, true) > encode(&b, true), "{a:?} > {b:?} desc");
+        }
+        for v in values {
+            let v = v.to_string();
+            assert_eq!(decode::<String>(&encode(&v, false), false), v);
+            assert_eq!(decode::<String>(&encode(&v, true), true), v);
+        }
+    }
+
+    #[test]
+    fn escaped_string_followed_by_segment() {
+        let mut writer = KeyWriter::new();
+        "ab".to_string().encode_segment(&mut writer);
+        7u16.encode_segment(&mut writer);
+        let shorter = writer.into_bytes();
+
+        let mut writer = KeyWriter::new();
+        "ab\0".to_string().encode_segment(&mut writer);
+        0u16.encode_segment(&mut writer);
+        assert!(shorter < writer.into_bytes());
+    }
+
+    #[test]
+    fn fixed_width_strings() {
+        let mut writer = KeyWriter::new();
+        "abc".to_string().encode_fixed(8, &mut writer);
+        let bytes = writer.into_bytes();
+        assert_eq!(bytes, b"abc\0\0\0\0\0");
+        let mut reader = KeyReader::new(&bytes);
+        assert_eq!(String::decode_fixed(8, &mut reader).unwrap(), "abc");
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn fixed_width_overflow_panics() {
+        let mut writer = KeyWriter::new();
+        "too long".to_string().encode_fixed(4, &mut writer);
+    }
+
+    #[test]
+    fn varint_segments_are_compact_and_ordered() {
+        assert_eq!(encode(&Varint(5u64), false), vec![0x01, 0x05]);
+        let values = [-70000i64, -1, 0, 1, 300, i64::MAX];
+        for pair in values.windows(2) {
+            let (a, b) = (Varint(pair[0]), Varint(pair[1]));
+            assert!(encode(&a, false) < encode(&b, false));
+            assert!(encode(&a, true) > encode(&b, true));
+        }
+        for v in values {
+            assert_eq!(decode::<Varint<i64>>(&encode(&Varint(v), true), true).0, v);
+        }
+
+        let mut reader = KeyReader::new(&[0x02, 0x01, 0x00]);
+        assert_eq!(
+            Varint::<u8>::decode_segment(&mut reader),
+            Err(KeyDecodeError::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn truncated_input_is_an_error() {
+        let bytes = encode(&"abc".to_string(), false);
+        let mut reader = KeyReader::new(&bytes[..bytes.len() - 1]);
+        assert_eq!(
+            String::decode_segment(&mut reader),
+            Err(KeyDecodeError::UnexpectedEnd)
+        );
+    }
+}
@@ -0,0 +1,372 @@
+use std::cmp::{Ordering, min};
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::keys::KeyTrait;
+use crate::partials::Partial;
+
+/// A normalization applied to strings before they become [`FoldedKey`]s.
+pub trait StringFolding {
+    /// Append the folded form of `s` to `out`.
+    fn fold_into(s: &str, out: &mut Vec<u8>);
+}
+
+/// ASCII case folding: `A`-`Z` become `a`-`z`, every other byte is kept as is.
+///
+/// Suitable for hostnames, HTTP header names, and other ASCII protocol identifiers.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AsciiCaseFold;
+
+impl StringFolding for AsciiCaseFold {
+    fn fold_into(s: &str, out: &mut Vec<u8>) {
+        out.extend(s.bytes().map(|b| b.to_ascii_lowercase()));
+    }
+}
+
+/// Unicode folding: NFC normalization followed by lowercasing each character.
+///
+/// Lowercasing uses [`char::to_lowercase`], which agrees with Unicode simple case folding for
+/// nearly all characters; the exceptions are a handful such as final sigma (`ς` stays distinct
+/// from `σ`) and characters whose lowercase form differs from their case fold (e.g. Cherokee).
+#[cfg(feature = "unicode-folding")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UnicodeCaseFold;
+
+#[cfg(feature = "unicode-folding")]
+impl StringFolding for UnicodeCaseFold {
+    fn fold_into(s: &str, out: &mut Vec<u8>) {
+        use unicode_normalization::UnicodeNormalization;
+
+        let mut buf = [0u8; 4];
+        for c in s.nfc().flat_map(char::to_lowercase) {
+            out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+        }
+    }
+}
+
+/// A key adapter that folds strings on construction, for case-insensitive trees.
+///
+/// `FoldedKey<K, F>` wraps an inner key type `K` (such as [`VectorKey`](crate::VectorKey),
+/// [`ArrayKey`](crate::ArrayKey), or [`OverflowKey`](crate::OverflowKey)) and applies the folding
+/// `F` whenever it is built from a string. The tree's generic methods (`insert`, `get`,
+/// `prefix_iter`, `longest_prefix_match`, ...) convert a `&str` or `String` argument into the
+/// tree's key type, so string arguments are folded the same way as stored keys. Keep the original
+/// spelling in the value if you need it back.
+///
+/// Two paths do not go through the folding:
+///
+/// - Keys built from raw bytes, with [`KeyTrait::new_from_slice`] or [`from_inner`], are used
+///   verbatim. The tree itself builds keys this way from bytes it already holds, so
+///   `new_from_slice` cannot fold; callers passing their own bytes must fold them first.
+/// - String keys are NUL-terminated after folding, like the inner key types. Passing a `&str`
+///   to `prefix_iter` or `longest_prefix_match` therefore searches for the string *plus* the
+///   terminator, which only matches that exact key. Build prefix arguments with
+///   [`FoldedKey::new_prefix`], which folds without appending the terminator.
+///
+/// [`from_inner`]: FoldedKey::from_inner
+///
+/// ```rust
+/// use rart::keys::folded_key::AsciiFoldedKey;
+/// use rart::{AdaptiveRadixTree, VectorKey};
+///
+/// let mut hosts = AdaptiveRadixTree::<AsciiFoldedKey<VectorKey>, &str>::new();
+/// hosts.insert("Example.COM", "Example.COM");
+///
+/// assert_eq!(hosts.get("example.com"), Some(&"Example.COM"));
+/// assert_eq!(hosts.get("EXAMPLE.com"), Some(&"Example.COM"));
+///
+/// let prefix = AsciiFoldedKey::<VectorKey>::new_prefix("EXAMPLE.");
+/// assert_eq!(hosts.prefix_iter(prefix).count(), 1);
+/// assert_eq!(hosts.prefix_iter("EXAMPLE.").count(), 0);
+/// ```
+pub struct FoldedKey<K, F = AsciiCaseFold> {
+    key: K,
+    _folding: PhantomData<fn() -> F>,
+}
+
+/// A [`FoldedKey`] using [`AsciiCaseFold`].
+pub type AsciiFoldedKey<K> = FoldedKey<K, AsciiCaseFold>;
+
+/// A [`FoldedKey`] using [`UnicodeCaseFold`].
+#[cfg(feature = "unicode-folding")]
+pub type UnicodeFoldedKey<K> = FoldedKey<K, UnicodeCaseFold>;
+
+impl<K: KeyTrait, F: StringFolding> FoldedKey<K, F> {
+    /// Fold `s` and build a NUL-terminated key from it.
+    pub fn new(s: &str) -> Self {
+        let mut data = Vec::with_capacity(s.len() + 1);
+        F::fold_into(s, &mut data);
+        data.push(0);
+        Self::from_inner(K::new_from_slice(&data))
+    }
+
+    /// Fold `s` and build a key from it without a terminator, for use as a raw prefix.
+    pub fn new_prefix(s: &str) -> Self {
+        let mut data = Vec::with_capacity(s.len());
+        F::fold_into(s, &mut data);
+        Self::from_inner(K::new_from_slice(&data))
+    }
+}
+
+impl<K, F> FoldedKey<K, F> {
+    /// Wrap an inner key that is already folded.
+    pub fn from_inner(key: K) -> Self {
+        Self {
+            key,
+            _folding: PhantomData,
+        }
+    }
+
+    pub fn inner(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_inner(self) -> K {
+        self.key
+    }
+}
+
+impl<K: Clone, F> Clone for FoldedKey<K, F> {
+    fn clone(&self) -> Self {
+        Self::from_inner(self.key.clone())
+    }
+}
+
+impl<K: PartialEq, F> PartialEq for FoldedKey<K, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<K: Eq, F> Eq for FoldedKey<K, F> {}
+
+impl<K: PartialOrd, F> PartialOrd for FoldedKey<K, F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.key.partial_cmp(&other.key)
+    }
+}
+
+impl<K: Ord, F> Ord for FoldedKey<K, F> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+impl<K: AsRef<[u8]>, F> fmt::Debug for FoldedKey<K, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FoldedKey")
+            .field(&self.key.as_ref())
+            .finish()
+    }
+}
+
+impl<K: AsRef<[u8]>, F> AsRef<[u8]> for FoldedKey<K, F> {
+    fn as_ref(&self) -> &[u8] {
+        self.key.as_ref()
+    }
+}
+
+impl<K: KeyTrait, F: StringFolding> KeyTrait for FoldedKey<K, F> {
+    type PartialType = FoldedPartial<K::PartialType>;
+    const MAXIMUM_SIZE: Option<usize> = K::MAXIMUM_SIZE;
+
+    /// Use `slice` verbatim; it is not folded. See the [type-level docs](FoldedKey).
+    fn new_from_slice(slice: &[u8]) -> Self {
+        Self::from_inner(K::new_from_slice(slice))
+    }
+
+    fn new_from_partial(partial: &Self::PartialType) -> Self {
+        Self::from_inner(K::new_from_partial(&partial.partial))
+    }
+
+    fn extend_from_partial(&self, partial: &Self::PartialType) -> Self {
+        Self::from_inner(self.key.extend_from_partial(&partial.partial))
+    }
+
+    fn truncated(&self, at_depth: usize) -> Self {
+        Self::from_inner(self.key.truncated(at_depth))
+    }
+
+    fn at(&self, pos: usize) -> u8 {
+        self.key.at(pos)
+    }
+
+    fn length_at(&self, at_depth: usize) -> usize {
+        self.key.length_at(at_depth)
+    }
+
+    fn to_partial(&self, at_depth: usize) -> Self::PartialType {
+        FoldedPartial {
+            partial: self.key.to_partial(at_depth),
+        }
+    }
+
+    fn matches_slice(&self, slice: &[u8]) -> bool {
+        self.key.matches_slice(slice)
+    }
+}
+
+impl<K: KeyTrait, F: StringFolding> From<&str> for FoldedKey<K, F> {
+    fn from(s: &str) -> Self {
+        Self::new(s)
+    }
+}
+
+impl<K: KeyTrait, F: StringFolding> From<&String> for FoldedKey<K, F> {
+    fn from(s: &String) -> Self {
+        Self::new(s)
+    }
+}
+
+impl<K: KeyTrait, F: StringFolding> From<String> for FoldedKey<K, F> {
+    fn from(s: String) -> Self {
+        Self::new(&s)
+    }
+}
+
+/// Partial key type for [`FoldedKey`], wrapping the inner key's partial.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FoldedPartial<P> {
+    partial: P,
+}
+
+impl<P: Partial> AsRef<[u8]> for FoldedPartial<P> {
+    fn as_ref(&self) -> &[u8] {
+        self.partial.as_ref()
+    }
+}
+
+impl<P: Partial> Partial for FoldedPartial<P> {
+    fn partial_before(&self, length: usize) -> Self {
+        Self {
+            partial: self.partial.partial_before(length),
+        }
+    }
+
+    fn partial_from(&self, src_offset: usize, length: usize) -> Self {
+        Self {
+            partial: self.partial.partial_from(src_offset, length),
+        }
+    }
+
+    fn partial_after(&self, start: usize) -> Self {
+        Self {
+            partial: self.partial.partial_after(start),
+        }
+    }
+
+    fn partial_extended_with(&self, other: &Self) -> Self {
+        Self {
+            partial: self.partial.partial_extended_with(&other.partial),
+        }
+    }
+
+    #[inline(always)]
+    fn at(&self, pos: usize) -> u8 {
+        self.partial.at(pos)
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.partial.len()
+    }
+
+    fn prefix_length_common(&self, other: &Self) -> usize {
+        self.partial.prefix_length_common(&other.partial)
+    }
+
+    fn prefix_length_key<'a, K>(&self, key: &'a K, at_depth: usize) -> usize
+    where
+        K: KeyTrait<PartialType = Self> + 'a,
+    {
+        let len = min(self.len(), key.length_at(at_depth));
+        self.partial
+            .prefix_length_slice(&key.as_ref()[at_depth..at_depth + len])
+    }
+
+    fn prefix_length_slice(&self, slice: &[u8]) -> usize {
+        self.partial.prefix_length_slice(slice)
+    }
+
+    fn to_slice(&self) -> &[u8] {
+        self.partial.to_slice()
+    }
+}
+
+impl<K: KeyTrait, F: StringFolding> From<FoldedKey<K, F>> for FoldedPartial<K::PartialType> {
+    fn from(value: FoldedKey<K, F>) -> Self {
+        value.to_partial(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::keys::folded_key::{AsciiFoldedKey, FoldedKey};
+    use crate::{AdaptiveRadixTree, ArrayKey, OverflowKey, VectorKey, VersionedAdaptiveRadixTree};
+
+    #[test]
+    fn ascii_folding_applies_to_queries() {
+        let mut tree = AdaptiveRadixTree::<AsciiFoldedKey<VectorKey>, &str>::new();
+        tree.insert("Content-Type", "Content-Type");
+        tree.insert("Content-Length", "Content-Length");
+        tree.insert("Host", "Host");
+
+        assert_eq!(tree.get("content-type"), Some(&"Content-Type"));
+        assert_eq!(tree.get("HOST"), Some(&"Host"));
+        assert_eq!(tree.insert("CONTENT-TYPE", "x"), Some("Content-Type"));
+
+        let prefix = AsciiFoldedKey::<VectorKey>::new_prefix("CONTENT-");
+        let matched: Vec<&str> = tree.prefix_iter_k(&prefix).map(|(_, v)| *v).collect();
+        assert_eq!(matched, vec!["Content-Length", "x"]);
+    }
+
+    #[test]
+    fn longest_prefix_match_with_folded_queries() {
+        let mut routes = AdaptiveRadixTree::<AsciiFoldedKey<ArrayKey<32>>, u32>::new();
+        routes.insert(AsciiFoldedKey::new_prefix("/API/"), 1);
+        routes.insert(AsciiFoldedKey::new_prefix("/api/Users/"), 2);
+
+        let query = AsciiFoldedKey::<ArrayKey<32>>::new_prefix("/Api/USERS/42");
+        assert_eq!(
+            routes.longest_prefix_match_k(&query).map(|(_, v)| *v),
+            Some(2)
+        );
+        let query = AsciiFoldedKey::<ArrayKey<32>>::new_prefix("/api/GROUPS");
+        assert_eq!(
+            routes.longest_prefix_match_k(&query).map(|(_, v)| *v),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn folded_overflow_keys_in_versioned_tree() {
+        type Key = FoldedKey<OverflowKey<16, 8>>;
+        let mut tree = VersionedAdaptiveRadixTree::<Key, u32>::new();
+        tree.insert("User.Handle.That.Is.Long", 1);
+        let snapshot = tree.snapshot();
+        tree.insert("OTHER", 2);
+
+        assert_eq!(snapshot.get("user.handle.that.is.long"), Some(&1));
+        assert_eq!(snapshot.get("other"), None);
+        assert_eq!(tree.get("Other"), Some(&2));
+        let keys: Vec<Vec<u8>> = tree.iter().map(|(k, _)| k.as_ref().to_vec()).collect();
+        assert_eq!(
+            keys,
+            vec![b"other\0".to_vec(), b"user.handle.that.is.long\0".to_vec()]
+        );
+    }
+
+    #[cfg(feature = "unicode-folding")]
+    #[test]
+    fn unicode_folding_normalizes_and_lowercases() {
+        use crate::keys::folded_key::UnicodeFoldedKey;
+
+        let mut tree = AdaptiveRadixTree::<UnicodeFoldedKey<VectorKey>, u32>::new();
+        // "É" precomposed, looked up as "e" + combining acute accent.
+        tree.insert("CAFÉ", 1);
+        assert_eq!(tree.get("cafe\u{301}"), Some(&1));
+        assert_eq!(tree.get("ΣΟΦΊΑ"), None);
+        tree.insert("Σοφία", 2);
+        assert_eq!(tree.get("ΣΟΦΊΑ"), Some(&2));
+    }
+}
@@ -7,9 +7,52 @@
 //!
 //! - [`ArrayKey<N>`](array_key::ArrayKey): Fixed-size keys up to N bytes, stored on the stack
 //! - [`VectorKey`](vector_key::VectorKey): Variable-size keys stored on the heap
+//! - [`ArcKey`](arc_key::ArcKey): Variable-size keys in shared `Arc<[u8]>` storage with
+//!   refcount-only clones
 //! - [`OverflowKey<K, P>`](overflow_key::OverflowKey): Inline storage for short dynamic keys with
 //!   boxed overflow for longer keys
 //!
+//! `Vec<u8>` and `Box<[u8]>` also implement [`KeyTrait`] directly (see [`std_keys`]), so a tree
+//! such as `AdaptiveRadixTree<Vec<u8>, V>` accepts `&[u8]` or `&str` keys and hands back plain
+//! byte vectors.
+//!
+//! Integers and fixed-size arrays go through thin wrappers, because the tree needs to borrow a
+//! key's bytes and to build keys for partial paths:
+//! [`IntKey<T>`](std_keys::IntKey) (for example `AdaptiveRadixTree<IntKey<u64>, V>`) and
+//! [`FixedKey<N>`](std_keys::FixedKey) convert from and back into `u64`, `i32`, `[u8; N]`, and so
+//! on. Integers are encoded big-endian, with the sign bit flipped for signed types, so that byte
+//! order matches numeric order. [`ArrayKey<N>`](array_key::ArrayKey) uses the same encoding for
+//! its integer conversions.
+//!
+//! ## String Encoding
+//!
+//! `String` does not implement [`KeyTrait`]: intermediate keys built while traversing the tree may
+//! end in the middle of a multi-byte UTF-8 sequence. Strings are instead converted into one of
+//! the byte key types. The built-in key types ([`ArrayKey`](array_key::ArrayKey),
+//! [`VectorKey`](vector_key::VectorKey), [`ArcKey`](arc_key::ArcKey), and
+//! [`OverflowKey`](overflow_key::OverflowKey)) append a `0x00` terminator when converting from
+//! `&str` or `String`. The terminator keeps string keys prefix-free, so `"app"` and `"apple"` are
+//! stored as `b"app\0"` and `b"apple\0"`, and string keys sort exactly as the strings do (assuming
+//! the strings contain no NUL bytes). Prefix queries built from a `&str` include the terminator too;
+//! use `&[u8]`-based lookups such as `get_bytes` or a key built with `new_from_slice` to query a raw
+//! byte prefix. `Vec<u8>` and `Box<[u8]>` keys store bytes exactly as given and add no terminator.
+//!
+//! ## Case-Insensitive Keys
+//!
+//! [`FoldedKey<K, F>`](folded_key::FoldedKey) wraps any of the key types above and folds strings
+//! (ASCII case folding by default, or NFC plus Unicode lowercasing with the `unicode-folding`
+//! feature) whenever a key is built from one, including the query keys passed to `get`,
+//! `prefix_iter`, and `longest_prefix_match`.
+//!
+//! ## Composite Keys
+//!
+//! The [`codec`] module provides an order-preserving encoding for structured keys such as
+//! `(tenant, timestamp, id)`. With the `derive` feature, `#[derive(ArtKey)]` generates the encoder,
+//! decoder, and a prefix builder for a struct.
+//!
+//! The [`varint`] module provides an order-preserving, length-prefixed integer encoding that keeps
+//! small ids short; it is available both as key constructors and as the [`codec::Varint`] segment.
+//!
 //! ## Custom Keys
 //!
 //! You can implement custom key types by implementing the [`KeyTrait`]:
@@ -24,8 +67,13 @@
 
 use crate::partials::Partial;
 
+pub mod arc_key;
 pub mod array_key;
+pub mod codec;
+pub mod folded_key;
 pub mod overflow_key;
+pub mod std_keys;
+pub mod varint;
 pub mod vector_key;
 
 /// Trait for types that can be used as keys in an Adaptive Radix Tree.
@@ -64,8 +112,11 @@ pub trait KeyTrait: Clone + PartialEq + Eq + PartialOrd + Ord + AsRef<[u8]> {
 
     /// Extend this key with bytes from a partial.
     fn extend_from_partial(&self, partial: &Self::PartialType) -> Self;
-    /// Truncate this key to the specified depth.
-    fn truncate(&self, at_depth: usize) -> Self;
+    /// A copy of this key truncated to the specified depth.
+    ///
+    /// Named so that it cannot shadow `Vec::truncate` on the `Vec<u8>` key impl.
+    #[must_use]
+    fn truncated(&self, at_depth: usize) -> Self;
     /// Get the byte at the specified position.
     fn at(&self, pos: usize) -> u8;
     /// Get the length of the key starting from the specified depth.
@@ -268,7 +268,7 @@ impl<const N: usize, const P: usize> KeyTrait for OverflowKey<N, P> {
         Self::new_from_slice(&data)
     }
 
-    fn truncate(&self, at_depth: usize) -> Self {
+    fn truncated(&self, at_depth: usize) -> Self {
         debug_assert!(at_depth <= self.len, "truncating beyond key length");
         Self::new_from_slice(&self.data()[..at_depth])
     }
@@ -401,7 +401,7 @@ mod tests {
         let k2 = k.extend_from_partial(&p);
         assert!(!k2.is_inline());
         assert!(k2.matches_slice(b"hello!"));
-        let k3 = k2.truncate(4);
+        let k3 = k2.truncated(4);
         assert!(k3.is_inline());
         assert!(k3.matches_slice(b"hell"));
     }
@@ -0,0 +1,487 @@
+//! [`KeyTrait`] implementations for standard library keys.
+//!
+//! `Vec<u8>` and `Box<[u8]>` can be used directly as tree keys, using [`VectorPartial`] for their
+//! node prefixes. Iterators, longest-prefix matches, and intersections then hand back a plain
+//! vector or boxed slice rather than a wrapper, and anything convertible into the key type
+//! (`&[u8]`, `&str`, `Vec<u8>`) can be passed to the generic `insert`/`get` style methods.
+//!
+//! Unlike [`VectorKey`](crate::VectorKey), these keys store exactly the bytes they are given: a
+//! string inserted as `"app"` or `s.as_bytes()` is *not* NUL-terminated. See the
+//! [module documentation](crate::keys#string-encoding) for why that matters.
+//!
+//! Integers and fixed-size byte arrays cannot implement [`KeyTrait`] themselves: integers cannot
+//! lend out their big-endian bytes through `AsRef<[u8]>`, and the tree builds keys for partial
+//! paths, which a `[u8; N]` cannot hold. [`IntKey<T>`] and [`FixedKey<N>`] are thin wrappers that
+//! convert from and back into the plain types:
+//!
+//! ```rust
+//! use rart::AdaptiveRadixTree;
+//! use rart::keys::std_keys::IntKey;
+//!
+//! let mut tree = AdaptiveRadixTree::<IntKey<i64>, &str>::new();
+//! tree.insert(-5i64, "minus five");
+//! tree.insert(3i64, "three");
+//!
+//! let keys: Vec<i64> = tree.iter().map(|(key, _)| key.get()).collect();
+//! assert_eq!(keys, vec![-5, 3]);
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::keys::KeyTrait;
+use crate::keys::array_key::ArrayKey;
+use crate::partials::array_partial::ArrPartial;
+use crate::partials::vector_partial::VectorPartial;
+
+impl KeyTrait for Vec<u8> {
+    type PartialType = VectorPartial;
+    const MAXIMUM_SIZE: Option<usize> = None;
+
+    fn new_from_slice(slice: &[u8]) -> Self {
+        slice.to_vec()
+    }
+
+    fn new_from_partial(partial: &Self::PartialType) -> Self {
+        partial.to_slice().to_vec()
+    }
+
+    fn extend_from_partial(&self, partial: &Self::PartialType) -> Self {
+        let mut v = Vec::with_capacity(self.len() + partial.to_slice().len());
+        v.extend_from_slice(self);
+        v.extend_from_slice(partial.to_slice());
+        v
+    }
+
+    fn truncated(&self, at_depth: usize) -> Self {
+        self[..at_depth.min(self.len())].to_vec()
+    }
+
+    fn at(&self, pos: usize) -> u8 {
+        self[pos]
+    }
+
+    fn length_at(&self, at_depth: usize) -> usize {
+        self.len() - at_depth
+    }
+
+    fn to_partial(&self, at_depth: usize) -> VectorPartial {
+        VectorPartial::from_slice(&self[at_depth..])
+    }
+
+    fn matches_slice(&self, slice: &[u8]) -> bool {
+        &self[..] == slice
+    }
+}
+
+impl KeyTrait for Box<[u8]> {
+    type PartialType = VectorPartial;
+    const MAXIMUM_SIZE: Option<usize> = None;
+
+    fn new_from_slice(slice: &[u8]) -> Self {
+        Box::from(slice)
+    }
+
+    fn new_from_partial(partial: &Self::PartialType) -> Self {
+        Box::from(partial.to_slice())
+    }
+
+    fn extend_from_partial(&self, partial: &Self::PartialType) -> Self {
+        let mut v = Vec::with_capacity(self.len() + partial.to_slice().len());
+        v.extend_from_slice(self);
+        v.extend_from_slice(partial.to_slice());
+        v.into_boxed_slice()
+    }
+
+    fn truncated(&self, at_depth: usize) -> Self {
+        Box::from(&self[..at_depth.min(self.len())])
+    }
+
+    fn at(&self, pos: usize) -> u8 {
+        self[pos]
+    }
+
+    fn length_at(&self, at_depth: usize) -> usize {
+        self.len() - at_depth
+    }
+
+    fn to_partial(&self, at_depth: usize) -> VectorPartial {
+        VectorPartial::from_slice(&self[at_depth..])
+    }
+
+    fn matches_slice(&self, slice: &[u8]) -> bool {
+        &self[..] == slice
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Integer types usable through [`IntKey`].
+///
+/// Values are encoded big-endian, with the sign bit flipped for signed types, so that byte order
+/// matches numeric order. This is the same encoding [`ArrayKey`]'s integer conversions use.
+pub trait KeyInteger: Copy + Ord + fmt::Debug + sealed::Sealed {
+    /// The encoded width in bytes.
+    const WIDTH: usize;
+
+    /// Encode `self` as a key.
+    fn encode(self) -> ArrayKey<16>;
+
+    /// Decode a value from exactly [`WIDTH`](Self::WIDTH) encoded bytes.
+    fn decode(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_key_integer {
+    ( $( $t:ty => $tu:ty ),* ) => {
+    $(
+        impl sealed::Sealed for $t {}
+
+        impl KeyInteger for $t {
+            const WIDTH: usize = std::mem::size_of::<$t>();
+
+            fn encode(self) -> ArrayKey<16> {
+                ArrayKey::from(self)
+            }
+
+            fn decode(bytes: &[u8]) -> Self {
+                let bytes = bytes.try_into().expect("key is not a complete integer");
+                let raw = <$tu>::from_be_bytes(bytes);
+                // Undo the sign bit flip; a no-op for unsigned types.
+                let unflipped = raw ^ (<$tu>::from(<$t>::MIN != 0) << (<$tu>::BITS - 1));
+                unflipped as $t
+            }
+        }
+    )*
+    };
+}
+
+impl_key_integer!(
+    u8 => u8, u16 => u16, u32 => u32, u64 => u64, u128 => u128, usize => usize,
+    i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize
+);
+
+/// A tree key holding an integer, ordered numerically.
+///
+/// Converts from the integer (so `insert(5u64, ..)` works on an `IntKey<u64>` tree) and back
+/// through [`get`](Self::get) or `From`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct IntKey<T> {
+    key: ArrayKey<16>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: KeyInteger> IntKey<T> {
+    pub fn new(value: T) -> Self {
+        Self::wrap(value.encode())
+    }
+
+    /// The integer this key holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key holds fewer bytes than a `T`, which only happens for keys built from
+    /// partial byte strings with [`KeyTrait::new_from_slice`].
+    pub fn get(&self) -> T {
+        T::decode(self.key.as_ref())
+    }
+
+    fn wrap(key: ArrayKey<16>) -> Self {
+        Self {
+            key,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: KeyInteger> fmt::Debug for IntKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.key.as_ref().len() == T::WIDTH {
+            f.debug_tuple("IntKey").field(&self.get()).finish()
+        } else {
+            f.debug_tuple("IntKey").field(&self.key.as_ref()).finish()
+        }
+    }
+}
+
+impl<T> AsRef<[u8]> for IntKey<T> {
+    fn as_ref(&self) -> &[u8] {
+        self.key.as_ref()
+    }
+}
+
+impl<T: KeyInteger> From<T> for IntKey<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: KeyInteger> From<IntKey<T>> for ArrPartial<16> {
+    fn from(key: IntKey<T>) -> Self {
+        key.key.into()
+    }
+}
+
+impl<T: KeyInteger> KeyTrait for IntKey<T> {
+    type PartialType = ArrPartial<16>;
+    const MAXIMUM_SIZE: Option<usize> = Some(16);
+
+    fn new_from_slice(slice: &[u8]) -> Self {
+        Self::wrap(ArrayKey::new_from_slice(slice))
+    }
+
+    fn new_from_partial(partial: &Self::PartialType) -> Self {
+        Self::wrap(ArrayKey::new_from_partial(partial))
+    }
+
+    fn extend_from_partial(&self, partial: &Self::PartialType) -> Self {
+        Self::wrap(self.key.extend_from_partial(partial))
+    }
+
+    fn truncated(&self, at_depth: usize) -> Self {
+        Self::wrap(self.key.truncated(at_depth))
+    }
+
+    #[inline(always)]
+    fn at(&self, pos: usize) -> u8 {
+        self.key.at(pos)
+    }
+
+    #[inline(always)]
+    fn length_at(&self, at_depth: usize) -> usize {
+        self.key.length_at(at_depth)
+    }
+
+    fn to_partial(&self, at_depth: usize) -> ArrPartial<16> {
+        self.key.to_partial(at_depth)
+    }
+
+    #[inline(always)]
+    fn matches_slice(&self, slice: &[u8]) -> bool {
+        self.key.matches_slice(slice)
+    }
+}
+
+/// A tree key holding exactly `N` bytes, ordered lexicographically.
+///
+/// Converts from `[u8; N]` and back through [`to_array`](Self::to_array) or `From`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct FixedKey<const N: usize> {
+    key: ArrayKey<N>,
+}
+
+impl<const N: usize> FixedKey<N> {
+    pub fn new(bytes: [u8; N]) -> Self {
+        Self {
+            key: ArrayKey::new_from_slice(&bytes),
+        }
+    }
+
+    /// The bytes this key holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key holds fewer than `N` bytes, which only happens for keys built from
+    /// partial byte strings with [`KeyTrait::new_from_slice`].
+    pub fn to_array(&self) -> [u8; N] {
+        self.key
+            .as_ref()
+            .try_into()
+            .expect("key does not hold a complete array")
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for FixedKey<N> {
+    fn as_ref(&self) -> &[u8] {
+        self.key.as_ref()
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for FixedKey<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for FixedKey<N> {
+    fn from(bytes: &[u8; N]) -> Self {
+        Self::new(*bytes)
+    }
+}
+
+impl<const N: usize> From<FixedKey<N>> for [u8; N] {
+    fn from(key: FixedKey<N>) -> Self {
+        key.to_array()
+    }
+}
+
+impl<const N: usize> From<FixedKey<N>> for ArrPartial<N> {
+    fn from(key: FixedKey<N>) -> Self {
+        key.key.into()
+    }
+}
+
+impl<const N: usize> KeyTrait for FixedKey<N> {
+    type PartialType = ArrPartial<N>;
+    const MAXIMUM_SIZE: Option<usize> = Some(N);
+
+    fn new_from_slice(slice: &[u8]) -> Self {
+        Self {
+            key: ArrayKey::new_from_slice(slice),
+        }
+    }
+
+    fn new_from_partial(partial: &Self::PartialType) -> Self {
+        Self {
+            key: ArrayKey::new_from_partial(partial),
+        }
+    }
+
+    fn extend_from_partial(&self, partial: &Self::PartialType) -> Self {
+        Self {
+            key: self.key.extend_from_partial(partial),
+        }
+    }
+
+    fn truncated(&self, at_depth: usize) -> Self {
+        Self {
+            key: self.key.truncated(at_depth),
+        }
+    }
+
+    #[inline(always)]
+    fn at(&self, pos: usize) -> u8 {
+        self.key.at(pos)
+    }
+
+    #[inline(always)]
+    fn length_at(&self, at_depth: usize) -> usize {
+        self.key.length_at(at_depth)
+    }
+
+    fn to_partial(&self, at_depth: usize) -> ArrPartial<N> {
+        self.key.to_partial(at_depth)
+    }
+
+    #[inline(always)]
+    fn matches_slice(&self, slice: &[u8]) -> bool {
+        self.key.matches_slice(slice)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FixedKey, IntKey, KeyInteger};
+    use crate::keys::KeyTrait;
+    use crate::partials::vector_partial::VectorPartial;
+    use crate::{AdaptiveRadixTree, VersionedAdaptiveRadixTree};
+
+    #[test]
+    fn make_extend_truncate() {
+        let b: Box<[u8]> = Box::from(&b"hel"[..]);
+        let b2 = b.extend_from_partial(&VectorPartial::from_slice(b"lo"));
+        assert!(b2.matches_slice(b"hello"));
+        assert!(b2.truncated(3).matches_slice(b"hel"));
+    }
+
+    #[test]
+    fn vec_truncate_is_not_shadowed_by_the_key_impl() {
+        let mut v = b"hello".to_vec();
+        v.truncate(2);
+        assert_eq!(v, b"he");
+        assert_eq!(v.truncated(1), b"h");
+    }
+
+    #[test]
+    fn boxed_keys_in_tree() {
+        let mut tree = AdaptiveRadixTree::<Box<[u8]>, u32>::new();
+        tree.insert(b"apple".to_vec(), 1);
+        tree.insert(b"app".to_vec(), 2);
+        tree.insert(&b"banana"[..], 3);
+        // String bytes are stored without a terminator.
+        tree.insert("band".as_bytes(), 4);
+
+        assert_eq!(tree.get(&b"app"[..]), Some(&2));
+        assert_eq!(tree.get("band".as_bytes()), Some(&4));
+
+        let keys: Vec<Box<[u8]>> = tree.iter().map(|(k, _)| k).collect();
+        let expected: Vec<Box<[u8]>> = [&b"app"[..], b"apple", b"banana", b"band"]
+            .into_iter()
+            .map(Box::from)
+            .collect();
+        assert_eq!(keys, expected);
+
+        let prefixed: Vec<u32> = tree.prefix_iter(&b"ap"[..]).map(|(_, v)| *v).collect();
+        assert_eq!(prefixed, vec![2, 1]);
+        assert_eq!(
+            tree.longest_prefix_match(&b"apples"[..]),
+            Some((Box::from(&b"apple"[..]), &1))
+        );
+    }
+
+    #[test]
+    fn boxed_keys_in_versioned_tree() {
+        let mut tree = VersionedAdaptiveRadixTree::<Box<[u8]>, u32>::new();
+        tree.insert(Box::<[u8]>::from(&b"one"[..]), 1);
+        let snapshot = tree.snapshot();
+        tree.insert(Box::<[u8]>::from(&b"two"[..]), 2);
+
+        assert_eq!(snapshot.get(Box::<[u8]>::from(&b"two"[..])), None);
+        let keys: Vec<Box<[u8]>> = tree.iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec![Box::from(&b"one"[..]), Box::from(&b"two"[..])]);
+    }
+
+    #[test]
+    fn vec_keys_in_tree() {
+        let mut tree = AdaptiveRadixTree::<Vec<u8>, u32>::new();
+        tree.insert(b"apple".to_vec(), 1);
+        tree.insert("app", 2);
+        tree.insert(&b"banana"[..], 3);
+
+        assert_eq!(tree.get("app"), Some(&2));
+        let keys: Vec<Vec<u8>> = tree.iter().map(|(k, _)| k).collect();
+        assert_eq!(
+            keys,
+            vec![b"app".to_vec(), b"apple".to_vec(), b"banana".to_vec()]
+        );
+        assert_eq!(
+            tree.longest_prefix_match("apples"),
+            Some((b"apple".to_vec(), &1))
+        );
+        let prefixed: Vec<u32> = tree.prefix_iter("ap").map(|(_, v)| *v).collect();
+        assert_eq!(prefixed, vec![2, 1]);
+    }
+
+    #[test]
+    fn integer_keys_round_trip_in_numeric_order() {
+        let mut tree = AdaptiveRadixTree::<IntKey<i32>, ()>::new();
+        let values = [i32::MIN, -70_000, -1, 0, 1, 255, 256, i32::MAX];
+        for value in values.into_iter().rev() {
+            tree.insert(value, ());
+        }
+
+        let keys: Vec<i32> = tree.iter().map(|(k, _)| k.get()).collect();
+        assert_eq!(keys, values);
+        assert_eq!(tree.get(-1), Some(&()));
+
+        for value in [u64::MIN, 1, u64::MAX] {
+            assert_eq!(IntKey::new(value).get(), value);
+            assert_eq!(u64::decode(IntKey::new(value).as_ref()), value);
+        }
+        assert_eq!(IntKey::new(i8::MIN).get(), i8::MIN);
+        assert_eq!(IntKey::new(-3isize).get(), -3);
+    }
+
+    #[test]
+    fn fixed_array_keys_round_trip() {
+        let mut tree = VersionedAdaptiveRadixTree::<FixedKey<4>, u32>::new();
+        tree.insert([0, 0, 1, 2], 1);
+        tree.insert([0, 0, 1, 1], 2);
+        tree.insert([9, 0, 0, 0], 3);
+
+        let keys: Vec<[u8; 4]> = tree.iter().map(|(k, _)| k.into()).collect();
+        assert_eq!(keys, vec![[0, 0, 1, 1], [0, 0, 1, 2], [9, 0, 0, 0]]);
+        assert_eq!(tree.get([9, 0, 0, 0]), Some(&3));
+    }
+}
@@ -0,0 +1,250 @@
+//! Order-preserving variable-length integer encoding.
+//!
+//! Fixed-width big-endian integers keep numeric order but spend a full 8 (or 16) bytes, and as
+//! many trie levels, on values that are usually small. This module encodes integers as a single
+//! header byte giving the payload length, followed by the minimal big-endian payload:
+//!
+//! | value                 | encoding            |
+//! |-----------------------|---------------------|
+//! | `0u64`                | `00`                |
+//! | `1u64`                | `01 01`             |
+//! | `256u64`              | `02 01 00`          |
+//! | `-257i64`             | `7d fe ff`          |
+//! | `-1i64`               | `7f`                |
+//! | `0i64`                | `80`                |
+//! | `255i64`              | `81 ff`             |
+//!
+//! Unsigned values use headers `0x00..=0x10`. Signed values use `0x80 + n` for non-negative
+//! values with an `n` byte payload, and `0x7f - n` for negative values, whose payload is the low
+//! `n` bytes of the two's complement value. In both cases a larger header means a larger number,
+//! and within a header the big-endian payload orders values, so byte order equals numeric order.
+//! The header fixes the length of the encoding, so no encoding is a prefix of another and the
+//! tree's prefix semantics are unaffected.
+//!
+//! The encoding of a value does not depend on the integer type it was encoded from: `5u8` and
+//! `5u64` produce the same bytes.
+//!
+//! ```rust
+//! use rart::keys::varint;
+//! use rart::{AdaptiveRadixTree, VectorKey};
+//!
+//! let mut tree = AdaptiveRadixTree::<VectorKey, &str>::new();
+//! tree.insert_k(&varint::unsigned_key(7u64), "seven");
+//! tree.insert_k(&varint::unsigned_key(300u64), "three hundred");
+//!
+//! let keys: Vec<u128> = tree
+//!     .iter()
+//!     .map(|(k, _)| varint::decode_unsigned(k.as_ref()).unwrap().0)
+//!     .collect();
+//! assert_eq!(keys, vec![7, 300]);
+//! ```
+
+use crate::keys::KeyTrait;
+use crate::keys::codec::KeyDecodeError;
+
+/// The largest possible encoding: a header byte and a 16 byte payload.
+pub const MAX_ENCODED_LEN: usize = 17;
+
+const SIGNED_ZERO_HEADER: u8 = 0x80;
+
+/// Encode `value` into `buf`, returning the number of bytes written.
+pub fn encode_unsigned_into(value: u128, buf: &mut [u8; MAX_ENCODED_LEN]) -> usize {
+    let n = 16 - (value.leading_zeros() as usize / 8);
+    buf[0] = n as u8;
+    buf[1..=n].copy_from_slice(&value.to_be_bytes()[16 - n..]);
+    n + 1
+}
+
+/// Encode `value` into `buf`, returning the number of bytes written.
+pub fn encode_signed_into(value: i128, buf: &mut [u8; MAX_ENCODED_LEN]) -> usize {
+    let bytes = value.to_be_bytes();
+    if value >= 0 {
+        let n = 16 - (value.leading_zeros() as usize / 8);
+        buf[0] = SIGNED_ZERO_HEADER + n as u8;
+        buf[1..=n].copy_from_slice(&bytes[16 - n..]);
+        n + 1
+    } else {
+        // Payload length is that of the magnitude minus one, i.e. of `!value`.
+        let n = 16 - ((!value).leading_zeros() as usize / 8);
+        buf[0] = SIGNED_ZERO_HEADER - 1 - n as u8;
+        buf[1..=n].copy_from_slice(&bytes[16 - n..]);
+        n + 1
+    }
+}
+
+/// Append the encoding of `value` to `out`.
+pub fn write_unsigned(value: u128, out: &mut Vec<u8>) {
+    let mut buf = [0; MAX_ENCODED_LEN];
+    let len = encode_unsigned_into(value, &mut buf);
+    out.extend_from_slice(&buf[..len]);
+}
+
+/// Append the encoding of `value` to `out`.
+pub fn write_signed(value: i128, out: &mut Vec<u8>) {
+    let mut buf = [0; MAX_ENCODED_LEN];
+    let len = encode_signed_into(value, &mut buf);
+    out.extend_from_slice(&buf[..len]);
+}
+
+/// Build a key holding the encoding of `value`.
+pub fn unsigned_key<K: KeyTrait>(value: impl Into<u128>) -> K {
+    let mut buf = [0; MAX_ENCODED_LEN];
+    let len = encode_unsigned_into(value.into(), &mut buf);
+    K::new_from_slice(&buf[..len])
+}
+
+/// Build a key holding the encoding of `value`.
+pub fn signed_key<K: KeyTrait>(value: impl Into<i128>) -> K {
+    let mut buf = [0; MAX_ENCODED_LEN];
+    let len = encode_signed_into(value.into(), &mut buf);
+    K::new_from_slice(&buf[..len])
+}
+
+/// The total encoded length implied by an unsigned header byte.
+pub fn unsigned_encoded_len(header: u8) -> Result<usize, KeyDecodeError> {
+    if header as usize > 16 {
+        return Err(KeyDecodeError::InvalidEncoding);
+    }
+    Ok(header as usize + 1)
+}
+
+/// The total encoded length implied by a signed header byte.
+pub fn signed_encoded_len(header: u8) -> Result<usize, KeyDecodeError> {
+    let n = if header >= SIGNED_ZERO_HEADER {
+        header - SIGNED_ZERO_HEADER
+    } else {
+        SIGNED_ZERO_HEADER - 1 - header
+    };
+    if n > 16 {
+        return Err(KeyDecodeError::InvalidEncoding);
+    }
+    Ok(n as usize + 1)
+}
+
+/// Decode an unsigned value from the start of `bytes`, returning it and the bytes consumed.
+///
+/// Fails with [`KeyDecodeError::InvalidEncoding`] for non-minimal payloads, so every value has
+/// exactly one accepted encoding.
+pub fn decode_unsigned(bytes: &[u8]) -> Result<(u128, usize), KeyDecodeError> {
+    let header = *bytes.first().ok_or(KeyDecodeError::UnexpectedEnd)?;
+    let len = unsigned_encoded_len(header)?;
+    let payload = bytes.get(1..len).ok_or(KeyDecodeError::UnexpectedEnd)?;
+    if payload.first() == Some(&0) {
+        return Err(KeyDecodeError::InvalidEncoding);
+    }
+    let mut be = [0u8; 16];
+    be[16 - payload.len()..].copy_from_slice(payload);
+    Ok((u128::from_be_bytes(be), len))
+}
+
+/// Decode a signed value from the start of `bytes`, returning it and the bytes consumed.
+///
+/// Fails with [`KeyDecodeError::InvalidEncoding`] for non-minimal payloads, so every value has
+/// exactly one accepted encoding.
+pub fn decode_signed(bytes: &[u8]) -> Result<(i128, usize), KeyDecodeError> {
+    let header = *bytes.first().ok_or(KeyDecodeError::UnexpectedEnd)?;
+    let len = signed_encoded_len(header)?;
+    let payload = bytes.get(1..len).ok_or(KeyDecodeError::UnexpectedEnd)?;
+    let negative = header < SIGNED_ZERO_HEADER;
+    let (fill, redundant) = if negative { (0xFF, 0xFF) } else { (0, 0) };
+    if payload.first() == Some(&redundant) {
+        return Err(KeyDecodeError::InvalidEncoding);
+    }
+    let mut be = [fill; 16];
+    be[16 - payload.len()..].copy_from_slice(payload);
+    Ok((i128::from_be_bytes(be), len))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn unsigned(value: u128) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_unsigned(value, &mut out);
+        out
+    }
+
+    fn signed(value: i128) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_signed(value, &mut out);
+        out
+    }
+
+    #[test]
+    fn documented_encodings() {
+        assert_eq!(unsigned(0), vec![0x00]);
+        assert_eq!(unsigned(1), vec![0x01, 0x01]);
+        assert_eq!(unsigned(256), vec![0x02, 0x01, 0x00]);
+        assert_eq!(signed(-257), vec![0x7d, 0xfe, 0xff]);
+        assert_eq!(signed(-1), vec![0x7f]);
+        assert_eq!(signed(0), vec![0x80]);
+        assert_eq!(signed(255), vec![0x81, 0xff]);
+        assert_eq!(signed(-256), vec![0x7e, 0x00]);
+    }
+
+    #[test]
+    fn unsigned_order_and_round_trip() {
+        let mut values = vec![0u128, 1, 127, 128, 255, 256, 65535, 65536, u64::MAX as u128];
+        values.push(u128::MAX);
+        for pair in values.windows(2) {
+            assert!(unsigned(pair[0]) < unsigned(pair[1]), "{pair:?}");
+        }
+        for v in values {
+            let encoded = unsigned(v);
+            assert_eq!(decode_unsigned(&encoded), Ok((v, encoded.len())));
+        }
+    }
+
+    #[test]
+    fn signed_order_and_round_trip() {
+        let values = [
+            i128::MIN,
+            i64::MIN as i128,
+            -65537,
+            -65536,
+            -257,
+            -256,
+            -255,
+            -129,
+            -128,
+            -2,
+            -1,
+            0,
+            1,
+            127,
+            128,
+            255,
+            256,
+            i64::MAX as i128,
+            i128::MAX,
+        ];
+        for pair in values.windows(2) {
+            assert!(signed(pair[0]) < signed(pair[1]), "{pair:?}");
+        }
+        for v in values {
+            let encoded = signed(v);
+            assert_eq!(decode_signed(&encoded), Ok((v, encoded.len())));
+        }
+    }
+
+    #[test]
+    fn rejects_non_minimal_and_truncated_input() {
+        assert_eq!(
+            decode_unsigned(&[0x02, 0x00, 0x01]),
+            Err(KeyDecodeError::InvalidEncoding)
+        );
+        assert_eq!(
+            decode_signed(&[0x7e, 0xff]),
+            Err(KeyDecodeError::InvalidEncoding)
+        );
+        assert_eq!(
+            decode_unsigned(&[0x11]),
+            Err(KeyDecodeError::InvalidEncoding)
+        );
+        assert_eq!(
+            decode_unsigned(&[0x02, 0x01]),
+            Err(KeyDecodeError::UnexpectedEnd)
+        );
+    }
+}
@@ -99,7 +99,7 @@ impl KeyTrait for VectorKey {
         }
     }
 
-    fn truncate(&self, at_depth: usize) -> Self {
+    fn truncated(&self, at_depth: usize) -> Self {
         let mut v = self.data.to_vec();
         v.truncate(at_depth);
         Self {
@@ -220,7 +220,7 @@ mod test {
         let p = VectorPartial::from_slice(b"lo");
         let k2 = k.extend_from_partial(&p);
         assert!(k2.matches_slice(b"hello"));
-        let k3 = k2.truncate(3);
+        let k3 = k2.truncated(3);
         assert!(k3.matches_slice(b"hel"));
     }
 
@@ -37,10 +37,11 @@
 //!
 //! ## Key Types
 //!
-//! RART supports three main key types:
+//! RART supports four main key types:
 //!
 //! - [`ArrayKey<N>`]: Fixed-size keys up to N bytes, stack-allocated
 //! - [`VectorKey`]: Variable-size keys, heap-allocated
+//! - [`ArcKey`]: Variable-size keys in shared storage, cheap to clone and share across threads
 //! - [`OverflowKey<K, P>`]: Variable-size keys with inline storage for short keys and boxed
 //!   overflow for longer keys
 //!
@@ -60,7 +61,11 @@
 //! ```
 
 // Private implementation modules
+mod epoch;
+mod join;
 mod node;
+mod parallel;
+mod sync;
 
 // Internal modules (public for benchmarking, not part of stable API)
 #[doc(hidden)]
@@ -69,13 +74,20 @@ pub mod mapping;
 pub mod utils;
 
 // Public API modules
+pub mod concurrent_tree;
 pub mod iter;
 pub mod keys;
 pub mod partials;
 pub mod range;
+pub mod routing;
+pub mod sharded_tree;
+pub mod shared_ptr;
+pub mod shared_tree;
 pub mod stats;
+pub mod transaction;
 pub mod tree;
 pub mod versioned_tree;
+pub mod write_batch;
 
 /// Controls whether callback-based traversal should continue visiting entries.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -97,12 +109,27 @@ pub enum SlotUpdate<V> {
     Insert(V),
 }
 
+/// Which tree an entry reported by a two-tree traversal belongs to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Side<T> {
+    /// The entry is only in the tree the method was called on.
+    Left(T),
+    /// The entry is only in the other tree.
+    Right(T),
+}
+
 // Re-export main types for convenience
+pub use concurrent_tree::ConcurrentAdaptiveRadixTree;
 pub use iter::LendingKeyView;
 pub use keys::{
-    KeyTrait, array_key::ArrayKey, overflow_key::OverflowKey, overflow_key::OverflowKeyBuilder,
-    vector_key::VectorKey,
+    KeyTrait, arc_key::ArcKey, array_key::ArrayKey, overflow_key::OverflowKey,
+    overflow_key::OverflowKeyBuilder, vector_key::VectorKey,
 };
 pub use partials::Partial;
+#[cfg(feature = "derive")]
+pub use rart_derive::ArtKey;
+pub use sharded_tree::ShardedAdaptiveRadixTree;
+pub use shared_tree::SharedVersionedTree;
+pub use transaction::Transaction;
 pub use tree::AdaptiveRadixTree;
-pub use versioned_tree::VersionedAdaptiveRadixTree;
+pub use versioned_tree::{PersistentAdaptiveRadixTree, VersionedAdaptiveRadixTree};
@@ -2,7 +2,7 @@ use std::mem::MaybeUninit;
 
 use crate::mapping::NodeMapping;
 use crate::mapping::indexed_mapping::IndexedMapping;
-use crate::utils::bitset::BitsetTrait;
+use crate::utils::bitset::{Bitset64, BitsetTrait};
 use crate::utils::u8_keys::{
     u8_keys_find_insert_position_sorted, u8_keys_find_key_position_sorted,
 };
@@ -160,6 +160,15 @@ impl<N, const WIDTH: usize> SortedKeyedMapping<N, WIDTH> {
         }
     }
 
+    /// The keys present in the mapping as a set.
+    pub(crate) fn key_set(&self) -> Bitset64<4> {
+        let mut set = Bitset64::new();
+        for key in &self.keys[..self.num_children as usize] {
+            set.set(*key as usize);
+        }
+        set
+    }
+
     #[inline]
     pub(crate) fn add_child_sorted(&mut self, key: u8, node: N) {
         let idx = self.num_children as usize;
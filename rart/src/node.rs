@@ -1,3 +1,4 @@
+use crate::join::NodeView;
 use crate::mapping::direct_mapping::DirectMapping;
 use crate::mapping::direct_mapping::DirectMappingIter;
 use crate::mapping::indexed_mapping::IndexedMapping;
@@ -217,6 +218,34 @@ impl<P: Partial, V> Node<P, V> for DefaultNode<P, V> {
     }
 }
 
+impl<P: Partial, V> NodeView for DefaultNode<P, V> {
+    type Value = V;
+
+    fn view_prefix(&self) -> &[u8] {
+        self.prefix.as_ref()
+    }
+
+    fn view_value(&self) -> Option<&V> {
+        self.value()
+    }
+
+    fn view_num_children(&self) -> usize {
+        self.num_children()
+    }
+
+    fn view_child(&self, edge: u8) -> Option<&Self> {
+        self.seek_child(edge)
+    }
+
+    fn view_child_key_set(&self) -> Bitset64<4> {
+        self.child_key_set()
+    }
+
+    fn view_children(&self) -> impl Iterator<Item = (u8, &Self)> {
+        self.iter()
+    }
+}
+
 impl<P: Partial, V> DefaultNode<P, V> {
     #[inline]
     #[allow(dead_code)]
@@ -345,6 +374,85 @@ impl<P: Partial, V> DefaultNode<P, V> {
             Content::Empty => unreachable!("bulk-loaded inner nodes have child storage"),
         }
     }
+
+    /// The set of edge bytes this node has children under.
+    pub(crate) fn child_key_set(&self) -> Bitset64<4> {
+        match &self.content {
+            Content::Node4(km) => km.key_set(),
+            Content::Node16(km) => km.key_set(),
+            Content::Node48(im) => im.child_ptr_indexes.bitset.clone(),
+            Content::Node256(dm) => dm.children.bitset.clone(),
+            Content::Empty => Bitset64::new(),
+        }
+    }
+
+    /// Creates an inner node with the smallest layout that holds `num_children` children.
+    pub(crate) fn with_child_capacity(prefix: P, num_children: usize) -> Self {
+        match num_children {
+            0..=4 => Self::new_4(prefix),
+            5..=16 => Self::new_16(prefix),
+            17..=48 => Self::new_48(prefix),
+            _ => Self::new_256(prefix),
+        }
+    }
+
+    /// Builds a node from its parts. `children` must be sorted by edge byte; a node without
+    /// children becomes a leaf and must then carry a value.
+    pub(crate) fn from_sorted_children(
+        prefix: P,
+        value: Option<V>,
+        children: Vec<(u8, Self)>,
+    ) -> Self {
+        if children.is_empty() {
+            debug_assert!(value.is_some(), "leaf nodes must hold a value");
+            return Self {
+                prefix,
+                value,
+                content: Content::Empty,
+            };
+        }
+
+        let mut node = Self::with_child_capacity(prefix, children.len());
+        node.value = value;
+        for (key, child) in children {
+            node.add_child_sorted_unchecked(key, child);
+        }
+        node
+    }
+
+    /// Takes the node apart into its prefix, value, and children in edge-byte order.
+    pub(crate) fn into_parts(self) -> (P, Option<V>, Vec<(u8, Self)>) {
+        let children = match self.content {
+            Content::Node4(km) => km.into_iter().collect(),
+            Content::Node16(km) => km.into_iter().collect(),
+            Content::Node48(im) => im.into_iter().collect(),
+            Content::Node256(dm) => dm.into_iter().collect(),
+            Content::Empty => Vec::new(),
+        };
+        (self.prefix, self.value, children)
+    }
+
+    /// Deep-copies this node and everything below it.
+    pub(crate) fn clone_subtree(&self) -> Self
+    where
+        V: Clone,
+    {
+        let prefix = self.prefix.partial_before(self.prefix.len());
+        if self.is_leaf() {
+            return Self {
+                prefix,
+                value: self.value.clone(),
+                content: Content::Empty,
+            };
+        }
+
+        let mut node = Self::with_child_capacity(prefix, self.num_children());
+        node.value = self.value.clone();
+        for (key, child) in self.iter() {
+            node.add_child_sorted_unchecked(key, child.clone_subtree());
+        }
+        node
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,94 @@
+//! Scoped-thread helpers for splitting tree work across cores.
+//!
+//! Work is split at the top-level children of a tree, so these helpers only deal in flat lists
+//! of tasks. Panics in a worker are re-raised on the calling thread with their original
+//! payload.
+
+use std::num::NonZeroUsize;
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// The number of threads to spread work over.
+pub(crate) fn worker_count() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// Run `work` on every item, handing items out one at a time to up to [`worker_count`]
+/// threads so that uneven items balance out.
+pub(crate) fn for_each<T, F>(items: &[T], work: F)
+where
+    T: Sync,
+    F: Fn(&T) + Sync,
+{
+    let workers = worker_count().min(items.len());
+    if workers <= 1 {
+        items.iter().for_each(work);
+        return;
+    }
+
+    let next = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    while let Some(item) = items.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        work(item);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            if let Err(payload) = handle.join() {
+                panic::resume_unwind(payload);
+            }
+        }
+    });
+}
+
+/// Map `tasks` to results in order, giving each of up to [`worker_count`] threads a
+/// contiguous run of tasks with about the same total `weight`.
+pub(crate) fn map_weighted<T, R, W, F>(tasks: Vec<T>, weight: W, work: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    W: Fn(&T) -> usize,
+    F: Fn(T) -> R + Sync,
+{
+    let workers = worker_count().min(tasks.len());
+    if workers <= 1 {
+        return tasks.into_iter().map(work).collect();
+    }
+
+    let total: usize = tasks.iter().map(&weight).sum();
+    let target = total.div_ceil(workers).max(1);
+    let mut batches = vec![Vec::new()];
+    let mut batch_weight = 0;
+    for task in tasks {
+        if batch_weight >= target {
+            batches.push(Vec::new());
+            batch_weight = 0;
+        }
+        batch_weight += weight(&task);
+        batches
+            .last_mut()
+            .expect("batches is never empty")
+            .push(task);
+    }
+
+    let work = &work;
+    thread::scope(|scope| {
+        let handles: Vec<_> = batches
+            .into_iter()
+            .map(|batch| scope.spawn(move || batch.into_iter().map(work).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|payload| panic::resume_unwind(payload))
+            })
+            .collect()
+    })
+}
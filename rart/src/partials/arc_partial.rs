@@ -0,0 +1,123 @@
+use std::cmp::min;
+use std::sync::Arc;
+
+use crate::keys::KeyTrait;
+use crate::keys::arc_key::ArcKey;
+use crate::partials::{Partial, prefix_length_bytes};
+
+/// Partial key type for [`ArcKey`], backed by a shared `Arc<[u8]>`.
+///
+/// A partial covering a whole key shares the key's allocation, so converting between the two is
+/// refcount-only.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ArcPartial {
+    data: Arc<[u8]>,
+}
+
+impl AsRef<[u8]> for ArcPartial {
+    fn as_ref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl ArcPartial {
+    pub fn key(src: &[u8]) -> Self {
+        let mut data = Vec::with_capacity(src.len() + 1);
+        data.extend_from_slice(src);
+        data.push(0);
+        Self { data: data.into() }
+    }
+
+    pub fn from_slice(src: &[u8]) -> Self {
+        Self { data: src.into() }
+    }
+
+    pub fn from_arc(data: Arc<[u8]>) -> Self {
+        Self { data }
+    }
+
+    pub fn to_arc(&self) -> Arc<[u8]> {
+        self.data.clone()
+    }
+
+    pub fn to_slice(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl From<&[u8]> for ArcPartial {
+    fn from(src: &[u8]) -> Self {
+        Self::from_slice(src)
+    }
+}
+
+impl Partial for ArcPartial {
+    fn partial_before(&self, length: usize) -> Self {
+        debug_assert!(length <= self.data.len());
+        if length == self.data.len() {
+            return self.clone();
+        }
+        ArcPartial::from_slice(&self.data[..length])
+    }
+
+    fn partial_from(&self, src_offset: usize, length: usize) -> Self {
+        debug_assert!(src_offset + length <= self.data.len());
+        if src_offset == 0 && length == self.data.len() {
+            return self.clone();
+        }
+        ArcPartial::from_slice(&self.data[src_offset..src_offset + length])
+    }
+
+    fn partial_after(&self, start: usize) -> Self {
+        debug_assert!(start <= self.data.len());
+        if start == 0 {
+            return self.clone();
+        }
+        ArcPartial::from_slice(&self.data[start..])
+    }
+
+    fn partial_extended_with(&self, other: &Self) -> Self {
+        let mut data = Vec::with_capacity(self.data.len() + other.data.len());
+        data.extend_from_slice(&self.data);
+        data.extend_from_slice(&other.data);
+        Self { data: data.into() }
+    }
+
+    #[inline(always)]
+    fn at(&self, pos: usize) -> u8 {
+        debug_assert!(pos < self.data.len());
+        self.data[pos]
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn prefix_length_common(&self, other: &Self) -> usize {
+        self.prefix_length_slice(other.to_slice())
+    }
+
+    fn prefix_length_key<'a, K>(&self, key: &'a K, at_depth: usize) -> usize
+    where
+        K: KeyTrait<PartialType = Self> + 'a,
+    {
+        let len = min(self.data.len(), key.length_at(at_depth));
+        prefix_length_bytes(&self.data[..len], &key.as_ref()[at_depth..at_depth + len])
+    }
+
+    fn prefix_length_slice(&self, slice: &[u8]) -> usize {
+        let len = min(self.data.len(), slice.len());
+        prefix_length_bytes(&self.data[..len], &slice[..len])
+    }
+
+    fn to_slice(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl From<ArcKey> for ArcPartial {
+    fn from(value: ArcKey) -> Self {
+        value.to_partial(0)
+    }
+}
@@ -8,6 +8,7 @@
 //!
 //! - [`ArrPartial<N>`](array_partial::ArrPartial): Fixed-size partial keys up to N bytes
 //! - [`VectorPartial`](vector_partial::VectorPartial): Variable-size partial keys
+//! - [`ArcPartial`](arc_partial::ArcPartial): Variable-size partial keys in shared storage
 //! - [`OverflowPartial<N>`](overflow_partial::OverflowPartial): Inline partial storage with boxed
 //!   overflow for longer prefixes
 //!
@@ -26,6 +27,7 @@
 
 use crate::keys::KeyTrait;
 
+pub mod arc_partial;
 pub mod array_partial;
 pub mod overflow_partial;
 pub mod vector_partial;
@@ -42,6 +42,20 @@ impl From<&[u8]> for VectorPartial {
     }
 }
 
+impl From<Vec<u8>> for VectorPartial {
+    fn from(src: Vec<u8>) -> Self {
+        Self {
+            data: src.into_boxed_slice(),
+        }
+    }
+}
+
+impl From<Box<[u8]>> for VectorPartial {
+    fn from(src: Box<[u8]>) -> Self {
+        Self { data: src }
+    }
+}
+
 impl Partial for VectorPartial {
     fn partial_before(&self, length: usize) -> Self {
         debug_assert!(length <= self.data.len());
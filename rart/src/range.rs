@@ -3,11 +3,13 @@
 //! This module provides efficient range iteration over Adaptive Radix Trees,
 //! allowing traversal of key-value pairs within specified bounds.
 
+use std::cmp::Ordering;
 use std::collections::Bound;
+use std::ops::RangeBounds;
 
 use crate::iter::Iter;
 use crate::keys::KeyTrait;
-use crate::partials::Partial;
+use crate::partials::{Partial, prefix_length_bytes};
 
 enum InnerResult<'a, K, V> {
     Iter(Option<(K, &'a V)>),
@@ -85,3 +87,67 @@ impl<'a, K: KeyTrait + 'a, V> Range<'a, K, V> {
         }
     }
 }
+
+/// A key range reduced to borrowed byte bounds, used to prune trie traversals that visit
+/// whole subtrees at a time.
+pub(crate) struct ByteRange<'k> {
+    start: Bound<&'k [u8]>,
+    end: Bound<&'k [u8]>,
+}
+
+impl<'k> ByteRange<'k> {
+    pub(crate) fn new<K: KeyTrait + 'k, R: RangeBounds<K>>(range: &'k R) -> Self {
+        Self {
+            start: range.start_bound().map(|key| key.as_ref()),
+            end: range.end_bound().map(|key| key.as_ref()),
+        }
+    }
+
+    pub(crate) fn from_bounds(start: Bound<&'k [u8]>, end: Bound<&'k [u8]>) -> Self {
+        Self { start, end }
+    }
+
+    /// The bytes every key in the range must start with. Empty unless both ends are bounded.
+    pub(crate) fn common_prefix(&self) -> &'k [u8] {
+        match (self.start, self.end) {
+            (
+                Bound::Included(start) | Bound::Excluded(start),
+                Bound::Included(end) | Bound::Excluded(end),
+            ) => &start[..prefix_length_bytes(start, end)],
+            _ => &[],
+        }
+    }
+
+    /// Whether any key starting with `path` can fall inside the range.
+    pub(crate) fn may_contain_prefixed(&self, path: &[u8]) -> bool {
+        if let Bound::Included(start) | Bound::Excluded(start) = self.start {
+            let len = path.len().min(start.len());
+            if path[..len] < start[..len] {
+                return false;
+            }
+        }
+        if let Bound::Included(end) | Bound::Excluded(end) = self.end {
+            let len = path.len().min(end.len());
+            match path[..len].cmp(&end[..len]) {
+                Ordering::Less => {}
+                Ordering::Equal if path.len() <= end.len() => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    pub(crate) fn contains(&self, key: &[u8]) -> bool {
+        let after_start = match self.start {
+            Bound::Included(start) => key >= start,
+            Bound::Excluded(start) => key > start,
+            Bound::Unbounded => true,
+        };
+        let before_end = match self.end {
+            Bound::Included(end) => key <= end,
+            Bound::Excluded(end) => key < end,
+            Bound::Unbounded => true,
+        };
+        after_start && before_end
+    }
+}
@@ -0,0 +1,403 @@
+//! IP routing tables with bit-granular CIDR prefixes.
+//!
+//! [`AdaptiveRadixTree`] matches prefixes at byte granularity, which cannot express routes such as
+//! `10.0.0.0/12` directly. [`IpRoutingTable`] stores IPv4 and IPv6 prefixes of any bit length by
+//! spelling each prefix bit as one key byte (`0` or `1`). Longest-prefix match, covering-prefix,
+//! and more-specific queries then map onto the tree's existing byte-prefix operations, and the
+//! tree's path compression collapses runs of bits without branching, so the structure behaves like
+//! a path-compressed binary trie.
+//!
+//! ## Key size
+//!
+//! Spelling bits as bytes makes keys eight times longer than the address: an IPv4 `/32` is a
+//! 32-byte key and an IPv6 `/128` a 128-byte one. Every query builds such a key (on the stack for
+//! IPv4, in one heap allocation for IPv6) and compares it byte by byte on the way down, and the
+//! path-compressed prefixes stored in the tree take one byte per bit of a route that no other
+//! route shares. Packing bits into octets would not avoid this cost: the tree only knows byte
+//! prefixes, so a route ending mid-octet (`10.0.0.0/12`) is a prefix of the addresses it covers
+//! only if every bit ends on a key byte. With packed keys, covering and longest-prefix queries
+//! would need a separate probe for each partial length under each octet (up to 129 exact
+//! lookups for an IPv6 address) instead of the single descent used here. Tables dominated by
+//! long IPv6 routes that need less memory should use a dedicated bit-level trie.
+//!
+//! ```rust
+//! use std::net::Ipv4Addr;
+//! use rart::routing::IpRoutingTable;
+//!
+//! let mut routes = IpRoutingTable::new();
+//! routes.insert(Ipv4Addr::new(10, 0, 0, 0), 8, "corp");
+//! routes.insert(Ipv4Addr::new(10, 0, 0, 0), 12, "datacenter");
+//!
+//! let (prefix, next_hop) = routes.longest_match(Ipv4Addr::new(10, 3, 2, 1)).unwrap();
+//! assert_eq!(prefix.to_string(), "10.0.0.0/12");
+//! assert_eq!(*next_hop, "datacenter");
+//!
+//! let (prefix, _) = routes.longest_match(Ipv4Addr::new(10, 16, 0, 1)).unwrap();
+//! assert_eq!(prefix.to_string(), "10.0.0.0/8");
+//! ```
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::keys::KeyTrait;
+use crate::keys::array_key::ArrayKey;
+use crate::keys::vector_key::VectorKey;
+use crate::tree::AdaptiveRadixTree;
+
+type V4Key = ArrayKey<32>;
+type V6Key = VectorKey;
+
+/// An IPv4 or IPv6 network prefix: an address and a prefix length in bits.
+///
+/// Host bits beyond the prefix length are always zero.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct IpPrefix {
+    addr: IpAddr,
+    len: u8,
+}
+
+impl IpPrefix {
+    /// Build a prefix, clearing host bits. Returns `None` if `len` exceeds the address width.
+    pub fn new(addr: impl Into<IpAddr>, len: u8) -> Option<Self> {
+        let addr = addr.into();
+        if len > max_len(&addr) {
+            return None;
+        }
+        let addr = match addr {
+            IpAddr::V4(a) => IpAddr::V4(Ipv4Addr::from(
+                u32::from(a) & u32::MAX.checked_shl(32 - len as u32).unwrap_or(0),
+            )),
+            IpAddr::V6(a) => IpAddr::V6(Ipv6Addr::from(
+                u128::from(a) & u128::MAX.checked_shl(128 - len as u32).unwrap_or(0),
+            )),
+        };
+        Some(Self { addr, len })
+    }
+
+    /// The network address, with host bits cleared.
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// The prefix length in bits.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u8 {
+        self.len
+    }
+
+    /// Returns true if `addr` falls within this prefix.
+    pub fn contains(&self, addr: impl Into<IpAddr>) -> bool {
+        let addr = addr.into();
+        if addr.is_ipv4() != self.addr.is_ipv4() {
+            return false;
+        }
+        IpPrefix::new(addr, self.len).is_some_and(|p| p.addr == self.addr)
+    }
+}
+
+impl fmt::Display for IpPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.len)
+    }
+}
+
+fn max_len(addr: &IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+/// Spell the first `len` bits of `octets` as one `0`/`1` byte per bit, so a key is `len` bytes
+/// long (see the module docs for why bits are not packed).
+fn bit_key<K: KeyTrait>(octets: &[u8], len: u8) -> K {
+    let mut bits = [0u8; 128];
+    for (i, bit) in bits[..len as usize].iter_mut().enumerate() {
+        *bit = (octets[i / 8] >> (7 - i % 8)) & 1;
+    }
+    K::new_from_slice(&bits[..len as usize])
+}
+
+/// Inverse of [`bit_key`]: pack bit bytes back into `octets`, returning the prefix length.
+fn unpack_bits(bits: &[u8], octets: &mut [u8]) -> u8 {
+    for (i, &bit) in bits.iter().enumerate() {
+        octets[i / 8] |= bit << (7 - i % 8);
+    }
+    bits.len() as u8
+}
+
+fn v4_prefix(key: &V4Key) -> IpPrefix {
+    let mut octets = [0u8; 4];
+    let len = unpack_bits(key.as_ref(), &mut octets);
+    IpPrefix {
+        addr: IpAddr::V4(Ipv4Addr::from(octets)),
+        len,
+    }
+}
+
+fn v6_prefix(key: &V6Key) -> IpPrefix {
+    let mut octets = [0u8; 16];
+    let len = unpack_bits(key.as_ref(), &mut octets);
+    IpPrefix {
+        addr: IpAddr::V6(Ipv6Addr::from(octets)),
+        len,
+    }
+}
+
+/// A routing table mapping IPv4 and IPv6 CIDR prefixes of arbitrary bit length to values.
+///
+/// IPv4 and IPv6 routes are kept in separate trees; queries for one family never match routes
+/// of the other. Methods taking an address and a prefix length panic if the length exceeds the
+/// address width (32 for IPv4, 128 for IPv6); host bits beyond the prefix length are ignored.
+///
+/// Keys take one byte per prefix bit; see the [module docs](self#key-size) for what that costs.
+pub struct IpRoutingTable<V> {
+    v4: AdaptiveRadixTree<V4Key, V>,
+    v6: AdaptiveRadixTree<V6Key, V>,
+}
+
+impl<V> Default for IpRoutingTable<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> IpRoutingTable<V> {
+    pub fn new() -> Self {
+        Self {
+            v4: AdaptiveRadixTree::new(),
+            v6: AdaptiveRadixTree::new(),
+        }
+    }
+
+    /// Number of routes in the table.
+    pub fn len(&self) -> usize {
+        self.v4.len() + self.v6.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.v4.is_empty() && self.v6.is_empty()
+    }
+
+    /// Insert a route, returning the previous value for the same prefix.
+    pub fn insert(&mut self, addr: impl Into<IpAddr>, prefix_len: u8, value: V) -> Option<V> {
+        match checked_prefix(addr, prefix_len).addr {
+            IpAddr::V4(a) => self.v4.insert_k(&bit_key(&a.octets(), prefix_len), value),
+            IpAddr::V6(a) => self.v6.insert_k(&bit_key(&a.octets(), prefix_len), value),
+        }
+    }
+
+    /// Remove a route, returning its value.
+    pub fn remove(&mut self, addr: impl Into<IpAddr>, prefix_len: u8) -> Option<V> {
+        match checked_prefix(addr, prefix_len).addr {
+            IpAddr::V4(a) => self.v4.remove_k(&bit_key(&a.octets(), prefix_len)),
+            IpAddr::V6(a) => self.v6.remove_k(&bit_key(&a.octets(), prefix_len)),
+        }
+    }
+
+    /// Look up the route for exactly this prefix.
+    pub fn get(&self, addr: impl Into<IpAddr>, prefix_len: u8) -> Option<&V> {
+        match checked_prefix(addr, prefix_len).addr {
+            IpAddr::V4(a) => self.v4.get_k(&bit_key(&a.octets(), prefix_len)),
+            IpAddr::V6(a) => self.v6.get_k(&bit_key(&a.octets(), prefix_len)),
+        }
+    }
+
+    /// The most specific route containing `addr`.
+    pub fn longest_match(&self, addr: impl Into<IpAddr>) -> Option<(IpPrefix, &V)> {
+        match addr.into() {
+            IpAddr::V4(a) => self
+                .v4
+                .longest_prefix_match_k(&bit_key(&a.octets(), 32))
+                .map(|(k, v)| (v4_prefix(&k), v)),
+            IpAddr::V6(a) => self
+                .v6
+                .longest_prefix_match_k(&bit_key(&a.octets(), 128))
+                .map(|(k, v)| (v6_prefix(&k), v)),
+        }
+    }
+
+    /// The value of the most specific route containing `addr`.
+    pub fn longest_match_value(&self, addr: impl Into<IpAddr>) -> Option<&V> {
+        match addr.into() {
+            IpAddr::V4(a) => self.v4.longest_prefix_value_k(&bit_key(&a.octets(), 32)),
+            IpAddr::V6(a) => self.v6.longest_prefix_value_k(&bit_key(&a.octets(), 128)),
+        }
+    }
+
+    /// Every route covering the given prefix, from least to most specific, including the prefix
+    /// itself if present. Pass a full-length prefix to find every route containing an address.
+    pub fn covering(
+        &self,
+        addr: impl Into<IpAddr>,
+        prefix_len: u8,
+    ) -> Box<dyn Iterator<Item = (IpPrefix, &V)> + '_> {
+        match checked_prefix(addr, prefix_len).addr {
+            IpAddr::V4(a) => Box::new(
+                self.v4
+                    .prefix_match_iter_k(&bit_key(&a.octets(), prefix_len))
+                    .map(|(k, v)| (v4_prefix(&k), v)),
+            ),
+            IpAddr::V6(a) => Box::new(
+                self.v6
+                    .prefix_match_iter_k(&bit_key(&a.octets(), prefix_len))
+                    .map(|(k, v)| (v6_prefix(&k), v)),
+            ),
+        }
+    }
+
+    /// Every route strictly more specific than the given prefix, in address order. The prefix
+    /// itself is not included; use [`IpRoutingTable::get`] for an exact match.
+    pub fn more_specifics(
+        &self,
+        addr: impl Into<IpAddr>,
+        prefix_len: u8,
+    ) -> Box<dyn Iterator<Item = (IpPrefix, &V)> + '_> {
+        match checked_prefix(addr, prefix_len).addr {
+            IpAddr::V4(a) => Box::new(
+                self.v4
+                    .prefix_iter_k(&bit_key(&a.octets(), prefix_len))
+                    .map(|(k, v)| (v4_prefix(&k), v))
+                    .filter(move |(p, _)| p.len > prefix_len),
+            ),
+            IpAddr::V6(a) => Box::new(
+                self.v6
+                    .prefix_iter_k(&bit_key(&a.octets(), prefix_len))
+                    .map(|(k, v)| (v6_prefix(&k), v))
+                    .filter(move |(p, _)| p.len > prefix_len),
+            ),
+        }
+    }
+
+    /// Every route, IPv4 before IPv6, each in address order with shorter prefixes first.
+    pub fn iter(&self) -> impl Iterator<Item = (IpPrefix, &V)> + '_ {
+        self.v4
+            .iter()
+            .map(|(k, v)| (v4_prefix(&k), v))
+            .chain(self.v6.iter().map(|(k, v)| (v6_prefix(&k), v)))
+    }
+}
+
+fn checked_prefix(addr: impl Into<IpAddr>, prefix_len: u8) -> IpPrefix {
+    let addr = addr.into();
+    IpPrefix::new(addr, prefix_len).unwrap_or_else(|| {
+        panic!(
+            "prefix length {prefix_len} exceeds the {} bit width of {addr}",
+            max_len(&addr)
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::{IpPrefix, IpRoutingTable};
+
+    fn v4(a: u8, b: u8, c: u8, d: u8) -> Ipv4Addr {
+        Ipv4Addr::new(a, b, c, d)
+    }
+
+    #[test]
+    fn prefix_masks_host_bits() {
+        let p = IpPrefix::new(v4(10, 15, 255, 1), 12).unwrap();
+        assert_eq!(p.to_string(), "10.0.0.0/12");
+        assert!(p.contains(v4(10, 15, 0, 0)));
+        assert!(!p.contains(v4(10, 16, 0, 0)));
+        assert!(IpPrefix::new(v4(1, 2, 3, 4), 33).is_none());
+        assert_eq!(
+            IpPrefix::new(v4(1, 2, 3, 4), 0).unwrap().to_string(),
+            "0.0.0.0/0"
+        );
+    }
+
+    #[test]
+    fn longest_match_at_bit_granularity() {
+        let mut routes = IpRoutingTable::new();
+        routes.insert(v4(0, 0, 0, 0), 0, "default");
+        routes.insert(v4(10, 0, 0, 0), 8, "ten");
+        routes.insert(v4(10, 0, 0, 0), 12, "ten-twelve");
+        routes.insert(v4(10, 16, 0, 0), 12, "ten-sixteen");
+        routes.insert(v4(192, 168, 1, 128), 25, "upper-half");
+
+        let lookup = |a| routes.longest_match_value(a).copied();
+        assert_eq!(lookup(v4(10, 15, 255, 255)), Some("ten-twelve"));
+        assert_eq!(lookup(v4(10, 16, 0, 0)), Some("ten-sixteen"));
+        assert_eq!(lookup(v4(10, 32, 0, 0)), Some("ten"));
+        assert_eq!(lookup(v4(192, 168, 1, 200)), Some("upper-half"));
+        assert_eq!(lookup(v4(192, 168, 1, 127)), Some("default"));
+
+        let (prefix, _) = routes.longest_match(v4(10, 17, 1, 1)).unwrap();
+        assert_eq!(prefix.to_string(), "10.16.0.0/12");
+    }
+
+    #[test]
+    fn covering_and_more_specific_routes() {
+        let mut routes = IpRoutingTable::new();
+        routes.insert(v4(10, 0, 0, 0), 8, 8);
+        routes.insert(v4(10, 0, 0, 0), 12, 12);
+        routes.insert(v4(10, 1, 0, 0), 16, 16);
+        routes.insert(v4(10, 1, 2, 0), 24, 24);
+        routes.insert(v4(10, 128, 0, 0), 9, 9);
+        routes.insert(v4(11, 0, 0, 0), 8, 0);
+
+        let covering: Vec<u8> = routes
+            .covering(v4(10, 1, 2, 3), 32)
+            .map(|(_, v)| *v)
+            .collect();
+        assert_eq!(covering, vec![8, 12, 16, 24]);
+        let covering: Vec<u8> = routes
+            .covering(v4(10, 1, 0, 0), 16)
+            .map(|(_, v)| *v)
+            .collect();
+        assert_eq!(covering, vec![8, 12, 16]);
+
+        let more: Vec<String> = routes
+            .more_specifics(v4(10, 0, 0, 0), 8)
+            .map(|(p, _)| p.to_string())
+            .collect();
+        assert_eq!(
+            more,
+            vec!["10.0.0.0/12", "10.1.0.0/16", "10.1.2.0/24", "10.128.0.0/9"]
+        );
+        assert_eq!(routes.more_specifics(v4(10, 1, 2, 0), 24).count(), 0);
+    }
+
+    #[test]
+    fn insert_remove_and_families() {
+        let mut routes = IpRoutingTable::new();
+        let v6: Ipv6Addr = "2001:db8::".parse().unwrap();
+        assert_eq!(routes.insert(v6, 32, "doc"), None);
+        assert_eq!(routes.insert(v6, 48, "site"), None);
+        assert_eq!(routes.insert(v4(0, 0, 0, 0), 0, "v4-default"), None);
+        assert_eq!(routes.insert(v6, 32, "doc2"), Some("doc"));
+        assert_eq!(routes.len(), 3);
+
+        let addr: Ipv6Addr = "2001:db8:1::1".parse().unwrap();
+        let (prefix, value) = routes.longest_match(addr).unwrap();
+        assert_eq!(
+            (prefix.to_string().as_str(), *value),
+            ("2001:db8::/32", "doc2")
+        );
+        let addr: Ipv6Addr = "2001:db8::1".parse().unwrap();
+        assert_eq!(routes.longest_match_value(addr), Some(&"site"));
+        let other: Ipv6Addr = "2001:db9::1".parse().unwrap();
+        assert_eq!(routes.longest_match(other), None);
+
+        assert_eq!(routes.remove(v6, 48), Some("site"));
+        assert_eq!(routes.get(v6, 48), None);
+        assert_eq!(routes.get(v6, 32), Some(&"doc2"));
+        let addr: Ipv6Addr = "2001:db8::1".parse().unwrap();
+        assert_eq!(routes.longest_match_value(addr), Some(&"doc2"));
+
+        let all: Vec<String> = routes.iter().map(|(p, _)| p.to_string()).collect();
+        assert_eq!(all, vec!["0.0.0.0/0", "2001:db8::/32"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_overlong_prefix() {
+        let mut routes = IpRoutingTable::new();
+        routes.insert(v4(1, 1, 1, 1), 33, ());
+    }
+}
@@ -0,0 +1,545 @@
+//! An Adaptive Radix Tree split into independently locked shards.
+//!
+//! [`ShardedAdaptiveRadixTree`] partitions the keyspace by the first one or two bytes of each
+//! key. Every shard is an ordinary [`AdaptiveRadixTree`] behind its own reader-writer lock, so
+//! writers to different shards never contend, and a batch writer can hold one shard's lock for
+//! the whole batch through a [`ShardWriteGuard`].
+//!
+//! Shards are numbered in key order: a key shorter than the shard prefix is sharded as if padded
+//! with zero bytes, which sorts before every longer key sharing its bytes. Walking shards in
+//! index order therefore visits keys in order, and a range or prefix query only needs the
+//! contiguous run of shards its bounds fall in.
+
+use std::ops::{Bound, Deref, RangeBounds, RangeInclusive};
+
+use crate::keys::KeyTrait;
+use crate::stats::{TreeStats, TreeStatsTrait, finish_tree_stats, merge_tree_stats};
+use crate::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use crate::tree::AdaptiveRadixTree;
+
+/// An [`AdaptiveRadixTree`] sharded by key prefix, with one lock per shard.
+///
+/// Each method locks the shards it needs one at a time. Single-key operations are atomic;
+/// operations spanning several shards (iteration, range and prefix queries, `len`, and stats)
+/// see each shard at a different moment and are not snapshots of the whole tree.
+///
+/// ```rust
+/// use rart::{ArrayKey, ShardedAdaptiveRadixTree};
+///
+/// let tree = ShardedAdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+/// tree.insert("apple", 1);
+/// tree.insert("banana", 2);
+///
+/// // Batch writes to one shard under a single lock.
+/// {
+///     let mut shard = tree.write_shard_for(&"apricot".into());
+///     shard.insert("apricot", 3);
+///     shard.insert("avocado", 4);
+/// }
+///
+/// assert_eq!(tree.len(), 4);
+/// assert_eq!(tree.get("apricot"), Some(3));
+///
+/// let mut values = Vec::new();
+/// // String keys end in a NUL byte, so match on the raw bytes of the prefix.
+/// tree.prefix_for_each(b"a", |_, value| values.push(*value));
+/// assert_eq!(values, vec![1, 3, 4]);
+/// ```
+pub struct ShardedAdaptiveRadixTree<KeyType, ValueType>
+where
+    KeyType: KeyTrait,
+{
+    shards: Box<[RwLock<AdaptiveRadixTree<KeyType, ValueType>>]>,
+    prefix_len: usize,
+}
+
+/// Exclusive access to one shard of a [`ShardedAdaptiveRadixTree`].
+///
+/// The guard dereferences to the shard's tree for reads. Writes go through the guard's own
+/// methods, which only accept keys belonging to the shard.
+pub struct ShardWriteGuard<'a, KeyType, ValueType>
+where
+    KeyType: KeyTrait,
+{
+    owner: &'a ShardedAdaptiveRadixTree<KeyType, ValueType>,
+    index: usize,
+    tree: RwLockWriteGuard<'a, AdaptiveRadixTree<KeyType, ValueType>>,
+}
+
+impl<KeyType, ValueType> ShardedAdaptiveRadixTree<KeyType, ValueType>
+where
+    KeyType: KeyTrait,
+{
+    /// Create an empty tree with 256 shards, one per leading key byte.
+    pub fn new() -> Self {
+        Self::with_prefix_len(1)
+    }
+
+    /// Create an empty tree sharded by the first `prefix_len` key bytes.
+    ///
+    /// A prefix of 1 byte gives 256 shards and a prefix of 2 bytes gives 65,536.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix_len` is not 1 or 2.
+    pub fn with_prefix_len(prefix_len: usize) -> Self {
+        assert!(
+            (1..=2).contains(&prefix_len),
+            "shard prefix length must be 1 or 2 bytes, got {prefix_len}"
+        );
+        let shards = (0..1usize << (8 * prefix_len))
+            .map(|_| RwLock::new(AdaptiveRadixTree::new()))
+            .collect();
+        Self { shards, prefix_len }
+    }
+
+    /// The number of key bytes that select a shard.
+    pub fn prefix_len(&self) -> usize {
+        self.prefix_len
+    }
+
+    /// The number of shards.
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The index of the shard `key` belongs to.
+    pub fn shard_index(&self, key: &KeyType) -> usize {
+        self.shard_of(key.as_ref(), 0)
+    }
+
+    /// The number of entries in the tree, summed shard by shard.
+    pub fn len(&self) -> usize {
+        (0..self.shards.len()).map(|i| self.read(i).len()).sum()
+    }
+
+    /// Check if the tree is empty.
+    pub fn is_empty(&self) -> bool {
+        (0..self.shards.len()).all(|i| self.read(i).is_empty())
+    }
+
+    /// Get a clone of the value for a key (generic version).
+    #[inline]
+    pub fn get<Key>(&self, key: Key) -> Option<ValueType>
+    where
+        Key: Into<KeyType>,
+        ValueType: Clone,
+    {
+        self.get_k(&key.into())
+    }
+
+    /// Get a clone of the value for a key.
+    pub fn get_k(&self, key: &KeyType) -> Option<ValueType>
+    where
+        ValueType: Clone,
+    {
+        self.read(self.shard_index(key)).get_k(key).cloned()
+    }
+
+    /// Insert a key-value pair (generic version), returning the value it replaced.
+    #[inline]
+    pub fn insert<KV>(&self, key: KV, value: ValueType) -> Option<ValueType>
+    where
+        KV: Into<KeyType>,
+    {
+        self.insert_k(&key.into(), value)
+    }
+
+    /// Insert a key-value pair, returning the value it replaced.
+    pub fn insert_k(&self, key: &KeyType, value: ValueType) -> Option<ValueType> {
+        self.write(self.shard_index(key)).insert_k(key, value)
+    }
+
+    /// Remove a key (generic version), returning its value.
+    #[inline]
+    pub fn remove<KV>(&self, key: KV) -> Option<ValueType>
+    where
+        KV: Into<KeyType>,
+    {
+        self.remove_k(&key.into())
+    }
+
+    /// Remove a key, returning its value.
+    pub fn remove_k(&self, key: &KeyType) -> Option<ValueType> {
+        self.write(self.shard_index(key)).remove_k(key)
+    }
+
+    /// Lock shard `index` for writing.
+    ///
+    /// Guards for different shards can be held at once, on the same thread or on different
+    /// ones. Taking guards in increasing index order avoids deadlocks between threads that hold
+    /// several.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not below [`shard_count`](Self::shard_count).
+    pub fn write_shard(&self, index: usize) -> ShardWriteGuard<'_, KeyType, ValueType> {
+        ShardWriteGuard {
+            owner: self,
+            index,
+            tree: self.write(index),
+        }
+    }
+
+    /// Lock the shard `key` belongs to for writing.
+    pub fn write_shard_for(&self, key: &KeyType) -> ShardWriteGuard<'_, KeyType, ValueType> {
+        self.write_shard(self.shard_index(key))
+    }
+
+    /// Visit every entry in key order.
+    pub fn for_each<F>(&self, mut on_each: F)
+    where
+        F: FnMut(KeyType, &ValueType),
+    {
+        for i in 0..self.shards.len() {
+            for (key, value) in self.read(i).iter() {
+                on_each(key, value);
+            }
+        }
+    }
+
+    /// Visit every entry within `range` in key order, locking only the shards the range spans.
+    pub fn range_for_each<R, F>(&self, range: R, mut on_each: F)
+    where
+        R: RangeBounds<KeyType>,
+        F: FnMut(KeyType, &ValueType),
+    {
+        let first = match range.start_bound() {
+            Bound::Included(key) | Bound::Excluded(key) => self.shard_index(key),
+            Bound::Unbounded => 0,
+        };
+        let last = match range.end_bound() {
+            Bound::Included(key) | Bound::Excluded(key) => self.shard_index(key),
+            Bound::Unbounded => self.shards.len() - 1,
+        };
+        for i in first..=last {
+            let bounds = (range.start_bound().cloned(), range.end_bound().cloned());
+            for (key, value) in self.read(i).range(bounds) {
+                on_each(key, value);
+            }
+        }
+    }
+
+    /// Visit every entry whose key starts with `prefix` (generic version).
+    #[inline]
+    pub fn prefix_for_each<Key, F>(&self, prefix: Key, on_each: F)
+    where
+        Key: Into<KeyType>,
+        F: FnMut(KeyType, &ValueType),
+    {
+        self.prefix_for_each_k(&prefix.into(), on_each)
+    }
+
+    /// Visit every entry whose key starts with `prefix` in key order, locking only the shards
+    /// such keys can be in: one shard when the prefix is at least as long as the shard prefix.
+    pub fn prefix_for_each_k<F>(&self, prefix: &KeyType, mut on_each: F)
+    where
+        F: FnMut(KeyType, &ValueType),
+    {
+        for i in self.prefix_shards(prefix.as_ref()) {
+            for (key, value) in self.read(i).prefix_iter_k(prefix) {
+                on_each(key, value);
+            }
+        }
+    }
+
+    /// The shard holding keys that start with `bytes`, padding short keys with `pad`.
+    fn shard_of(&self, bytes: &[u8], pad: u8) -> usize {
+        (0..self.prefix_len).fold(0, |index, i| {
+            (index << 8) | usize::from(bytes.get(i).copied().unwrap_or(pad))
+        })
+    }
+
+    /// The shards that can hold keys starting with `prefix`.
+    fn prefix_shards(&self, prefix: &[u8]) -> RangeInclusive<usize> {
+        if prefix.len() >= self.prefix_len {
+            let index = self.shard_of(prefix, 0);
+            return index..=index;
+        }
+        // Keys equal to the prefix are padded with zeros; longer ones may hold any byte.
+        self.shard_of(prefix, 0)..=self.shard_of(prefix, u8::MAX)
+    }
+
+    fn read(&self, index: usize) -> RwLockReadGuard<'_, AdaptiveRadixTree<KeyType, ValueType>> {
+        self.shards[index]
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self, index: usize) -> RwLockWriteGuard<'_, AdaptiveRadixTree<KeyType, ValueType>> {
+        self.shards[index]
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<KeyType, ValueType> Default for ShardedAdaptiveRadixTree<KeyType, ValueType>
+where
+    KeyType: KeyTrait,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<KeyType, ValueType> TreeStatsTrait for ShardedAdaptiveRadixTree<KeyType, ValueType>
+where
+    KeyType: KeyTrait,
+{
+    /// Node counts over all shards. Heights are measured within each shard.
+    fn get_tree_stats(&self) -> TreeStats {
+        let mut stats = TreeStats::default();
+        for i in 0..self.shards.len() {
+            merge_tree_stats(&mut stats, self.read(i).get_tree_stats());
+        }
+        if !stats.node_stats.is_empty() {
+            finish_tree_stats(&mut stats);
+        }
+        stats
+    }
+}
+
+impl<KeyType, ValueType> ShardWriteGuard<'_, KeyType, ValueType>
+where
+    KeyType: KeyTrait,
+{
+    /// The index of the locked shard.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Insert a key-value pair (generic version), returning the value it replaced.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key belongs to a different shard.
+    #[inline]
+    pub fn insert<KV>(&mut self, key: KV, value: ValueType) -> Option<ValueType>
+    where
+        KV: Into<KeyType>,
+    {
+        self.insert_k(&key.into(), value)
+    }
+
+    /// Insert a key-value pair, returning the value it replaced.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key belongs to a different shard.
+    pub fn insert_k(&mut self, key: &KeyType, value: ValueType) -> Option<ValueType> {
+        self.check_key(key);
+        self.tree.insert_k(key, value)
+    }
+
+    /// Remove a key (generic version), returning its value.
+    #[inline]
+    pub fn remove<KV>(&mut self, key: KV) -> Option<ValueType>
+    where
+        KV: Into<KeyType>,
+    {
+        self.remove_k(&key.into())
+    }
+
+    /// Remove a key, returning its value. Keys from other shards are never in this one.
+    pub fn remove_k(&mut self, key: &KeyType) -> Option<ValueType> {
+        self.tree.remove_k(key)
+    }
+
+    /// Get a mutable reference to the value for a key.
+    pub fn get_mut_k(&mut self, key: &KeyType) -> Option<&mut ValueType> {
+        self.tree.get_mut_k(key)
+    }
+
+    fn check_key(&self, key: &KeyType) {
+        let index = self.owner.shard_index(key);
+        assert_eq!(
+            index, self.index,
+            "key belongs to shard {index}, not the locked shard {}",
+            self.index
+        );
+    }
+}
+
+impl<KeyType, ValueType> Deref for ShardWriteGuard<'_, KeyType, ValueType>
+where
+    KeyType: KeyTrait,
+{
+    type Target = AdaptiveRadixTree<KeyType, ValueType>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::vector_key::VectorKey;
+    use proptest::prelude::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    #[should_panic(expected = "not the locked shard")]
+    fn write_guard_rejects_keys_from_other_shards() {
+        let tree = ShardedAdaptiveRadixTree::<VectorKey, u32>::new();
+        let mut shard = tree.write_shard(usize::from(b'a'));
+        shard.insert(VectorKey::new_from_slice(b"b"), 1);
+    }
+
+    fn shard_queries_match_model(
+        prefix_len: usize,
+        ops: &[(Vec<u8>, Option<u32>)],
+        start: &[u8],
+        end: &[u8],
+        prefix: &[u8],
+    ) {
+        let tree = ShardedAdaptiveRadixTree::<VectorKey, u32>::with_prefix_len(prefix_len);
+        let mut map = BTreeMap::new();
+        for (key, value) in ops {
+            let tree_key = VectorKey::new_from_slice(key);
+            match value {
+                Some(value) => {
+                    assert_eq!(
+                        tree.insert_k(&tree_key, *value),
+                        map.insert(key.clone(), *value)
+                    );
+                }
+                None => {
+                    assert_eq!(tree.remove_k(&tree_key), map.remove(key));
+                }
+            }
+            assert_eq!(tree.get_k(&tree_key), map.get(key).copied());
+        }
+        assert_eq!(tree.len(), map.len());
+        assert_eq!(tree.get_tree_stats().num_values, map.len());
+
+        let mut all = Vec::new();
+        tree.for_each(|key, value| all.push((key.as_ref().to_vec(), *value)));
+        assert_eq!(all, map.clone().into_iter().collect::<Vec<_>>());
+
+        let mut ranged = Vec::new();
+        let bounds = VectorKey::new_from_slice(start)..VectorKey::new_from_slice(end);
+        tree.range_for_each(bounds, |key, value| {
+            ranged.push((key.as_ref().to_vec(), *value))
+        });
+        let expected: Vec<_> = if start <= end {
+            map.range(start.to_vec()..end.to_vec())
+                .map(|(key, value)| (key.clone(), *value))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        assert_eq!(ranged, expected);
+
+        let mut prefixed = Vec::new();
+        tree.prefix_for_each_k(&VectorKey::new_from_slice(prefix), |key, value| {
+            prefixed.push((key.as_ref().to_vec(), *value))
+        });
+        let expected: Vec<_> = map
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| (key.clone(), *value))
+            .collect();
+        assert_eq!(prefixed, expected);
+    }
+
+    fn key_strategy() -> impl Strategy<Value = Vec<u8>> {
+        // Short keys and the extreme bytes exercise the zero padding of short keys.
+        proptest::collection::vec(prop_oneof![Just(0u8), Just(1u8), Just(u8::MAX)], 0..=3)
+    }
+
+    #[test]
+    fn two_byte_shards_match_reference_model() {
+        // Every query walks all 65,536 shards, so this layout gets one fixed case.
+        let ops: Vec<_> = [
+            (&b""[..], Some(1)),
+            (b"\x00", Some(2)),
+            (b"\x00\x00\x01", Some(3)),
+            (b"\x01", Some(4)),
+            (b"\x01\xff", Some(5)),
+            (b"\x01\xff\x00", Some(6)),
+            (b"\xff\xff\xff", Some(7)),
+            (b"\x01", None),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_vec(), value))
+        .collect();
+        shard_queries_match_model(2, &ops, b"\x00\x00", b"\x01\xff\x01", b"\x01");
+        shard_queries_match_model(2, &ops, b"", b"\xff", b"");
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn prop_sharded_queries_match_reference_model(
+            ops in proptest::collection::vec(
+                (key_strategy(), proptest::option::of(any::<u32>())),
+                0..48
+            ),
+            start in key_strategy(),
+            end in key_strategy(),
+            prefix in key_strategy(),
+        ) {
+            shard_queries_match_model(1, &ops, &start, &end, &prefix);
+        }
+    }
+}
+
+#[cfg(all(test, shuttle))]
+mod shuttle_tests {
+    use super::*;
+    use crate::keys::array_key::ArrayKey;
+    use shuttle::{Config, Runner, sync::Arc as ShuttleArc, thread};
+
+    fn run_dfs(f: impl Fn() + Send + Sync + 'static) {
+        let runner = Runner::new(
+            shuttle::scheduler::DfsScheduler::new(Some(1000), false),
+            Config::new(),
+        );
+        runner.run(f);
+    }
+
+    #[test]
+    fn shuttle_batch_writers_on_disjoint_shards() {
+        run_dfs(|| {
+            let tree = ShuttleArc::new(ShardedAdaptiveRadixTree::<ArrayKey<16>, u32>::new());
+            let writers: Vec<_> = [b'a', b'b']
+                .into_iter()
+                .map(|first| {
+                    let tree = ShuttleArc::clone(&tree);
+                    thread::spawn(move || {
+                        let mut shard = tree.write_shard(usize::from(first));
+                        for i in 0..3u8 {
+                            shard.insert([first, b'0' + i], u32::from(i));
+                        }
+                    })
+                })
+                .collect();
+            let reader = {
+                let tree = ShuttleArc::clone(&tree);
+                thread::spawn(move || {
+                    // A batch becomes visible all at once.
+                    let mut count = 0;
+                    tree.prefix_for_each(b"a", |_, _| count += 1);
+                    assert!(count == 0 || count == 3);
+                })
+            };
+            for handle in writers {
+                handle.join().unwrap();
+            }
+            reader.join().unwrap();
+
+            assert_eq!(tree.len(), 6);
+            let mut keys = Vec::new();
+            tree.for_each(|key, _| keys.push(key.as_ref()[..2].to_vec()));
+            assert_eq!(
+                keys,
+                vec![b"a0", b"a1", b"a2", b"b0", b"b1", b"b2"]
+                    .into_iter()
+                    .map(|key| key.to_vec())
+                    .collect::<Vec<_>>()
+            );
+        });
+    }
+}
@@ -0,0 +1,186 @@
+//! Shared pointer types for [`VersionedAdaptiveRadixTree`](crate::VersionedAdaptiveRadixTree).
+//!
+//! Versioned trees share nodes between snapshots through a reference-counted pointer. The
+//! pointer type is chosen by the tree's last type parameter, a [`SharedPtr`] family:
+//!
+//! - [`ArcPtr`]: `std::sync::Arc`. Trees can be sent and shared across threads.
+//! - `TriompheArcPtr` (with the `triomphe-arc` feature): `triomphe::Arc`, which drops the
+//!   weak count. Also thread-safe.
+//! - [`RcPtr`]: `std::rc::Rc`. Reference counting without atomics for single-threaded use;
+//!   the tree is then neither `Send` nor `Sync`.
+//!
+//! [`DefaultPtr`] is `TriompheArcPtr` when the `triomphe-arc` feature is enabled and `ArcPtr`
+//! otherwise. [`PersistentAdaptiveRadixTree`](crate::PersistentAdaptiveRadixTree) names the
+//! `Rc`-backed tree.
+
+use std::ops::Deref;
+use std::rc::Rc;
+
+/// A family of reference-counted pointers that tree nodes can be shared through.
+///
+/// The methods mirror the associated functions of `Arc` and `Rc`.
+pub trait SharedPtr: 'static {
+    /// The pointer type for a value of type `T`.
+    type Ptr<T>: Clone + Deref<Target = T> + AsRef<T>;
+
+    /// Move `value` into a new allocation.
+    fn new<T>(value: T) -> Self::Ptr<T>;
+
+    /// A mutable reference to the value if `this` is its only reference.
+    fn get_mut<T>(this: &mut Self::Ptr<T>) -> Option<&mut T>;
+
+    /// Take the value out if `this` is its only reference, and give the pointer back otherwise.
+    fn try_unwrap<T>(this: Self::Ptr<T>) -> Result<T, Self::Ptr<T>>;
+
+    /// The number of references to the value.
+    fn strong_count<T>(this: &Self::Ptr<T>) -> usize;
+
+    /// Whether two pointers point to the same allocation.
+    fn ptr_eq<T>(this: &Self::Ptr<T>, other: &Self::Ptr<T>) -> bool;
+
+    /// The address of the value.
+    fn as_ptr<T>(this: &Self::Ptr<T>) -> *const T;
+}
+
+/// A [`SharedPtr`] family whose pointers can be shared between threads, as required by
+/// [`SharedVersionedTree`](crate::SharedVersionedTree).
+///
+/// # Safety
+///
+/// `Self::Ptr<T>` must be `Send` and `Sync` whenever `T` is, and its reference counting must be
+/// safe to perform from several threads at once. [`SharedVersionedTree`](crate::SharedVersionedTree)
+/// hands clones of the same pointer to readers on other threads on the strength of this trait
+/// alone, so implementing it for an `Rc`-like pointer is undefined behavior.
+pub unsafe trait SyncSharedPtr: SharedPtr {}
+
+/// Nodes shared through `std::sync::Arc`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ArcPtr;
+
+impl SharedPtr for ArcPtr {
+    type Ptr<T> = std::sync::Arc<T>;
+
+    #[inline]
+    fn new<T>(value: T) -> Self::Ptr<T> {
+        std::sync::Arc::new(value)
+    }
+
+    #[inline]
+    fn get_mut<T>(this: &mut Self::Ptr<T>) -> Option<&mut T> {
+        std::sync::Arc::get_mut(this)
+    }
+
+    #[inline]
+    fn try_unwrap<T>(this: Self::Ptr<T>) -> Result<T, Self::Ptr<T>> {
+        std::sync::Arc::try_unwrap(this)
+    }
+
+    #[inline]
+    fn strong_count<T>(this: &Self::Ptr<T>) -> usize {
+        std::sync::Arc::strong_count(this)
+    }
+
+    #[inline]
+    fn ptr_eq<T>(this: &Self::Ptr<T>, other: &Self::Ptr<T>) -> bool {
+        std::sync::Arc::ptr_eq(this, other)
+    }
+
+    #[inline]
+    fn as_ptr<T>(this: &Self::Ptr<T>) -> *const T {
+        std::sync::Arc::as_ptr(this)
+    }
+}
+
+// SAFETY: `Arc<T>` is `Send + Sync` for `T: Send + Sync`, with atomic reference counts.
+unsafe impl SyncSharedPtr for ArcPtr {}
+
+/// Nodes shared through `triomphe::Arc`.
+#[cfg(feature = "triomphe-arc")]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TriompheArcPtr;
+
+#[cfg(feature = "triomphe-arc")]
+impl SharedPtr for TriompheArcPtr {
+    type Ptr<T> = triomphe::Arc<T>;
+
+    #[inline]
+    fn new<T>(value: T) -> Self::Ptr<T> {
+        triomphe::Arc::new(value)
+    }
+
+    #[inline]
+    fn get_mut<T>(this: &mut Self::Ptr<T>) -> Option<&mut T> {
+        triomphe::Arc::get_mut(this)
+    }
+
+    #[inline]
+    fn try_unwrap<T>(this: Self::Ptr<T>) -> Result<T, Self::Ptr<T>> {
+        triomphe::Arc::try_unwrap(this)
+    }
+
+    #[inline]
+    fn strong_count<T>(this: &Self::Ptr<T>) -> usize {
+        triomphe::Arc::strong_count(this)
+    }
+
+    #[inline]
+    fn ptr_eq<T>(this: &Self::Ptr<T>, other: &Self::Ptr<T>) -> bool {
+        triomphe::Arc::ptr_eq(this, other)
+    }
+
+    #[inline]
+    fn as_ptr<T>(this: &Self::Ptr<T>) -> *const T {
+        triomphe::Arc::as_ptr(this)
+    }
+}
+
+#[cfg(feature = "triomphe-arc")]
+// SAFETY: as for `ArcPtr`; `triomphe::Arc` counts atomically and is `Send + Sync` on the same
+// terms.
+unsafe impl SyncSharedPtr for TriompheArcPtr {}
+
+/// Nodes shared through `std::rc::Rc`, for single-threaded trees.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RcPtr;
+
+impl SharedPtr for RcPtr {
+    type Ptr<T> = Rc<T>;
+
+    #[inline]
+    fn new<T>(value: T) -> Self::Ptr<T> {
+        Rc::new(value)
+    }
+
+    #[inline]
+    fn get_mut<T>(this: &mut Self::Ptr<T>) -> Option<&mut T> {
+        Rc::get_mut(this)
+    }
+
+    #[inline]
+    fn try_unwrap<T>(this: Self::Ptr<T>) -> Result<T, Self::Ptr<T>> {
+        Rc::try_unwrap(this)
+    }
+
+    #[inline]
+    fn strong_count<T>(this: &Self::Ptr<T>) -> usize {
+        Rc::strong_count(this)
+    }
+
+    #[inline]
+    fn ptr_eq<T>(this: &Self::Ptr<T>, other: &Self::Ptr<T>) -> bool {
+        Rc::ptr_eq(this, other)
+    }
+
+    #[inline]
+    fn as_ptr<T>(this: &Self::Ptr<T>) -> *const T {
+        Rc::as_ptr(this)
+    }
+}
+
+/// The pointer family versioned trees use unless another one is named.
+#[cfg(not(feature = "triomphe-arc"))]
+pub type DefaultPtr = ArcPtr;
+
+/// The pointer family versioned trees use unless another one is named.
+#[cfg(feature = "triomphe-arc")]
+pub type DefaultPtr = TriompheArcPtr;
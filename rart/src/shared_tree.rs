@@ -0,0 +1,447 @@
+//! A versioned tree published through an atomically swapped root.
+//!
+//! [`SharedVersionedTree`] is the single-writer, many-reader handle most users of
+//! [`VersionedAdaptiveRadixTree`] would otherwise build from a lock and `snapshot()`. Readers
+//! take a consistent snapshot without locking; a writer copies the published state into a
+//! private snapshot, mutates it with the usual copy-on-write operations, and makes its changes
+//! visible all at once with [`SharedTreeWriter::commit`].
+//!
+//! The published tree lives behind an atomic pointer. A reader pins the current epoch (kept by
+//! the crate's internal `epoch` module), loads the pointer, clones the root (an O(1) `Arc` clone,
+//! so the snapshot keeps its nodes alive on its own), and unpins. Publishing swaps the pointer
+//! and then waits for the epoch to advance twice, which outlasts every reader pinned before the
+//! swap, before freeing the previously published tree. Readers never wait on the writer, and because new
+//! readers pin the latest epoch rather than the one being waited on, a steady stream of
+//! readers cannot starve the writer.
+//!
+//! The tree may share its nodes through any thread-safe pointer family ([`SyncSharedPtr`]), so
+//! a `triomphe`-backed tree can be published the same way as the default one.
+
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use crate::epoch::Epochs;
+use crate::keys::KeyTrait;
+use crate::shared_ptr::{DefaultPtr, SyncSharedPtr};
+use crate::sync::{AtomicPtr, Mutex, MutexGuard, Ordering};
+use crate::versioned_tree::VersionedAdaptiveRadixTree;
+
+/// A [`VersionedAdaptiveRadixTree`] shared between many lock-free readers and one writer at a
+/// time.
+///
+/// ```rust
+/// use rart::{ArrayKey, SharedVersionedTree};
+///
+/// let shared = SharedVersionedTree::<ArrayKey<16>, i32>::new();
+///
+/// let mut writer = shared.writer();
+/// writer.insert("a", 1);
+/// writer.insert("b", 2);
+/// // Nothing is visible to readers until the writer commits.
+/// assert_eq!(shared.snapshot().get("a"), None);
+/// writer.commit();
+/// drop(writer);
+///
+/// let snapshot = shared.snapshot();
+/// assert_eq!(snapshot.get("a"), Some(&1));
+/// assert_eq!(snapshot.get("b"), Some(&2));
+/// ```
+pub struct SharedVersionedTree<KeyType, ValueType, S = DefaultPtr>
+where
+    KeyType: KeyTrait,
+    ValueType: Clone,
+    S: SyncSharedPtr,
+{
+    /// The published tree, owned by this handle (from `Box::into_raw`).
+    current: AtomicPtr<VersionedAdaptiveRadixTree<KeyType, ValueType, S>>,
+    /// Pinned by readers between loading `current` and finishing their root clone.
+    epochs: Epochs,
+    /// Serializes writers, and with them pointer swaps and grace periods.
+    writer: Mutex<()>,
+    /// Share the thread-safety of the tree itself.
+    _marker: PhantomData<VersionedAdaptiveRadixTree<KeyType, ValueType, S>>,
+}
+
+/// Exclusive write access to a [`SharedVersionedTree`].
+///
+/// The writer dereferences to a private snapshot of the tree taken when it was created. Changes
+/// made through it are published by [`commit`](Self::commit); dropping the writer discards any
+/// uncommitted changes. Only one writer exists at a time.
+pub struct SharedTreeWriter<'a, KeyType, ValueType, S = DefaultPtr>
+where
+    KeyType: KeyTrait,
+    ValueType: Clone,
+    S: SyncSharedPtr,
+{
+    shared: &'a SharedVersionedTree<KeyType, ValueType, S>,
+    tree: VersionedAdaptiveRadixTree<KeyType, ValueType, S>,
+    _guard: MutexGuard<'a, ()>,
+}
+
+impl<KeyType, ValueType, S> SharedVersionedTree<KeyType, ValueType, S>
+where
+    KeyType: KeyTrait,
+    ValueType: Clone,
+    S: SyncSharedPtr,
+{
+    /// Create a handle publishing an empty tree.
+    pub fn new() -> Self {
+        Self::from_tree(VersionedAdaptiveRadixTree::new())
+    }
+
+    /// Create a handle publishing `tree`.
+    pub fn from_tree(tree: VersionedAdaptiveRadixTree<KeyType, ValueType, S>) -> Self {
+        Self {
+            current: AtomicPtr::new(Box::into_raw(Box::new(tree))),
+            epochs: Epochs::new(),
+            writer: Mutex::new(()),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Take a snapshot of the most recently published tree.
+    ///
+    /// This never blocks: it costs a few atomic operations and one root `Arc` clone, and the
+    /// returned snapshot is unaffected by later commits.
+    pub fn snapshot(&self) -> VersionedAdaptiveRadixTree<KeyType, ValueType, S> {
+        let _guard = self.epochs.pin();
+        let current = self.current.load(Ordering::SeqCst);
+        // SAFETY: `current` was published by `from_tree` or `commit`, and a published tree is
+        // only freed after a grace period in which every reader pinned before the swap that
+        // retired it has left; this reader stays pinned until the snapshot is taken.
+        unsafe { &*current }.snapshot()
+    }
+
+    /// Begin writing, blocking while another writer is active.
+    pub fn writer(&self) -> SharedTreeWriter<'_, KeyType, ValueType, S> {
+        let guard = self
+            .writer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.writer_with(guard)
+    }
+
+    /// Begin writing if no other writer is active.
+    pub fn try_writer(&self) -> Option<SharedTreeWriter<'_, KeyType, ValueType, S>> {
+        let guard = match self.writer.try_lock() {
+            Ok(guard) => guard,
+            Err(std::sync::TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(std::sync::TryLockError::WouldBlock) => return None,
+        };
+        Some(self.writer_with(guard))
+    }
+
+    fn writer_with<'a>(
+        &'a self,
+        guard: MutexGuard<'a, ()>,
+    ) -> SharedTreeWriter<'a, KeyType, ValueType, S> {
+        SharedTreeWriter {
+            shared: self,
+            tree: self.snapshot(),
+            _guard: guard,
+        }
+    }
+
+    /// Swap in `tree` as the published tree and free the one it replaces once no reader can
+    /// still be using it. Must only be called while holding the writer lock.
+    fn publish(&self, tree: VersionedAdaptiveRadixTree<KeyType, ValueType, S>) {
+        let retired = self
+            .current
+            .swap(Box::into_raw(Box::new(tree)), Ordering::SeqCst);
+
+        // Every reader that may have loaded `retired` was pinned before the swap.
+        self.epochs.synchronize();
+
+        // SAFETY: `retired` came from `Box::into_raw`, is no longer reachable through
+        // `current`, and the grace period above outlasted every reader that could have loaded
+        // it.
+        drop(unsafe { Box::from_raw(retired) });
+    }
+}
+
+impl<KeyType, ValueType, S> Default for SharedVersionedTree<KeyType, ValueType, S>
+where
+    KeyType: KeyTrait,
+    ValueType: Clone,
+    S: SyncSharedPtr,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<KeyType, ValueType, S> From<VersionedAdaptiveRadixTree<KeyType, ValueType, S>>
+    for SharedVersionedTree<KeyType, ValueType, S>
+where
+    KeyType: KeyTrait,
+    ValueType: Clone,
+    S: SyncSharedPtr,
+{
+    fn from(tree: VersionedAdaptiveRadixTree<KeyType, ValueType, S>) -> Self {
+        Self::from_tree(tree)
+    }
+}
+
+impl<KeyType, ValueType, S> Drop for SharedVersionedTree<KeyType, ValueType, S>
+where
+    KeyType: KeyTrait,
+    ValueType: Clone,
+    S: SyncSharedPtr,
+{
+    fn drop(&mut self) {
+        // SAFETY: `&mut self` rules out concurrent readers and writers, and `current` always
+        // holds a pointer from `Box::into_raw`.
+        drop(unsafe { Box::from_raw(*self.current.get_mut()) });
+    }
+}
+
+impl<KeyType, ValueType, S> SharedTreeWriter<'_, KeyType, ValueType, S>
+where
+    KeyType: KeyTrait,
+    ValueType: Clone,
+    S: SyncSharedPtr,
+{
+    /// Publish the writer's changes so far, returning the published version.
+    ///
+    /// Readers see either everything committed here or none of it. The writer stays usable,
+    /// and later changes are again private until the next commit.
+    pub fn commit(&mut self) -> u64 {
+        let published = self.tree.snapshot();
+        let version = published.version();
+        self.shared.publish(published);
+        version
+    }
+
+    /// Discard uncommitted changes, resetting the writer to the published tree.
+    pub fn rollback(&mut self) {
+        self.tree = self.shared.snapshot();
+    }
+}
+
+impl<KeyType, ValueType, S> Deref for SharedTreeWriter<'_, KeyType, ValueType, S>
+where
+    KeyType: KeyTrait,
+    ValueType: Clone,
+    S: SyncSharedPtr,
+{
+    type Target = VersionedAdaptiveRadixTree<KeyType, ValueType, S>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tree
+    }
+}
+
+impl<KeyType, ValueType, S> DerefMut for SharedTreeWriter<'_, KeyType, ValueType, S>
+where
+    KeyType: KeyTrait,
+    ValueType: Clone,
+    S: SyncSharedPtr,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::array_key::ArrayKey;
+
+    #[test]
+    fn readers_see_whole_commits_on_std_threads() {
+        let shared = SharedVersionedTree::<ArrayKey<16>, i32>::new();
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let mut writer = shared.writer();
+                for round in 1..=20 {
+                    writer.insert("a", round);
+                    writer.insert("b", round);
+                    writer.commit();
+                }
+            });
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        let snapshot = shared.snapshot();
+                        assert_eq!(snapshot.get("a"), snapshot.get("b"));
+                    }
+                });
+            }
+        });
+        assert_eq!(shared.snapshot().get("b"), Some(&20));
+    }
+}
+
+#[cfg(all(test, shuttle))]
+mod shuttle_tests {
+    use super::*;
+    use crate::keys::array_key::ArrayKey;
+    use shuttle::{Config, Runner, sync::Arc as ShuttleArc, thread};
+
+    #[test]
+    fn shuttle_many_readers_one_writer() {
+        let runner = Runner::new(
+            shuttle::scheduler::DfsScheduler::new(Some(1000), false),
+            Config::new(),
+        );
+        runner.run(|| {
+            let shared = ShuttleArc::new(SharedVersionedTree::<ArrayKey<16>, i32>::new());
+            {
+                let mut writer = shared.writer();
+                for i in 0..10 {
+                    writer.insert(i, i * 3);
+                }
+                writer.commit();
+            }
+
+            let mut reader_handles = Vec::new();
+            for reader_id in 0..3 {
+                let shared = ShuttleArc::clone(&shared);
+                reader_handles.push(thread::spawn(move || {
+                    let snapshot = shared.snapshot();
+                    let sum: i32 = (0..15).filter_map(|i| snapshot.get(i)).sum();
+                    (reader_id, sum)
+                }));
+            }
+
+            let shared_for_writer = ShuttleArc::clone(&shared);
+            let writer_handle = thread::spawn(move || {
+                let mut writer = shared_for_writer.writer();
+                for i in 10..15 {
+                    writer.insert(i, i * 5);
+                }
+                writer.commit();
+            });
+
+            // Each reader sees the whole batch or none of it.
+            let before = (0..10).map(|i| i * 3).sum::<i32>();
+            let after = before + (10..15).map(|i| i * 5).sum::<i32>();
+            for handle in reader_handles {
+                let (reader_id, sum) = handle.join().unwrap();
+                assert!(
+                    sum == before || sum == after,
+                    "Reader {reader_id} saw a partial commit: {sum}"
+                );
+            }
+            writer_handle.join().unwrap();
+
+            let snapshot = shared.snapshot();
+            assert_eq!((0..15).filter_map(|i| snapshot.get(i)).sum::<i32>(), after);
+        });
+    }
+
+    #[test]
+    fn shuttle_writers_do_not_lose_updates() {
+        let runner = Runner::new(
+            shuttle::scheduler::DfsScheduler::new(Some(1000), false),
+            Config::new(),
+        );
+        runner.run(|| {
+            let shared = ShuttleArc::new(SharedVersionedTree::<ArrayKey<16>, i32>::new());
+
+            let handles: Vec<_> = (0..2)
+                .map(|_| {
+                    let shared = ShuttleArc::clone(&shared);
+                    thread::spawn(move || {
+                        let mut writer = shared.writer();
+                        let count = writer.get("count").copied().unwrap_or(0);
+                        writer.insert("count", count + 1);
+                        writer.commit();
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+
+            assert_eq!(shared.snapshot().get("count"), Some(&2));
+        });
+    }
+
+    #[test]
+    fn shuttle_snapshots_outlive_later_commits() {
+        let runner = Runner::new(
+            shuttle::scheduler::DfsScheduler::new(Some(1000), false),
+            Config::new(),
+        );
+        runner.run(|| {
+            let shared = ShuttleArc::new(SharedVersionedTree::<ArrayKey<16>, String>::new());
+
+            let shared_for_writer = ShuttleArc::clone(&shared);
+            let writer_handle = thread::spawn(move || {
+                let mut writer = shared_for_writer.writer();
+                for round in 0..3 {
+                    writer.insert("key", format!("round {round}"));
+                    writer.commit();
+                }
+                writer.remove("key");
+                writer.rollback();
+                assert_eq!(writer.get("key"), Some(&"round 2".to_string()));
+            });
+
+            // Hold on to a snapshot across the writer's commits; it must stay readable and
+            // unchanged after the tree it was taken from has been retired.
+            let shared_for_reader = ShuttleArc::clone(&shared);
+            let reader_handle = thread::spawn(move || {
+                let snapshot = shared_for_reader.snapshot();
+                let seen = snapshot.get("key").cloned();
+                thread::yield_now();
+                assert_eq!(snapshot.get("key").cloned(), seen);
+            });
+
+            writer_handle.join().unwrap();
+            reader_handle.join().unwrap();
+            assert_eq!(shared.snapshot().get("key"), Some(&"round 2".to_string()));
+            assert!(shared.try_writer().is_some());
+        });
+    }
+
+    #[test]
+    fn shuttle_publishes_arc_backed_tree() {
+        shuttle::check_random(
+            || {
+                let shared = ShuttleArc::new(SharedVersionedTree::<
+                    ArrayKey<16>,
+                    i32,
+                    crate::shared_ptr::ArcPtr,
+                >::new());
+                let reader = {
+                    let shared = ShuttleArc::clone(&shared);
+                    thread::spawn(move || shared.snapshot().get("a").copied())
+                };
+                let mut writer = shared.writer();
+                writer.insert("a", 1);
+                writer.commit();
+                drop(writer);
+                assert!(matches!(reader.join().unwrap(), None | Some(1)));
+                assert_eq!(shared.snapshot().get("a"), Some(&1));
+            },
+            100,
+        );
+    }
+
+    #[cfg(feature = "triomphe-arc")]
+    #[test]
+    fn shuttle_publishes_triomphe_backed_tree() {
+        shuttle::check_random(
+            || {
+                let shared = ShuttleArc::new(SharedVersionedTree::<
+                    ArrayKey<16>,
+                    i32,
+                    crate::shared_ptr::TriompheArcPtr,
+                >::new());
+                let reader = {
+                    let shared = ShuttleArc::clone(&shared);
+                    thread::spawn(move || shared.snapshot().get("a").copied())
+                };
+                let mut writer = shared.writer();
+                writer.insert("a", 1);
+                writer.commit();
+                drop(writer);
+                assert!(matches!(reader.join().unwrap(), None | Some(1)));
+                assert_eq!(shared.snapshot().get("a"), Some(&1));
+            },
+            100,
+        );
+    }
+}
@@ -66,17 +66,89 @@ pub(crate) fn update_tree_stats<NodeType, PartialType, ValueType>(
     NodeType: Node<PartialType, ValueType>,
     PartialType: Partial,
 {
+    record_inner_node(tree_stats, node.capacity(), node.num_children());
+}
+
+pub(crate) fn record_inner_node(tree_stats: &mut TreeStats, capacity: usize, num_children: usize) {
     tree_stats
         .node_stats
-        .entry(node.capacity())
+        .entry(capacity)
         .and_modify(|e| {
             e.total_nodes += 1;
-            e.total_children += node.num_children();
+            e.total_children += num_children;
         })
         .or_insert(NodeStats {
-            width: node.capacity(),
+            width: capacity,
             total_nodes: 1,
-            total_children: node.num_children(),
+            total_children: num_children,
             density: 0.0,
         });
 }
+
+/// Add the counts from `other` into `stats`, as if both trees hung below a common root. Call
+/// [`finish_tree_stats`] afterwards to recompute the densities.
+pub(crate) fn merge_tree_stats(stats: &mut TreeStats, other: TreeStats) {
+    for (capacity, ns) in other.node_stats {
+        let entry = stats.node_stats.entry(capacity).or_insert(NodeStats {
+            width: capacity,
+            ..NodeStats::default()
+        });
+        entry.total_nodes += ns.total_nodes;
+        entry.total_children += ns.total_children;
+    }
+    stats.num_leaves += other.num_leaves;
+    stats.num_values += other.num_values;
+    stats.max_height = stats.max_height.max(other.max_height);
+}
+
+/// Fill in the inner node count and densities once every node has been recorded.
+pub(crate) fn finish_tree_stats(stats: &mut TreeStats) {
+    let total_inner_nodes = stats
+        .node_stats
+        .values()
+        .map(|ns| ns.total_nodes)
+        .sum::<usize>();
+    let mut total_children = 0;
+    let mut total_width = 0;
+    for ns in stats.node_stats.values_mut() {
+        total_children += ns.total_children;
+        total_width += ns.width * ns.total_nodes;
+        ns.density = ns.total_children as f64 / (ns.width * ns.total_nodes) as f64;
+    }
+    let total_density = total_children as f64 / total_width as f64;
+    stats.num_inner_nodes = total_inner_nodes;
+    stats.total_density = total_density;
+}
+
+/// Memory use of one snapshot of a
+/// [`VersionedAdaptiveRadixTree`](crate::VersionedAdaptiveRadixTree), as reported by
+/// [`snapshot_stats`](crate::VersionedAdaptiveRadixTree::snapshot_stats).
+///
+/// Byte counts cover each node and its child table; they don't include heap memory owned by
+/// the stored values themselves.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SnapshotStats {
+    /// Nodes reachable from the snapshot's root.
+    pub total_nodes: usize,
+    /// Bytes taken by those nodes.
+    pub total_bytes: usize,
+    /// Nodes referenced only once (`Arc::strong_count == 1`).
+    pub unique_nodes: usize,
+    /// Nodes referenced more than once, usually because another snapshot holds them too.
+    pub shared_nodes: usize,
+    /// Nodes that would be freed if the snapshot were dropped: those reached through uniquely
+    /// referenced nodes all the way from the root.
+    pub freed_on_drop_nodes: usize,
+    /// Bytes that would be freed if the snapshot were dropped.
+    pub freed_on_drop_bytes: usize,
+}
+
+/// Structure two snapshots have in common, as reported by
+/// [`shared_with`](crate::VersionedAdaptiveRadixTree::shared_with).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SharingStats {
+    /// Nodes reachable from both snapshots.
+    pub shared_nodes: usize,
+    /// Bytes taken by those nodes.
+    pub shared_bytes: usize,
+}
@@ -0,0 +1,24 @@
+//! Synchronization primitives used by the concurrent structures.
+//!
+//! Unit tests built with `--cfg shuttle` swap these for shuttle's instrumented versions so the
+//! shuttle schedulers can interleave (and spin-wait on) every atomic access. Those primitives
+//! only work inside a shuttle runner, so the `shuttle_tests` modules that need them are built
+//! under the same cfg, and every other build uses `std`:
+//!
+//! ```sh
+//! RUSTFLAGS="--cfg shuttle" cargo test -p rart --lib shuttle
+//! ```
+
+#[cfg(all(test, shuttle))]
+pub(crate) use shuttle::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+#[cfg(all(test, shuttle))]
+pub(crate) use shuttle::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+#[cfg(all(test, shuttle))]
+pub(crate) use shuttle::thread::yield_now;
+
+#[cfg(not(all(test, shuttle)))]
+pub(crate) use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+#[cfg(not(all(test, shuttle)))]
+pub(crate) use std::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+#[cfg(not(all(test, shuttle)))]
+pub(crate) use std::thread::yield_now;
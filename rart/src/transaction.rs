@@ -0,0 +1,497 @@
+//! Optimistic multi-key transactions over versioned snapshots.
+//!
+//! A [`Transaction`] reads from the snapshot it began with, buffers its writes, and records
+//! every key, prefix, and range it read. [`Transaction::commit`] validates those reads against
+//! the current head: if any entry the transaction read may have changed since its snapshot, it
+//! fails with a [`TransactionConflict`] and nothing is written; otherwise its writes are applied
+//! to the head.
+//!
+//! Validation walks the snapshot and the head together over each read, skipping every subtree
+//! the two still share by `Arc` identity, so its cost follows the paths changed since the
+//! transaction began rather than the size of what it read. It is conservative at node
+//! granularity: an entry whose node was copied since the snapshot counts as changed even if it
+//! holds an equal value. Besides writes to the entry itself, that happens when a key below it
+//! is written, or when an insert splits the compressed prefix the entry's node sits on.
+
+use std::collections::BTreeMap;
+use std::collections::Bound;
+use std::convert::Infallible;
+use std::fmt;
+use std::ops::RangeBounds;
+
+use crate::join::KeySpan;
+use crate::keys::KeyTrait;
+use crate::range::ByteRange;
+use crate::shared_ptr::{DefaultPtr, SharedPtr};
+use crate::versioned_tree::VersionedAdaptiveRadixTree;
+use crate::write_batch::WriteBatch;
+
+/// A set of keys a transaction read, kept as encoded key bytes.
+enum ReadSpan {
+    /// Every key starting with the prefix.
+    Prefix(Vec<u8>),
+    /// Every key within the bounds; a point read is a range containing one key.
+    Range(Bound<Vec<u8>>, Bound<Vec<u8>>),
+}
+
+impl ReadSpan {
+    fn key(key: &[u8]) -> Self {
+        Self::Range(Bound::Included(key.to_vec()), Bound::Included(key.to_vec()))
+    }
+
+    fn byte_range<'a>(start: &'a Bound<Vec<u8>>, end: &'a Bound<Vec<u8>>) -> ByteRange<'a> {
+        ByteRange::from_bounds(
+            start.as_ref().map(Vec::as_slice),
+            end.as_ref().map(Vec::as_slice),
+        )
+    }
+
+    /// The bytes every key in the span starts with.
+    fn seek(&self) -> &[u8] {
+        match self {
+            Self::Prefix(prefix) => prefix,
+            Self::Range(start, end) => Self::byte_range(start, end).common_prefix(),
+        }
+    }
+}
+
+impl KeySpan for ReadSpan {
+    fn may_contain_prefixed(&self, path: &[u8]) -> bool {
+        match self {
+            Self::Prefix(prefix) => {
+                let len = path.len().min(prefix.len());
+                path[..len] == prefix[..len]
+            }
+            Self::Range(start, end) => Self::byte_range(start, end).may_contain_prefixed(path),
+        }
+    }
+
+    fn contains(&self, key: &[u8]) -> bool {
+        match self {
+            Self::Prefix(prefix) => key.starts_with(prefix),
+            Self::Range(start, end) => Self::byte_range(start, end).contains(key),
+        }
+    }
+}
+
+/// A read-write transaction that commits only if what it read is unchanged.
+///
+/// Reads see the snapshot the transaction began from, overlaid with the transaction's own
+/// writes. Reads of keys the transaction already wrote are answered from its write set and not
+/// recorded.
+///
+/// ```rust
+/// use rart::{ArrayKey, VersionedAdaptiveRadixTree};
+/// use rart::transaction::Transaction;
+///
+/// let mut head = VersionedAdaptiveRadixTree::<ArrayKey<16>, i32>::new();
+/// head.insert("alice", 100);
+/// head.insert("bob", 50);
+///
+/// // Move 30 from alice to bob.
+/// let mut transfer = Transaction::begin(&head);
+/// let alice = *transfer.get("alice").unwrap();
+/// let bob = *transfer.get("bob").unwrap();
+/// transfer.insert("alice", alice - 30);
+/// transfer.insert("bob", bob + 30);
+///
+/// // A concurrent transaction that also read alice's balance.
+/// let mut withdraw = Transaction::begin(&head);
+/// let alice = *withdraw.get("alice").unwrap();
+/// withdraw.insert("alice", alice - 80);
+///
+/// assert!(transfer.commit(&mut head).is_ok());
+/// assert!(withdraw.commit(&mut head).is_err());
+/// assert_eq!(head.get("alice"), Some(&70));
+/// assert_eq!(head.get("bob"), Some(&80));
+/// ```
+pub struct Transaction<KeyType, ValueType, S = DefaultPtr>
+where
+    KeyType: KeyTrait,
+    ValueType: Clone,
+    S: SharedPtr,
+{
+    start_version: u64,
+    snapshot: VersionedAdaptiveRadixTree<KeyType, ValueType, S>,
+    reads: Vec<ReadSpan>,
+    /// Buffered writes by encoded key; `None` removes the key.
+    writes: BTreeMap<Vec<u8>, Option<ValueType>>,
+}
+
+/// Returned by [`Transaction::commit`] when a key or range the transaction read may have
+/// changed since the snapshot it began from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TransactionConflict {
+    /// The version of the snapshot the transaction began from.
+    pub start_version: u64,
+    /// The version of the head the transaction failed to commit against.
+    pub head_version: u64,
+}
+
+impl fmt::Display for TransactionConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "data read by the transaction changed between version {} and version {}",
+            self.start_version, self.head_version
+        )
+    }
+}
+
+impl std::error::Error for TransactionConflict {}
+
+impl<KeyType, ValueType, S> Transaction<KeyType, ValueType, S>
+where
+    KeyType: KeyTrait,
+    ValueType: Clone,
+    S: SharedPtr,
+{
+    /// Begin a transaction reading from a snapshot of `tree`.
+    pub fn begin(tree: &VersionedAdaptiveRadixTree<KeyType, ValueType, S>) -> Self {
+        Self {
+            start_version: tree.version(),
+            snapshot: tree.snapshot(),
+            reads: Vec::new(),
+            writes: BTreeMap::new(),
+        }
+    }
+
+    /// The version of the tree the transaction began from.
+    pub fn start_version(&self) -> u64 {
+        self.start_version
+    }
+
+    /// Whether the transaction has buffered no writes.
+    pub fn is_read_only(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /// Read a key (generic version), recording it in the read set.
+    #[inline]
+    pub fn get<Key>(&mut self, key: Key) -> Option<&ValueType>
+    where
+        Key: Into<KeyType>,
+    {
+        self.get_k(&key.into())
+    }
+
+    /// Read a key, recording it in the read set.
+    pub fn get_k(&mut self, key: &KeyType) -> Option<&ValueType> {
+        let bytes = key.as_ref();
+        if let Some(written) = self.writes.get(bytes) {
+            return written.as_ref();
+        }
+        self.reads.push(ReadSpan::key(bytes));
+        self.snapshot.get_k(key)
+    }
+
+    /// Buffer an insert (generic version).
+    #[inline]
+    pub fn insert<Key>(&mut self, key: Key, value: ValueType)
+    where
+        Key: Into<KeyType>,
+    {
+        self.insert_k(&key.into(), value)
+    }
+
+    /// Buffer an insert, replacing any earlier write to the same key.
+    pub fn insert_k(&mut self, key: &KeyType, value: ValueType) {
+        self.writes.insert(key.as_ref().to_vec(), Some(value));
+    }
+
+    /// Buffer a removal (generic version).
+    #[inline]
+    pub fn remove<Key>(&mut self, key: Key)
+    where
+        Key: Into<KeyType>,
+    {
+        self.remove_k(&key.into())
+    }
+
+    /// Buffer a removal, replacing any earlier write to the same key.
+    pub fn remove_k(&mut self, key: &KeyType) {
+        self.writes.insert(key.as_ref().to_vec(), None);
+    }
+
+    /// Visit every entry whose key starts with `prefix` (generic version), in key order,
+    /// recording the prefix in the read set.
+    #[inline]
+    pub fn prefix_for_each<Key, F>(&mut self, prefix: Key, f: F)
+    where
+        Key: Into<KeyType>,
+        F: FnMut(KeyType, &ValueType),
+    {
+        self.prefix_for_each_k(&prefix.into(), f)
+    }
+
+    /// Visit every entry whose key starts with `prefix`, in key order, recording the prefix
+    /// in the read set.
+    pub fn prefix_for_each_k<F>(&mut self, prefix: &KeyType, mut f: F)
+    where
+        F: FnMut(KeyType, &ValueType),
+    {
+        let prefix_bytes = prefix.as_ref();
+        self.reads.push(ReadSpan::Prefix(prefix_bytes.to_vec()));
+        let writes = self
+            .writes
+            .range::<[u8], _>((Bound::Included(prefix_bytes), Bound::Unbounded))
+            .take_while(|(key, _)| key.starts_with(prefix_bytes));
+        Self::overlay_for_each(self.snapshot.prefix_iter_k(prefix), writes, &mut f);
+    }
+
+    /// Visit every entry within `range`, in key order, recording the range in the read set.
+    pub fn range_for_each<R, F>(&mut self, range: R, mut f: F)
+    where
+        R: RangeBounds<KeyType>,
+        F: FnMut(KeyType, &ValueType),
+    {
+        let start = range.start_bound().map(|key| key.as_ref().to_vec());
+        let end = range.end_bound().map(|key| key.as_ref().to_vec());
+        let span = ReadSpan::byte_range(&start, &end);
+        let writes = self
+            .writes
+            .range::<[u8], _>((start.as_ref().map(Vec::as_slice), Bound::Unbounded))
+            .take_while(|(key, _)| span.contains(key));
+        Self::overlay_for_each(self.snapshot.range(range), writes, &mut f);
+        self.reads.push(ReadSpan::Range(start, end));
+    }
+
+    /// Merge snapshot entries with buffered writes over the same keys, both in key order.
+    fn overlay_for_each<'a, F>(
+        entries: impl Iterator<Item = (KeyType, &'a ValueType)>,
+        writes: impl Iterator<Item = (&'a Vec<u8>, &'a Option<ValueType>)>,
+        f: &mut F,
+    ) where
+        ValueType: 'a,
+        F: FnMut(KeyType, &ValueType),
+    {
+        let mut entries = entries.peekable();
+        let mut writes = writes.peekable();
+        loop {
+            let write_first = match (entries.peek(), writes.peek()) {
+                (None, None) => return,
+                (Some(_), None) => false,
+                (None, Some(_)) => true,
+                (Some((key, _)), Some((written, _))) => written.as_slice() <= key.as_ref(),
+            };
+            if !write_first {
+                let (key, value) = entries.next().expect("peeked an entry");
+                f(key, value);
+                continue;
+            }
+            let (written, value) = writes.next().expect("peeked a write");
+            if entries
+                .peek()
+                .is_some_and(|(key, _)| key.as_ref() == written.as_slice())
+            {
+                entries.next();
+            }
+            if let Some(value) = value {
+                f(KeyType::new_from_slice(written), value);
+            }
+        }
+    }
+
+    /// Check the read set against `head` without committing.
+    ///
+    /// Fails if any key or range the transaction read may have changed in `head` since the
+    /// transaction's snapshot.
+    pub fn validate(
+        &self,
+        head: &VersionedAdaptiveRadixTree<KeyType, ValueType, S>,
+    ) -> Result<(), TransactionConflict> {
+        let conflict = self
+            .reads
+            .iter()
+            .any(|read| head.changed_within(&self.snapshot, read.seek(), read));
+        if conflict {
+            return Err(TransactionConflict {
+                start_version: self.start_version,
+                head_version: head.version(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Validate the read set against `head` and, if nothing read has changed, apply the
+    /// buffered writes to it as one [`WriteBatch`]. Returns the version of `head` afterwards.
+    ///
+    /// On conflict `head` is left untouched.
+    pub fn commit(
+        self,
+        head: &mut VersionedAdaptiveRadixTree<KeyType, ValueType, S>,
+    ) -> Result<u64, TransactionConflict> {
+        self.validate(head)?;
+        let mut batch: WriteBatch<'_, KeyType, ValueType> =
+            WriteBatch::with_capacity(self.writes.len());
+        for (key, value) in self.writes {
+            let key = KeyType::new_from_slice(&key);
+            match value {
+                Some(value) => batch.insert(key, value),
+                None => batch.remove(key),
+            }
+        }
+        head.apply_batch(batch)
+            .unwrap_or_else(|never: Infallible| match never {});
+        Ok(head.version())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::array_key::ArrayKey;
+    use proptest::prelude::*;
+    use std::collections::BTreeMap;
+
+    type Tree = VersionedAdaptiveRadixTree<ArrayKey<8>, u8>;
+
+    fn key(bytes: &[u8]) -> ArrayKey<8> {
+        ArrayKey::new_from_slice(bytes)
+    }
+
+    #[test]
+    fn scans_conflict_only_with_writes_inside_them() {
+        let mut head = Tree::new();
+        for k in [&b"aa"[..], b"ab", b"ba", b"bb"] {
+            head.insert_k(&key(k), 0);
+        }
+
+        let mut scan = Transaction::begin(&head);
+        let mut seen = Vec::new();
+        scan.prefix_for_each_k(&key(b"a"), |k, v| seen.push((k.as_ref().to_vec(), *v)));
+        assert_eq!(seen, vec![(b"aa".to_vec(), 0), (b"ab".to_vec(), 0)]);
+        scan.insert_k(&key(b"total"), 2);
+
+        // A write outside the scanned prefix does not conflict.
+        let mut other = Transaction::begin(&head);
+        other.insert_k(&key(b"bc"), 1);
+        other.commit(&mut head).unwrap();
+        scan.commit(&mut head).unwrap();
+
+        let mut retry = Transaction::begin(&head);
+        retry.range_for_each(key(b"aa")..key(b"b"), |_, _| {});
+        retry.insert_k(&key(b"total"), 2);
+
+        // A phantom inserted into the range does.
+        let mut phantom = Transaction::begin(&head);
+        phantom.insert_k(&key(b"ac"), 1);
+        phantom.commit(&mut head).unwrap();
+
+        let version = head.version();
+        let conflict = retry.commit(&mut head).unwrap_err();
+        assert_eq!(conflict.head_version, version);
+        assert_eq!(head.version(), version);
+    }
+
+    #[test]
+    fn reads_see_own_writes_in_key_order() {
+        let mut head = Tree::new();
+        for k in [&b"a"[..], b"b", b"c"] {
+            head.insert_k(&key(k), 1);
+        }
+        let mut txn = Transaction::begin(&head);
+        txn.remove_k(&key(b"b"));
+        txn.insert_k(&key(b"c"), 3);
+        txn.insert_k(&key(b"bb"), 2);
+        assert_eq!(txn.get_k(&key(b"b")), None);
+        assert_eq!(txn.get_k(&key(b"c")), Some(&3));
+
+        let mut seen = Vec::new();
+        txn.range_for_each(.., |k, v| seen.push((k.as_ref().to_vec(), *v)));
+        assert_eq!(
+            seen,
+            vec![(b"a".to_vec(), 1), (b"bb".to_vec(), 2), (b"c".to_vec(), 3)]
+        );
+        assert_eq!(head.get_k(&key(b"bb")), None);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_transactions_detect_changed_reads(
+            base_entries in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'c', 1..=4), any::<u8>()),
+                0..32
+            ),
+            reads in proptest::collection::vec(proptest::collection::vec(b'a'..=b'c', 0..=4), 0..6),
+            writes in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'c', 1..=4), proptest::option::of(any::<u8>())),
+                0..6
+            ),
+            concurrent in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'c', 1..=4), proptest::option::of(any::<u8>())),
+                0..6
+            )
+        ) {
+            let mut head = Tree::new();
+            let mut base_map = BTreeMap::new();
+            for (k, v) in base_entries {
+                head.insert_k(&key(&k), v);
+                base_map.insert(k, v);
+            }
+
+            // Even-length reads are prefix scans, odd-length ones point reads.
+            let mut txn = Transaction::begin(&head);
+            for read in &reads {
+                if read.len() % 2 == 0 {
+                    let mut seen = Vec::new();
+                    txn.prefix_for_each_k(&key(read), |k, v| seen.push((k.as_ref().to_vec(), *v)));
+                    let expected: Vec<_> = base_map
+                        .iter()
+                        .filter(|(k, _)| k.starts_with(read))
+                        .map(|(k, v)| (k.clone(), *v))
+                        .collect();
+                    prop_assert_eq!(seen, expected);
+                } else {
+                    prop_assert_eq!(txn.get_k(&key(read)), base_map.get(read));
+                }
+            }
+            for (k, v) in &writes {
+                match v {
+                    Some(v) => txn.insert_k(&key(k), *v),
+                    None => txn.remove_k(&key(k)),
+                }
+            }
+            prop_assert!(txn.validate(&head).is_ok());
+
+            let mut current_map = base_map.clone();
+            for (k, v) in concurrent {
+                match v {
+                    Some(v) => {
+                        head.insert_k(&key(&k), v);
+                        current_map.insert(k, v);
+                    }
+                    None => {
+                        head.remove_k(&key(&k));
+                        current_map.remove(&k);
+                    }
+                }
+            }
+            let read_changed = reads.iter().any(|read| {
+                let covers = |k: &Vec<u8>| {
+                    if read.len() % 2 == 0 { k.starts_with(read) } else { k == read }
+                };
+                let before = base_map.iter().filter(|(k, _)| covers(k));
+                let after = current_map.iter().filter(|(k, _)| covers(k));
+                !before.eq(after)
+            });
+
+            let before_commit = current_map.clone();
+            match txn.commit(&mut head) {
+                Ok(_) => {
+                    prop_assert!(!read_changed);
+                    for (k, v) in writes {
+                        match v {
+                            Some(v) => current_map.insert(k, v),
+                            None => current_map.remove(&k),
+                        };
+                    }
+                }
+                Err(_) => current_map = before_commit,
+            }
+            let got: Vec<_> = head.iter().map(|(k, v)| (k.as_ref().to_vec(), *v)).collect();
+            let expected: Vec<_> = current_map.into_iter().collect();
+            prop_assert_eq!(got, expected);
+        }
+    }
+}
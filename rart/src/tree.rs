@@ -9,12 +9,16 @@ use std::convert::Infallible;
 use std::ops::RangeBounds;
 
 use crate::iter::{Iter, LendingIterInner, LendingKeyView, PrefixMatchIter, ValuesIter};
+use crate::join::{self, FilterJoinIter, NodeView, Only};
 use crate::keys::KeyTrait;
 use crate::node::{DefaultNode, Node};
-use crate::partials::Partial;
-use crate::range::Range;
-use crate::stats::{TreeStats, TreeStatsTrait, update_tree_stats};
-use crate::{Slot, SlotUpdate, VisitControl};
+use crate::parallel;
+use crate::partials::{Partial, prefix_length_bytes};
+use crate::range::{ByteRange, Range};
+use crate::shared_ptr::SharedPtr;
+use crate::stats::{TreeStats, TreeStatsTrait, finish_tree_stats, update_tree_stats};
+use crate::versioned_tree::VersionedAdaptiveRadixTree;
+use crate::{Side, Slot, SlotUpdate, VisitControl};
 
 /// An Adaptive Radix Tree (ART) - a high-performance, memory-efficient trie data structure.
 ///
@@ -78,12 +82,27 @@ pub struct AdaptiveRadixTree<KeyType, ValueType>
 where
     KeyType: KeyTrait,
 {
-    root: Option<DefaultNode<KeyType::PartialType, ValueType>>,
+    pub(crate) root: Option<DefaultNode<KeyType::PartialType, ValueType>>,
     len: usize,
     _phantom: std::marker::PhantomData<KeyType>,
 }
 
 type PrefixSubtreeView<'a, P, V> = (&'a DefaultNode<P, V>, Vec<&'a [u8]>, usize);
+type PairedSide<'a, P, V> = (Option<&'a V>, Vec<(u8, &'a DefaultNode<P, V>, usize)>);
+type MergeSide<P, V> = (Option<V>, Vec<(u8, DefaultNode<P, V>)>);
+
+/// The first step of bulk-loading a node from the sorted items below it.
+enum BulkNodeStart<P: Partial, V> {
+    /// The node is complete: it has no children.
+    Leaf(DefaultNode<P, V>),
+    /// The node's children still have to be built from `items[first_child..]`, grouped by the
+    /// key byte at `depth`.
+    Inner {
+        node: DefaultNode<P, V>,
+        first_child: usize,
+        depth: usize,
+    },
+}
 
 enum UpdateRecurseResult {
     Unchanged,
@@ -126,26 +145,30 @@ where
     where
         I: IntoIterator<Item = (KeyType, ValueType)>,
     {
-        let iter = items.into_iter();
-        let (lower, _) = iter.size_hint();
-        let mut unique: Vec<(KeyType, Option<ValueType>)> = Vec::with_capacity(lower);
-
-        for (key, value) in iter {
-            if let Some((last_key, last_value)) = unique.last_mut() {
-                match Ord::cmp(&*last_key, &key) {
-                    Ordering::Greater => panic!("bulk_load_sorted input is not sorted"),
-                    Ordering::Equal => {
-                        *last_value = Some(value);
-                        continue;
-                    }
-                    Ordering::Less => {}
-                }
-            }
+        Self::from_unique_sorted_items(Self::dedup_sorted_items(items, "bulk_load_sorted"))
+    }
 
-            unique.push((key, Some(value)));
+    /// Build an Adaptive Radix Tree from already sorted key-value pairs on multiple threads.
+    ///
+    /// Accepts the same input as [`bulk_load_sorted`](Self::bulk_load_sorted). The input is
+    /// split at the boundaries between the root's children, and the subtrees below them are
+    /// built on `std::thread::scope` workers, each taking a contiguous run of children with
+    /// about the same number of keys. Keys that share a common leading prefix are split below
+    /// it, so parallelism is bounded by the number of distinct bytes after that prefix.
+    pub fn bulk_load_sorted_parallel<I>(items: I) -> Self
+    where
+        I: IntoIterator<Item = (KeyType, ValueType)>,
+        KeyType: Send,
+        KeyType::PartialType: Send,
+        ValueType: Send,
+    {
+        let mut items = Self::dedup_sorted_items(items, "bulk_load_sorted_parallel");
+        if items.is_empty() {
+            return Self::new();
         }
 
-        Self::from_unique_sorted_items(unique)
+        let len = items.len();
+        Self::from_root(Self::build_bulk_node_parallel(&mut items), len)
     }
 
     /// Build an Adaptive Radix Tree from strictly sorted, unique key-value pairs.
@@ -673,6 +696,48 @@ where
         LendingIterInner::for_each(self.root.as_ref(), on_each);
     }
 
+    /// Visit all key-value pairs using a lending borrowed key view, on multiple threads.
+    ///
+    /// The root's value is visited first on the calling thread. The subtrees below the root's
+    /// children are then handed out to `std::thread::scope` workers: entries within one
+    /// subtree are visited in key order on one thread, while different subtrees are visited
+    /// concurrently, so `on_each` sees them interleaved.
+    pub fn par_for_each_view<F>(&self, on_each: F)
+    where
+        F: for<'view> Fn(LendingKeyView<'_, 'view>, &ValueType) + Sync,
+        KeyType::PartialType: Sync,
+        ValueType: Sync,
+    {
+        let Some(root) = self.root.as_ref() else {
+            return;
+        };
+        let root_segments = if root.prefix.is_empty() {
+            Vec::new()
+        } else {
+            vec![root.prefix.as_ref()]
+        };
+        let root_len = root.prefix.len();
+
+        if let Some(value) = root.value() {
+            on_each(LendingKeyView::new(&root_segments, root_len), value);
+        }
+
+        let children: Vec<_> = root.iter().map(|(_, child)| child).collect();
+        parallel::for_each(&children, |child| {
+            let mut segments = root_segments.clone();
+            let segment = child.prefix.as_ref();
+            if !segment.is_empty() {
+                segments.push(segment);
+            }
+            LendingIterInner::for_each_with_prefix(
+                Some(*child),
+                segments,
+                root_len + segment.len(),
+                &on_each,
+            );
+        });
+    }
+
     /// Create an iterator over only the values in the tree.
     ///
     /// This iterator skips key reconstruction entirely and only yields values.
@@ -694,7 +759,14 @@ where
         };
 
         let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
-        Self::intersect_nodes(left_root, 0, right_root, 0, &mut key_buf, &mut on_match);
+        join::intersect(
+            left_root,
+            0,
+            right_root,
+            0,
+            &mut key_buf,
+            &mut |key, left, right| on_match(KeyType::new_from_slice(key), left, right),
+        );
     }
 
     /// Intersect two trees using ART-native traversal and yield lending key views.
@@ -711,7 +783,7 @@ where
 
         let mut segments = Vec::new();
         let mut key_len = 0usize;
-        Self::intersect_nodes_lending(
+        join::intersect_lending(
             left_root,
             0,
             right_root,
@@ -733,7 +805,7 @@ where
             return;
         };
 
-        Self::intersect_nodes_values(left_root, 0, right_root, 0, &mut on_match);
+        join::intersect_values(left_root, 0, right_root, 0, &mut on_match);
     }
 
     /// Count the number of keys that exist in both trees.
@@ -745,6 +817,650 @@ where
         count
     }
 
+    /// Intersect the entries of two trees whose keys start with `prefix`.
+    ///
+    /// Both trees are first descended to their `prefix` subtrees, and only those subtrees are
+    /// walked in lockstep, so the cost does not depend on how many keys lie outside the prefix.
+    ///
+    /// ```rust
+    /// use rart::{AdaptiveRadixTree, KeyTrait, VectorKey};
+    ///
+    /// let mut left = AdaptiveRadixTree::<VectorKey, u32>::new();
+    /// let mut right = AdaptiveRadixTree::<VectorKey, u32>::new();
+    /// for (key, value) in [(&b"user/ann"[..], 1), (b"user/bob", 2), (b"group/ops", 3)] {
+    ///     left.insert_k(&VectorKey::new_from_slice(key), value);
+    ///     right.insert_k(&VectorKey::new_from_slice(key), value * 10);
+    /// }
+    ///
+    /// let mut matches = Vec::new();
+    /// left.intersect_prefix_with(&right, &VectorKey::new_from_slice(b"user/"), |key, l, r| {
+    ///     matches.push((key.as_ref().to_vec(), *l, *r));
+    /// });
+    /// assert_eq!(
+    ///     matches,
+    ///     vec![(b"user/ann".to_vec(), 1, 10), (b"user/bob".to_vec(), 2, 20)]
+    /// );
+    /// ```
+    pub fn intersect_prefix_with<'a, F>(
+        &'a self,
+        other: &'a Self,
+        prefix: &KeyType,
+        mut on_match: F,
+    ) where
+        F: FnMut(KeyType, &'a ValueType, &'a ValueType),
+    {
+        let (Some(left_root), Some(right_root)) = (self.root.as_ref(), other.root.as_ref()) else {
+            return;
+        };
+        let Some((left, left_offset)) = Self::find_prefix_subtree_offset(left_root, prefix) else {
+            return;
+        };
+        let Some((right, right_offset)) = Self::find_prefix_subtree_offset(right_root, prefix)
+        else {
+            return;
+        };
+
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        key_buf.extend_from_slice(&prefix.as_ref()[..prefix.length_at(0)]);
+        join::intersect(
+            left,
+            left_offset,
+            right,
+            right_offset,
+            &mut key_buf,
+            &mut |key, left_value, right_value| {
+                on_match(KeyType::new_from_slice(key), left_value, right_value)
+            },
+        );
+    }
+
+    /// Intersect the entries of two trees whose keys start with `prefix`, yielding value pairs
+    /// only.
+    pub fn intersect_prefix_values_with<'a, F>(
+        &'a self,
+        other: &'a Self,
+        prefix: &KeyType,
+        mut on_match: F,
+    ) where
+        F: FnMut(&'a ValueType, &'a ValueType),
+    {
+        let (Some(left_root), Some(right_root)) = (self.root.as_ref(), other.root.as_ref()) else {
+            return;
+        };
+        let Some((left, left_offset)) = Self::find_prefix_subtree_offset(left_root, prefix) else {
+            return;
+        };
+        let Some((right, right_offset)) = Self::find_prefix_subtree_offset(right_root, prefix)
+        else {
+            return;
+        };
+
+        join::intersect_values(left, left_offset, right, right_offset, &mut on_match);
+    }
+
+    /// Count the keys starting with `prefix` that exist in both trees.
+    pub fn intersect_prefix_count(&self, other: &Self, prefix: &KeyType) -> usize {
+        let mut count = 0usize;
+        self.intersect_prefix_values_with(other, prefix, |_left_value, _right_value| {
+            count += 1;
+        });
+        count
+    }
+
+    /// Intersect the entries of two trees whose keys fall within `range`.
+    ///
+    /// When both ends of the range are bounded, both trees are first descended to the subtree
+    /// for the bytes the bounds share. The lockstep walk below that point skips any subtree
+    /// whose keys all lie outside the range.
+    pub fn intersect_range_with<'a, R, F>(&'a self, other: &'a Self, range: R, mut on_match: F)
+    where
+        R: RangeBounds<KeyType>,
+        F: FnMut(KeyType, &'a ValueType, &'a ValueType),
+    {
+        self.intersect_range_raw(other, &range, &mut |key, left_value, right_value| {
+            on_match(KeyType::new_from_slice(key), left_value, right_value)
+        });
+    }
+
+    /// Intersect the entries of two trees whose keys fall within `range`, yielding value pairs
+    /// only.
+    pub fn intersect_range_values_with<'a, R, F>(
+        &'a self,
+        other: &'a Self,
+        range: R,
+        mut on_match: F,
+    ) where
+        R: RangeBounds<KeyType>,
+        F: FnMut(&'a ValueType, &'a ValueType),
+    {
+        self.intersect_range_raw(other, &range, &mut |_key, left_value, right_value| {
+            on_match(left_value, right_value)
+        });
+    }
+
+    /// Count the keys within `range` that exist in both trees.
+    pub fn intersect_range_count<R>(&self, other: &Self, range: R) -> usize
+    where
+        R: RangeBounds<KeyType>,
+    {
+        let mut count = 0usize;
+        self.intersect_range_values_with(other, range, |_left_value, _right_value| {
+            count += 1;
+        });
+        count
+    }
+
+    /// Intersect any number of trees at once, descending all tries together.
+    ///
+    /// `on_match` is called in key order for every key present in all of `trees`, with a view
+    /// of the key and the values from each tree, in the order of `trees`. At each node the
+    /// children's edge bytes are intersected as bitsets across all trees, so only edges
+    /// present everywhere are followed. No intermediate trees are built, which makes this the
+    /// building block for worst-case optimal joins over several indexes. An empty slice
+    /// matches nothing.
+    ///
+    /// ```rust
+    /// use rart::{AdaptiveRadixTree, ArrayKey};
+    ///
+    /// let mut a = AdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+    /// let mut b = AdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+    /// let mut c = AdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+    /// for (tree, offset) in [(&mut a, 0), (&mut b, 10), (&mut c, 20)] {
+    ///     tree.insert("ant", offset + 1);
+    ///     tree.insert("bee", offset + 2);
+    /// }
+    /// b.remove("ant");
+    ///
+    /// let mut matches = Vec::new();
+    /// AdaptiveRadixTree::intersect_many(&[&a, &b, &c], |key, values| {
+    ///     matches.push((key.to_vec(), values.iter().map(|v| **v).collect::<Vec<_>>()));
+    /// });
+    /// assert_eq!(matches, vec![(b"bee\0".to_vec(), vec![2, 12, 22])]);
+    /// ```
+    pub fn intersect_many<'a, F>(trees: &[&'a Self], mut on_match: F)
+    where
+        F: for<'view> FnMut(LendingKeyView<'a, 'view>, &[&'a ValueType]),
+    {
+        let mut cursors = Vec::with_capacity(trees.len());
+        for tree in trees {
+            let Some(root) = tree.root.as_ref() else {
+                return;
+            };
+            cursors.push((root, 0));
+        }
+        if cursors.is_empty() {
+            return;
+        }
+
+        let mut segments = Vec::new();
+        let mut key_len = 0usize;
+        let mut values = Vec::with_capacity(cursors.len());
+        join::intersect_many(
+            &cursors,
+            &mut segments,
+            &mut key_len,
+            &mut values,
+            &mut on_match,
+        );
+    }
+
+    /// Visit the entries of this tree whose keys are not in `other`, in key order.
+    ///
+    /// Both tries are walked together like [`Self::intersect_with`]. Where `other` has no
+    /// matching child, the whole subtree is reported without looking anything up in `other`.
+    pub fn difference_with<'a, F>(&'a self, other: &'a Self, mut on_entry: F)
+    where
+        F: FnMut(KeyType, &'a ValueType),
+    {
+        let Some(left_root) = self.root.as_ref() else {
+            return;
+        };
+
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        let mut on_entry = |key: &[u8], value| on_entry(KeyType::new_from_slice(key), value);
+        match other.root.as_ref() {
+            Some(right_root) => join::difference(
+                left_root,
+                0,
+                right_root,
+                0,
+                false,
+                &mut key_buf,
+                &mut |key, only| {
+                    if let Only::Left(value) = only {
+                        on_entry(key, value);
+                    }
+                },
+            ),
+            None => join::for_each_from(left_root, 0, &mut key_buf, &mut on_entry),
+        }
+    }
+
+    /// Visit the entries whose keys are in exactly one of the two trees, in key order.
+    ///
+    /// Entries from this tree are reported as [`Side::Left`] and entries from `other` as
+    /// [`Side::Right`]. Subtrees with no counterpart on the other side are reported whole.
+    ///
+    /// ```rust
+    /// use rart::{AdaptiveRadixTree, ArrayKey, Side};
+    ///
+    /// let mut before = AdaptiveRadixTree::<ArrayKey<16>, ()>::new();
+    /// before.insert("read", ());
+    /// before.insert("write", ());
+    /// let mut after = AdaptiveRadixTree::<ArrayKey<16>, ()>::new();
+    /// after.insert("read", ());
+    /// after.insert("admin", ());
+    ///
+    /// let mut changes = Vec::new();
+    /// before.symmetric_difference_with(&after, |key, side| {
+    ///     changes.push((key, matches!(side, Side::Left(_))));
+    /// });
+    /// assert_eq!(changes, vec![("admin".into(), false), ("write".into(), true)]);
+    /// ```
+    pub fn symmetric_difference_with<'a, F>(&'a self, other: &'a Self, mut on_entry: F)
+    where
+        F: FnMut(KeyType, Side<&'a ValueType>),
+    {
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        let mut on_entry = |key: &[u8], side| on_entry(KeyType::new_from_slice(key), side);
+        match (self.root.as_ref(), other.root.as_ref()) {
+            (None, None) => {}
+            (Some(left_root), None) => {
+                join::for_each_from(left_root, 0, &mut key_buf, &mut |key, value| {
+                    on_entry(key, Side::Left(value))
+                })
+            }
+            (None, Some(right_root)) => {
+                join::for_each_from(right_root, 0, &mut key_buf, &mut |key, value| {
+                    on_entry(key, Side::Right(value))
+                })
+            }
+            (Some(left_root), Some(right_root)) => join::difference(
+                left_root,
+                0,
+                right_root,
+                0,
+                true,
+                &mut key_buf,
+                &mut |key, only| on_entry(key, only.into()),
+            ),
+        }
+    }
+
+    /// Count the number of keys in this tree that are not in `other`.
+    ///
+    /// Only paths present in both trees are visited.
+    pub fn difference_count(&self, other: &Self) -> usize {
+        self.len - self.intersect_count(other)
+    }
+
+    /// Intersect this tree with a `VersionedAdaptiveRadixTree` with the same key type.
+    ///
+    /// Both tries are walked in lockstep like [`Self::intersect_with`], so neither side has
+    /// to be converted first. The two trees may hold different value types.
+    pub fn intersect_versioned_with<'a, W, S, F>(
+        &'a self,
+        other: &'a VersionedAdaptiveRadixTree<KeyType, W, S>,
+        mut on_match: F,
+    ) where
+        W: Clone,
+        S: SharedPtr,
+        F: FnMut(KeyType, &'a ValueType, &'a W),
+    {
+        let (Some(left_root), Some(right_root)) = (self.root.as_ref(), other.root.as_deref())
+        else {
+            return;
+        };
+
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        join::intersect(
+            left_root,
+            0,
+            right_root,
+            0,
+            &mut key_buf,
+            &mut |key, left, right| on_match(KeyType::new_from_slice(key), left, right),
+        );
+    }
+
+    /// Intersect this tree with a `VersionedAdaptiveRadixTree` and invoke a callback with value pairs only.
+    pub fn intersect_versioned_values_with<'a, W, S, F>(
+        &'a self,
+        other: &'a VersionedAdaptiveRadixTree<KeyType, W, S>,
+        mut on_match: F,
+    ) where
+        W: Clone,
+        S: SharedPtr,
+        F: FnMut(&'a ValueType, &'a W),
+    {
+        let (Some(left_root), Some(right_root)) = (self.root.as_ref(), other.root.as_deref())
+        else {
+            return;
+        };
+
+        join::intersect_values(left_root, 0, right_root, 0, &mut on_match);
+    }
+
+    /// Count the number of keys that exist in both this tree and a `VersionedAdaptiveRadixTree`.
+    pub fn intersect_versioned_count<W, S>(
+        &self,
+        other: &VersionedAdaptiveRadixTree<KeyType, W, S>,
+    ) -> usize
+    where
+        W: Clone,
+        S: SharedPtr,
+    {
+        let mut count = 0usize;
+        self.intersect_versioned_values_with(other, |_left_value, _right_value| {
+            count += 1;
+        });
+        count
+    }
+
+    /// Visit every entry of this tree whose key is not in a `VersionedAdaptiveRadixTree`.
+    ///
+    /// Subtrees with no counterpart in `other` are reported whole, without comparing their
+    /// keys one at a time.
+    pub fn difference_versioned_with<'a, W, S, F>(
+        &'a self,
+        other: &VersionedAdaptiveRadixTree<KeyType, W, S>,
+        mut on_entry: F,
+    ) where
+        W: Clone,
+        S: SharedPtr,
+        F: FnMut(KeyType, &'a ValueType),
+    {
+        let Some(left_root) = self.root.as_ref() else {
+            return;
+        };
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        let mut on_entry = |key: &[u8], value| on_entry(KeyType::new_from_slice(key), value);
+        match other.root.as_deref() {
+            Some(right_root) => join::difference(
+                left_root,
+                0,
+                right_root,
+                0,
+                false,
+                &mut key_buf,
+                &mut |key, only| {
+                    if let Only::Left(value) = only {
+                        on_entry(key, value);
+                    }
+                },
+            ),
+            None => join::for_each_from(left_root, 0, &mut key_buf, &mut on_entry),
+        }
+    }
+
+    /// Count the number of keys in this tree that are not in a `VersionedAdaptiveRadixTree`.
+    pub fn difference_versioned_count<W, S>(
+        &self,
+        other: &VersionedAdaptiveRadixTree<KeyType, W, S>,
+    ) -> usize
+    where
+        W: Clone,
+        S: SharedPtr,
+    {
+        self.len - self.intersect_versioned_count(other)
+    }
+
+    /// For every key in this tree, visit each entry of `other` whose key is a prefix of it.
+    ///
+    /// `on_match` receives this tree's key, the `other` key (a prefix of the first, possibly
+    /// equal to it), and both values. Keys are visited in order, and for each key the
+    /// matching prefixes from shortest to longest. Both tries are descended together while
+    /// tracking the `other` entries passed on the way down, so this costs one paired walk
+    /// rather than a [`Self::prefix_match_for_each_k`] probe per key. Subtrees of this tree
+    /// that no `other` key leads into or covers are skipped.
+    ///
+    /// [`prefix_join_under_with`](Self::prefix_join_under_with) is the reverse direction.
+    ///
+    /// ```rust
+    /// use rart::{AdaptiveRadixTree, KeyTrait, VectorKey};
+    ///
+    /// let mut paths = AdaptiveRadixTree::<VectorKey, u32>::new();
+    /// paths.insert_k(&VectorKey::new_from_slice(b"/srv/www/index"), 1);
+    /// paths.insert_k(&VectorKey::new_from_slice(b"/tmp/x"), 2);
+    /// let mut rules = AdaptiveRadixTree::<VectorKey, &str>::new();
+    /// rules.insert_k(&VectorKey::new_from_slice(b"/srv"), "read");
+    /// rules.insert_k(&VectorKey::new_from_slice(b"/srv/www"), "serve");
+    /// rules.insert_k(&VectorKey::new_from_slice(b"/home"), "none");
+    ///
+    /// let mut matches = Vec::new();
+    /// paths.prefix_join_with(&rules, |path, rule_key, _, rule| {
+    ///     matches.push((path.as_ref().to_vec(), rule_key.as_ref().to_vec(), *rule));
+    /// });
+    /// assert_eq!(
+    ///     matches,
+    ///     vec![
+    ///         (b"/srv/www/index".to_vec(), b"/srv".to_vec(), "read"),
+    ///         (b"/srv/www/index".to_vec(), b"/srv/www".to_vec(), "serve"),
+    ///     ]
+    /// );
+    /// ```
+    pub fn prefix_join_with<'a, W, F>(
+        &'a self,
+        other: &'a AdaptiveRadixTree<KeyType, W>,
+        mut on_match: F,
+    ) where
+        F: FnMut(KeyType, KeyType, &'a ValueType, &'a W),
+    {
+        let (Some(left_root), Some(right_root)) = (self.root.as_ref(), other.root.as_ref()) else {
+            return;
+        };
+
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        let mut passed = Vec::new();
+        join::prefix_join(
+            left_root,
+            0,
+            right_root,
+            0,
+            &mut key_buf,
+            &mut passed,
+            &mut |key, prefix, value, prefix_value| {
+                on_match(
+                    KeyType::new_from_slice(key),
+                    KeyType::new_from_slice(prefix),
+                    value,
+                    prefix_value,
+                )
+            },
+        );
+    }
+
+    /// For every key in this tree, visit each entry of `other` whose key starts with it: the
+    /// reverse direction of [`prefix_join_with`](Self::prefix_join_with).
+    ///
+    /// `on_match` receives this tree's key, the `other` key (an extension of the first,
+    /// possibly equal to it), and both values. Entries are visited in `other` key order, and
+    /// for each `other` key the matching keys of this tree from shortest to longest. This is
+    /// the same paired descent as `prefix_join_with` with the roles of the trees swapped.
+    ///
+    /// ```rust
+    /// use rart::{AdaptiveRadixTree, KeyTrait, VectorKey};
+    ///
+    /// let mut rules = AdaptiveRadixTree::<VectorKey, &str>::new();
+    /// rules.insert_k(&VectorKey::new_from_slice(b"/srv"), "read");
+    /// rules.insert_k(&VectorKey::new_from_slice(b"/tmp"), "scratch");
+    /// let mut paths = AdaptiveRadixTree::<VectorKey, u32>::new();
+    /// paths.insert_k(&VectorKey::new_from_slice(b"/srv/www/index"), 1);
+    /// paths.insert_k(&VectorKey::new_from_slice(b"/srv/db"), 2);
+    /// paths.insert_k(&VectorKey::new_from_slice(b"/home/me"), 3);
+    ///
+    /// let mut covered = Vec::new();
+    /// rules.prefix_join_under_with(&paths, |_, path, rule, _| {
+    ///     covered.push((*rule, path.as_ref().to_vec()));
+    /// });
+    /// assert_eq!(
+    ///     covered,
+    ///     vec![
+    ///         ("read", b"/srv/db".to_vec()),
+    ///         ("read", b"/srv/www/index".to_vec()),
+    ///     ]
+    /// );
+    /// ```
+    pub fn prefix_join_under_with<'a, W, F>(
+        &'a self,
+        other: &'a AdaptiveRadixTree<KeyType, W>,
+        mut on_match: F,
+    ) where
+        F: FnMut(KeyType, KeyType, &'a ValueType, &'a W),
+    {
+        other.prefix_join_with(self, |other_key, key, other_value, value| {
+            on_match(key, other_key, value, other_value)
+        });
+    }
+
+    /// Iterate over the entries of this tree whose keys are also in `other`.
+    ///
+    /// Only the keys of `other` matter, so it may hold any value type. Both tries are walked
+    /// together, and subtrees of this tree with no counterpart in `other` are skipped whole.
+    pub fn semi_join_iter<'a, W>(
+        &'a self,
+        other: &'a AdaptiveRadixTree<KeyType, W>,
+    ) -> impl Iterator<Item = (KeyType, &'a ValueType)> + 'a {
+        FilterJoinIter::<KeyType, _, _>::new(self.root.as_ref(), other.root.as_ref(), true)
+    }
+
+    /// Iterate over the entries of this tree whose keys are not in `other`.
+    ///
+    /// Only the keys of `other` matter, so it may hold any value type. Subtrees of this tree
+    /// with no counterpart in `other` are yielded whole without further comparison.
+    pub fn anti_join_iter<'a, W>(
+        &'a self,
+        other: &'a AdaptiveRadixTree<KeyType, W>,
+    ) -> impl Iterator<Item = (KeyType, &'a ValueType)> + 'a {
+        FilterJoinIter::<KeyType, _, _>::new(self.root.as_ref(), other.root.as_ref(), false)
+    }
+
+    /// Keep only the entries whose keys are also in `other`, which may hold any value type.
+    ///
+    /// Subtrees with no counterpart in `other` are dropped whole.
+    pub fn retain_intersecting<W>(&mut self, other: &AdaptiveRadixTree<KeyType, W>) {
+        self.retain_by_presence(other, true);
+    }
+
+    /// Remove every entry whose key is in `other`, which may hold any value type.
+    ///
+    /// Subtrees with no counterpart in `other` are kept whole without visiting their keys.
+    pub fn retain_not_in<W>(&mut self, other: &AdaptiveRadixTree<KeyType, W>) {
+        self.retain_by_presence(other, false);
+    }
+
+    /// Build a new tree holding every key of `self` and `other`.
+    ///
+    /// Both tries are walked together like [`Self::intersect_with`]. Subtrees that exist on
+    /// only one side are cloned whole without visiting their keys one at a time. For keys
+    /// present in both trees, `resolver` receives the key, this tree's value, and `other`'s
+    /// value, and its result is stored.
+    ///
+    /// ```rust
+    /// use rart::{AdaptiveRadixTree, ArrayKey};
+    ///
+    /// let mut left = AdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+    /// left.insert("apple", 1);
+    /// left.insert("banana", 2);
+    /// let mut right = AdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+    /// right.insert("banana", 10);
+    /// right.insert("cherry", 20);
+    ///
+    /// let union = left.union_with(&right, |_key, a, b| a + b);
+    /// assert_eq!(union.len(), 3);
+    /// assert_eq!(union.get("banana"), Some(&12));
+    /// ```
+    pub fn union_with<F>(&self, other: &Self, mut resolver: F) -> Self
+    where
+        ValueType: Clone,
+        F: FnMut(KeyType, &ValueType, &ValueType) -> ValueType,
+    {
+        match (self.root.as_ref(), other.root.as_ref()) {
+            (None, None) => Self::new(),
+            (Some(root), None) => Self::from_root(root.clone_subtree(), self.len),
+            (None, Some(root)) => Self::from_root(root.clone_subtree(), other.len),
+            (Some(left_root), Some(right_root)) => {
+                let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+                let mut overlapping = 0usize;
+                let root = Self::union_nodes(
+                    left_root,
+                    0,
+                    right_root,
+                    0,
+                    &mut key_buf,
+                    &mut resolver,
+                    &mut overlapping,
+                );
+                Self::from_root(root, self.len + other.len - overlapping)
+            }
+        }
+    }
+
+    /// Merge `other` into this tree, consuming it.
+    ///
+    /// Both tries are walked together, and subtrees that exist only in `other` are moved into
+    /// this tree whole. For keys present in both trees, `resolver` receives the key, this
+    /// tree's value, and `other`'s value, and its result is stored.
+    pub fn merge_from<F>(&mut self, other: Self, mut resolver: F)
+    where
+        F: FnMut(KeyType, ValueType, ValueType) -> ValueType,
+    {
+        let Some(right_root) = other.root else {
+            return;
+        };
+        let Some(left_root) = self.root.take() else {
+            self.root = Some(right_root);
+            self.len = other.len;
+            return;
+        };
+
+        let left_len = std::mem::take(&mut self.len);
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        let mut overlapping = 0usize;
+        self.root = Some(Self::merge_nodes(
+            left_root,
+            right_root,
+            &mut key_buf,
+            &mut resolver,
+            &mut overlapping,
+        ));
+        self.len = left_len + other.len - overlapping;
+    }
+
+    /// Merge a borrowed `other` into this tree, leaving `other` unchanged.
+    ///
+    /// `other` is walked alongside this tree's existing nodes, which are kept and only split
+    /// where `other`'s keys leave their prefixes. For keys present in both trees, `resolver`
+    /// receives the key, this tree's value, and `other`'s value, and its result replaces this
+    /// tree's value. Subtrees found only in `other` are cloned in; prefer
+    /// [`merge_from`](Self::merge_from) when `other` is no longer needed, which moves them.
+    pub fn merge_from_ref<F>(&mut self, other: &Self, mut resolver: F)
+    where
+        ValueType: Clone,
+        F: FnMut(KeyType, &ValueType, &ValueType) -> ValueType,
+    {
+        let Some(right_root) = other.root.as_ref() else {
+            return;
+        };
+        let Some(left_root) = self.root.as_mut() else {
+            self.root = Some(right_root.clone_subtree());
+            self.len = other.len;
+            return;
+        };
+
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        let mut overlapping = 0usize;
+        Self::merge_ref_into(
+            left_root,
+            right_root,
+            0,
+            &mut key_buf,
+            &mut resolver,
+            &mut overlapping,
+        );
+        self.len += other.len - overlapping;
+    }
+
     /// Create an iterator over key-value pairs within a specified range.
     ///
     /// The range can be any type that implements `RangeBounds<KeyType>`.
@@ -842,6 +1558,48 @@ where
         }
     }
 
+    /// Visit only values whose keys start with `prefix`, on multiple threads.
+    ///
+    /// The work is split by the children of the node the prefix leads to, as in
+    /// [`par_for_each_view`](Self::par_for_each_view); values are not visited in key order.
+    #[inline]
+    pub fn par_prefix_values_for_each<Key, F>(&self, prefix: Key, on_each: F)
+    where
+        Key: Into<KeyType>,
+        F: Fn(&ValueType) + Sync,
+        KeyType::PartialType: Sync,
+        ValueType: Sync,
+    {
+        self.par_prefix_values_for_each_k(&prefix.into(), on_each)
+    }
+
+    /// Visit only values whose keys start with `prefix`, on multiple threads.
+    ///
+    /// The work is split by the children of the node the prefix leads to, as in
+    /// [`par_for_each_view`](Self::par_for_each_view); values are not visited in key order.
+    pub fn par_prefix_values_for_each_k<F>(&self, prefix: &KeyType, on_each: F)
+    where
+        F: Fn(&ValueType) + Sync,
+        KeyType::PartialType: Sync,
+        ValueType: Sync,
+    {
+        let Some(root) = self.root.as_ref() else {
+            return;
+        };
+        let Some(subtree_root) = AdaptiveRadixTree::find_prefix_subtree_node(root, prefix) else {
+            return;
+        };
+
+        if let Some(value) = subtree_root.value() {
+            on_each(value);
+        }
+
+        let children: Vec<_> = subtree_root.iter().map(|(_, child)| child).collect();
+        parallel::for_each(&children, |child| {
+            ValuesIter::new(Some(*child)).for_each(&on_each);
+        });
+    }
+
     /// Fallibly visit only values whose keys start with `prefix`.
     ///
     /// Returning [`VisitControl::Stop`] stops traversal immediately. Returning
@@ -932,21 +1690,7 @@ where
             1,
         );
 
-        let total_inner_nodes = stats
-            .node_stats
-            .values()
-            .map(|ns| ns.total_nodes)
-            .sum::<usize>();
-        let mut total_children = 0;
-        let mut total_width = 0;
-        for ns in stats.node_stats.values_mut() {
-            total_children += ns.total_children;
-            total_width += ns.width * ns.total_nodes;
-            ns.density = ns.total_children as f64 / (ns.width * ns.total_nodes) as f64;
-        }
-        let total_density = total_children as f64 / total_width as f64;
-        stats.num_inner_nodes = total_inner_nodes;
-        stats.total_density = total_density;
+        finish_tree_stats(&mut stats);
 
         stats
     }
@@ -957,6 +1701,32 @@ impl<KeyType, ValueType> AdaptiveRadixTree<KeyType, ValueType>
 where
     KeyType: KeyTrait,
 {
+    fn dedup_sorted_items<I>(items: I, caller: &str) -> Vec<(KeyType, Option<ValueType>)>
+    where
+        I: IntoIterator<Item = (KeyType, ValueType)>,
+    {
+        let iter = items.into_iter();
+        let (lower, _) = iter.size_hint();
+        let mut unique: Vec<(KeyType, Option<ValueType>)> = Vec::with_capacity(lower);
+
+        for (key, value) in iter {
+            if let Some((last_key, last_value)) = unique.last_mut() {
+                match Ord::cmp(&*last_key, &key) {
+                    Ordering::Greater => panic!("{caller} input is not sorted"),
+                    Ordering::Equal => {
+                        *last_value = Some(value);
+                        continue;
+                    }
+                    Ordering::Less => {}
+                }
+            }
+
+            unique.push((key, Some(value)));
+        }
+
+        unique
+    }
+
     fn from_unique_sorted_items(mut items: Vec<(KeyType, Option<ValueType>)>) -> Self {
         if items.is_empty() {
             return Self::new();
@@ -970,14 +1740,79 @@ where
         items: &mut [(KeyType, Option<ValueType>)],
         depth: usize,
     ) -> DefaultNode<KeyType::PartialType, ValueType> {
+        let (mut node, first_child, node_depth) = match Self::bulk_node_start(items, depth) {
+            BulkNodeStart::Leaf(leaf) => return leaf,
+            BulkNodeStart::Inner {
+                node,
+                first_child,
+                depth,
+            } => (node, first_child, depth),
+        };
+
+        let mut start = first_child;
+        while start < items.len() {
+            let edge = items[start].0.at(node_depth);
+            let end = Self::bulk_child_end(items, start, node_depth);
+            let child = Self::build_bulk_node(&mut items[start..end], node_depth);
+            node.add_child_sorted_unchecked(edge, child);
+            start = end;
+        }
+
+        node
+    }
+
+    /// Build the root like [`build_bulk_node`](Self::build_bulk_node), with the subtrees below
+    /// its children built on worker threads.
+    fn build_bulk_node_parallel(
+        items: &mut [(KeyType, Option<ValueType>)],
+    ) -> DefaultNode<KeyType::PartialType, ValueType>
+    where
+        KeyType: Send,
+        KeyType::PartialType: Send,
+        ValueType: Send,
+    {
+        let (mut node, first_child, node_depth) = match Self::bulk_node_start(items, 0) {
+            BulkNodeStart::Leaf(leaf) => return leaf,
+            BulkNodeStart::Inner {
+                node,
+                first_child,
+                depth,
+            } => (node, first_child, depth),
+        };
+
+        let mut groups = Vec::new();
+        let mut rest = &mut items[first_child..];
+        while !rest.is_empty() {
+            let end = Self::bulk_child_end(rest, 0, node_depth);
+            let (group, tail) = std::mem::take(&mut rest).split_at_mut(end);
+            groups.push((group[0].0.at(node_depth), group));
+            rest = tail;
+        }
+
+        let children = parallel::map_weighted(
+            groups,
+            |(_, group)| group.len(),
+            |(edge, group)| (edge, Self::build_bulk_node(group, node_depth)),
+        );
+        for (edge, child) in children {
+            node.add_child_sorted_unchecked(edge, child);
+        }
+
+        node
+    }
+
+    fn bulk_node_start(
+        items: &mut [(KeyType, Option<ValueType>)],
+        depth: usize,
+    ) -> BulkNodeStart<KeyType::PartialType, ValueType> {
         debug_assert!(!items.is_empty());
 
         if items.len() == 1 {
             let (key, value) = &mut items[0];
-            return DefaultNode::new_leaf(
+            return BulkNodeStart::Leaf(DefaultNode::new_leaf(
                 key.to_partial(depth),
                 value.take().expect("bulk-load value already consumed"),
-            );
+            ));
         }
 
         let prefix_len = Self::common_prefix_len(&items[0].0, &items[items.len() - 1].0, depth);
@@ -989,38 +1824,34 @@ where
 
         if child_count == 0 {
             debug_assert!(has_value);
-            return DefaultNode::new_leaf(
+            return BulkNodeStart::Leaf(DefaultNode::new_leaf(
                 prefix,
                 items[0].1.take().expect("bulk-load value already consumed"),
-            );
+            ));
         }
 
-        let mut node = match child_count {
-            0..=4 => DefaultNode::new_4(prefix),
-            5..=16 => DefaultNode::new_16(prefix),
-            17..=48 => DefaultNode::new_48(prefix),
-            _ => DefaultNode::new_256(prefix),
-        };
+        let mut node = DefaultNode::with_child_capacity(prefix, child_count);
 
         if has_value {
             node.value = Some(items[0].1.take().expect("bulk-load value already consumed"));
         }
 
-        let mut start = first_child;
-        while start < items.len() {
-            debug_assert!(items[start].0.length_at(0) > node_depth);
-            let edge = items[start].0.at(node_depth);
-            let mut end = start + 1;
-            while end < items.len() && items[end].0.at(node_depth) == edge {
-                end += 1;
-            }
-
-            let child = Self::build_bulk_node(&mut items[start..end], node_depth);
-            node.add_child_sorted_unchecked(edge, child);
-            start = end;
+        BulkNodeStart::Inner {
+            node,
+            first_child,
+            depth: node_depth,
         }
+    }
 
-        node
+    /// The end of the run of items starting at `start` that share the key byte at `depth`.
+    fn bulk_child_end(items: &[(KeyType, Option<ValueType>)], start: usize, depth: usize) -> usize {
+        debug_assert!(items[start].0.length_at(0) > depth);
+        let edge = items[start].0.at(depth);
+        let mut end = start + 1;
+        while end < items.len() && items[end].0.at(depth) == edge {
+            end += 1;
+        }
+        end
     }
 
     fn build_bulk_node_by_index<'a, KF, VF>(
@@ -1055,12 +1886,7 @@ where
             return DefaultNode::new_leaf(prefix, take_value_at(start));
         }
 
-        let mut node = match child_count {
-            0..=4 => DefaultNode::new_4(prefix),
-            5..=16 => DefaultNode::new_16(prefix),
-            17..=48 => DefaultNode::new_48(prefix),
-            _ => DefaultNode::new_256(prefix),
-        };
+        let mut node = DefaultNode::with_child_capacity(prefix, child_count);
 
         if has_value {
             node.value = Some(take_value_at(start));
@@ -1353,6 +2179,15 @@ where
         cur_node: &'a DefaultNode<KeyType::PartialType, ValueType>,
         prefix: &KeyType,
     ) -> Option<&'a DefaultNode<KeyType::PartialType, ValueType>> {
+        Self::find_prefix_subtree_offset(cur_node, prefix).map(|(node, _)| node)
+    }
+
+    /// Like `find_prefix_subtree_node`, but also returns how many bytes of the node's own
+    /// prefix the search prefix covers, so the node can seed an offset-based paired descent.
+    fn find_prefix_subtree_offset<'a>(
+        cur_node: &'a DefaultNode<KeyType::PartialType, ValueType>,
+        prefix: &KeyType,
+    ) -> Option<(&'a DefaultNode<KeyType::PartialType, ValueType>, usize)> {
         let mut cur_node = cur_node;
         let mut depth = 0;
 
@@ -1360,13 +2195,13 @@ where
             let prefix_common_match = cur_node.prefix.prefix_length_key(prefix, depth);
             if prefix_common_match != cur_node.prefix.len() {
                 if prefix_common_match == prefix.length_at(depth) {
-                    return Some(cur_node);
+                    return Some((cur_node, prefix_common_match));
                 }
                 return None;
             }
 
             if cur_node.prefix.len() == prefix.length_at(depth) {
-                return Some(cur_node);
+                return Some((cur_node, cur_node.prefix.len()));
             }
 
             let k = prefix.at(depth + cur_node.prefix.len());
@@ -1415,302 +2250,323 @@ where
         }
     }
 
-    /// Recursively intersect two nodes, supporting different prefix-compression boundaries
-    /// through in-prefix offsets.
-    fn intersect_nodes<'a, F>(
-        left: &'a DefaultNode<KeyType::PartialType, ValueType>,
-        mut left_offset: usize,
-        right: &'a DefaultNode<KeyType::PartialType, ValueType>,
-        mut right_offset: usize,
-        key_buf: &mut Vec<u8>,
-        on_match: &mut F,
-    ) where
-        F: FnMut(KeyType, &'a ValueType, &'a ValueType),
+    fn intersect_range_raw<'a, R, F>(&'a self, other: &'a Self, range: &R, on_match: &mut F)
+    where
+        R: RangeBounds<KeyType>,
+        F: FnMut(&[u8], &'a ValueType, &'a ValueType),
     {
-        let restore_len = key_buf.len();
-        let left_prefix = left.prefix.as_ref();
-        let right_prefix = right.prefix.as_ref();
-
-        while left_offset < left_prefix.len() && right_offset < right_prefix.len() {
-            let left_byte = left_prefix[left_offset];
-            let right_byte = right_prefix[right_offset];
-            if left_byte != right_byte {
-                key_buf.truncate(restore_len);
-                return;
-            }
-            key_buf.push(left_byte);
-            left_offset += 1;
-            right_offset += 1;
-        }
-
-        // The remaining byte in the longer prefix must transition through a matching
-        // child edge in the shorter side to continue.
-        if left_offset < left_prefix.len() {
-            if !right.is_inner() {
-                key_buf.truncate(restore_len);
-                return;
-            }
-
-            let edge = left_prefix[left_offset];
-            let Some(right_child) = right.seek_child(edge) else {
-                key_buf.truncate(restore_len);
-                return;
-            };
-
-            key_buf.push(edge);
-            Self::intersect_nodes(left, left_offset + 1, right_child, 1, key_buf, on_match);
-            key_buf.truncate(restore_len);
+        let (Some(left_root), Some(right_root)) = (self.root.as_ref(), other.root.as_ref()) else {
             return;
-        }
-
-        if right_offset < right_prefix.len() {
-            if !left.is_inner() {
-                key_buf.truncate(restore_len);
+        };
+        let range = ByteRange::new(range);
+        let common = range.common_prefix();
+        let (left, left_offset, right, right_offset) = if common.is_empty() {
+            (left_root, 0, right_root, 0)
+        } else {
+            let prefix = KeyType::new_from_slice(common);
+            let Some((left, left_offset)) = Self::find_prefix_subtree_offset(left_root, &prefix)
+            else {
                 return;
-            }
-
-            let edge = right_prefix[right_offset];
-            let Some(left_child) = left.seek_child(edge) else {
-                key_buf.truncate(restore_len);
+            };
+            let Some((right, right_offset)) = Self::find_prefix_subtree_offset(right_root, &prefix)
+            else {
                 return;
             };
+            (left, left_offset, right, right_offset)
+        };
 
-            key_buf.push(edge);
-            Self::intersect_nodes(left_child, 1, right, right_offset + 1, key_buf, on_match);
-            key_buf.truncate(restore_len);
-            return;
-        }
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        key_buf.extend_from_slice(common);
+        join::intersect_in_range(
+            left,
+            left_offset,
+            right,
+            right_offset,
+            &range,
+            &mut key_buf,
+            on_match,
+        );
+    }
 
-        if let (Some(left_value), Some(right_value)) = (left.value(), right.value()) {
-            on_match(
-                KeyType::new_from_slice(key_buf.as_slice()),
-                left_value,
-                right_value,
+    /// Recursively union two nodes that sit at the same depth, starting `left_offset` and
+    /// `right_offset` bytes into their prefixes. Only nodes on paths shared by both sides are
+    /// rebuilt; everything else is cloned as a whole subtree.
+    fn union_nodes<F>(
+        left: &DefaultNode<KeyType::PartialType, ValueType>,
+        left_offset: usize,
+        right: &DefaultNode<KeyType::PartialType, ValueType>,
+        right_offset: usize,
+        key_buf: &mut Vec<u8>,
+        resolver: &mut F,
+        overlapping: &mut usize,
+    ) -> DefaultNode<KeyType::PartialType, ValueType>
+    where
+        ValueType: Clone,
+        F: FnMut(KeyType, &ValueType, &ValueType) -> ValueType,
+    {
+        let left_rest = &left.prefix.as_ref()[left_offset..];
+        let right_rest = &right.prefix.as_ref()[right_offset..];
+        let common = prefix_length_bytes(left_rest, right_rest);
+        let prefix = left.prefix.partial_from(left_offset, common);
+
+        // The prefixes diverge, so the two subtrees share no keys and hang side by side
+        // below a new parent.
+        if common < left_rest.len() && common < right_rest.len() {
+            let mut parent = DefaultNode::new_inner(prefix);
+            parent.add_child(
+                left_rest[common],
+                Self::clone_subtree_from(left, left_offset + common),
             );
+            parent.add_child(
+                right_rest[common],
+                Self::clone_subtree_from(right, right_offset + common),
+            );
+            return parent;
         }
 
-        if left.is_inner() && right.is_inner() {
-            if left.num_children() <= right.num_children() {
-                for (edge, left_child) in left.iter() {
-                    if let Some(right_child) = right.seek_child(edge) {
-                        Self::intersect_nodes(left_child, 0, right_child, 0, key_buf, on_match);
-                    }
+        let restore_len = key_buf.len();
+        key_buf.extend_from_slice(&left_rest[..common]);
+
+        let (left_value, left_children) = Self::paired_side(left, left_offset + common);
+        let (right_value, right_children) = Self::paired_side(right, right_offset + common);
+        let value = match (left_value, right_value) {
+            (Some(left_value), Some(right_value)) => {
+                *overlapping += 1;
+                Some(resolver(
+                    KeyType::new_from_slice(key_buf.as_slice()),
+                    left_value,
+                    right_value,
+                ))
+            }
+            (left_value, right_value) => left_value.or(right_value).cloned(),
+        };
+
+        let mut children = Vec::with_capacity(left_children.len() + right_children.len());
+        let mut left_children = left_children.into_iter().peekable();
+        let mut right_children = right_children.into_iter().peekable();
+        loop {
+            let next = match (left_children.peek(), right_children.peek()) {
+                (None, None) => break,
+                (Some(l), Some(r)) if l.0 == r.0 => {
+                    let (edge, left_child, left_child_offset) = left_children.next().unwrap();
+                    let (_, right_child, right_child_offset) = right_children.next().unwrap();
+                    let child = Self::union_nodes(
+                        left_child,
+                        left_child_offset,
+                        right_child,
+                        right_child_offset,
+                        key_buf,
+                        resolver,
+                        overlapping,
+                    );
+                    (edge, child)
                 }
-            } else {
-                for (edge, right_child) in right.iter() {
-                    if let Some(left_child) = left.seek_child(edge) {
-                        Self::intersect_nodes(left_child, 0, right_child, 0, key_buf, on_match);
-                    }
+                (Some(l), r) if r.is_none_or(|r| l.0 < r.0) => {
+                    let (edge, child, offset) = left_children.next().unwrap();
+                    (edge, Self::clone_subtree_from(child, offset))
                 }
-            }
+                _ => {
+                    let (edge, child, offset) = right_children.next().unwrap();
+                    (edge, Self::clone_subtree_from(child, offset))
+                }
+            };
+            children.push(next);
         }
 
         key_buf.truncate(restore_len);
+        DefaultNode::from_sorted_children(prefix, value, children)
     }
 
-    fn intersect_nodes_lending<'a, F>(
-        left: &'a DefaultNode<KeyType::PartialType, ValueType>,
-        mut left_offset: usize,
-        right: &'a DefaultNode<KeyType::PartialType, ValueType>,
-        mut right_offset: usize,
-        key_segments: &mut Vec<&'a [u8]>,
-        key_len: &mut usize,
-        on_match: &mut F,
-    ) where
-        F: for<'view> FnMut(LendingKeyView<'a, 'view>, &'a ValueType, &'a ValueType),
-    {
-        let restore_segments = key_segments.len();
-        let restore_len = *key_len;
-        let left_prefix = left.prefix.as_ref();
-        let right_prefix = right.prefix.as_ref();
-        let matched_left_start = left_offset;
-
-        while left_offset < left_prefix.len() && right_offset < right_prefix.len() {
-            if left_prefix[left_offset] != right_prefix[right_offset] {
-                key_segments.truncate(restore_segments);
-                *key_len = restore_len;
-                return;
-            }
-            left_offset += 1;
-            right_offset += 1;
+    /// The value and children of `node` as seen from `offset` bytes into its prefix. A node
+    /// whose prefix continues past `offset` has no value there and is its own only child.
+    fn paired_side(
+        node: &DefaultNode<KeyType::PartialType, ValueType>,
+        offset: usize,
+    ) -> PairedSide<'_, KeyType::PartialType, ValueType> {
+        if offset < node.prefix.len() {
+            return (None, vec![(node.prefix.at(offset), node, offset)]);
         }
+        let children = node.iter().map(|(edge, child)| (edge, child, 0)).collect();
+        (node.value(), children)
+    }
 
-        if left_offset > matched_left_start {
-            let matched = &left_prefix[matched_left_start..left_offset];
-            key_segments.push(matched);
-            *key_len += matched.len();
+    /// Clone the subtree at `node`, dropping the first `offset` bytes of its prefix.
+    fn clone_subtree_from(
+        node: &DefaultNode<KeyType::PartialType, ValueType>,
+        offset: usize,
+    ) -> DefaultNode<KeyType::PartialType, ValueType>
+    where
+        ValueType: Clone,
+    {
+        let mut copy = node.clone_subtree();
+        if offset > 0 {
+            copy.prefix = node.prefix.partial_after(offset);
         }
+        copy
+    }
 
-        if left_offset < left_prefix.len() {
-            if !right.is_inner() {
-                key_segments.truncate(restore_segments);
-                *key_len = restore_len;
-                return;
+    /// Recursively merge two owned nodes that sit at the same depth, moving subtrees that
+    /// exist on only one side into the result unchanged.
+    fn merge_nodes<F>(
+        left: DefaultNode<KeyType::PartialType, ValueType>,
+        right: DefaultNode<KeyType::PartialType, ValueType>,
+        key_buf: &mut Vec<u8>,
+        resolver: &mut F,
+        overlapping: &mut usize,
+    ) -> DefaultNode<KeyType::PartialType, ValueType>
+    where
+        F: FnMut(KeyType, ValueType, ValueType) -> ValueType,
+    {
+        let common = left.prefix.prefix_length_common(&right.prefix);
+        let prefix = left.prefix.partial_before(common);
+
+        // The prefixes diverge, so the two subtrees share no keys and hang side by side
+        // below a new parent.
+        if common < left.prefix.len() && common < right.prefix.len() {
+            let mut parent = DefaultNode::new_inner(prefix);
+            for mut child in [left, right] {
+                child.prefix = child.prefix.partial_after(common);
+                parent.add_child(child.prefix.at(0), child);
             }
-
-            let edge = left_prefix[left_offset];
-            let Some(right_child) = right.seek_child(edge) else {
-                key_segments.truncate(restore_segments);
-                *key_len = restore_len;
-                return;
-            };
-
-            let edge_segment = &left_prefix[left_offset..left_offset + 1];
-            key_segments.push(edge_segment);
-            *key_len += 1;
-            Self::intersect_nodes_lending(
-                left,
-                left_offset + 1,
-                right_child,
-                1,
-                key_segments,
-                key_len,
-                on_match,
-            );
-            key_segments.truncate(restore_segments);
-            *key_len = restore_len;
-            return;
+            return parent;
         }
 
-        if right_offset < right_prefix.len() {
-            if !left.is_inner() {
-                key_segments.truncate(restore_segments);
-                *key_len = restore_len;
-                return;
+        let restore_len = key_buf.len();
+        key_buf.extend_from_slice(prefix.as_ref());
+
+        let (left_value, left_children) = Self::merge_side(left, common);
+        let (right_value, right_children) = Self::merge_side(right, common);
+        let value = match (left_value, right_value) {
+            (Some(left_value), Some(right_value)) => {
+                *overlapping += 1;
+                Some(resolver(
+                    KeyType::new_from_slice(key_buf.as_slice()),
+                    left_value,
+                    right_value,
+                ))
             }
+            (left_value, right_value) => left_value.or(right_value),
+        };
 
-            let edge = right_prefix[right_offset];
-            let Some(left_child) = left.seek_child(edge) else {
-                key_segments.truncate(restore_segments);
-                *key_len = restore_len;
-                return;
+        let mut children = Vec::with_capacity(left_children.len() + right_children.len());
+        let mut left_children = left_children.into_iter().peekable();
+        let mut right_children = right_children.into_iter().peekable();
+        loop {
+            let next = match (left_children.peek(), right_children.peek()) {
+                (None, None) => break,
+                (Some(l), Some(r)) if l.0 == r.0 => {
+                    let (edge, left_child) = left_children.next().unwrap();
+                    let (_, right_child) = right_children.next().unwrap();
+                    let child =
+                        Self::merge_nodes(left_child, right_child, key_buf, resolver, overlapping);
+                    (edge, child)
+                }
+                (Some(l), r) if r.is_none_or(|r| l.0 < r.0) => left_children.next().unwrap(),
+                _ => right_children.next().unwrap(),
             };
-
-            let edge_segment = &right_prefix[right_offset..right_offset + 1];
-            key_segments.push(edge_segment);
-            *key_len += 1;
-            Self::intersect_nodes_lending(
-                left_child,
-                1,
-                right,
-                right_offset + 1,
-                key_segments,
-                key_len,
-                on_match,
-            );
-            key_segments.truncate(restore_segments);
-            *key_len = restore_len;
-            return;
+            children.push(next);
         }
 
-        if let (Some(left_value), Some(right_value)) = (left.value(), right.value()) {
-            on_match(
-                LendingKeyView::new(key_segments, *key_len),
-                left_value,
-                right_value,
-            );
+        key_buf.truncate(restore_len);
+        DefaultNode::from_sorted_children(prefix, value, children)
+    }
+
+    /// Merge `right`, seen from `right_offset` bytes into its prefix, into `left` in place.
+    /// `left` is split where `right` leaves its prefix; subtrees only `right` has are cloned in.
+    fn merge_ref_into<F>(
+        left: &mut DefaultNode<KeyType::PartialType, ValueType>,
+        right: &DefaultNode<KeyType::PartialType, ValueType>,
+        right_offset: usize,
+        key_buf: &mut Vec<u8>,
+        resolver: &mut F,
+        overlapping: &mut usize,
+    ) where
+        ValueType: Clone,
+        F: FnMut(KeyType, &ValueType, &ValueType) -> ValueType,
+    {
+        let right_rest = &right.prefix.as_ref()[right_offset..];
+        let common = left.prefix.prefix_length_slice(right_rest);
+        if common < left.prefix.len() {
+            let parent = DefaultNode::new_inner(left.prefix.partial_before(common));
+            let mut child = std::mem::replace(left, parent);
+            child.prefix = child.prefix.partial_after(common);
+            left.add_child(child.prefix.at(0), child);
         }
 
-        if left.is_inner() && right.is_inner() {
-            if left.num_children() <= right.num_children() {
-                for (edge, left_child) in left.iter() {
-                    if let Some(right_child) = right.seek_child(edge) {
-                        Self::intersect_nodes_lending(
-                            left_child,
-                            0,
-                            right_child,
-                            0,
-                            key_segments,
-                            key_len,
-                            on_match,
-                        );
-                    }
-                }
-            } else {
-                for (edge, right_child) in right.iter() {
-                    if let Some(left_child) = left.seek_child(edge) {
-                        Self::intersect_nodes_lending(
-                            left_child,
-                            0,
-                            right_child,
-                            0,
-                            key_segments,
-                            key_len,
-                            on_match,
-                        );
+        let restore_len = key_buf.len();
+        key_buf.extend_from_slice(left.prefix.as_ref());
+        if common < right_rest.len() {
+            // `right` continues below this node under a single edge.
+            Self::merge_child_into(
+                left,
+                right_rest[common],
+                right,
+                right_offset + common,
+                key_buf,
+                resolver,
+                overlapping,
+            );
+        } else {
+            if let Some(right_value) = right.value() {
+                match left.value_mut() {
+                    Some(left_value) => {
+                        *overlapping += 1;
+                        let key = KeyType::new_from_slice(key_buf.as_slice());
+                        *left_value = resolver(key, left_value, right_value);
                     }
+                    None => left.value = Some(right_value.clone()),
                 }
             }
+            for (edge, right_child) in right.iter() {
+                Self::merge_child_into(left, edge, right_child, 0, key_buf, resolver, overlapping);
+            }
         }
-
-        key_segments.truncate(restore_segments);
-        *key_len = restore_len;
+        key_buf.truncate(restore_len);
     }
 
-    /// Recursively intersect two nodes and emit only value pairs (no key reconstruction).
-    fn intersect_nodes_values<'a, F>(
-        left: &'a DefaultNode<KeyType::PartialType, ValueType>,
-        mut left_offset: usize,
-        right: &'a DefaultNode<KeyType::PartialType, ValueType>,
-        mut right_offset: usize,
-        on_match: &mut F,
+    /// Merge `right`, from `right_offset` bytes into its prefix, into `left`'s child under
+    /// `edge`, or clone it in as that child if `left` has none.
+    fn merge_child_into<F>(
+        left: &mut DefaultNode<KeyType::PartialType, ValueType>,
+        edge: u8,
+        right: &DefaultNode<KeyType::PartialType, ValueType>,
+        right_offset: usize,
+        key_buf: &mut Vec<u8>,
+        resolver: &mut F,
+        overlapping: &mut usize,
     ) where
-        F: FnMut(&'a ValueType, &'a ValueType),
+        ValueType: Clone,
+        F: FnMut(KeyType, &ValueType, &ValueType) -> ValueType,
     {
-        let left_prefix = left.prefix.as_ref();
-        let right_prefix = right.prefix.as_ref();
-
-        while left_offset < left_prefix.len() && right_offset < right_prefix.len() {
-            if left_prefix[left_offset] != right_prefix[right_offset] {
-                return;
-            }
-            left_offset += 1;
-            right_offset += 1;
-        }
-
-        if left_offset < left_prefix.len() {
-            if !right.is_inner() {
-                return;
-            }
-            let edge = left_prefix[left_offset];
-            let Some(right_child) = right.seek_child(edge) else {
-                return;
-            };
-            Self::intersect_nodes_values(left, left_offset + 1, right_child, 1, on_match);
-            return;
-        }
-
-        if right_offset < right_prefix.len() {
-            if !left.is_inner() {
-                return;
+        match left.seek_child_mut(edge) {
+            Some(left_child) => Self::merge_ref_into(
+                left_child,
+                right,
+                right_offset,
+                key_buf,
+                resolver,
+                overlapping,
+            ),
+            None => {
+                let mut subtree = right.clone_subtree();
+                subtree.prefix = subtree.prefix.partial_after(right_offset);
+                left.add_child(edge, subtree);
             }
-            let edge = right_prefix[right_offset];
-            let Some(left_child) = left.seek_child(edge) else {
-                return;
-            };
-            Self::intersect_nodes_values(left_child, 1, right, right_offset + 1, on_match);
-            return;
-        }
-
-        if let (Some(left_value), Some(right_value)) = (left.value(), right.value()) {
-            on_match(left_value, right_value);
         }
+    }
 
-        if left.is_inner() && right.is_inner() {
-            if left.num_children() <= right.num_children() {
-                for (edge, left_child) in left.iter() {
-                    if let Some(right_child) = right.seek_child(edge) {
-                        Self::intersect_nodes_values(left_child, 0, right_child, 0, on_match);
-                    }
-                }
-            } else {
-                for (edge, right_child) in right.iter() {
-                    if let Some(left_child) = left.seek_child(edge) {
-                        Self::intersect_nodes_values(left_child, 0, right_child, 0, on_match);
-                    }
-                }
-            }
+    /// The value and children of `node` as seen from `at` bytes into its prefix, the owned
+    /// counterpart of [`Self::paired_side`].
+    fn merge_side(
+        mut node: DefaultNode<KeyType::PartialType, ValueType>,
+        at: usize,
+    ) -> MergeSide<KeyType::PartialType, ValueType> {
+        if at < node.prefix.len() {
+            node.prefix = node.prefix.partial_after(at);
+            return (None, vec![(node.prefix.at(0), node)]);
         }
+        let (_, value, children) = node.into_parts();
+        (value, children)
     }
 
     fn get_iterate_mut<'a>(
@@ -1836,7 +2692,111 @@ where
             .seek_child_mut(k)
             .expect("child existence was checked above");
 
-        AdaptiveRadixTree::insert_recurse(child, key, value, depth + longest_common_prefix, replace)
+        AdaptiveRadixTree::insert_recurse(child, key, value, depth + longest_common_prefix, replace)
+    }
+
+    fn retain_by_presence<W>(&mut self, other: &AdaptiveRadixTree<KeyType, W>, keep_present: bool) {
+        let Some(root) = self.root.as_mut() else {
+            return;
+        };
+        let mut removed = 0usize;
+        let emptied = match other.root.as_ref() {
+            Some(other_root) => {
+                Self::retain_against(root, 0, other_root, 0, keep_present, &mut removed)
+            }
+            None => Self::retain_unmatched(root, keep_present, &mut removed),
+        };
+        if emptied {
+            self.root = None;
+        }
+        self.len -= removed;
+    }
+
+    /// Drop the entries below `node`, matched `offset` bytes into its prefix against `other`,
+    /// whose presence in `other` differs from `keep_present`. Returns whether `node` was left
+    /// without entries, in which case the caller unlinks it.
+    fn retain_against<R: NodeView>(
+        node: &mut DefaultNode<KeyType::PartialType, ValueType>,
+        mut offset: usize,
+        other: &R,
+        mut other_offset: usize,
+        keep_present: bool,
+        removed: &mut usize,
+    ) -> bool {
+        let prefix = node.prefix.as_ref();
+        let other_prefix = other.view_prefix();
+        while offset < prefix.len() && other_offset < other_prefix.len() {
+            if prefix[offset] != other_prefix[other_offset] {
+                return Self::retain_unmatched(node, keep_present, removed);
+            }
+            offset += 1;
+            other_offset += 1;
+        }
+
+        if offset < prefix.len() {
+            return match other.view_child(prefix[offset]) {
+                Some(other_child) => {
+                    Self::retain_against(node, offset, other_child, 0, keep_present, removed)
+                }
+                None => Self::retain_unmatched(node, keep_present, removed),
+            };
+        }
+
+        let other_has_value = other_offset == other_prefix.len() && other.view_value().is_some();
+        if node.value.is_some() && other_has_value != keep_present {
+            node.value = None;
+            *removed += 1;
+        }
+
+        let edges: Vec<u8> = node.iter().map(|(edge, _)| edge).collect();
+        let mut emptied = Vec::new();
+        for edge in edges {
+            let other_next = if other_offset < other_prefix.len() {
+                (other_prefix[other_offset] == edge).then_some((other, other_offset))
+            } else {
+                other.view_child(edge).map(|other_child| (other_child, 0))
+            };
+            let child = node
+                .seek_child_mut(edge)
+                .expect("edge was just read from this node");
+            let child_emptied = match other_next {
+                Some((other_next, other_next_offset)) => Self::retain_against(
+                    child,
+                    0,
+                    other_next,
+                    other_next_offset,
+                    keep_present,
+                    removed,
+                ),
+                None => Self::retain_unmatched(child, keep_present, removed),
+            };
+            if child_emptied {
+                emptied.push(edge);
+            }
+        }
+
+        if node.value.is_none() && emptied.len() == node.num_children() {
+            return true;
+        }
+        // A deletion that leaves a single child may collapse that child into this node. At
+        // least one child survives here, so only the final deletion can do that.
+        for edge in emptied {
+            node.delete_child(edge);
+        }
+        false
+    }
+
+    /// Apply a retain to a subtree `other` has no keys in: drop it whole when keeping only
+    /// present keys, otherwise leave it untouched.
+    fn retain_unmatched(
+        node: &DefaultNode<KeyType::PartialType, ValueType>,
+        keep_present: bool,
+        removed: &mut usize,
+    ) -> bool {
+        if keep_present {
+            *removed += ValuesIter::new(Some(node)).count();
+        }
+        keep_present
     }
 
     fn remove_recurse(
@@ -2329,6 +3289,7 @@ mod tests {
     use std::collections::BTreeMap;
     use std::fmt::Debug;
     use std::ops::Bound::{Excluded, Included, Unbounded};
+    use std::ops::RangeBounds;
     use std::sync::Mutex;
     use std::sync::atomic::{AtomicBool, Ordering};
 
@@ -2340,7 +3301,8 @@ mod tests {
     use crate::keys::vector_key::VectorKey;
     use crate::partials::array_partial::ArrPartial;
     use crate::tree::AdaptiveRadixTree;
-    use crate::{Slot, SlotUpdate, VisitControl};
+    use crate::versioned_tree::VersionedAdaptiveRadixTree;
+    use crate::{Side, Slot, SlotUpdate, VisitControl};
 
     fn collect_items(tree: &AdaptiveRadixTree<ArrayKey<16>, u64>) -> Vec<(Vec<u8>, u64)> {
         tree.iter()
@@ -2456,6 +3418,121 @@ mod tests {
         let _ = AdaptiveRadixTree::<ArrayKey<16>, u64>::bulk_load_sorted(items);
     }
 
+    #[test]
+    fn bulk_load_sorted_parallel_matches_bulk_load_sorted() {
+        let mut items: Vec<_> = (0..20_000u64)
+            .flat_map(|value| {
+                [
+                    (ArrayKey::from(value / 2), value),
+                    (ArrayKey::from(value / 2), value),
+                ]
+            })
+            .collect();
+        items.push((ArrayKey::from(u64::MAX), 0));
+
+        let sequential = AdaptiveRadixTree::<ArrayKey<16>, u64>::bulk_load_sorted(items.clone());
+        let parallel = AdaptiveRadixTree::<ArrayKey<16>, u64>::bulk_load_sorted_parallel(items);
+
+        assert_eq!(parallel.len(), sequential.len());
+        assert_eq!(collect_items(&parallel), collect_items(&sequential));
+    }
+
+    #[test]
+    fn bulk_load_sorted_parallel_handles_root_value_and_single_child() {
+        let items = vec![
+            (VectorKey::new_from_slice(b""), 0),
+            (VectorKey::new_from_slice(b"a"), 1),
+            (VectorKey::new_from_slice(b"ab"), 2),
+        ];
+        let tree = AdaptiveRadixTree::<VectorKey, u64>::bulk_load_sorted_parallel(items.clone());
+
+        assert_eq!(tree.len(), 3);
+        for (key, value) in &items {
+            assert_eq!(tree.get_k(key), Some(value));
+        }
+        assert!(AdaptiveRadixTree::<VectorKey, u64>::bulk_load_sorted_parallel([]).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "bulk_load_sorted_parallel input is not sorted")]
+    fn bulk_load_sorted_parallel_rejects_unsorted_input() {
+        let items = vec![
+            (ArrayKey::new_from_slice(b"b"), 1),
+            (ArrayKey::new_from_slice(b"a"), 2),
+        ];
+
+        let _ = AdaptiveRadixTree::<ArrayKey<16>, u64>::bulk_load_sorted_parallel(items);
+    }
+
+    #[test]
+    fn par_for_each_view_visits_every_entry_once() {
+        let mut tree = AdaptiveRadixTree::<VectorKey, u64>::new();
+        tree.insert_k(&VectorKey::new_from_slice(b""), 0);
+        for value in 0..5_000u64 {
+            tree.insert_k(&VectorKey::new_from_slice(&value.to_be_bytes()[5..]), value);
+        }
+
+        let visited = Mutex::new(Vec::new());
+        tree.par_for_each_view(|key, value| {
+            visited.lock().unwrap().push((key.to_vec(), *value));
+        });
+        let mut visited = visited.into_inner().unwrap();
+        visited.sort_unstable();
+
+        let expected: Vec<_> = tree
+            .iter()
+            .map(|(key, value)| (key.as_ref().to_vec(), *value))
+            .collect();
+        assert_eq!(visited, expected);
+    }
+
+    #[test]
+    fn par_prefix_values_for_each_matches_prefix_values_for_each() {
+        let tree = AdaptiveRadixTree::<ArrayKey<16>, u64>::bulk_load_sorted_unique(
+            (0..10_000u64).map(|value| (ArrayKey::from(value), value)),
+        );
+
+        for prefix in [
+            &[][..],
+            &[0, 0, 0, 0, 0, 0],
+            &[0, 0, 0, 0, 0, 0, 0x10],
+            &[1],
+        ] {
+            let prefix = ArrayKey::new_from_slice(prefix);
+            let mut expected = Vec::new();
+            tree.prefix_values_for_each_k(&prefix, |value| expected.push(*value));
+
+            let visited = Mutex::new(Vec::new());
+            tree.par_prefix_values_for_each_k(&prefix, |value| {
+                visited.lock().unwrap().push(*value);
+            });
+            let mut visited = visited.into_inner().unwrap();
+            visited.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(visited, expected);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parallel_bulk_load_matches_sequential(
+            keys in proptest::collection::vec(proptest::collection::vec(0u8..4, 0..4), 0..64)
+        ) {
+            let mut items: Vec<_> = keys
+                .into_iter()
+                .enumerate()
+                .map(|(value, key)| (VectorKey::new_from_slice(&key), value))
+                .collect();
+            items.sort_by(|left, right| left.0.cmp(&right.0));
+
+            let sequential = AdaptiveRadixTree::<VectorKey, usize>::bulk_load_sorted(items.clone());
+            let parallel = AdaptiveRadixTree::<VectorKey, usize>::bulk_load_sorted_parallel(items);
+
+            prop_assert_eq!(parallel.len(), sequential.len());
+            prop_assert!(parallel.iter().eq(sequential.iter()));
+        }
+    }
+
     #[test]
     fn values_iter_includes_root_value() {
         let mut tree = AdaptiveRadixTree::<ArrayKey<16>, i32>::new();
@@ -2637,8 +3714,8 @@ mod tests {
             Self(self.0.extend_from_partial(partial))
         }
 
-        fn truncate(&self, at_depth: usize) -> Self {
-            Self(self.0.truncate(at_depth))
+        fn truncated(&self, at_depth: usize) -> Self {
+            Self(self.0.truncated(at_depth))
         }
 
         fn at(&self, pos: usize) -> u8 {
@@ -2878,140 +3955,521 @@ mod tests {
                 prop_assert_eq!(tree.is_empty(), btree.is_empty());
             }
 
-            let art_items = collect_art_u8_items(&tree);
-            let btree_items: Vec<_> = btree.iter().map(|(key, value)| (*key, *value)).collect();
-            prop_assert_eq!(art_items, btree_items);
+            let art_items = collect_art_u8_items(&tree);
+            let btree_items: Vec<_> = btree.iter().map(|(key, value)| (*key, *value)).collect();
+            prop_assert_eq!(art_items, btree_items);
+        }
+
+        #[test]
+        fn prop_range_queries_match_btreemap(
+            entries in proptest::collection::vec((any::<u8>(), any::<u16>()), 0..96),
+            queries in proptest::collection::vec(range_query_strategy(), 0..32)
+        ) {
+            let mut tree = AdaptiveRadixTree::<ArrayKey<16>, u16>::new();
+            let mut btree = BTreeMap::<u8, u16>::new();
+
+            for (key, value) in entries {
+                tree.insert(key, value);
+                btree.insert(key, value);
+            }
+
+            for query in &queries {
+                let art_items = collect_art_range_u8_items(&tree, query);
+                let btree_items = collect_btree_range_u8_items(&btree, query);
+                prop_assert_eq!(art_items, btree_items);
+            }
+        }
+
+        #[test]
+        fn prop_prefix_queries_match_reference_model(
+            entries in proptest::collection::vec((ascii_key_strategy(), any::<u8>()), 0..64),
+            probes in proptest::collection::vec(ascii_key_strategy(), 0..32)
+        ) {
+            let mut tree = AdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut map = BTreeMap::<Vec<u8>, u8>::new();
+
+            for (key, value) in entries {
+                tree.insert_k(&ArrayKey::<8>::new_from_slice(&key), value);
+                map.insert(key, value);
+            }
+
+            for probe in probes {
+                let prefix = ArrayKey::<8>::new_from_slice(&probe);
+                let got_prefix: Vec<_> = tree
+                    .prefix_iter_k(&prefix)
+                    .map(|(key, value)| (trim_array_key_bytes(key.as_ref()), *value))
+                    .collect();
+                let expected_prefix: Vec<_> = map
+                    .iter()
+                    .filter(|(key, _)| key.starts_with(&probe))
+                    .map(|(key, value)| (key.clone(), *value))
+                    .collect();
+                prop_assert_eq!(got_prefix, expected_prefix);
+
+                let got_longest = tree
+                    .longest_prefix_match_k(&prefix)
+                    .map(|(key, value)| (trim_array_key_bytes(key.as_ref()), *value));
+                let expected_longest = map
+                    .iter()
+                    .filter(|(key, _)| probe.starts_with(key.as_slice()))
+                    .max_by_key(|(key, _)| key.len())
+                    .map(|(key, value)| (key.clone(), *value));
+                prop_assert_eq!(got_longest, expected_longest);
+
+                let got_prefix_matches: Vec<_> = tree
+                    .prefix_match_iter_k(&prefix)
+                    .map(|(key, value)| (trim_array_key_bytes(key.as_ref()), *value))
+                    .collect();
+                let expected_prefix_matches: Vec<_> = map
+                    .iter()
+                    .filter(|(key, _)| probe.starts_with(key.as_slice()))
+                    .map(|(key, value)| (key.clone(), *value))
+                    .collect();
+                prop_assert_eq!(&got_prefix_matches, &expected_prefix_matches);
+
+                let mut got_prefix_matches_with_callback = Vec::new();
+                tree.prefix_match_for_each_k(&prefix, |key, value| {
+                    got_prefix_matches_with_callback.push((key.to_vec(), *value));
+                });
+                prop_assert_eq!(
+                    &got_prefix_matches_with_callback,
+                    &expected_prefix_matches
+                );
+            }
+        }
+
+        #[test]
+        fn prop_intersection_matches_reference_model(
+            left_entries in proptest::collection::vec((ascii_key_strategy(), any::<u8>()), 0..64),
+            right_entries in proptest::collection::vec((ascii_key_strategy(), any::<u8>()), 0..64)
+        ) {
+            let mut left = AdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut right = AdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut left_map = BTreeMap::<Vec<u8>, u8>::new();
+            let mut right_map = BTreeMap::<Vec<u8>, u8>::new();
+
+            for (key, value) in left_entries {
+                left.insert_k(&ArrayKey::<8>::new_from_slice(&key), value);
+                left_map.insert(key, value);
+            }
+
+            for (key, value) in right_entries {
+                right.insert_k(&ArrayKey::<8>::new_from_slice(&key), value);
+                right_map.insert(key, value);
+            }
+
+            let expected: Vec<_> = left_map
+                .iter()
+                .filter_map(|(key, left_value)| {
+                    right_map
+                        .get(key)
+                        .map(|right_value| (key.clone(), *left_value, *right_value))
+                })
+                .collect();
+            let expected_count = expected.len();
+
+            let mut got = Vec::new();
+            left.intersect_with(&right, |key, left_value, right_value| {
+                got.push((trim_array_key_bytes(key.as_ref()), *left_value, *right_value));
+            });
+            got.sort();
+
+            let mut got_values = Vec::new();
+            left.intersect_values_with(&right, |left_value, right_value| {
+                got_values.push((*left_value, *right_value));
+            });
+            got_values.sort();
+
+            let mut expected_values: Vec<_> = expected
+                .iter()
+                .map(|(_, left_value, right_value)| (*left_value, *right_value))
+                .collect();
+            expected_values.sort();
+
+            prop_assert_eq!(got, expected);
+            prop_assert_eq!(got_values, expected_values);
+            prop_assert_eq!(left.intersect_count(&right), expected_count);
+        }
+
+        #[test]
+        fn prop_intersect_many_matches_reference_model(
+            entries in proptest::collection::vec(
+                proptest::collection::vec((ascii_key_strategy(), any::<u8>()), 0..48),
+                1..5
+            )
+        ) {
+            let mut trees = Vec::new();
+            let mut maps = Vec::new();
+            for tree_entries in entries {
+                let mut tree = AdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+                let mut map = BTreeMap::<Vec<u8>, u8>::new();
+                for (key, value) in tree_entries {
+                    tree.insert_k(&ArrayKey::<8>::new_from_slice(&key), value);
+                    map.insert(key, value);
+                }
+                trees.push(tree);
+                maps.push(map);
+            }
+
+            let expected: Vec<_> = maps[0]
+                .keys()
+                .filter_map(|key| {
+                    let values: Option<Vec<u8>> =
+                        maps.iter().map(|map| map.get(key).copied()).collect();
+                    values.map(|values| (key.clone(), values))
+                })
+                .collect();
+
+            let tree_refs: Vec<_> = trees.iter().collect();
+            let mut got = Vec::new();
+            AdaptiveRadixTree::intersect_many(&tree_refs, |key, values| {
+                got.push((
+                    trim_array_key_bytes(&key.to_vec()),
+                    values.iter().map(|value| **value).collect::<Vec<_>>(),
+                ));
+            });
+            prop_assert_eq!(got, expected);
+        }
+
+        #[test]
+        fn prop_difference_matches_reference_model(
+            left_entries in proptest::collection::vec((ascii_key_strategy(), any::<u8>()), 0..64),
+            right_entries in proptest::collection::vec((ascii_key_strategy(), any::<u8>()), 0..64)
+        ) {
+            let mut left = AdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut right = AdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut left_map = BTreeMap::<Vec<u8>, u8>::new();
+            let mut right_map = BTreeMap::<Vec<u8>, u8>::new();
+
+            for (key, value) in left_entries {
+                left.insert_k(&ArrayKey::<8>::new_from_slice(&key), value);
+                left_map.insert(key, value);
+            }
+            for (key, value) in right_entries {
+                right.insert_k(&ArrayKey::<8>::new_from_slice(&key), value);
+                right_map.insert(key, value);
+            }
+
+            let expected: Vec<_> = left_map
+                .iter()
+                .filter(|(key, _)| !right_map.contains_key(*key))
+                .map(|(key, value)| (key.clone(), *value))
+                .collect();
+            let mut got = Vec::new();
+            left.difference_with(&right, |key, value| {
+                got.push((trim_array_key_bytes(key.as_ref()), *value));
+            });
+            prop_assert_eq!(&got, &expected);
+            prop_assert_eq!(left.difference_count(&right), expected.len());
+
+            let mut expected_symmetric: Vec<_> = expected
+                .into_iter()
+                .map(|(key, value)| (key, Side::Left(value)))
+                .chain(
+                    right_map
+                        .iter()
+                        .filter(|(key, _)| !left_map.contains_key(*key))
+                        .map(|(key, value)| (key.clone(), Side::Right(*value))),
+                )
+                .collect();
+            expected_symmetric.sort_by(|a, b| a.0.cmp(&b.0));
+            let mut got = Vec::new();
+            left.symmetric_difference_with(&right, |key, side| {
+                let side = match side {
+                    Side::Left(value) => Side::Left(*value),
+                    Side::Right(value) => Side::Right(*value),
+                };
+                got.push((trim_array_key_bytes(key.as_ref()), side));
+            });
+            prop_assert_eq!(got, expected_symmetric);
+        }
+
+        #[test]
+        fn prop_prefix_and_range_intersection_match_reference_model(
+            left_entries in proptest::collection::vec((ascii_key_strategy(), any::<u8>()), 0..64),
+            right_entries in proptest::collection::vec((ascii_key_strategy(), any::<u8>()), 0..64),
+            prefix in proptest::collection::vec(b'a'..=b'd', 0..=3),
+            start in proptest::option::of((ascii_key_strategy(), any::<bool>())),
+            end in proptest::option::of((ascii_key_strategy(), any::<bool>()))
+        ) {
+            let mut left = AdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut right = AdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut left_map = BTreeMap::<Vec<u8>, u8>::new();
+            let mut right_map = BTreeMap::<Vec<u8>, u8>::new();
+
+            for (key, value) in left_entries {
+                left.insert_k(&ArrayKey::<8>::new_from_slice(&key), value);
+                left_map.insert(key, value);
+            }
+            for (key, value) in right_entries {
+                right.insert_k(&ArrayKey::<8>::new_from_slice(&key), value);
+                right_map.insert(key, value);
+            }
+            let both: Vec<_> = left_map
+                .iter()
+                .filter_map(|(key, l)| right_map.get(key).map(|r| (key.clone(), *l, *r)))
+                .collect();
+
+            let expected: Vec<_> = both
+                .iter()
+                .filter(|(key, _, _)| key.starts_with(&prefix))
+                .cloned()
+                .collect();
+            let prefix_key = ArrayKey::<8>::new_from_slice(&prefix);
+            let mut got = Vec::new();
+            left.intersect_prefix_with(&right, &prefix_key, |key, l, r| {
+                got.push((key.as_ref().to_vec(), *l, *r));
+            });
+            prop_assert_eq!(&got, &expected);
+            let mut got_values = Vec::new();
+            left.intersect_prefix_values_with(&right, &prefix_key, |l, r| got_values.push((*l, *r)));
+            prop_assert_eq!(
+                got_values,
+                expected.iter().map(|(_, l, r)| (*l, *r)).collect::<Vec<_>>()
+            );
+            prop_assert_eq!(left.intersect_prefix_count(&right, &prefix_key), expected.len());
+
+            let to_bound = |bound: Option<(Vec<u8>, bool)>| match bound {
+                Some((key, true)) => Included(ArrayKey::<8>::new_from_slice(&key)),
+                Some((key, false)) => Excluded(ArrayKey::<8>::new_from_slice(&key)),
+                None => Unbounded,
+            };
+            let range = (to_bound(start), to_bound(end));
+            let expected: Vec<_> = both
+                .iter()
+                .filter(|(key, _, _)| {
+                    range.contains(&ArrayKey::<8>::new_from_slice(key))
+                })
+                .cloned()
+                .collect();
+            let mut got = Vec::new();
+            left.intersect_range_with(&right, range, |key, l, r| {
+                got.push((key.as_ref().to_vec(), *l, *r));
+            });
+            prop_assert_eq!(&got, &expected);
+            prop_assert_eq!(left.intersect_range_count(&right, range), expected.len());
         }
 
         #[test]
-        fn prop_range_queries_match_btreemap(
-            entries in proptest::collection::vec((any::<u8>(), any::<u16>()), 0..96),
-            queries in proptest::collection::vec(range_query_strategy(), 0..32)
+        fn prop_versioned_joins_match_reference_model(
+            left_entries in proptest::collection::vec((ascii_key_strategy(), any::<u8>()), 0..64),
+            right_entries in proptest::collection::vec((ascii_key_strategy(), any::<u16>()), 0..64)
         ) {
-            let mut tree = AdaptiveRadixTree::<ArrayKey<16>, u16>::new();
-            let mut btree = BTreeMap::<u8, u16>::new();
+            let mut left = AdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut right = VersionedAdaptiveRadixTree::<ArrayKey<8>, u16>::new();
+            let mut left_map = BTreeMap::<Vec<u8>, u8>::new();
+            let mut right_map = BTreeMap::<Vec<u8>, u16>::new();
 
-            for (key, value) in entries {
-                tree.insert(key, value);
-                btree.insert(key, value);
+            for (key, value) in left_entries {
+                left.insert_k(&ArrayKey::<8>::new_from_slice(&key), value);
+                left_map.insert(key, value);
             }
-
-            for query in &queries {
-                let art_items = collect_art_range_u8_items(&tree, query);
-                let btree_items = collect_btree_range_u8_items(&btree, query);
-                prop_assert_eq!(art_items, btree_items);
+            for (key, value) in right_entries {
+                right.insert_k(&ArrayKey::<8>::new_from_slice(&key), value);
+                right_map.insert(key, value);
             }
+
+            let expected: Vec<_> = left_map
+                .iter()
+                .filter_map(|(key, l)| right_map.get(key).map(|r| (key.clone(), *l, *r)))
+                .collect();
+            let mut got = Vec::new();
+            left.intersect_versioned_with(&right, |key, l, r| {
+                got.push((key.as_ref().to_vec(), *l, *r));
+            });
+            prop_assert_eq!(&got, &expected);
+            prop_assert_eq!(left.intersect_versioned_count(&right), expected.len());
+
+            let mut got = Vec::new();
+            right.intersect_unversioned_with(&left, |key, r, l| {
+                got.push((key.as_ref().to_vec(), *l, *r));
+            });
+            prop_assert_eq!(&got, &expected);
+
+            let expected: Vec<_> = left_map
+                .iter()
+                .filter(|(key, _)| !right_map.contains_key(*key))
+                .map(|(key, value)| (key.clone(), *value))
+                .collect();
+            let mut got = Vec::new();
+            left.difference_versioned_with(&right, |key, value| {
+                got.push((key.as_ref().to_vec(), *value));
+            });
+            prop_assert_eq!(&got, &expected);
+            prop_assert_eq!(left.difference_versioned_count(&right), expected.len());
+
+            let expected: Vec<_> = right_map
+                .iter()
+                .filter(|(key, _)| !left_map.contains_key(*key))
+                .map(|(key, value)| (key.clone(), *value))
+                .collect();
+            let mut got = Vec::new();
+            right.difference_unversioned_with(&left, |key, value| {
+                got.push((key.as_ref().to_vec(), *value));
+            });
+            prop_assert_eq!(got, expected);
         }
 
         #[test]
-        fn prop_prefix_queries_match_reference_model(
-            entries in proptest::collection::vec((ascii_key_strategy(), any::<u8>()), 0..64),
-            probes in proptest::collection::vec(ascii_key_strategy(), 0..32)
+        fn prop_prefix_join_matches_reference_model(
+            probe_entries in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'c', 1..=6), any::<u8>()),
+                0..64
+            ),
+            prefix_entries in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'c', 1..=4), any::<u16>()),
+                0..32
+            )
         ) {
-            let mut tree = AdaptiveRadixTree::<ArrayKey<8>, u8>::new();
-            let mut map = BTreeMap::<Vec<u8>, u8>::new();
+            let mut probes = AdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut prefixes = AdaptiveRadixTree::<ArrayKey<8>, u16>::new();
+            let mut probe_map = BTreeMap::<Vec<u8>, u8>::new();
+            let mut prefix_map = BTreeMap::<Vec<u8>, u16>::new();
+
+            for (key, value) in probe_entries {
+                probes.insert_k(&ArrayKey::<8>::new_from_slice(&key), value);
+                probe_map.insert(key, value);
+            }
+            for (key, value) in prefix_entries {
+                prefixes.insert_k(&ArrayKey::<8>::new_from_slice(&key), value);
+                prefix_map.insert(key, value);
+            }
 
-            for (key, value) in entries {
-                tree.insert_k(&ArrayKey::<8>::new_from_slice(&key), value);
-                map.insert(key, value);
+            let mut expected = Vec::new();
+            for (key, value) in &probe_map {
+                for (prefix, prefix_value) in &prefix_map {
+                    if key.starts_with(prefix) {
+                        expected.push((key.clone(), prefix.clone(), *value, *prefix_value));
+                    }
+                }
             }
+            let mut got = Vec::new();
+            probes.prefix_join_with(&prefixes, |key, prefix, value, prefix_value| {
+                got.push((key.as_ref().to_vec(), prefix.as_ref().to_vec(), *value, *prefix_value));
+            });
+            prop_assert_eq!(&got, &expected);
 
-            for probe in probes {
-                let prefix = ArrayKey::<8>::new_from_slice(&probe);
-                let got_prefix: Vec<_> = tree
-                    .prefix_iter_k(&prefix)
-                    .map(|(key, value)| (trim_array_key_bytes(key.as_ref()), *value))
-                    .collect();
-                let expected_prefix: Vec<_> = map
-                    .iter()
-                    .filter(|(key, _)| key.starts_with(&probe))
-                    .map(|(key, value)| (key.clone(), *value))
-                    .collect();
-                prop_assert_eq!(got_prefix, expected_prefix);
+            // The reverse direction reports the same pairs, ordered by the extending key.
+            let mut reversed = Vec::new();
+            prefixes.prefix_join_under_with(&probes, |prefix, key, prefix_value, value| {
+                reversed.push((key.as_ref().to_vec(), prefix.as_ref().to_vec(), *value, *prefix_value));
+            });
+            prop_assert_eq!(reversed, expected);
+        }
 
-                let got_longest = tree
-                    .longest_prefix_match_k(&prefix)
-                    .map(|(key, value)| (trim_array_key_bytes(key.as_ref()), *value));
-                let expected_longest = map
-                    .iter()
-                    .filter(|(key, _)| probe.starts_with(key.as_slice()))
-                    .max_by_key(|(key, _)| key.len())
-                    .map(|(key, value)| (key.clone(), *value));
-                prop_assert_eq!(got_longest, expected_longest);
+        #[test]
+        fn prop_semi_and_anti_joins_match_reference_model(
+            left_entries in proptest::collection::vec((ascii_key_strategy(), any::<u8>()), 0..64),
+            right_keys in proptest::collection::vec(ascii_key_strategy(), 0..64)
+        ) {
+            let mut left = AdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut right = AdaptiveRadixTree::<ArrayKey<8>, ()>::new();
+            let mut left_map = BTreeMap::<Vec<u8>, u8>::new();
 
-                let got_prefix_matches: Vec<_> = tree
-                    .prefix_match_iter_k(&prefix)
-                    .map(|(key, value)| (trim_array_key_bytes(key.as_ref()), *value))
-                    .collect();
-                let expected_prefix_matches: Vec<_> = map
-                    .iter()
-                    .filter(|(key, _)| probe.starts_with(key.as_slice()))
-                    .map(|(key, value)| (key.clone(), *value))
-                    .collect();
-                prop_assert_eq!(&got_prefix_matches, &expected_prefix_matches);
+            for (key, value) in left_entries {
+                left.insert_k(&ArrayKey::<8>::new_from_slice(&key), value);
+                left_map.insert(key, value);
+            }
+            for key in &right_keys {
+                right.insert_k(&ArrayKey::<8>::new_from_slice(key), ());
+            }
 
-                let mut got_prefix_matches_with_callback = Vec::new();
-                tree.prefix_match_for_each_k(&prefix, |key, value| {
-                    got_prefix_matches_with_callback.push((key.to_vec(), *value));
-                });
-                prop_assert_eq!(
-                    &got_prefix_matches_with_callback,
-                    &expected_prefix_matches
-                );
+            let (present, absent): (Vec<_>, Vec<_>) = left_map
+                .iter()
+                .map(|(key, value)| (key.clone(), *value))
+                .partition(|(key, _)| right_keys.contains(key));
+            let collect = |iter: &mut dyn Iterator<Item = (ArrayKey<8>, &u8)>| -> Vec<_> {
+                iter.map(|(key, value)| (key.as_ref().to_vec(), *value)).collect()
+            };
+            let copy_left = || {
+                let mut tree = AdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+                for (key, value) in &left_map {
+                    tree.insert_k(&ArrayKey::<8>::new_from_slice(key), *value);
+                }
+                tree
+            };
+            prop_assert_eq!(&collect(&mut left.semi_join_iter(&right)), &present);
+            prop_assert_eq!(&collect(&mut left.anti_join_iter(&right)), &absent);
+
+            let mut semi = copy_left();
+            semi.retain_intersecting(&right);
+            prop_assert_eq!(semi.len(), present.len());
+            prop_assert_eq!(&collect(&mut semi.iter()), &present);
+
+            let mut anti = copy_left();
+            anti.retain_not_in(&right);
+            prop_assert_eq!(anti.len(), absent.len());
+            prop_assert_eq!(&collect(&mut anti.iter()), &absent);
+            for (key, value) in &present {
+                anti.insert_k(&ArrayKey::<8>::new_from_slice(key), *value);
             }
+            prop_assert_eq!(anti.len(), left.len());
         }
 
         #[test]
-        fn prop_intersection_matches_reference_model(
+        fn prop_union_and_merge_match_reference_model(
             left_entries in proptest::collection::vec((ascii_key_strategy(), any::<u8>()), 0..64),
             right_entries in proptest::collection::vec((ascii_key_strategy(), any::<u8>()), 0..64)
         ) {
             let mut left = AdaptiveRadixTree::<ArrayKey<8>, u8>::new();
             let mut right = AdaptiveRadixTree::<ArrayKey<8>, u8>::new();
-            let mut left_map = BTreeMap::<Vec<u8>, u8>::new();
+            let mut expected = BTreeMap::<Vec<u8>, u8>::new();
             let mut right_map = BTreeMap::<Vec<u8>, u8>::new();
 
             for (key, value) in left_entries {
                 left.insert_k(&ArrayKey::<8>::new_from_slice(&key), value);
-                left_map.insert(key, value);
+                expected.insert(key, value);
             }
-
             for (key, value) in right_entries {
                 right.insert_k(&ArrayKey::<8>::new_from_slice(&key), value);
                 right_map.insert(key, value);
             }
+            for (key, right_value) in right_map {
+                expected
+                    .entry(key)
+                    .and_modify(|left_value| *left_value = left_value.wrapping_sub(right_value))
+                    .or_insert(right_value);
+            }
+            let expected: Vec<_> = expected.into_iter().collect();
+            let left_before: Vec<_> = left.iter().map(|(k, v)| (k, *v)).collect();
 
-            let expected: Vec<_> = left_map
+            let union = left.union_with(&right, |_key, l, r| l.wrapping_sub(*r));
+            let got: Vec<_> = union
                 .iter()
-                .filter_map(|(key, left_value)| {
-                    right_map
-                        .get(key)
-                        .map(|right_value| (key.clone(), *left_value, *right_value))
-                })
+                .map(|(key, value)| (trim_array_key_bytes(key.as_ref()), *value))
                 .collect();
-            let expected_count = expected.len();
-
-            let mut got = Vec::new();
-            left.intersect_with(&right, |key, left_value, right_value| {
-                got.push((trim_array_key_bytes(key.as_ref()), *left_value, *right_value));
-            });
-            got.sort();
-
-            let mut got_values = Vec::new();
-            left.intersect_values_with(&right, |left_value, right_value| {
-                got_values.push((*left_value, *right_value));
-            });
-            got_values.sort();
+            prop_assert_eq!(&got, &expected);
+            prop_assert_eq!(union.len(), expected.len());
+            let left_after: Vec<_> = left.iter().map(|(k, v)| (k, *v)).collect();
+            prop_assert!(left_before == left_after);
+
+            let mut by_ref = AdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            for (key, value) in &left_before {
+                by_ref.insert_k(key, *value);
+            }
+            by_ref.merge_from_ref(&right, |_key, l, r| l.wrapping_sub(*r));
+            let got: Vec<_> = by_ref
+                .iter()
+                .map(|(key, value)| (trim_array_key_bytes(key.as_ref()), *value))
+                .collect();
+            prop_assert_eq!(&got, &expected);
+            prop_assert_eq!(by_ref.len(), expected.len());
 
-            let mut expected_values: Vec<_> = expected
+            let mut merged = left;
+            merged.merge_from(right, |_key, l, r| l.wrapping_sub(r));
+            let got: Vec<_> = merged
                 .iter()
-                .map(|(_, left_value, right_value)| (*left_value, *right_value))
+                .map(|(key, value)| (trim_array_key_bytes(key.as_ref()), *value))
                 .collect();
-            expected_values.sort();
+            prop_assert_eq!(&got, &expected);
+            prop_assert_eq!(merged.len(), expected.len());
 
-            prop_assert_eq!(got, expected);
-            prop_assert_eq!(got_values, expected_values);
-            prop_assert_eq!(left.intersect_count(&right), expected_count);
+            for (key, value) in &expected {
+                let key = ArrayKey::<8>::new_from_slice(key);
+                prop_assert_eq!(merged.get_k(&key), Some(value));
+                prop_assert_eq!(merged.remove_k(&key), Some(*value));
+            }
+            prop_assert!(merged.is_empty());
         }
 
     }
@@ -3754,6 +5212,216 @@ mod tests {
         assert_eq!(left.intersect_count(&right), 2);
     }
 
+    #[test]
+    fn test_intersect_many_across_node_widths() {
+        let mut dense = AdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+        let mut medium = AdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+        let mut sparse = AdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+        for i in 0..=255u32 {
+            dense.insert_k(&ArrayKey::new_from_slice(&[7, i as u8, 1]), i);
+            if i % 6 == 0 {
+                medium.insert_k(&ArrayKey::new_from_slice(&[7, i as u8, 1]), i + 1000);
+            }
+            if i % 4 == 0 && i < 100 {
+                sparse.insert_k(&ArrayKey::new_from_slice(&[7, i as u8, 1]), i + 2000);
+            }
+        }
+        // A key that only some trees hold must not be reported, even when it shares a
+        // compressed prefix with matching keys.
+        sparse.insert_k(&ArrayKey::new_from_slice(&[7, 12, 1, 9]), 0);
+
+        let mut seen = Vec::new();
+        AdaptiveRadixTree::intersect_many(&[&dense, &medium, &sparse], |key, values| {
+            assert_eq!(key.len(), 3);
+            seen.push(values.iter().map(|v| **v).collect::<Vec<_>>());
+        });
+        let expected: Vec<_> = (0..100u32)
+            .filter(|i| i % 12 == 0)
+            .map(|i| vec![i, i + 1000, i + 2000])
+            .collect();
+        assert_eq!(seen, expected);
+
+        let mut count = 0;
+        AdaptiveRadixTree::intersect_many(&[&dense], |_key, _values| count += 1);
+        assert_eq!(count, 256);
+
+        let empty = AdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+        AdaptiveRadixTree::intersect_many(&[&dense, &empty], |_key, _values| {
+            panic!("an empty tree intersects with nothing")
+        });
+        AdaptiveRadixTree::<ArrayKey<16>, u32>::intersect_many(&[], |_key, _values| {
+            panic!("no trees intersect to nothing")
+        });
+    }
+
+    #[test]
+    fn test_difference_reports_one_sided_subtrees() {
+        let mut left = AdaptiveRadixTree::<ArrayKey<32>, i32>::new();
+        let mut right = AdaptiveRadixTree::<ArrayKey<32>, i32>::new();
+
+        for (k, v) in [
+            ("a", 1),
+            ("ab", 2),
+            ("abc", 3),
+            ("abd", 4),
+            ("bzz", 5),
+            ("cat", 6),
+        ] {
+            left.insert(k, v);
+        }
+        for (k, v) in [
+            ("ab", 20),
+            ("abc", 30),
+            ("abcdef", 60),
+            ("bzz", 50),
+            ("dog", 70),
+        ] {
+            right.insert(k, v);
+        }
+
+        let mut only_left = Vec::new();
+        left.difference_with(&right, |key, value| {
+            only_left.push((trim_array_key_bytes(key.as_ref()), *value));
+        });
+        assert_eq!(
+            only_left,
+            vec![
+                (b"a".to_vec(), 1),
+                (b"abd".to_vec(), 4),
+                (b"cat".to_vec(), 6)
+            ]
+        );
+        assert_eq!(left.difference_count(&right), 3);
+        assert_eq!(right.difference_count(&left), 2);
+
+        let mut either = Vec::new();
+        left.symmetric_difference_with(&right, |key, side| {
+            either.push((
+                trim_array_key_bytes(key.as_ref()),
+                match side {
+                    Side::Left(value) => Side::Left(*value),
+                    Side::Right(value) => Side::Right(*value),
+                },
+            ));
+        });
+        assert_eq!(
+            either,
+            vec![
+                (b"a".to_vec(), Side::Left(1)),
+                (b"abcdef".to_vec(), Side::Right(60)),
+                (b"abd".to_vec(), Side::Left(4)),
+                (b"cat".to_vec(), Side::Left(6)),
+                (b"dog".to_vec(), Side::Right(70)),
+            ]
+        );
+
+        let empty = AdaptiveRadixTree::<ArrayKey<32>, i32>::new();
+        let mut count = 0;
+        empty.symmetric_difference_with(&right, |_key, side| {
+            assert!(matches!(side, Side::Right(_)));
+            count += 1;
+        });
+        assert_eq!(count, 5);
+        assert_eq!(left.difference_count(&empty), 6);
+        assert_eq!(empty.difference_count(&left), 0);
+    }
+
+    #[test]
+    fn test_union_with_resolves_overlapping_keys() {
+        let mut left = AdaptiveRadixTree::<ArrayKey<32>, i32>::new();
+        let mut right = AdaptiveRadixTree::<ArrayKey<32>, i32>::new();
+
+        for (k, v) in [("a", 1), ("ab", 2), ("abc", 3), ("abd", 4), ("bzz", 5)] {
+            left.insert(k, v);
+        }
+        for (k, v) in [
+            ("ab", 20),
+            ("abc", 30),
+            ("abcdef", 60),
+            ("bzz", 50),
+            ("dog", 70),
+        ] {
+            right.insert(k, v);
+        }
+
+        let mut resolved = Vec::new();
+        let union = left.union_with(&right, |key, l, r| {
+            resolved.push(trim_array_key_bytes(key.as_ref()));
+            l * 100 + r
+        });
+        resolved.sort();
+        assert_eq!(
+            resolved,
+            vec![b"ab".to_vec(), b"abc".to_vec(), b"bzz".to_vec()]
+        );
+
+        let got: Vec<_> = union
+            .iter()
+            .map(|(k, v)| (trim_array_key_bytes(k.as_ref()), *v))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (b"a".to_vec(), 1),
+                (b"ab".to_vec(), 220),
+                (b"abc".to_vec(), 330),
+                (b"abcdef".to_vec(), 60),
+                (b"abd".to_vec(), 4),
+                (b"bzz".to_vec(), 550),
+                (b"dog".to_vec(), 70),
+            ]
+        );
+        assert_eq!(union.len(), 7);
+        assert_eq!(left.len(), 5);
+        assert_eq!(right.len(), 5);
+    }
+
+    #[test]
+    fn test_merge_from_moves_disjoint_subtrees_and_grows_nodes() {
+        let mut left = AdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+        let mut right = AdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+
+        // Interleaved single-byte branches force the merged root past Node48.
+        for i in 0..=255u32 {
+            let key = [i as u8, 1];
+            if i % 2 == 0 {
+                left.insert_k(&ArrayKey::new_from_slice(&key), i);
+            } else {
+                right.insert_k(&ArrayKey::new_from_slice(&key), i);
+            }
+        }
+        right.insert_k(&ArrayKey::new_from_slice(&[0, 1]), 1000);
+
+        left.merge_from(right, |_key, l, r| l + r);
+        assert_eq!(left.len(), 256);
+        assert_eq!(left.get_k(&ArrayKey::new_from_slice(&[0, 1])), Some(&1000));
+        for i in 1..=255u32 {
+            assert_eq!(
+                left.get_k(&ArrayKey::new_from_slice(&[i as u8, 1])),
+                Some(&i)
+            );
+        }
+
+        let mut by_ref = AdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+        by_ref.insert_k(&ArrayKey::new_from_slice(&[0, 1]), 5);
+        by_ref.merge_from_ref(&left, |_key, l, r| l + r);
+        assert_eq!(by_ref.len(), 256);
+        assert_eq!(
+            by_ref.get_k(&ArrayKey::new_from_slice(&[0, 1])),
+            Some(&1005)
+        );
+
+        let mut empty = AdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+        empty.merge_from(left, |_key, l, _r| l);
+        assert_eq!(empty.len(), 256);
+        assert_eq!(
+            empty
+                .union_with(&AdaptiveRadixTree::new(), |_key, l, _r| *l)
+                .len(),
+            256
+        );
+    }
+
     #[test]
     fn test_range_stops_after_first_out_of_bounds_regression() {
         let _guard = PANIC_TEST_LOCK.lock().unwrap();
@@ -101,6 +101,19 @@ where
         }
     }
 
+    /// Like `get`, but checks the bitset only once, for optimistic readers of a concurrent
+    /// node that may race with a writer and discard what they read if validation fails.
+    #[inline]
+    pub(crate) fn peek(&self, pos: usize) -> Option<&X> {
+        if self.bitset.check(pos) {
+            // SAFETY: `pos` is in range, and initialized unless a concurrent writer is changing
+            // the array, in which case the caller discards what it reads.
+            Some(unsafe { self.storage[pos].assume_init_ref() })
+        } else {
+            None
+        }
+    }
+
     #[inline]
     pub(crate) unsafe fn get_known_present(&self, pos: usize) -> &X {
         debug_assert!(pos < RANGE_WIDTH);
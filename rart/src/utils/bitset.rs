@@ -74,6 +74,14 @@ where
         }
     }
 
+    /// Keep only the bits that are also set in `other`.
+    #[inline]
+    pub fn intersect_with(&mut self, other: &Self) {
+        for (word, other_word) in self.bitset.iter_mut().zip(other.bitset.iter()) {
+            *word = *word & *other_word;
+        }
+    }
+
     #[inline]
     fn first_empty_specialized(&self) -> Option<usize> {
         if std::mem::size_of::<StorageType>() == 8 {
@@ -290,6 +298,20 @@ pub type Bitset8<const STORAGE_WIDTH_U8: usize> = Bitset<u8, STORAGE_WIDTH_U8>;
 mod tests {
     use crate::utils::bitset::BitsetTrait;
 
+    #[test]
+    fn test_intersect_with() {
+        let mut a = super::Bitset64::<4>::new();
+        let mut b = super::Bitset64::<4>::new();
+        for pos in [0, 3, 64, 200, 255] {
+            a.set(pos);
+        }
+        for pos in [3, 65, 200, 255] {
+            b.set(pos);
+        }
+        a.intersect_with(&b);
+        assert_eq!(a.iter().collect::<Vec<_>>(), vec![3, 200, 255]);
+    }
+
     #[test]
     fn test_first_free_8s() {
         let mut bs = super::Bitset8::<4>::new();
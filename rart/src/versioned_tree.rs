@@ -6,8 +6,10 @@
 use std::cmp::min;
 use std::collections::Bound;
 use std::ops::RangeBounds;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use crate::iter::LendingKeyView;
+use crate::join::{self, FilterJoinIter, KeySpan, NodeView, Only};
 use crate::keys::KeyTrait;
 use crate::mapping::{
     NodeMapping,
@@ -15,33 +17,62 @@ use crate::mapping::{
     indexed_mapping::{IndexedMapping, IndexedMappingIter},
     sorted_keyed_mapping::{SortedKeyedMapping, SortedKeyedMappingIter},
 };
-use crate::partials::Partial;
+use crate::partials::{Partial, prefix_length_bytes};
+use crate::range::ByteRange;
+use crate::shared_ptr::{DefaultPtr, RcPtr, SharedPtr};
+use crate::stats::{
+    SharingStats, SnapshotStats, TreeStats, TreeStatsTrait, finish_tree_stats, record_inner_node,
+};
+use crate::tree::AdaptiveRadixTree;
 use crate::utils::bitset::Bitset64;
-use crate::{Slot, SlotUpdate, VisitControl};
+use crate::write_batch::{BatchOp, WriteBatch};
+use crate::{Side, Slot, SlotUpdate, VisitControl};
 
-#[cfg(not(feature = "triomphe-arc"))]
-use std::sync::Arc;
-#[cfg(feature = "triomphe-arc")]
-use triomphe::Arc;
+/// A shared pointer to a versioned node.
+type NodePtr<P, V, S> = <S as SharedPtr>::Ptr<VersionedNode<P, V, S>>;
 
 /// Type alias for remove operation result to reduce type complexity
-type RemoveResult<P, V> = (Option<Arc<VersionedNode<P, V>>>, V);
-type DeleteResult<P, V> = Option<Arc<VersionedNode<P, V>>>;
-type UpdateResult<P, V> = (Option<Arc<VersionedNode<P, V>>>, bool);
-type VersionedPrefixSubtreeView<'a, P, V> = (&'a VersionedNode<P, V>, Vec<&'a [u8]>, usize);
-
-type VersionedIterEntry<'a, P, V> = (u8, &'a VersionedNode<P, V>);
+type RemoveResult<P, V, S> = (Option<NodePtr<P, V, S>>, V);
+type DeleteResult<P, V, S> = Option<NodePtr<P, V, S>>;
+type UpdateResult<P, V, S> = (Option<NodePtr<P, V, S>>, bool);
+type VersionedPrefixSubtreeView<'a, P, V, S> = (&'a VersionedNode<P, V, S>, Vec<&'a [u8]>, usize);
+
+type VersionedIterEntry<'a, P, V, S> = (u8, &'a VersionedNode<P, V, S>);
+type VersionedNodeCursor<'a, P, V, S> = (&'a VersionedNode<P, V, S>, usize);
+type VersionedSubtreeMatch<'a, K, V, S> =
+    (&'a VersionedNode<<K as KeyTrait>::PartialType, V, S>, K);
+type Merge3Cursor<'a, P, V, S> = Option<(&'a NodePtr<P, V, S>, usize)>;
+type VersionedPairedSide<'a, P, V, S> = (Option<&'a V>, Vec<(u8, &'a NodePtr<P, V, S>, usize)>);
+
+/// A versioned node taken apart into its prefix, value, and children.
+type VersionedParts<P, V, S> = (P, Option<V>, Vec<(u8, NodePtr<P, V, S>)>);
+
+/// A write batch op keyed for [`VersionedAdaptiveRadixTree::apply_batch`], taken once applied.
+type BatchEntry<'f, K, V, E> = (K, Option<BatchOp<'f, V, E>>);
+
+/// A subtree rebuilt by a write batch, and whether it changed.
+type BatchResult<P, V, S, E> = Result<(Option<NodePtr<P, V, S>>, bool), E>;
+
+/// The owned counterpart of [`VersionedPairedSide`].
+type VersionedOwnedSide<P, V, S> = (Option<V>, Vec<(u8, NodePtr<P, V, S>, usize)>);
+
+/// What a retain did to a shared subtree.
+enum Retained<P: Partial, V, S: SharedPtr> {
+    Unchanged,
+    Emptied,
+    Rebuilt(NodePtr<P, V, S>),
+}
 
-enum VersionedIterFrameIter<'a, P: Partial, V> {
-    Plain(VersionedNodeIter<'a, P, V>),
+enum VersionedIterFrameIter<'a, P: Partial, V, S: SharedPtr> {
+    Plain(VersionedNodeIter<'a, P, V, S>),
     Leading {
-        first: Option<VersionedIterEntry<'a, P, V>>,
-        rest: VersionedNodeIter<'a, P, V>,
+        first: Option<VersionedIterEntry<'a, P, V, S>>,
+        rest: VersionedNodeIter<'a, P, V, S>,
     },
 }
 
-impl<'a, P: Partial, V> Iterator for VersionedIterFrameIter<'a, P, V> {
-    type Item = VersionedIterEntry<'a, P, V>;
+impl<'a, P: Partial, V, S: SharedPtr> Iterator for VersionedIterFrameIter<'a, P, V, S> {
+    type Item = VersionedIterEntry<'a, P, V, S>;
 
     fn next(&mut self) -> Option<Self::Item> {
         match self {
@@ -51,38 +82,44 @@ impl<'a, P: Partial, V> Iterator for VersionedIterFrameIter<'a, P, V> {
     }
 }
 
-pub(crate) enum VersionedNodeIter<'a, P: Partial, V> {
-    Node4(SortedKeyedMappingIter<'a, Arc<VersionedNode<P, V>>, 4>),
-    Node16(SortedKeyedMappingIter<'a, Arc<VersionedNode<P, V>>, 16>),
-    Node48(IndexedMappingIter<'a, Arc<VersionedNode<P, V>>, 48, Bitset64<1>>),
-    Node256(DirectMappingIter<'a, Arc<VersionedNode<P, V>>>),
+pub(crate) enum VersionedNodeIter<'a, P: Partial, V, S: SharedPtr> {
+    Node4(SortedKeyedMappingIter<'a, NodePtr<P, V, S>, 4>),
+    Node16(SortedKeyedMappingIter<'a, NodePtr<P, V, S>, 16>),
+    Node48(IndexedMappingIter<'a, NodePtr<P, V, S>, 48, Bitset64<1>>),
+    Node256(DirectMappingIter<'a, NodePtr<P, V, S>>),
     Empty,
 }
 
-impl<'a, P: Partial, V> Iterator for VersionedNodeIter<'a, P, V> {
-    type Item = (u8, &'a VersionedNode<P, V>);
-
-    fn next(&mut self) -> Option<Self::Item> {
+impl<'a, P: Partial, V, S: SharedPtr> VersionedNodeIter<'a, P, V, S> {
+    fn next_arc(&mut self) -> Option<(u8, &'a NodePtr<P, V, S>)> {
         match self {
-            VersionedNodeIter::Node4(iter) => iter.next().map(|(key, child)| (key, child.as_ref())),
-            VersionedNodeIter::Node16(iter) => {
-                iter.next().map(|(key, child)| (key, child.as_ref()))
-            }
-            VersionedNodeIter::Node48(iter) => {
-                iter.next().map(|(key, child)| (key, child.as_ref()))
-            }
-            VersionedNodeIter::Node256(iter) => {
-                iter.next().map(|(key, child)| (key, child.as_ref()))
-            }
+            VersionedNodeIter::Node4(iter) => iter.next(),
+            VersionedNodeIter::Node16(iter) => iter.next(),
+            VersionedNodeIter::Node48(iter) => iter.next(),
+            VersionedNodeIter::Node256(iter) => iter.next(),
             VersionedNodeIter::Empty => None,
         }
     }
 }
 
+impl<'a, P: Partial + 'a, V: 'a, S: SharedPtr> Iterator for VersionedNodeIter<'a, P, V, S> {
+    type Item = (u8, &'a VersionedNode<P, V, S>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_arc().map(|(key, child)| (key, child.as_ref()))
+    }
+}
+
 /// Iterator over all key-value pairs in a [`VersionedAdaptiveRadixTree`].
-pub struct VersionedIter<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> {
+pub struct VersionedIter<
+    'a,
+    K: KeyTrait<PartialType = P>,
+    P: Partial + 'a,
+    V,
+    S: SharedPtr = DefaultPtr,
+> {
     inner: Box<dyn Iterator<Item = (K, &'a V)> + 'a>,
-    _marker: std::marker::PhantomData<(K, P)>,
+    _marker: std::marker::PhantomData<(K, P, S)>,
 }
 
 /// Iterator over stored keys that are prefixes of a probe key in a
@@ -90,38 +127,103 @@ pub struct VersionedIter<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> {
 ///
 /// This iterator follows only the path described by the probe key and yields
 /// matching stored keys from shortest to longest.
-pub struct VersionedPrefixMatchIter<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> {
-    cur_node: Option<&'a VersionedNode<P, V>>,
+pub struct VersionedPrefixMatchIter<
+    'a,
+    K: KeyTrait<PartialType = P>,
+    P: Partial + 'a,
+    V,
+    S: SharedPtr = DefaultPtr,
+> {
+    cur_node: Option<&'a VersionedNode<P, V, S>>,
     probe: K,
     cur_key: Vec<u8>,
     depth: usize,
 }
 
-struct VersionedIterInner<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> {
-    node_iter_stack: Vec<(usize, VersionedIterFrameIter<'a, P, V>)>,
+struct VersionedIterInner<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V, S: SharedPtr> {
+    node_iter_stack: Vec<(usize, VersionedIterFrameIter<'a, P, V, S>)>,
     cur_key: Vec<u8>,
     start_bound: Option<Bound<K>>,
 }
 
-pub(crate) struct VersionedLendingIterInner<'a, P: Partial + 'a, V> {
-    node_iter_stack: Vec<(usize, usize, VersionedIterFrameIter<'a, P, V>)>,
+pub(crate) struct VersionedLendingIterInner<'a, P: Partial + 'a, V, S: SharedPtr> {
+    node_iter_stack: Vec<(usize, usize, VersionedIterFrameIter<'a, P, V, S>)>,
     cur_segments: Vec<&'a [u8]>,
     cur_len: usize,
     end_bound: Option<(Vec<u8>, bool)>,
 }
 
 /// Iterator over only values in a [`VersionedAdaptiveRadixTree`].
-pub struct VersionedValuesIter<'a, P: Partial + 'a, V> {
+pub struct VersionedValuesIter<'a, P: Partial + 'a, V, S: SharedPtr = DefaultPtr> {
     root_value: Option<&'a V>,
-    node_iter_stack: Vec<VersionedNodeIter<'a, P, V>>,
+    node_iter_stack: Vec<VersionedNodeIter<'a, P, V, S>>,
 }
 
 /// Iterator over versioned key-value pairs within a specified range.
-pub struct VersionedRange<'a, K: KeyTrait + 'a, V> {
-    iter: VersionedIter<'a, K, K::PartialType, V>,
+pub struct VersionedRange<'a, K: KeyTrait + 'a, V, S: SharedPtr = DefaultPtr> {
+    iter: VersionedIter<'a, K, K::PartialType, V, S>,
     end: Bound<K>,
 }
 
+/// One difference between two versions of a tree, as reported by
+/// [`VersionedAdaptiveRadixTree::diff`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Change<K, V> {
+    /// The key is only in the newer tree.
+    Added { key: K, value: V },
+    /// The key is only in the older tree.
+    Removed { key: K, value: V },
+    /// The key is in both trees with different values.
+    Modified { key: K, old: V, new: V },
+}
+
+/// Iterator over the [`Change`]s between two versioned trees, in key order.
+pub struct VersionedDiffIter<
+    'a,
+    K: KeyTrait<PartialType = P>,
+    P: Partial + 'a,
+    V,
+    S: SharedPtr = DefaultPtr,
+> {
+    stack: Vec<DiffFrame<'a, P, V, S>>,
+    key_buf: Vec<u8>,
+    _marker: std::marker::PhantomData<K>,
+}
+
+/// Iterator over the entries of a versioned tree written after a given version, in key order.
+/// Returned by [`VersionedAdaptiveRadixTree::changed_since`].
+pub struct ChangedSinceIter<
+    'a,
+    K: KeyTrait<PartialType = P>,
+    P: Partial + 'a,
+    V,
+    S: SharedPtr = DefaultPtr,
+> {
+    since: u64,
+    /// Nodes still to visit, with the key length at which each one's prefix starts.
+    stack: Vec<(&'a VersionedNode<P, V, S>, usize)>,
+    key_buf: Vec<u8>,
+    _marker: std::marker::PhantomData<K>,
+}
+
+/// A pending step of a [`VersionedDiffIter`]: either a node of each tree to compare, starting
+/// at the given offsets into their prefixes, or a subtree found in only one tree.
+enum DiffFrame<'a, P: Partial, V, S: SharedPtr> {
+    Paired {
+        new: &'a NodePtr<P, V, S>,
+        new_offset: usize,
+        old: &'a NodePtr<P, V, S>,
+        old_offset: usize,
+        key_len: usize,
+    },
+    OneSided {
+        node: &'a VersionedNode<P, V, S>,
+        offset: usize,
+        added: bool,
+        key_len: usize,
+    },
+}
+
 /// A versioned Adaptive Radix Tree that supports snapshot-based copy-on-write mutations.
 ///
 /// Unlike the standard [`AdaptiveRadixTree`], this version allows taking O(1) snapshots
@@ -169,58 +271,101 @@ pub struct VersionedRange<'a, K: KeyTrait + 'a, V> {
 /// assert_eq!(tree.insert_and_replace("key", 100), None);      // new key
 /// assert_eq!(tree.insert_and_replace("key", 200), Some(100)); // got old value
 /// ```
-pub struct VersionedAdaptiveRadixTree<KeyType, ValueType>
+///
+/// ## Pointer type
+///
+/// Nodes are shared between versions through the [`SharedPtr`] family `S`, which defaults to
+/// an atomically counted `Arc` so trees can cross threads. Single-threaded code can use
+/// [`RcPtr`] (see [`PersistentAdaptiveRadixTree`]) to skip the atomic reference counting.
+pub struct VersionedAdaptiveRadixTree<KeyType, ValueType, S = DefaultPtr>
 where
     KeyType: KeyTrait,
     ValueType: Clone,
+    S: SharedPtr,
 {
-    root: Option<Arc<VersionedNode<KeyType::PartialType, ValueType>>>,
+    pub(crate) root: Option<NodePtr<KeyType::PartialType, ValueType, S>>,
     version: u64,
+    parent_version: Option<u64>,
+    /// The version counter shared by every tree derived from the same [`new`](Self::new).
+    versions: S::Ptr<AtomicU64>,
     _phantom: std::marker::PhantomData<KeyType>,
 }
 
+/// A single-threaded [`VersionedAdaptiveRadixTree`] whose nodes are shared through `Rc`.
+///
+/// It has the same API and the same structural sharing between snapshots, but reference
+/// counts are updated without atomics. The tree is neither `Send` nor `Sync`.
+///
+/// ```rust
+/// use rart::{ArrayKey, PersistentAdaptiveRadixTree};
+///
+/// let mut tree = PersistentAdaptiveRadixTree::<ArrayKey<16>, i32>::new();
+/// tree.insert("a", 1);
+///
+/// // Keep cheap undo points.
+/// let undo = tree.snapshot();
+/// tree.insert("b", 2);
+/// tree.remove("a");
+///
+/// let tree = undo;
+/// assert_eq!(tree.get("a"), Some(&1));
+/// assert_eq!(tree.get("b"), None);
+/// ```
+pub type PersistentAdaptiveRadixTree<KeyType, ValueType> =
+    VersionedAdaptiveRadixTree<KeyType, ValueType, RcPtr>;
+
 /// A versioned node that can be shared between multiple tree versions.
-pub struct VersionedNode<P: Partial, V> {
+pub struct VersionedNode<P: Partial, V, S: SharedPtr = DefaultPtr> {
     pub(crate) prefix: P,
     pub(crate) value: Option<V>,
-    pub(crate) content: VersionedContent<P, V>,
+    pub(crate) content: VersionedContent<P, V, S>,
+    /// The version of the mutation that last wrote this node.
+    ///
+    /// Every mutation runs at a fresh version id, newer than any node already reachable from the
+    /// tree, so a subtree whose root is no newer than some version has not changed since then.
+    /// The version does not decide copy-on-write on its own: a mutation modifies a node in place
+    /// only while it holds the sole reference to it, restamping it with its own version if it is
+    /// older, and copies it otherwise.
     pub(crate) version: u64,
 }
 
-/// Content of a versioned node, using Arc for child sharing.
-pub(crate) enum VersionedContent<P: Partial, V> {
+/// Content of a versioned node, sharing children through the tree's pointer type.
+pub(crate) enum VersionedContent<P: Partial, V, S: SharedPtr> {
     Empty,
-    Node4(Box<SortedKeyedMapping<Arc<VersionedNode<P, V>>, 4>>),
-    Node16(Box<SortedKeyedMapping<Arc<VersionedNode<P, V>>, 16>>),
-    Node48(Box<IndexedMapping<Arc<VersionedNode<P, V>>, 48, Bitset64<1>>>),
-    Node256(Box<DirectMapping<Arc<VersionedNode<P, V>>>>),
+    Node4(Box<SortedKeyedMapping<NodePtr<P, V, S>, 4>>),
+    Node16(Box<SortedKeyedMapping<NodePtr<P, V, S>, 16>>),
+    Node48(Box<IndexedMapping<NodePtr<P, V, S>, 48, Bitset64<1>>>),
+    Node256(Box<DirectMapping<NodePtr<P, V, S>>>),
 }
 
-impl<KeyType: KeyTrait, ValueType: Clone> Default
-    for VersionedAdaptiveRadixTree<KeyType, ValueType>
+impl<KeyType: KeyTrait, ValueType: Clone, S: SharedPtr> Default
+    for VersionedAdaptiveRadixTree<KeyType, ValueType, S>
 {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<KeyType, ValueType> Clone for VersionedAdaptiveRadixTree<KeyType, ValueType>
+impl<KeyType, ValueType, S: SharedPtr> Clone for VersionedAdaptiveRadixTree<KeyType, ValueType, S>
 where
     KeyType: KeyTrait,
     ValueType: Clone,
 {
-    /// Clone creates a new snapshot at the current version.
-    /// This is equivalent to calling `snapshot()`.
+    /// Clone creates a new handle on the current version.
+    /// Unlike `snapshot()`, the clone keeps this tree's version and parent version; each tree
+    /// still moves to its own fresh version on its next mutation.
     fn clone(&self) -> Self {
         Self {
             root: self.root.clone(),
             version: self.version,
+            parent_version: self.parent_version,
+            versions: self.versions.clone(),
             _phantom: std::marker::PhantomData,
         }
     }
 }
 
-impl<KeyType, ValueType> VersionedAdaptiveRadixTree<KeyType, ValueType>
+impl<KeyType, ValueType, S: SharedPtr> VersionedAdaptiveRadixTree<KeyType, ValueType, S>
 where
     KeyType: KeyTrait,
     ValueType: Clone,
@@ -229,7 +374,9 @@ where
     pub fn new() -> Self {
         Self {
             root: None,
-            version: 0,
+            version: 1,
+            parent_version: None,
+            versions: S::new(AtomicU64::new(2)),
             _phantom: std::marker::PhantomData,
         }
     }
@@ -239,10 +386,15 @@ where
     /// This is an O(1) operation that creates a new tree sharing the same
     /// underlying nodes. Subsequent mutations to either tree will use
     /// copy-on-write to maintain independence.
+    ///
+    /// The snapshot gets a fresh version whose parent is this tree's version, so sibling
+    /// snapshots of one tree never share a version.
     pub fn snapshot(&self) -> Self {
         Self {
             root: self.root.clone(),
-            version: self.version + 1,
+            version: self.fresh_version(),
+            parent_version: Some(self.version),
+            versions: self.versions.clone(),
             _phantom: std::marker::PhantomData,
         }
     }
@@ -282,7 +434,7 @@ where
     pub fn get_mut_k(&mut self, key: &KeyType) -> Option<&mut ValueType> {
         self.get_k(key)?;
 
-        self.version += 1;
+        self.advance_version();
         let root = self
             .root
             .take()
@@ -302,7 +454,7 @@ where
     }
 
     /// Iterate over all key-value pairs in lexicographic order.
-    pub fn iter(&self) -> VersionedIter<'_, KeyType, KeyType::PartialType, ValueType> {
+    pub fn iter(&self) -> VersionedIter<'_, KeyType, KeyType::PartialType, ValueType, S> {
         VersionedIter::new(self.root.as_deref())
     }
 
@@ -315,7 +467,7 @@ where
     }
 
     /// Create an iterator over only the values in the tree.
-    pub fn values_iter(&self) -> VersionedValuesIter<'_, KeyType::PartialType, ValueType> {
+    pub fn values_iter(&self) -> VersionedValuesIter<'_, KeyType::PartialType, ValueType, S> {
         VersionedValuesIter::new(self.root.as_deref())
     }
 
@@ -333,7 +485,14 @@ where
         };
 
         let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
-        Self::intersect_nodes(left_root, 0, right_root, 0, &mut key_buf, &mut on_match);
+        join::intersect(
+            left_root,
+            0,
+            right_root,
+            0,
+            &mut key_buf,
+            &mut |key, left, right| on_match(KeyType::new_from_slice(key), left, right),
+        );
     }
 
     /// Intersect two trees using ART-native traversal and yield lending key views.
@@ -348,7 +507,7 @@ where
 
         let mut segments = Vec::new();
         let mut key_len = 0usize;
-        Self::intersect_nodes_lending(
+        join::intersect_lending(
             left_root,
             0,
             right_root,
@@ -371,7 +530,7 @@ where
             return;
         };
 
-        Self::intersect_nodes_values(left_root, 0, right_root, 0, &mut on_match);
+        join::intersect_values(left_root, 0, right_root, 0, &mut on_match);
     }
 
     /// Count the number of keys that exist in both trees.
@@ -383,6 +542,747 @@ where
         count
     }
 
+    /// Intersect the entries of two trees whose keys start with `prefix`.
+    ///
+    /// Both trees are first descended to their `prefix` subtrees, and only those subtrees are
+    /// walked in lockstep, so the cost does not depend on how many keys lie outside the prefix.
+    ///
+    /// ```rust
+    /// use rart::{KeyTrait, VectorKey, VersionedAdaptiveRadixTree};
+    ///
+    /// let mut left = VersionedAdaptiveRadixTree::<VectorKey, u32>::new();
+    /// let mut right = VersionedAdaptiveRadixTree::<VectorKey, u32>::new();
+    /// for (key, value) in [(&b"user/ann"[..], 1), (b"user/bob", 2), (b"group/ops", 3)] {
+    ///     left.insert_k(&VectorKey::new_from_slice(key), value);
+    ///     right.insert_k(&VectorKey::new_from_slice(key), value * 10);
+    /// }
+    ///
+    /// let mut matches = Vec::new();
+    /// left.intersect_prefix_with(&right, &VectorKey::new_from_slice(b"user/"), |key, l, r| {
+    ///     matches.push((key.as_ref().to_vec(), *l, *r));
+    /// });
+    /// assert_eq!(
+    ///     matches,
+    ///     vec![(b"user/ann".to_vec(), 1, 10), (b"user/bob".to_vec(), 2, 20)]
+    /// );
+    /// ```
+    pub fn intersect_prefix_with<'a, F>(
+        &'a self,
+        other: &'a Self,
+        prefix: &KeyType,
+        mut on_match: F,
+    ) where
+        F: FnMut(KeyType, &'a ValueType, &'a ValueType),
+    {
+        let (Some(left_root), Some(right_root)) = (self.root.as_deref(), other.root.as_deref())
+        else {
+            return;
+        };
+        let Some((left, left_offset)) = Self::find_prefix_subtree_offset(left_root, prefix) else {
+            return;
+        };
+        let Some((right, right_offset)) = Self::find_prefix_subtree_offset(right_root, prefix)
+        else {
+            return;
+        };
+
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        key_buf.extend_from_slice(&prefix.as_ref()[..prefix.length_at(0)]);
+        join::intersect(
+            left,
+            left_offset,
+            right,
+            right_offset,
+            &mut key_buf,
+            &mut |key, left_value, right_value| {
+                on_match(KeyType::new_from_slice(key), left_value, right_value)
+            },
+        );
+    }
+
+    /// Intersect the entries of two trees whose keys start with `prefix`, yielding value pairs
+    /// only.
+    pub fn intersect_prefix_values_with<'a, F>(
+        &'a self,
+        other: &'a Self,
+        prefix: &KeyType,
+        mut on_match: F,
+    ) where
+        F: FnMut(&'a ValueType, &'a ValueType),
+    {
+        let (Some(left_root), Some(right_root)) = (self.root.as_deref(), other.root.as_deref())
+        else {
+            return;
+        };
+        let Some((left, left_offset)) = Self::find_prefix_subtree_offset(left_root, prefix) else {
+            return;
+        };
+        let Some((right, right_offset)) = Self::find_prefix_subtree_offset(right_root, prefix)
+        else {
+            return;
+        };
+
+        join::intersect_values(left, left_offset, right, right_offset, &mut on_match);
+    }
+
+    /// Count the keys starting with `prefix` that exist in both trees.
+    pub fn intersect_prefix_count(&self, other: &Self, prefix: &KeyType) -> usize {
+        let mut count = 0usize;
+        self.intersect_prefix_values_with(other, prefix, |_left_value, _right_value| {
+            count += 1;
+        });
+        count
+    }
+
+    /// Intersect the entries of two trees whose keys fall within `range`.
+    ///
+    /// When both ends of the range are bounded, both trees are first descended to the subtree
+    /// for the bytes the bounds share. The lockstep walk below that point skips any subtree
+    /// whose keys all lie outside the range.
+    pub fn intersect_range_with<'a, R, F>(&'a self, other: &'a Self, range: R, mut on_match: F)
+    where
+        R: RangeBounds<KeyType>,
+        F: FnMut(KeyType, &'a ValueType, &'a ValueType),
+    {
+        self.intersect_range_raw(other, &range, &mut |key, left_value, right_value| {
+            on_match(KeyType::new_from_slice(key), left_value, right_value)
+        });
+    }
+
+    /// Intersect the entries of two trees whose keys fall within `range`, yielding value pairs
+    /// only.
+    pub fn intersect_range_values_with<'a, R, F>(
+        &'a self,
+        other: &'a Self,
+        range: R,
+        mut on_match: F,
+    ) where
+        R: RangeBounds<KeyType>,
+        F: FnMut(&'a ValueType, &'a ValueType),
+    {
+        self.intersect_range_raw(other, &range, &mut |_key, left_value, right_value| {
+            on_match(left_value, right_value)
+        });
+    }
+
+    /// Count the keys within `range` that exist in both trees.
+    pub fn intersect_range_count<R>(&self, other: &Self, range: R) -> usize
+    where
+        R: RangeBounds<KeyType>,
+    {
+        let mut count = 0usize;
+        self.intersect_range_values_with(other, range, |_left_value, _right_value| {
+            count += 1;
+        });
+        count
+    }
+
+    /// Intersect any number of trees at once, descending all tries together.
+    ///
+    /// `on_match` is called in key order for every key present in all of `trees`, with a view
+    /// of the key and the values from each tree, in the order of `trees`. At each node the
+    /// children's edge bytes are intersected as bitsets across all trees, so only edges
+    /// present everywhere are followed. No intermediate trees are built, which makes this the
+    /// building block for worst-case optimal joins over several indexes. An empty slice
+    /// matches nothing.
+    ///
+    /// ```rust
+    /// use rart::{VersionedAdaptiveRadixTree, ArrayKey};
+    ///
+    /// let mut a = VersionedAdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+    /// let mut b = VersionedAdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+    /// let mut c = VersionedAdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+    /// for (tree, offset) in [(&mut a, 0), (&mut b, 10), (&mut c, 20)] {
+    ///     tree.insert("ant", offset + 1);
+    ///     tree.insert("bee", offset + 2);
+    /// }
+    /// b.remove("ant");
+    ///
+    /// let mut matches = Vec::new();
+    /// VersionedAdaptiveRadixTree::intersect_many(&[&a, &b, &c], |key, values| {
+    ///     matches.push((key.to_vec(), values.iter().map(|v| **v).collect::<Vec<_>>()));
+    /// });
+    /// assert_eq!(matches, vec![(b"bee\0".to_vec(), vec![2, 12, 22])]);
+    /// ```
+    pub fn intersect_many<'a, F>(trees: &[&'a Self], mut on_match: F)
+    where
+        F: for<'view> FnMut(LendingKeyView<'a, 'view>, &[&'a ValueType]),
+    {
+        let mut cursors = Vec::with_capacity(trees.len());
+        for tree in trees {
+            let Some(root) = tree.root.as_deref() else {
+                return;
+            };
+            cursors.push((root, 0));
+        }
+        if cursors.is_empty() {
+            return;
+        }
+
+        let mut segments = Vec::new();
+        let mut key_len = 0usize;
+        let mut values = Vec::with_capacity(cursors.len());
+        join::intersect_many(
+            &cursors,
+            &mut segments,
+            &mut key_len,
+            &mut values,
+            &mut on_match,
+        );
+    }
+
+    /// Visit the entries of this tree whose keys are not in `other`, in key order.
+    ///
+    /// Both tries are walked together like [`Self::intersect_with`]. Where `other` has no
+    /// matching child, the whole subtree is reported without looking anything up in `other`.
+    pub fn difference_with<'a, F>(&'a self, other: &'a Self, mut on_entry: F)
+    where
+        F: FnMut(KeyType, &'a ValueType),
+    {
+        let Some(left_root) = self.root.as_deref() else {
+            return;
+        };
+
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        let mut on_entry = |key: &[u8], value| on_entry(KeyType::new_from_slice(key), value);
+        match other.root.as_deref() {
+            Some(right_root) => join::difference(
+                left_root,
+                0,
+                right_root,
+                0,
+                false,
+                &mut key_buf,
+                &mut |key, only| {
+                    if let Only::Left(value) = only {
+                        on_entry(key, value);
+                    }
+                },
+            ),
+            None => join::for_each_from(left_root, 0, &mut key_buf, &mut on_entry),
+        }
+    }
+
+    /// Visit the entries whose keys are in exactly one of the two trees, in key order.
+    ///
+    /// Entries from this tree are reported as [`Side::Left`] and entries from `other` as
+    /// [`Side::Right`]. Subtrees with no counterpart on the other side are reported whole.
+    pub fn symmetric_difference_with<'a, F>(&'a self, other: &'a Self, mut on_entry: F)
+    where
+        F: FnMut(KeyType, Side<&'a ValueType>),
+    {
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        let mut on_entry = |key: &[u8], side| on_entry(KeyType::new_from_slice(key), side);
+        match (self.root.as_deref(), other.root.as_deref()) {
+            (None, None) => {}
+            (Some(left_root), None) => {
+                join::for_each_from(left_root, 0, &mut key_buf, &mut |key, value| {
+                    on_entry(key, Side::Left(value))
+                })
+            }
+            (None, Some(right_root)) => {
+                join::for_each_from(right_root, 0, &mut key_buf, &mut |key, value| {
+                    on_entry(key, Side::Right(value))
+                })
+            }
+            (Some(left_root), Some(right_root)) => join::difference(
+                left_root,
+                0,
+                right_root,
+                0,
+                true,
+                &mut key_buf,
+                &mut |key, only| on_entry(key, only.into()),
+            ),
+        }
+    }
+
+    /// Count the number of keys in this tree that are not in `other`.
+    pub fn difference_count(&self, other: &Self) -> usize {
+        self.values_iter().count() - self.intersect_count(other)
+    }
+
+    /// Intersect this tree with an `AdaptiveRadixTree` with the same key type.
+    ///
+    /// Both tries are walked in lockstep like [`Self::intersect_with`], so neither side has
+    /// to be converted first. The two trees may hold different value types.
+    pub fn intersect_unversioned_with<'a, W, F>(
+        &'a self,
+        other: &'a AdaptiveRadixTree<KeyType, W>,
+        mut on_match: F,
+    ) where
+        F: FnMut(KeyType, &'a ValueType, &'a W),
+    {
+        let (Some(left_root), Some(right_root)) = (self.root.as_deref(), other.root.as_ref())
+        else {
+            return;
+        };
+
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        join::intersect(
+            left_root,
+            0,
+            right_root,
+            0,
+            &mut key_buf,
+            &mut |key, left, right| on_match(KeyType::new_from_slice(key), left, right),
+        );
+    }
+
+    /// Intersect this tree with an `AdaptiveRadixTree` and invoke a callback with value pairs only.
+    pub fn intersect_unversioned_values_with<'a, W, F>(
+        &'a self,
+        other: &'a AdaptiveRadixTree<KeyType, W>,
+        mut on_match: F,
+    ) where
+        F: FnMut(&'a ValueType, &'a W),
+    {
+        let (Some(left_root), Some(right_root)) = (self.root.as_deref(), other.root.as_ref())
+        else {
+            return;
+        };
+
+        join::intersect_values(left_root, 0, right_root, 0, &mut on_match);
+    }
+
+    /// Count the number of keys that exist in both this tree and an `AdaptiveRadixTree`.
+    pub fn intersect_unversioned_count<W>(&self, other: &AdaptiveRadixTree<KeyType, W>) -> usize {
+        let mut count = 0usize;
+        self.intersect_unversioned_values_with(other, |_left_value, _right_value| {
+            count += 1;
+        });
+        count
+    }
+
+    /// Visit every entry of this tree whose key is not in an `AdaptiveRadixTree`.
+    ///
+    /// Subtrees with no counterpart in `other` are reported whole, without comparing their
+    /// keys one at a time.
+    pub fn difference_unversioned_with<'a, W, F>(
+        &'a self,
+        other: &AdaptiveRadixTree<KeyType, W>,
+        mut on_entry: F,
+    ) where
+        F: FnMut(KeyType, &'a ValueType),
+    {
+        let Some(left_root) = self.root.as_deref() else {
+            return;
+        };
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        let mut on_entry = |key: &[u8], value| on_entry(KeyType::new_from_slice(key), value);
+        match other.root.as_ref() {
+            Some(right_root) => join::difference(
+                left_root,
+                0,
+                right_root,
+                0,
+                false,
+                &mut key_buf,
+                &mut |key, only| {
+                    if let Only::Left(value) = only {
+                        on_entry(key, value);
+                    }
+                },
+            ),
+            None => join::for_each_from(left_root, 0, &mut key_buf, &mut on_entry),
+        }
+    }
+
+    /// Count the number of keys in this tree that are not in an `AdaptiveRadixTree`.
+    pub fn difference_unversioned_count<W>(&self, other: &AdaptiveRadixTree<KeyType, W>) -> usize {
+        self.values_iter().count() - self.intersect_unversioned_count(other)
+    }
+
+    /// For every key in this tree, visit each entry of `other` whose key is a prefix of it.
+    ///
+    /// `on_match` receives this tree's key, the `other` key (a prefix of the first, possibly
+    /// equal to it), and both values. Keys are visited in order, and for each key the
+    /// matching prefixes from shortest to longest. Both tries are descended together while
+    /// tracking the `other` entries passed on the way down, so this costs one paired walk
+    /// rather than a [`Self::prefix_match_for_each_k`] probe per key. Subtrees of this tree
+    /// that no `other` key leads into or covers are skipped.
+    ///
+    /// [`prefix_join_under_with`](Self::prefix_join_under_with) is the reverse direction.
+    ///
+    /// ```rust
+    /// use rart::{VersionedAdaptiveRadixTree, KeyTrait, VectorKey};
+    ///
+    /// let mut paths = VersionedAdaptiveRadixTree::<VectorKey, u32>::new();
+    /// paths.insert_k(&VectorKey::new_from_slice(b"/srv/www/index"), 1);
+    /// paths.insert_k(&VectorKey::new_from_slice(b"/tmp/x"), 2);
+    /// let mut rules = VersionedAdaptiveRadixTree::<VectorKey, &str>::new();
+    /// rules.insert_k(&VectorKey::new_from_slice(b"/srv"), "read");
+    /// rules.insert_k(&VectorKey::new_from_slice(b"/srv/www"), "serve");
+    /// rules.insert_k(&VectorKey::new_from_slice(b"/home"), "none");
+    ///
+    /// let mut matches = Vec::new();
+    /// paths.prefix_join_with(&rules, |path, rule_key, _, rule| {
+    ///     matches.push((path.as_ref().to_vec(), rule_key.as_ref().to_vec(), *rule));
+    /// });
+    /// assert_eq!(
+    ///     matches,
+    ///     vec![
+    ///         (b"/srv/www/index".to_vec(), b"/srv".to_vec(), "read"),
+    ///         (b"/srv/www/index".to_vec(), b"/srv/www".to_vec(), "serve"),
+    ///     ]
+    /// );
+    /// ```
+    pub fn prefix_join_with<'a, W, F>(
+        &'a self,
+        other: &'a VersionedAdaptiveRadixTree<KeyType, W, S>,
+        mut on_match: F,
+    ) where
+        W: Clone,
+        F: FnMut(KeyType, KeyType, &'a ValueType, &'a W),
+    {
+        let (Some(left_root), Some(right_root)) = (self.root.as_deref(), other.root.as_deref())
+        else {
+            return;
+        };
+
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        let mut passed = Vec::new();
+        join::prefix_join(
+            left_root,
+            0,
+            right_root,
+            0,
+            &mut key_buf,
+            &mut passed,
+            &mut |key, prefix, value, prefix_value| {
+                on_match(
+                    KeyType::new_from_slice(key),
+                    KeyType::new_from_slice(prefix),
+                    value,
+                    prefix_value,
+                )
+            },
+        );
+    }
+
+    /// For every key in this tree, visit each entry of `other` whose key starts with it: the
+    /// reverse direction of [`prefix_join_with`](Self::prefix_join_with).
+    ///
+    /// `on_match` receives this tree's key, the `other` key (an extension of the first,
+    /// possibly equal to it), and both values. Entries are visited in `other` key order, and
+    /// for each `other` key the matching keys of this tree from shortest to longest. This is
+    /// the same paired descent as `prefix_join_with` with the roles of the trees swapped.
+    ///
+    /// ```rust
+    /// use rart::{VersionedAdaptiveRadixTree, KeyTrait, VectorKey};
+    ///
+    /// let mut rules = VersionedAdaptiveRadixTree::<VectorKey, &str>::new();
+    /// rules.insert_k(&VectorKey::new_from_slice(b"/srv"), "read");
+    /// rules.insert_k(&VectorKey::new_from_slice(b"/tmp"), "scratch");
+    /// let mut paths = VersionedAdaptiveRadixTree::<VectorKey, u32>::new();
+    /// paths.insert_k(&VectorKey::new_from_slice(b"/srv/www/index"), 1);
+    /// paths.insert_k(&VectorKey::new_from_slice(b"/srv/db"), 2);
+    /// paths.insert_k(&VectorKey::new_from_slice(b"/home/me"), 3);
+    ///
+    /// let mut covered = Vec::new();
+    /// rules.prefix_join_under_with(&paths, |_, path, rule, _| {
+    ///     covered.push((*rule, path.as_ref().to_vec()));
+    /// });
+    /// assert_eq!(
+    ///     covered,
+    ///     vec![
+    ///         ("read", b"/srv/db".to_vec()),
+    ///         ("read", b"/srv/www/index".to_vec()),
+    ///     ]
+    /// );
+    /// ```
+    pub fn prefix_join_under_with<'a, W, F>(
+        &'a self,
+        other: &'a VersionedAdaptiveRadixTree<KeyType, W, S>,
+        mut on_match: F,
+    ) where
+        W: Clone,
+        F: FnMut(KeyType, KeyType, &'a ValueType, &'a W),
+    {
+        other.prefix_join_with(self, |other_key, key, other_value, value| {
+            on_match(key, other_key, value, other_value)
+        });
+    }
+
+    /// Iterate over the entries of this tree whose keys are also in `other`.
+    ///
+    /// Only the keys of `other` matter, so it may hold any value type. Both tries are walked
+    /// together, and subtrees of this tree with no counterpart in `other` are skipped whole.
+    pub fn semi_join_iter<'a, W>(
+        &'a self,
+        other: &'a VersionedAdaptiveRadixTree<KeyType, W, S>,
+    ) -> impl Iterator<Item = (KeyType, &'a ValueType)> + 'a
+    where
+        W: Clone,
+    {
+        FilterJoinIter::<KeyType, _, _>::new(self.root.as_deref(), other.root.as_deref(), true)
+    }
+
+    /// Iterate over the entries of this tree whose keys are not in `other`.
+    ///
+    /// Only the keys of `other` matter, so it may hold any value type. Subtrees of this tree
+    /// with no counterpart in `other` are yielded whole without further comparison.
+    pub fn anti_join_iter<'a, W>(
+        &'a self,
+        other: &'a VersionedAdaptiveRadixTree<KeyType, W, S>,
+    ) -> impl Iterator<Item = (KeyType, &'a ValueType)> + 'a
+    where
+        W: Clone,
+    {
+        FilterJoinIter::<KeyType, _, _>::new(self.root.as_deref(), other.root.as_deref(), false)
+    }
+
+    /// Keep only the entries whose keys are also in `other`, which may hold any value type.
+    ///
+    /// Subtrees with no counterpart in `other` are dropped whole. Nodes on changed paths are
+    /// copied; untouched subtrees stay shared with earlier snapshots.
+    pub fn retain_intersecting<W: Clone>(
+        &mut self,
+        other: &VersionedAdaptiveRadixTree<KeyType, W, S>,
+    ) {
+        self.retain_by_presence(other, true);
+    }
+
+    /// Remove every entry whose key is in `other`, which may hold any value type.
+    ///
+    /// Subtrees with no counterpart in `other` are kept whole without visiting their keys.
+    pub fn retain_not_in<W: Clone>(&mut self, other: &VersionedAdaptiveRadixTree<KeyType, W, S>) {
+        self.retain_by_presence(other, false);
+    }
+
+    /// Build a new tree holding every key of `self` and `other`.
+    ///
+    /// Both tries are walked together like [`Self::intersect_with`]. Subtrees that exist on
+    /// only one side are shared with the result by `Arc` clone rather than copied, so only
+    /// nodes on paths present in both trees are rebuilt. For keys present in both trees,
+    /// `resolver` receives the key, this tree's value, and `other`'s value, and its result is
+    /// stored.
+    ///
+    /// The result's version is newer than both inputs.
+    ///
+    /// ```rust
+    /// use rart::{VersionedAdaptiveRadixTree, ArrayKey};
+    ///
+    /// let mut left = VersionedAdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+    /// left.insert("apple", 1);
+    /// left.insert("banana", 2);
+    /// let mut right = VersionedAdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+    /// right.insert("banana", 10);
+    /// right.insert("cherry", 20);
+    ///
+    /// let union = left.union_with(&right, |_key, a, b| a + b);
+    /// assert_eq!(union.get("banana"), Some(&12));
+    /// assert_eq!(union.get("cherry"), Some(&20));
+    /// assert_eq!(left.get("cherry"), None);
+    /// ```
+    pub fn union_with<F>(&self, other: &Self, mut resolver: F) -> Self
+    where
+        F: FnMut(KeyType, &ValueType, &ValueType) -> ValueType,
+    {
+        let version = self.fresh_version_after(other.version);
+        let root = match (self.root.as_ref(), other.root.as_ref()) {
+            (None, None) => None,
+            (Some(root), None) | (None, Some(root)) => Some(Clone::clone(root)),
+            (Some(left_root), Some(right_root)) => {
+                let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+                Some(Self::union_nodes(
+                    left_root,
+                    0,
+                    right_root,
+                    0,
+                    &mut key_buf,
+                    version,
+                    &mut resolver,
+                ))
+            }
+        };
+
+        Self {
+            root,
+            version,
+            parent_version: Some(self.version),
+            versions: self.versions.clone(),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Merge `other` into this tree, consuming it.
+    ///
+    /// Both tries are walked together, and subtrees found only in `other` are moved into this
+    /// tree whole. For keys present in both trees, `resolver` receives the key, this tree's
+    /// value, and `other`'s value, and its result is stored. Values are moved out of nodes that
+    /// no snapshot shares; only a node still shared with another tree has its value cloned.
+    pub fn merge_from<F>(&mut self, other: Self, mut resolver: F)
+    where
+        F: FnMut(KeyType, ValueType, ValueType) -> ValueType,
+    {
+        let version = self.fresh_version_after(other.version);
+        self.root = match (self.root.take(), other.root) {
+            (None, None) => None,
+            (Some(root), None) | (None, Some(root)) => Some(root),
+            (Some(left_root), Some(right_root)) => {
+                let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+                Some(Self::merge_owned_nodes(
+                    left_root,
+                    0,
+                    right_root,
+                    0,
+                    &mut key_buf,
+                    version,
+                    &mut resolver,
+                ))
+            }
+        };
+        self.advance_to(version);
+    }
+
+    /// Merge a borrowed `other` into this tree, leaving `other` unchanged.
+    ///
+    /// This replaces the tree with [`Self::union_with`]`(other, resolver)`: subtrees found only
+    /// in `other` are shared by `Arc` clone.
+    pub fn merge_from_ref<F>(&mut self, other: &Self, resolver: F)
+    where
+        F: FnMut(KeyType, &ValueType, &ValueType) -> ValueType,
+    {
+        *self = self.union_with(other, resolver);
+    }
+
+    /// Iterate over the entries written after `version`, in key order.
+    ///
+    /// Every write creates fresh copies of the nodes on the path from the root to the key, each
+    /// tagged with the writing version, so only subtrees whose top node is newer than `version`
+    /// are descended and the cost follows the paths written since then. Pass a
+    /// [`version`](Self::version) of this tree or of a tree it was snapshotted from.
+    ///
+    /// Entries are reported by the node holding them, so the result may also include an
+    /// unchanged entry whose node was copied because a key below it, or a key splitting its
+    /// compressed prefix, was written. Subtrees taken whole from another tree by a union or
+    /// merge keep their original versions. The tree keeps no tombstones, so removed keys are
+    /// not reported; use [`diff`](Self::diff) against a retained snapshot for those.
+    ///
+    /// ```rust
+    /// use rart::{ArrayKey, VersionedAdaptiveRadixTree};
+    ///
+    /// let mut tree = VersionedAdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+    /// for i in 0..1000u32 {
+    ///     tree.insert(i, i);
+    /// }
+    /// let checkpoint = tree.version();
+    /// tree.insert(5u32, 50);
+    /// tree.insert(1000u32, 1000);
+    ///
+    /// let changed: Vec<_> = tree.changed_since(checkpoint).map(|(_, value)| *value).collect();
+    /// assert_eq!(changed, vec![50, 1000]);
+    /// ```
+    pub fn changed_since(
+        &self,
+        version: u64,
+    ) -> ChangedSinceIter<'_, KeyType, KeyType::PartialType, ValueType, S> {
+        ChangedSinceIter::new(self.root.as_deref(), version)
+    }
+
+    /// Three-way merge two trees, `ours` and `theirs`, that were both forked from `base`.
+    ///
+    /// The trees are descended together, and any subtree that one side still shares with
+    /// `base` (by `Arc` pointer) is taken whole from the other side, so only paths changed on
+    /// both sides are visited. For a key whose value changed on just one side, or the same way
+    /// on both, that change wins. For a key changed differently on both sides, `resolver`
+    /// receives the key and the base, our, and their values (`None` where the key is absent)
+    /// and returns the merged value, or `None` to leave the key out.
+    ///
+    /// The result is a new version sharing every node neither side touched.
+    ///
+    /// ```rust
+    /// use rart::{ArrayKey, VersionedAdaptiveRadixTree};
+    ///
+    /// let mut base = VersionedAdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+    /// base.insert("a", 1);
+    /// base.insert("b", 2);
+    /// let mut ours = base.snapshot();
+    /// ours.insert("a", 10);
+    /// ours.insert("b", 20);
+    /// let mut theirs = base.snapshot();
+    /// theirs.insert("b", 200);
+    /// theirs.insert("c", 3);
+    ///
+    /// let merged = VersionedAdaptiveRadixTree::merge3(&base, &ours, &theirs, |_key, _, o, t| {
+    ///     Some(o.copied().unwrap_or(0).max(t.copied().unwrap_or(0)))
+    /// });
+    /// assert_eq!(merged.get("a"), Some(&10));
+    /// assert_eq!(merged.get("b"), Some(&200));
+    /// assert_eq!(merged.get("c"), Some(&3));
+    /// ```
+    pub fn merge3<F>(base: &Self, ours: &Self, theirs: &Self, mut resolver: F) -> Self
+    where
+        ValueType: PartialEq,
+        F: FnMut(
+            KeyType,
+            Option<&ValueType>,
+            Option<&ValueType>,
+            Option<&ValueType>,
+        ) -> Option<ValueType>,
+    {
+        let version = ours.fresh_version_after(base.version.max(theirs.version));
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        let root = Self::merge3_nodes(
+            base.root.as_ref().map(|root| (root, 0)),
+            ours.root.as_ref().map(|root| (root, 0)),
+            theirs.root.as_ref().map(|root| (root, 0)),
+            &mut key_buf,
+            version,
+            &mut resolver,
+        );
+
+        Self {
+            root,
+            version,
+            parent_version: Some(ours.version),
+            versions: ours.versions.clone(),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Iterate over the differences between this tree and `older`, in key order.
+    ///
+    /// Both trees are descended together, and wherever they point at the same shared node
+    /// the whole subtree is skipped. Diffing two snapshots that differ in a handful of keys
+    /// therefore costs time proportional to those changes and the tree depth, not its size.
+    /// Values at keys on copied paths are compared with `PartialEq`, so only real
+    /// modifications are reported.
+    ///
+    /// ```rust
+    /// use rart::{ArrayKey, VersionedAdaptiveRadixTree};
+    /// use rart::versioned_tree::Change;
+    ///
+    /// let mut older = VersionedAdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+    /// for i in 0..1000u32 {
+    ///     older.insert(i, i);
+    /// }
+    /// let mut newer = older.snapshot();
+    /// newer.insert(5u32, 50);
+    /// newer.remove(7u32);
+    /// newer.insert(1000u32, 1000);
+    ///
+    /// let changes: Vec<_> = newer.diff(&older).collect();
+    /// assert_eq!(
+    ///     changes,
+    ///     vec![
+    ///         Change::Modified { key: ArrayKey::from(5u32), old: &5, new: &50 },
+    ///         Change::Removed { key: ArrayKey::from(7u32), value: &7 },
+    ///         Change::Added { key: ArrayKey::from(1000u32), value: &1000 },
+    ///     ]
+    /// );
+    /// ```
+    pub fn diff<'a>(
+        &'a self,
+        older: &'a Self,
+    ) -> VersionedDiffIter<'a, KeyType, KeyType::PartialType, ValueType, S>
+    where
+        ValueType: PartialEq,
+    {
+        VersionedDiffIter::new(self.root.as_ref(), older.root.as_ref())
+    }
+
     /// Iterate over stored key/value pairs whose keys are prefixes of `key`.
     ///
     /// Matches are yielded from shortest to longest. This differs from
@@ -391,7 +1291,7 @@ where
     pub fn prefix_match_iter<Key>(
         &self,
         key: Key,
-    ) -> VersionedPrefixMatchIter<'_, KeyType, KeyType::PartialType, ValueType>
+    ) -> VersionedPrefixMatchIter<'_, KeyType, KeyType::PartialType, ValueType, S>
     where
         Key: Into<KeyType>,
     {
@@ -406,7 +1306,7 @@ where
     pub fn prefix_match_iter_k(
         &self,
         key: &KeyType,
-    ) -> VersionedPrefixMatchIter<'_, KeyType, KeyType::PartialType, ValueType> {
+    ) -> VersionedPrefixMatchIter<'_, KeyType, KeyType::PartialType, ValueType, S> {
         VersionedPrefixMatchIter::new(self.root.as_deref(), key.clone())
     }
 
@@ -445,7 +1345,7 @@ where
     pub fn prefix_iter<Key>(
         &self,
         prefix: Key,
-    ) -> VersionedIter<'_, KeyType, KeyType::PartialType, ValueType>
+    ) -> VersionedIter<'_, KeyType, KeyType::PartialType, ValueType, S>
     where
         Key: Into<KeyType>,
     {
@@ -456,7 +1356,7 @@ where
     pub fn prefix_iter_k(
         &self,
         prefix: &KeyType,
-    ) -> VersionedIter<'_, KeyType, KeyType::PartialType, ValueType> {
+    ) -> VersionedIter<'_, KeyType, KeyType::PartialType, ValueType, S> {
         let Some(root) = self.root.as_deref() else {
             return VersionedIter::empty();
         };
@@ -570,7 +1470,7 @@ where
     }
 
     /// Create an iterator over key-value pairs within a specified range.
-    pub fn range<'a, R>(&'a self, range: R) -> VersionedRange<'a, KeyType, ValueType>
+    pub fn range<'a, R>(&'a self, range: R) -> VersionedRange<'a, KeyType, ValueType, S>
     where
         R: RangeBounds<KeyType> + 'a,
     {
@@ -661,19 +1561,22 @@ where
     ///
     /// [`insert_and_replace_k`]: Self::insert_and_replace_k
     pub fn insert_k(&mut self, key: &KeyType, value: ValueType) -> bool {
-        self.version += 1;
+        let version = self.advance_version();
+        self.insert_at(key, value, version)
+    }
 
+    /// Insert with nodes written at `version`, which the caller has already allocated.
+    fn insert_at(&mut self, key: &KeyType, value: ValueType, version: u64) -> bool {
         let Some(root) = self.root.take() else {
-            self.root = Some(Arc::new(VersionedNode::new_leaf(
+            self.root = Some(S::new(VersionedNode::new_leaf(
                 key.to_partial(0),
                 value,
-                self.version,
+                version,
             )));
             return false;
         };
 
-        let (new_root, was_replaced) =
-            Self::insert_recurse(root, key, value, 0, self.version, None);
+        let (new_root, was_replaced) = Self::insert_recurse(root, key, value, 0, version, None);
         self.root = Some(new_root);
         was_replaced
     }
@@ -750,10 +1653,10 @@ where
     ///
     /// [`insert_k`]: Self::insert_k
     pub fn insert_and_replace_k(&mut self, key: &KeyType, value: ValueType) -> Option<ValueType> {
-        self.version += 1;
+        self.advance_version();
 
         let Some(root) = self.root.take() else {
-            self.root = Some(Arc::new(VersionedNode::new_leaf(
+            self.root = Some(S::new(VersionedNode::new_leaf(
                 key.to_partial(0),
                 value,
                 self.version,
@@ -786,7 +1689,7 @@ where
     pub fn remove_k(&mut self, key: &KeyType) -> Option<ValueType> {
         self.get_k(key)?;
 
-        self.version += 1;
+        self.advance_version();
         let root = self
             .root
             .take()
@@ -799,7 +1702,7 @@ where
                 return None;
             }
 
-            match Arc::try_unwrap(root) {
+            match S::try_unwrap(root) {
                 Ok(mut owned_root) => {
                     return owned_root.value.take();
                 }
@@ -814,7 +1717,7 @@ where
 
         if root.prefix.len() == key.length_at(0) {
             let new_root = Self::ensure_cow_node(root, self.version);
-            let mut new_root = match Arc::try_unwrap(new_root) {
+            let mut new_root = match S::try_unwrap(new_root) {
                 Ok(owned) => owned,
                 Err(_) => panic!("ensure_cow_node should have given us exclusive ownership"),
             };
@@ -822,7 +1725,7 @@ where
             if new_root.num_children() == 0 && new_root.value.is_none() {
                 self.root = None;
             } else {
-                self.root = Some(Arc::new(new_root));
+                self.root = Some(S::new(new_root));
             }
             return removed;
         }
@@ -863,7 +1766,13 @@ where
             return false;
         }
 
-        self.version += 1;
+        let version = self.advance_version();
+        self.delete_at(key, version);
+        true
+    }
+
+    /// Delete a key known to be present, with nodes written at `version`.
+    fn delete_at(&mut self, key: &KeyType, version: u64) {
         let root = self
             .root
             .take()
@@ -871,12 +1780,12 @@ where
 
         if root.is_leaf() {
             self.root = None;
-            return true;
+            return;
         }
 
         if root.prefix.len() == key.length_at(0) {
-            let new_root = Self::ensure_cow_node(root, self.version);
-            let mut new_root = match Arc::try_unwrap(new_root) {
+            let new_root = Self::ensure_cow_node(root, version);
+            let mut new_root = match S::try_unwrap(new_root) {
                 Ok(owned) => owned,
                 Err(_) => panic!("ensure_cow_node should have given us exclusive ownership"),
             };
@@ -884,17 +1793,16 @@ where
             if new_root.num_children() == 0 {
                 self.root = None;
             } else {
-                self.root = Some(Arc::new(new_root));
+                self.root = Some(S::new(new_root));
             }
-            return true;
+            return;
         }
 
-        let new_root = Self::delete_recurse(root, key, 0, self.version)
+        let new_root = Self::delete_recurse(root, key, 0, version)
             .expect("prechecked key should be deletable");
         self.root = new_root.filter(|root| {
             !(root.is_inner() && root.num_children() == 0 && root.value().is_none())
         });
-        true
     }
 
     /// Update a value slot by key.
@@ -909,11 +1817,24 @@ where
 
     /// Update a value slot by key reference.
     pub fn update_k<F>(&mut self, key: &KeyType, update: F) -> bool
+    where
+        F: FnOnce(Slot<'_, ValueType>) -> SlotUpdate<ValueType>,
+    {
+        let version = self.fresh_version();
+        let changed = self.update_at(key, version, update);
+        if changed {
+            self.advance_to(version);
+        }
+        changed
+    }
+
+    /// Update a value slot with any changed nodes written at `version`, returning whether the
+    /// tree changed. The tree's own version is left to the caller.
+    fn update_at<F>(&mut self, key: &KeyType, version: u64, update: F) -> bool
     where
         F: FnOnce(Slot<'_, ValueType>) -> SlotUpdate<ValueType>,
     {
         let mut update = Some(update);
-        let next_version = self.version + 1;
 
         let Some(root) = self.root.take() else {
             let action =
@@ -922,11 +1843,10 @@ where
                     .expect("update callback should be called once")(Slot::Vacant);
             return match action {
                 SlotUpdate::Insert(value) => {
-                    self.version = next_version;
-                    self.root = Some(Arc::new(VersionedNode::new_leaf(
+                    self.root = Some(S::new(VersionedNode::new_leaf(
                         key.to_partial(0),
                         value,
-                        self.version,
+                        version,
                     )));
                     true
                 }
@@ -934,15 +1854,56 @@ where
             };
         };
 
-        let (new_root, changed) = Self::update_recurse(root, key, 0, next_version, &mut update);
+        let (new_root, changed) = Self::update_recurse(root, key, 0, version, &mut update);
         debug_assert!(update.is_none());
-        if changed {
-            self.version = next_version;
-        }
         self.root = new_root;
         changed
     }
 
+    /// Apply every operation of `batch` as one mutation.
+    ///
+    /// Operations are sorted by key (keeping the order they were added in for each key) and
+    /// applied in a single descent that splits them by child edge as it goes, so each node on
+    /// their paths is visited once and, if it is shared with another snapshot and changes,
+    /// copied once, all under one new version. If a fallible update returns an error, the tree
+    /// is restored to its state before the batch and the error is returned.
+    pub fn apply_batch<E>(
+        &mut self,
+        batch: WriteBatch<'_, KeyType, ValueType, E>,
+    ) -> Result<(), E> {
+        let mut ops: Vec<_> = batch
+            .ops
+            .into_iter()
+            .map(|(key, op)| (key, Some(op)))
+            .collect();
+        ops.sort_by(|(left, _), (right, _)| left.as_ref().cmp(right.as_ref()));
+
+        // Only updates can fail. Holding the original root keeps every node the batch touches
+        // shared, so they are copied rather than changed in place and survive for a rollback.
+        let has_updates = ops
+            .iter()
+            .any(|(_, op)| matches!(op, Some(BatchOp::Update(_))));
+        let original_root = has_updates.then(|| self.root.clone());
+        let version = self.fresh_version();
+        let result = match self.root.take() {
+            Some(root) => Self::batch_node(root, 0, &mut ops, version),
+            None => Self::batch_insert_absent(None, 0, &mut ops, version),
+        };
+        match result {
+            Ok((root, changed)) => {
+                self.root = root;
+                if changed {
+                    self.advance_to(version);
+                }
+                Ok(())
+            }
+            Err(err) => {
+                self.root = original_root.expect("original root is kept for updates");
+                Err(err)
+            }
+        }
+    }
+
     /// Fallibly update a value slot by key.
     #[inline]
     pub fn try_update<KV, E, F>(&mut self, key: KV, update: F) -> Result<bool, E>
@@ -1006,10 +1967,112 @@ where
     }
 
     /// Get the current version number of this tree.
+    ///
+    /// A tree created with [`new`](Self::new) and every tree derived from it (snapshots, clones,
+    /// and union or merge results with it on the left) share one version counter, so versions
+    /// are unique within that family and increase over time. Each mutation, snapshot, and merge
+    /// result gets a fresh one, so equal versions within a family mean the same tree state (as
+    /// with a clone). A union or merge result is also newer than every tree it combines.
     pub fn version(&self) -> u64 {
         self.version
     }
 
+    /// The version this tree's current version was derived from: the tree a snapshot was
+    /// taken of, the state before the latest mutation, or the left-hand tree of a union or
+    /// merge. `None` for a tree created with [`new`](Self::new) and not yet modified.
+    pub fn parent_version(&self) -> Option<u64> {
+        self.parent_version
+    }
+
+    /// Report how much memory this snapshot holds and how much of it is shared.
+    ///
+    /// A node counts as shared when more than one tree or parent node references it. Dropping
+    /// the snapshot frees only the nodes it reaches through uniquely referenced nodes all the
+    /// way from the root; a clone of this tree handle shares the root, so nothing would be
+    /// freed while it lives.
+    ///
+    /// ```rust
+    /// use rart::{ArrayKey, VersionedAdaptiveRadixTree};
+    ///
+    /// let mut tree = VersionedAdaptiveRadixTree::<ArrayKey<16>, i32>::new();
+    /// for i in 0..100 {
+    ///     tree.insert(i, i);
+    /// }
+    /// let stats = tree.snapshot_stats();
+    /// assert_eq!(stats.shared_nodes, 0);
+    /// assert_eq!(stats.freed_on_drop_bytes, stats.total_bytes);
+    ///
+    /// let snapshot = tree.snapshot();
+    /// tree.insert(100, 100);
+    /// let stats = tree.snapshot_stats();
+    /// assert!(stats.shared_nodes > 0);
+    /// assert!(stats.freed_on_drop_bytes < stats.total_bytes);
+    /// # drop(snapshot);
+    /// ```
+    pub fn snapshot_stats(&self) -> SnapshotStats {
+        let mut stats = SnapshotStats::default();
+        if let Some(root) = &self.root {
+            Self::snapshot_stats_recurse(root, true, &mut stats);
+        }
+        stats
+    }
+
+    /// Report how many nodes this tree has in common with `other`, typically a snapshot of
+    /// it or a tree it was snapshotted from.
+    ///
+    /// Both tries are descended together, and a node that is the same allocation on both
+    /// sides is counted with its whole subtree without descending further, so the cost follows
+    /// the paths where the trees differ, like [`diff`](Self::diff). Nodes shared at different
+    /// keys in the two trees are not counted.
+    ///
+    /// ```rust
+    /// use rart::{ArrayKey, VersionedAdaptiveRadixTree};
+    ///
+    /// let mut tree = VersionedAdaptiveRadixTree::<ArrayKey<16>, i32>::new();
+    /// for i in 0..100 {
+    ///     tree.insert(i, i);
+    /// }
+    /// let snapshot = tree.snapshot();
+    /// let total = tree.snapshot_stats().total_nodes;
+    /// assert_eq!(tree.shared_with(&snapshot).shared_nodes, total);
+    ///
+    /// tree.insert(100, 100);
+    /// let sharing = tree.shared_with(&snapshot);
+    /// assert!(sharing.shared_nodes > 0 && sharing.shared_nodes < total);
+    /// ```
+    pub fn shared_with(&self, other: &Self) -> SharingStats {
+        let mut stats = SharingStats::default();
+        let (Some(root), Some(other_root)) = (&self.root, &other.root) else {
+            return stats;
+        };
+        Self::shared_with_recurse(root, 0, other_root, 0, &mut stats);
+        stats
+    }
+
+    /// Move to a fresh version derived from the current one.
+    fn advance_version(&mut self) -> u64 {
+        let version = self.fresh_version();
+        self.advance_to(version);
+        version
+    }
+
+    fn advance_to(&mut self, version: u64) {
+        self.parent_version = Some(self.version);
+        self.version = version;
+    }
+
+    /// Take the next version from this tree's family counter.
+    fn fresh_version(&self) -> u64 {
+        self.versions.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Take a version newer than both this family's and `other`, for a tree that may adopt
+    /// nodes written at versions up to `other` by another family.
+    fn fresh_version_after(&self, other: u64) -> u64 {
+        self.versions.fetch_max(other + 1, Ordering::Relaxed);
+        self.fresh_version()
+    }
+
     /// Convert this versioned tree into a regular AdaptiveRadixTree.
     ///
     /// This method attempts to avoid cloning when possible:
@@ -1051,7 +2114,7 @@ where
     /// Convert a versioned node to an unversioned node.
     /// Uses fast path when possible (unique ownership), slow path when shared.
     fn convert_to_unversioned_node(
-        node: Arc<VersionedNode<KeyType::PartialType, ValueType>>,
+        node: NodePtr<KeyType::PartialType, ValueType, S>,
     ) -> crate::node::DefaultNode<KeyType::PartialType, ValueType> {
         use crate::mapping::{
             direct_mapping::DirectMapping, indexed_mapping::IndexedMapping,
@@ -1059,7 +2122,7 @@ where
         };
         use crate::node::{Content, DefaultNode};
 
-        match Arc::try_unwrap(node) {
+        match S::try_unwrap(node) {
             Ok(owned_node) => {
                 // Fast path: we have unique ownership, convert in-place
                 let VersionedNode {
@@ -1118,7 +2181,7 @@ where
                         let mut new_km = SortedKeyedMapping::new();
                         for (key, child) in km.iter() {
                             let converted_child =
-                                Self::convert_to_unversioned_node(Arc::clone(child));
+                                Self::convert_to_unversioned_node(Clone::clone(child));
                             new_km.add_child(key, converted_child);
                         }
                         Content::Node4(Box::new(new_km))
@@ -1127,7 +2190,7 @@ where
                         let mut new_km = SortedKeyedMapping::new();
                         for (key, child) in km.iter() {
                             let converted_child =
-                                Self::convert_to_unversioned_node(Arc::clone(child));
+                                Self::convert_to_unversioned_node(Clone::clone(child));
                             new_km.add_child(key, converted_child);
                         }
                         Content::Node16(Box::new(new_km))
@@ -1136,7 +2199,7 @@ where
                         let mut new_km = IndexedMapping::new();
                         for (key, child) in km.iter() {
                             let converted_child =
-                                Self::convert_to_unversioned_node(Arc::clone(child));
+                                Self::convert_to_unversioned_node(Clone::clone(child));
                             new_km.add_child(key, converted_child);
                         }
                         Content::Node48(Box::new(new_km))
@@ -1145,7 +2208,7 @@ where
                         let mut new_km = DirectMapping::new();
                         for (key, child) in km.iter() {
                             let converted_child =
-                                Self::convert_to_unversioned_node(Arc::clone(child));
+                                Self::convert_to_unversioned_node(Clone::clone(child));
                             new_km.add_child(key, converted_child);
                         }
                         Content::Node256(Box::new(new_km))
@@ -1162,7 +2225,7 @@ where
     }
 }
 
-impl<P: Partial, V> VersionedNode<P, V> {
+impl<P: Partial, V, S: SharedPtr> VersionedNode<P, V, S> {
     /// Create a new leaf node.
     pub fn new_leaf(prefix: P, value: V, version: u64) -> Self {
         Self {
@@ -1199,7 +2262,7 @@ impl<P: Partial, V> VersionedNode<P, V> {
     }
 
     /// Seek a child by key.
-    pub fn seek_child(&self, key: u8) -> Option<&Arc<VersionedNode<P, V>>> {
+    pub fn seek_child(&self, key: u8) -> Option<&NodePtr<P, V, S>> {
         match &self.content {
             VersionedContent::Node4(km) => km.seek_child(key),
             VersionedContent::Node16(km) => km.seek_child(key),
@@ -1209,7 +2272,7 @@ impl<P: Partial, V> VersionedNode<P, V> {
         }
     }
 
-    fn seek_child_mut(&mut self, key: u8) -> Option<&mut Arc<VersionedNode<P, V>>> {
+    fn seek_child_mut(&mut self, key: u8) -> Option<&mut NodePtr<P, V, S>> {
         match &mut self.content {
             VersionedContent::Node4(km) => km.seek_child_mut(key),
             VersionedContent::Node16(km) => km.seek_child_mut(key),
@@ -1230,6 +2293,29 @@ impl<P: Partial, V> VersionedNode<P, V> {
         }
     }
 
+    /// Get the maximum number of children this node can hold before growing.
+    pub fn capacity(&self) -> usize {
+        match &self.content {
+            VersionedContent::Node4(_) => 4,
+            VersionedContent::Node16(_) => 16,
+            VersionedContent::Node48(_) => 48,
+            VersionedContent::Node256(_) => 256,
+            VersionedContent::Empty => 0,
+        }
+    }
+
+    /// Bytes taken by this node and its child table, not counting the children themselves.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        let table = match &self.content {
+            VersionedContent::Node4(km) => size_of_val(&**km),
+            VersionedContent::Node16(km) => size_of_val(&**km),
+            VersionedContent::Node48(im) => size_of_val(&**im),
+            VersionedContent::Node256(dm) => size_of_val(&**dm),
+            VersionedContent::Empty => 0,
+        };
+        size_of::<Self>() + table
+    }
+
     /// Check if this node is full and needs to grow.
     pub fn is_full(&self) -> bool {
         match &self.content {
@@ -1255,7 +2341,7 @@ impl<P: Partial, V> VersionedNode<P, V> {
                     // Grow Node4 to Node16
                     let mut new_km = SortedKeyedMapping::new();
                     for (key, child) in km.iter() {
-                        new_km.add_child(key, Arc::clone(child));
+                        new_km.add_child(key, Clone::clone(child));
                     }
                     VersionedContent::Node16(Box::new(new_km))
                 }
@@ -1263,7 +2349,7 @@ impl<P: Partial, V> VersionedNode<P, V> {
                     // Grow Node16 to Node48
                     let mut new_km = IndexedMapping::new();
                     for (key, child) in km.iter() {
-                        new_km.add_child(key, Arc::clone(child));
+                        new_km.add_child(key, Clone::clone(child));
                     }
                     VersionedContent::Node48(Box::new(new_km))
                 }
@@ -1271,7 +2357,7 @@ impl<P: Partial, V> VersionedNode<P, V> {
                     // Grow Node48 to Node256
                     let mut new_km = DirectMapping::new();
                     for (key, child) in km.iter() {
-                        new_km.add_child(key, Arc::clone(child));
+                        new_km.add_child(key, Clone::clone(child));
                     }
                     VersionedContent::Node256(Box::new(new_km))
                 }
@@ -1301,7 +2387,7 @@ impl<P: Partial, V> VersionedNode<P, V> {
                     // Manually clone Node4 mapping
                     let mut new_km = SortedKeyedMapping::new();
                     for (key, child) in km.iter() {
-                        new_km.add_child(key, Arc::clone(child));
+                        new_km.add_child(key, Clone::clone(child));
                     }
                     VersionedContent::Node4(Box::new(new_km))
                 }
@@ -1309,7 +2395,7 @@ impl<P: Partial, V> VersionedNode<P, V> {
                     // Manually clone Node16 mapping
                     let mut new_km = SortedKeyedMapping::new();
                     for (key, child) in km.iter() {
-                        new_km.add_child(key, Arc::clone(child));
+                        new_km.add_child(key, Clone::clone(child));
                     }
                     VersionedContent::Node16(Box::new(new_km))
                 }
@@ -1324,7 +2410,7 @@ impl<P: Partial, V> VersionedNode<P, V> {
         }
     }
 
-    fn add_child(&mut self, key: u8, child: Arc<VersionedNode<P, V>>)
+    fn add_child(&mut self, key: u8, child: NodePtr<P, V, S>)
     where
         P: Clone,
         V: Clone,
@@ -1348,7 +2434,7 @@ impl<P: Partial, V> VersionedNode<P, V> {
         }
     }
 
-    fn delete_child(&mut self, key: u8) -> Option<Arc<VersionedNode<P, V>>> {
+    fn delete_child(&mut self, key: u8) -> Option<NodePtr<P, V, S>> {
         match &mut self.content {
             VersionedContent::Node4(km) => km.delete_child(key),
             VersionedContent::Node16(km) => km.delete_child(key),
@@ -1358,7 +2444,7 @@ impl<P: Partial, V> VersionedNode<P, V> {
         }
     }
 
-    pub(crate) fn iter(&self) -> VersionedNodeIter<'_, P, V> {
+    pub(crate) fn iter(&self) -> VersionedNodeIter<'_, P, V, S> {
         match &self.content {
             VersionedContent::Node4(n) => VersionedNodeIter::Node4(n.iter()),
             VersionedContent::Node16(n) => VersionedNodeIter::Node16(n.iter()),
@@ -1367,9 +2453,378 @@ impl<P: Partial, V> VersionedNode<P, V> {
             VersionedContent::Empty => VersionedNodeIter::Empty,
         }
     }
+
+    /// The set of edge bytes this node has children under.
+    pub(crate) fn child_key_set(&self) -> Bitset64<4> {
+        match &self.content {
+            VersionedContent::Node4(km) => km.key_set(),
+            VersionedContent::Node16(km) => km.key_set(),
+            VersionedContent::Node48(im) => im.child_ptr_indexes.bitset.clone(),
+            VersionedContent::Node256(dm) => dm.children.bitset.clone(),
+            VersionedContent::Empty => Bitset64::new(),
+        }
+    }
+
+    /// Takes the node apart into its prefix, value, and children in edge-byte order.
+    pub(crate) fn into_parts(self) -> VersionedParts<P, V, S> {
+        let children = match self.content {
+            VersionedContent::Node4(km) => km.into_iter().collect(),
+            VersionedContent::Node16(km) => km.into_iter().collect(),
+            VersionedContent::Node48(im) => im.into_iter().collect(),
+            VersionedContent::Node256(dm) => dm.into_iter().collect(),
+            VersionedContent::Empty => Vec::new(),
+        };
+        (self.prefix, self.value, children)
+    }
+
+    /// Iterate over the children as the shared pointers the node holds.
+    pub(crate) fn iter_arcs(&self) -> impl Iterator<Item = (u8, &NodePtr<P, V, S>)> {
+        let mut iter = self.iter();
+        std::iter::from_fn(move || iter.next_arc())
+    }
+
+    /// Build a node from its parts. `children` must be sorted by edge byte; a node without
+    /// children becomes a leaf and must then carry a value.
+    pub(crate) fn from_sorted_children(
+        prefix: P,
+        value: Option<V>,
+        children: Vec<(u8, NodePtr<P, V, S>)>,
+        version: u64,
+    ) -> Self {
+        let content = match children.len() {
+            0 => {
+                debug_assert!(value.is_some(), "leaf nodes must hold a value");
+                VersionedContent::Empty
+            }
+            1..=4 => {
+                let mut km = SortedKeyedMapping::new();
+                for (key, child) in children {
+                    km.add_child_sorted(key, child);
+                }
+                VersionedContent::Node4(Box::new(km))
+            }
+            5..=16 => {
+                let mut km = SortedKeyedMapping::new();
+                for (key, child) in children {
+                    km.add_child_sorted(key, child);
+                }
+                VersionedContent::Node16(Box::new(km))
+            }
+            17..=48 => {
+                let mut im = IndexedMapping::new();
+                for (key, child) in children {
+                    im.add_child_sorted(key, child);
+                }
+                VersionedContent::Node48(Box::new(im))
+            }
+            _ => {
+                let mut dm = DirectMapping::new();
+                for (key, child) in children {
+                    dm.add_child(key, child);
+                }
+                VersionedContent::Node256(Box::new(dm))
+            }
+        };
+
+        Self {
+            prefix,
+            value,
+            content,
+            version,
+        }
+    }
+}
+
+impl<P: Partial, V, S: SharedPtr> NodeView for VersionedNode<P, V, S> {
+    type Value = V;
+
+    fn view_prefix(&self) -> &[u8] {
+        self.prefix.as_ref()
+    }
+
+    fn view_value(&self) -> Option<&V> {
+        self.value()
+    }
+
+    fn view_num_children(&self) -> usize {
+        self.num_children()
+    }
+
+    fn view_child(&self, edge: u8) -> Option<&Self> {
+        self.seek_child(edge).map(|child| child.as_ref())
+    }
+
+    fn view_child_key_set(&self) -> Bitset64<4> {
+        self.child_key_set()
+    }
+
+    fn view_children(&self) -> impl Iterator<Item = (u8, &Self)> {
+        self.iter()
+    }
+}
+
+impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V: PartialEq, S: SharedPtr>
+    VersionedDiffIter<'a, K, P, V, S>
+{
+    fn new(new_root: Option<&'a NodePtr<P, V, S>>, old_root: Option<&'a NodePtr<P, V, S>>) -> Self {
+        let frame = match (new_root, old_root) {
+            (Some(new), Some(old)) => Some(DiffFrame::Paired {
+                new,
+                new_offset: 0,
+                old,
+                old_offset: 0,
+                key_len: 0,
+            }),
+            (Some(node), None) | (None, Some(node)) => Some(DiffFrame::OneSided {
+                node,
+                offset: 0,
+                added: new_root.is_some(),
+                key_len: 0,
+            }),
+            (None, None) => None,
+        };
+        Self {
+            stack: frame.into_iter().collect(),
+            key_buf: Vec::with_capacity(K::MAXIMUM_SIZE.unwrap_or(64)),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    fn one_sided_change(&self, added: bool, value: &'a V) -> Change<K, &'a V> {
+        let key = K::new_from_slice(&self.key_buf);
+        if added {
+            Change::Added { key, value }
+        } else {
+            Change::Removed { key, value }
+        }
+    }
+
+    fn push_one_sided(&mut self, node: &'a VersionedNode<P, V, S>, offset: usize, added: bool) {
+        self.stack.push(DiffFrame::OneSided {
+            node,
+            offset,
+            added,
+            key_len: self.key_buf.len(),
+        });
+    }
+
+    fn visit_one_sided(
+        &mut self,
+        node: &'a VersionedNode<P, V, S>,
+        offset: usize,
+        added: bool,
+    ) -> Option<Change<K, &'a V>> {
+        self.key_buf
+            .extend_from_slice(&node.prefix.as_ref()[offset..]);
+        let start = self.stack.len();
+        for (_, child) in node.iter() {
+            self.push_one_sided(child, 0, added);
+        }
+        self.stack[start..].reverse();
+        node.value()
+            .map(|value| self.one_sided_change(added, value))
+    }
+
+    fn visit_paired(
+        &mut self,
+        new: &'a NodePtr<P, V, S>,
+        mut new_offset: usize,
+        old: &'a NodePtr<P, V, S>,
+        mut old_offset: usize,
+    ) -> Option<Change<K, &'a V>> {
+        // Both sides share this subtree, so nothing below it changed.
+        if new_offset == old_offset && S::ptr_eq(new, old) {
+            return None;
+        }
+
+        let new_prefix = new.prefix.as_ref();
+        let old_prefix = old.prefix.as_ref();
+        while new_offset < new_prefix.len() && old_offset < old_prefix.len() {
+            let new_byte = new_prefix[new_offset];
+            let old_byte = old_prefix[old_offset];
+            if new_byte != old_byte {
+                // The subtrees diverge and share no keys. The one later in key order goes
+                // on the stack first.
+                if new_byte < old_byte {
+                    self.push_one_sided(old, old_offset, false);
+                    self.push_one_sided(new, new_offset, true);
+                } else {
+                    self.push_one_sided(new, new_offset, true);
+                    self.push_one_sided(old, old_offset, false);
+                }
+                return None;
+            }
+            self.key_buf.push(new_byte);
+            new_offset += 1;
+            old_offset += 1;
+        }
+
+        let key_len = self.key_buf.len();
+        let start = self.stack.len();
+
+        // Only one node ends at this key. Its value and every child except the one continuing
+        // the other node's prefix exist on that side alone.
+        if new_offset < new_prefix.len() || old_offset < old_prefix.len() {
+            let (long, long_offset, short, long_is_new) = if new_offset < new_prefix.len() {
+                (new, new_offset, old, true)
+            } else {
+                (old, old_offset, new, false)
+            };
+            let edge = long.prefix.as_ref()[long_offset];
+            let mut placed = false;
+            for (child_edge, child) in short.iter_arcs() {
+                if !placed && child_edge >= edge {
+                    placed = true;
+                    if child_edge == edge {
+                        let (new, new_offset, old, old_offset) = if long_is_new {
+                            (long, long_offset, child, 0)
+                        } else {
+                            (child, 0, long, long_offset)
+                        };
+                        self.stack.push(DiffFrame::Paired {
+                            new,
+                            new_offset,
+                            old,
+                            old_offset,
+                            key_len,
+                        });
+                        continue;
+                    }
+                    self.push_one_sided(long, long_offset, long_is_new);
+                }
+                self.push_one_sided(child, 0, !long_is_new);
+            }
+            if !placed {
+                self.push_one_sided(long, long_offset, long_is_new);
+            }
+            self.stack[start..].reverse();
+            return short
+                .value()
+                .map(|value| self.one_sided_change(!long_is_new, value));
+        }
+
+        let mut new_children = new.iter_arcs().peekable();
+        let mut old_children = old.iter_arcs().peekable();
+        loop {
+            match (new_children.peek(), old_children.peek()) {
+                (None, None) => break,
+                (Some(n), Some(o)) if n.0 == o.0 => {
+                    let (_, new_child) = new_children.next().unwrap();
+                    let (_, old_child) = old_children.next().unwrap();
+                    self.stack.push(DiffFrame::Paired {
+                        new: new_child,
+                        new_offset: 0,
+                        old: old_child,
+                        old_offset: 0,
+                        key_len,
+                    });
+                }
+                (Some(n), o) if o.is_none_or(|o| n.0 < o.0) => {
+                    let (_, child) = new_children.next().unwrap();
+                    self.push_one_sided(child, 0, true);
+                }
+                _ => {
+                    let (_, child) = old_children.next().unwrap();
+                    self.push_one_sided(child, 0, false);
+                }
+            }
+        }
+        self.stack[start..].reverse();
+
+        match (new.value(), old.value()) {
+            (Some(new_value), Some(old_value)) if new_value != old_value => {
+                Some(Change::Modified {
+                    key: K::new_from_slice(&self.key_buf),
+                    old: old_value,
+                    new: new_value,
+                })
+            }
+            (Some(value), None) => Some(self.one_sided_change(true, value)),
+            (None, Some(value)) => Some(self.one_sided_change(false, value)),
+            _ => None,
+        }
+    }
+}
+
+impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V: PartialEq, S: SharedPtr> Iterator
+    for VersionedDiffIter<'a, K, P, V, S>
+{
+    type Item = Change<K, &'a V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(frame) = self.stack.pop() {
+            let change = match frame {
+                DiffFrame::Paired {
+                    new,
+                    new_offset,
+                    old,
+                    old_offset,
+                    key_len,
+                } => {
+                    self.key_buf.truncate(key_len);
+                    self.visit_paired(new, new_offset, old, old_offset)
+                }
+                DiffFrame::OneSided {
+                    node,
+                    offset,
+                    added,
+                    key_len,
+                } => {
+                    self.key_buf.truncate(key_len);
+                    self.visit_one_sided(node, offset, added)
+                }
+            };
+            if change.is_some() {
+                return change;
+            }
+        }
+        None
+    }
+}
+
+impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V, S: SharedPtr>
+    ChangedSinceIter<'a, K, P, V, S>
+{
+    fn new(root: Option<&'a VersionedNode<P, V, S>>, since: u64) -> Self {
+        Self {
+            since,
+            stack: root.map(|root| (root, 0)).into_iter().collect(),
+            key_buf: Vec::with_capacity(K::MAXIMUM_SIZE.unwrap_or(64)),
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V, S: SharedPtr> Iterator
+    for ChangedSinceIter<'a, K, P, V, S>
+{
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, key_len)) = self.stack.pop() {
+            // Writes copy every node on the path to the root, so a subtree whose top node is
+            // no newer than `since` was not written after it.
+            if node.version <= self.since {
+                continue;
+            }
+            self.key_buf.truncate(key_len);
+            self.key_buf.extend_from_slice(node.prefix.as_ref());
+            let start = self.stack.len();
+            for (_, child) in node.iter() {
+                self.stack.push((child, self.key_buf.len()));
+            }
+            self.stack[start..].reverse();
+            if let Some(value) = node.value() {
+                return Some((K::new_from_slice(&self.key_buf), value));
+            }
+        }
+        None
+    }
 }
 
-impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> VersionedIterInner<'a, K, P, V> {
+impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V, S: SharedPtr>
+    VersionedIterInner<'a, K, P, V, S>
+{
     #[inline]
     fn key_order(lhs: &K, rhs: &K) -> std::cmp::Ordering {
         let lhs_len = lhs.length_at(0);
@@ -1384,7 +2839,7 @@ impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> VersionedIterInner<'a
         lhs_len.cmp(&rhs_len)
     }
 
-    fn from_node_and_key(node: &'a VersionedNode<P, V>, cur_key: K) -> Self {
+    fn from_node_and_key(node: &'a VersionedNode<P, V, S>, cur_key: K) -> Self {
         Self {
             node_iter_stack: vec![(
                 cur_key.length_at(0),
@@ -1395,11 +2850,11 @@ impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> VersionedIterInner<'a
         }
     }
 
-    fn new(node: &'a VersionedNode<P, V>) -> Self {
+    fn new(node: &'a VersionedNode<P, V, S>) -> Self {
         Self::from_node_and_key(node, K::new_from_partial(&node.prefix))
     }
 
-    fn new_with_start_bound(node: &'a VersionedNode<P, V>, start_bound: Bound<K>) -> Self {
+    fn new_with_start_bound(node: &'a VersionedNode<P, V, S>, start_bound: Bound<K>) -> Self {
         let seek_key = match &start_bound {
             Bound::Included(key) | Bound::Excluded(key) => Some(key),
             Bound::Unbounded => None,
@@ -1424,10 +2879,10 @@ impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> VersionedIterInner<'a
     }
 
     fn build_positioned_stack(
-        node: &'a VersionedNode<P, V>,
+        node: &'a VersionedNode<P, V, S>,
         seek_key: &K,
         depth: usize,
-    ) -> Vec<(usize, VersionedIterFrameIter<'a, P, V>)> {
+    ) -> Vec<(usize, VersionedIterFrameIter<'a, P, V, S>)> {
         let prefix_common = node.prefix.prefix_length_key(seek_key, depth);
         if prefix_common != node.prefix.len() {
             let seek_remaining = seek_key.length_at(depth);
@@ -1479,7 +2934,9 @@ impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> VersionedIterInner<'a
     }
 }
 
-impl<'a, K: KeyTrait<PartialType = P> + 'a, P: Partial + 'a, V> VersionedIter<'a, K, P, V> {
+impl<'a, K: KeyTrait<PartialType = P> + 'a, P: Partial + 'a, V, S: SharedPtr>
+    VersionedIter<'a, K, P, V, S>
+{
     fn empty() -> Self {
         Self {
             inner: Box::new(std::iter::empty()),
@@ -1490,7 +2947,7 @@ impl<'a, K: KeyTrait<PartialType = P> + 'a, P: Partial + 'a, V> VersionedIter<'a
     fn from_root_and_children(
         root_key: K,
         root_value: Option<&'a V>,
-        children: VersionedIterInner<'a, K, P, V>,
+        children: VersionedIterInner<'a, K, P, V, S>,
     ) -> Self {
         let inner: Box<dyn Iterator<Item = (K, &'a V)> + 'a> = match root_value {
             Some(value) => Box::new(std::iter::once((root_key, value)).chain(children)),
@@ -1503,7 +2960,7 @@ impl<'a, K: KeyTrait<PartialType = P> + 'a, P: Partial + 'a, V> VersionedIter<'a
         }
     }
 
-    fn new(node: Option<&'a VersionedNode<P, V>>) -> Self {
+    fn new(node: Option<&'a VersionedNode<P, V, S>>) -> Self {
         let Some(root_node) = node else {
             return Self::empty();
         };
@@ -1524,7 +2981,7 @@ impl<'a, K: KeyTrait<PartialType = P> + 'a, P: Partial + 'a, V> VersionedIter<'a
         Self::from_root_and_children(root_key, root_value, VersionedIterInner::new(root_node))
     }
 
-    fn new_with_prefix(node: Option<&'a VersionedNode<P, V>>, root_key: K) -> Self {
+    fn new_with_prefix(node: Option<&'a VersionedNode<P, V, S>>, root_key: K) -> Self {
         let Some(root_node) = node else {
             return Self::empty();
         };
@@ -1548,7 +3005,10 @@ impl<'a, K: KeyTrait<PartialType = P> + 'a, P: Partial + 'a, V> VersionedIter<'a
         )
     }
 
-    fn new_with_start_bound(node: Option<&'a VersionedNode<P, V>>, start_bound: Bound<K>) -> Self {
+    fn new_with_start_bound(
+        node: Option<&'a VersionedNode<P, V, S>>,
+        start_bound: Bound<K>,
+    ) -> Self {
         let Some(root_node) = node else {
             return Self::empty();
         };
@@ -1557,11 +3017,11 @@ impl<'a, K: KeyTrait<PartialType = P> + 'a, P: Partial + 'a, V> VersionedIter<'a
         let root_value = root_node.value();
         let satisfies_start = match &start_bound {
             Bound::Included(start_key) => {
-                VersionedIterInner::<K, P, V>::key_order(&root_key, start_key)
+                VersionedIterInner::<K, P, V, S>::key_order(&root_key, start_key)
                     >= std::cmp::Ordering::Equal
             }
             Bound::Excluded(start_key) => {
-                VersionedIterInner::<K, P, V>::key_order(&root_key, start_key)
+                VersionedIterInner::<K, P, V, S>::key_order(&root_key, start_key)
                     > std::cmp::Ordering::Equal
             }
             Bound::Unbounded => true,
@@ -1593,7 +3053,9 @@ impl<'a, K: KeyTrait<PartialType = P> + 'a, P: Partial + 'a, V> VersionedIter<'a
     }
 }
 
-impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> Iterator for VersionedIter<'a, K, P, V> {
+impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V, S: SharedPtr> Iterator
+    for VersionedIter<'a, K, P, V, S>
+{
     type Item = (K, &'a V);
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -1601,8 +3063,10 @@ impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> Iterator for Versione
     }
 }
 
-impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> VersionedPrefixMatchIter<'a, K, P, V> {
-    fn new(node: Option<&'a VersionedNode<P, V>>, probe: K) -> Self {
+impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V, S: SharedPtr>
+    VersionedPrefixMatchIter<'a, K, P, V, S>
+{
+    fn new(node: Option<&'a VersionedNode<P, V, S>>, probe: K) -> Self {
         Self {
             cur_node: node,
             probe,
@@ -1612,8 +3076,8 @@ impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> VersionedPrefixMatchI
     }
 }
 
-impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> Iterator
-    for VersionedPrefixMatchIter<'a, K, P, V>
+impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V, S: SharedPtr> Iterator
+    for VersionedPrefixMatchIter<'a, K, P, V, S>
 {
     type Item = (K, &'a V);
 
@@ -1646,8 +3110,8 @@ impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> Iterator
     }
 }
 
-impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> Iterator
-    for VersionedIterInner<'a, K, P, V>
+impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V, S: SharedPtr> Iterator
+    for VersionedIterInner<'a, K, P, V, S>
 {
     type Item = (K, &'a V);
 
@@ -1680,11 +3144,11 @@ impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> Iterator
                 if let Some(start_bound) = self.start_bound.as_ref() {
                     let satisfies_start = match start_bound {
                         Bound::Included(start_key) => {
-                            VersionedIterInner::<K, P, V>::key_order(&key, start_key)
+                            VersionedIterInner::<K, P, V, S>::key_order(&key, start_key)
                                 >= std::cmp::Ordering::Equal
                         }
                         Bound::Excluded(start_key) => {
-                            VersionedIterInner::<K, P, V>::key_order(&key, start_key)
+                            VersionedIterInner::<K, P, V, S>::key_order(&key, start_key)
                                 > std::cmp::Ordering::Equal
                         }
                         Bound::Unbounded => true,
@@ -1704,7 +3168,7 @@ impl<'a, K: KeyTrait<PartialType = P>, P: Partial + 'a, V> Iterator
     }
 }
 
-impl<'a, P: Partial + 'a, V> VersionedLendingIterInner<'a, P, V> {
+impl<'a, P: Partial + 'a, V, S: SharedPtr> VersionedLendingIterInner<'a, P, V, S> {
     fn cmp_segments_to_slice(segments: &[&[u8]], len: usize, slice: &[u8]) -> std::cmp::Ordering {
         let mut offset = 0usize;
         for segment in segments {
@@ -1809,10 +3273,10 @@ impl<'a, P: Partial + 'a, V> VersionedLendingIterInner<'a, P, V> {
     }
 
     fn build_positioned_stack<K: KeyTrait<PartialType = P>>(
-        node: &'a VersionedNode<P, V>,
+        node: &'a VersionedNode<P, V, S>,
         seek_key: &K,
         depth: usize,
-    ) -> Vec<(usize, usize, VersionedIterFrameIter<'a, P, V>)> {
+    ) -> Vec<(usize, usize, VersionedIterFrameIter<'a, P, V, S>)> {
         let root_segment_depth = usize::from(!node.prefix.as_ref().is_empty());
 
         let prefix_common = node.prefix.prefix_length_key(seek_key, depth);
@@ -1869,7 +3333,7 @@ impl<'a, P: Partial + 'a, V> VersionedLendingIterInner<'a, P, V> {
         vec![]
     }
 
-    fn for_each<F>(node: Option<&'a VersionedNode<P, V>>, mut on_each: F)
+    fn for_each<F>(node: Option<&'a VersionedNode<P, V, S>>, mut on_each: F)
     where
         F: for<'view> FnMut(LendingKeyView<'a, 'view>, &'a V),
     {
@@ -1904,7 +3368,7 @@ impl<'a, P: Partial + 'a, V> VersionedLendingIterInner<'a, P, V> {
     }
 
     fn for_each_with_prefix<F>(
-        node: Option<&'a VersionedNode<P, V>>,
+        node: Option<&'a VersionedNode<P, V, S>>,
         root_segments: Vec<&'a [u8]>,
         root_len: usize,
         mut on_each: F,
@@ -1935,7 +3399,7 @@ impl<'a, P: Partial + 'a, V> VersionedLendingIterInner<'a, P, V> {
     }
 
     fn for_each_with_bounds<K, F>(
-        node: Option<&'a VersionedNode<P, V>>,
+        node: Option<&'a VersionedNode<P, V, S>>,
         start_bound: Bound<K>,
         end_bound: Bound<K>,
         mut on_each: F,
@@ -2021,8 +3485,8 @@ impl<'a, P: Partial + 'a, V> VersionedLendingIterInner<'a, P, V> {
     }
 }
 
-impl<'a, P: Partial + 'a, V> VersionedValuesIter<'a, P, V> {
-    fn new(node: Option<&'a VersionedNode<P, V>>) -> Self {
+impl<'a, P: Partial + 'a, V, S: SharedPtr> VersionedValuesIter<'a, P, V, S> {
+    fn new(node: Option<&'a VersionedNode<P, V, S>>) -> Self {
         let Some(root_node) = node else {
             return Self {
                 root_value: None,
@@ -2037,7 +3501,7 @@ impl<'a, P: Partial + 'a, V> VersionedValuesIter<'a, P, V> {
     }
 }
 
-impl<'a, P: Partial + 'a, V> Iterator for VersionedValuesIter<'a, P, V> {
+impl<'a, P: Partial + 'a, V, S: SharedPtr> Iterator for VersionedValuesIter<'a, P, V, S> {
     type Item = &'a V;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -2057,41 +3521,162 @@ impl<'a, P: Partial + 'a, V> Iterator for VersionedValuesIter<'a, P, V> {
                 self.node_iter_stack.push(node.iter());
             }
 
-            if let Some(value) = node.value() {
-                return Some(value);
+            if let Some(value) = node.value() {
+                return Some(value);
+            }
+        }
+    }
+}
+
+impl<'a, K: KeyTrait + 'a, V, S: SharedPtr> Iterator for VersionedRange<'a, K, V, S> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.iter.next()?;
+        match &self.end {
+            Bound::Included(end_key) => match next.0.cmp(end_key) {
+                std::cmp::Ordering::Less | std::cmp::Ordering::Equal => Some(next),
+                std::cmp::Ordering::Greater => None,
+            },
+            Bound::Excluded(end_key) => match next.0.cmp(end_key) {
+                std::cmp::Ordering::Less => Some(next),
+                std::cmp::Ordering::Equal | std::cmp::Ordering::Greater => None,
+            },
+            Bound::Unbounded => Some(next),
+        }
+    }
+}
+
+// Internal implementation
+impl<KeyType, ValueType, S: SharedPtr> TreeStatsTrait
+    for VersionedAdaptiveRadixTree<KeyType, ValueType, S>
+where
+    KeyType: KeyTrait,
+    ValueType: Clone,
+{
+    fn get_tree_stats(&self) -> TreeStats {
+        let mut stats = TreeStats::default();
+
+        let Some(root) = &self.root else {
+            return stats;
+        };
+
+        Self::get_tree_stats_recurse(root, &mut stats, 1);
+        finish_tree_stats(&mut stats);
+
+        stats
+    }
+}
+
+impl<KeyType, ValueType, S: SharedPtr> VersionedAdaptiveRadixTree<KeyType, ValueType, S>
+where
+    KeyType: KeyTrait,
+    ValueType: Clone,
+{
+    fn snapshot_stats_recurse(
+        node: &NodePtr<KeyType::PartialType, ValueType, S>,
+        owned_path: bool,
+        stats: &mut SnapshotStats,
+    ) {
+        let bytes = node.heap_bytes();
+        let unique = S::strong_count(node) == 1;
+        stats.total_nodes += 1;
+        stats.total_bytes += bytes;
+        if unique {
+            stats.unique_nodes += 1;
+        } else {
+            stats.shared_nodes += 1;
+        }
+        let owned_path = owned_path && unique;
+        if owned_path {
+            stats.freed_on_drop_nodes += 1;
+            stats.freed_on_drop_bytes += bytes;
+        }
+        for (_, child) in node.iter_arcs() {
+            Self::snapshot_stats_recurse(child, owned_path, stats);
+        }
+    }
+
+    /// Count the nodes shared by two subtrees whose remaining prefixes, `left_offset` and
+    /// `right_offset` bytes in, start at the same key depth.
+    fn shared_with_recurse(
+        left: &NodePtr<KeyType::PartialType, ValueType, S>,
+        left_offset: usize,
+        right: &NodePtr<KeyType::PartialType, ValueType, S>,
+        right_offset: usize,
+        stats: &mut SharingStats,
+    ) {
+        if left_offset == 0 && right_offset == 0 && S::ptr_eq(left, right) {
+            // Everything below a shared node is shared too.
+            Self::count_subtree(left, stats);
+            return;
+        }
+
+        let left_rest = &left.prefix.as_ref()[left_offset..];
+        let right_rest = &right.prefix.as_ref()[right_offset..];
+        let common = prefix_length_bytes(left_rest, right_rest);
+        if common < left_rest.len() && common < right_rest.len() {
+            // The prefixes diverge, so no key below one subtree is below the other.
+            return;
+        }
+
+        let (_, left_children) = Self::paired_side(left, left_offset + common);
+        let (_, right_children) = Self::paired_side(right, right_offset + common);
+        let mut right_children = right_children.into_iter().peekable();
+        for (edge, left_child, left_child_offset) in left_children {
+            while right_children
+                .next_if(|(right_edge, _, _)| *right_edge < edge)
+                .is_some()
+            {}
+            if let Some((_, right_child, right_child_offset)) =
+                right_children.next_if(|(right_edge, _, _)| *right_edge == edge)
+            {
+                Self::shared_with_recurse(
+                    left_child,
+                    left_child_offset,
+                    right_child,
+                    right_child_offset,
+                    stats,
+                );
             }
         }
     }
-}
 
-impl<'a, K: KeyTrait + 'a, V> Iterator for VersionedRange<'a, K, V> {
-    type Item = (K, &'a V);
+    fn count_subtree(
+        node: &VersionedNode<KeyType::PartialType, ValueType, S>,
+        stats: &mut SharingStats,
+    ) {
+        stats.shared_nodes += 1;
+        stats.shared_bytes += node.heap_bytes();
+        for (_, child) in node.iter_arcs() {
+            Self::count_subtree(child, stats);
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let next = self.iter.next()?;
-        match &self.end {
-            Bound::Included(end_key) => match next.0.cmp(end_key) {
-                std::cmp::Ordering::Less | std::cmp::Ordering::Equal => Some(next),
-                std::cmp::Ordering::Greater => None,
-            },
-            Bound::Excluded(end_key) => match next.0.cmp(end_key) {
-                std::cmp::Ordering::Less => Some(next),
-                std::cmp::Ordering::Equal | std::cmp::Ordering::Greater => None,
-            },
-            Bound::Unbounded => Some(next),
+    fn get_tree_stats_recurse(
+        node: &VersionedNode<KeyType::PartialType, ValueType, S>,
+        tree_stats: &mut TreeStats,
+        height: usize,
+    ) {
+        if height > tree_stats.max_height {
+            tree_stats.max_height = height;
+        }
+        if node.value().is_some() {
+            tree_stats.num_values += 1;
+        }
+        if node.is_leaf() {
+            tree_stats.num_leaves += 1;
+        } else {
+            record_inner_node(tree_stats, node.capacity(), node.num_children());
+        }
+        for (_, child) in node.iter_arcs() {
+            Self::get_tree_stats_recurse(child, tree_stats, height + 1);
         }
     }
-}
 
-// Internal implementation
-impl<KeyType, ValueType> VersionedAdaptiveRadixTree<KeyType, ValueType>
-where
-    KeyType: KeyTrait,
-    ValueType: Clone,
-{
     /// Get operation that traverses the tree without modification.
     fn get_iterate<'a>(
-        cur_node: &'a VersionedNode<KeyType::PartialType, ValueType>,
+        cur_node: &'a VersionedNode<KeyType::PartialType, ValueType, S>,
         key: &KeyType,
     ) -> Option<&'a ValueType> {
         let mut cur_node = cur_node;
@@ -2114,11 +3699,14 @@ where
     }
 
     fn get_iterate_mut_cow_ready<'a>(
-        cur_node: &'a mut Arc<VersionedNode<KeyType::PartialType, ValueType>>,
+        cur_node: &'a mut NodePtr<KeyType::PartialType, ValueType, S>,
         key: &KeyType,
         depth: usize,
-    ) -> Option<&'a mut ValueType> {
-        let cur_node = Arc::get_mut(cur_node).expect("CoW path should be uniquely owned");
+    ) -> Option<&'a mut ValueType>
+    where
+        KeyType::PartialType: 'a,
+    {
+        let cur_node = S::get_mut(cur_node).expect("CoW path should be uniquely owned");
         let prefix_common_match = cur_node.prefix.prefix_length_key(key, depth);
         if prefix_common_match != cur_node.prefix.len() {
             return None;
@@ -2135,7 +3723,7 @@ where
     }
 
     fn prefix_match_for_each_impl<'a, F>(
-        cur_node: &'a VersionedNode<KeyType::PartialType, ValueType>,
+        cur_node: &'a VersionedNode<KeyType::PartialType, ValueType, S>,
         key: &KeyType,
         mut on_match: F,
     ) where
@@ -2170,346 +3758,414 @@ where
         }
     }
 
-    /// Recursively intersect two nodes, supporting different prefix-compression boundaries
-    /// through in-prefix offsets.
-    fn intersect_nodes<'a, F>(
-        left: &'a VersionedNode<KeyType::PartialType, ValueType>,
-        mut left_offset: usize,
-        right: &'a VersionedNode<KeyType::PartialType, ValueType>,
-        mut right_offset: usize,
-        key_buf: &mut Vec<u8>,
-        on_match: &mut F,
-    ) where
-        F: FnMut(KeyType, &'a ValueType, &'a ValueType),
+    fn intersect_range_raw<'a, R, F>(&'a self, other: &'a Self, range: &R, on_match: &mut F)
+    where
+        R: RangeBounds<KeyType>,
+        F: FnMut(&[u8], &'a ValueType, &'a ValueType),
     {
-        let restore_len = key_buf.len();
-        let left_prefix = left.prefix.as_ref();
-        let right_prefix = right.prefix.as_ref();
-
-        while left_offset < left_prefix.len() && right_offset < right_prefix.len() {
-            let left_byte = left_prefix[left_offset];
-            let right_byte = right_prefix[right_offset];
-            if left_byte != right_byte {
-                key_buf.truncate(restore_len);
-                return;
-            }
-            key_buf.push(left_byte);
-            left_offset += 1;
-            right_offset += 1;
-        }
-
-        if left_offset < left_prefix.len() {
-            if !right.is_inner() {
-                key_buf.truncate(restore_len);
+        let (Some(left_root), Some(right_root)) = (self.root.as_deref(), other.root.as_deref())
+        else {
+            return;
+        };
+        let range = ByteRange::new(range);
+        let common = range.common_prefix();
+        let (left, left_offset, right, right_offset) = if common.is_empty() {
+            (left_root, 0, right_root, 0)
+        } else {
+            let prefix = KeyType::new_from_slice(common);
+            let Some((left, left_offset)) = Self::find_prefix_subtree_offset(left_root, &prefix)
+            else {
                 return;
-            }
-
-            let edge = left_prefix[left_offset];
-            let Some(right_child) = right.seek_child(edge) else {
-                key_buf.truncate(restore_len);
+            };
+            let Some((right, right_offset)) = Self::find_prefix_subtree_offset(right_root, &prefix)
+            else {
                 return;
             };
+            (left, left_offset, right, right_offset)
+        };
 
-            key_buf.push(edge);
-            Self::intersect_nodes(
-                left,
-                left_offset + 1,
-                right_child.as_ref(),
-                1,
-                key_buf,
-                on_match,
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        key_buf.extend_from_slice(common);
+        join::intersect_in_range(
+            left,
+            left_offset,
+            right,
+            right_offset,
+            &range,
+            &mut key_buf,
+            on_match,
+        );
+    }
+
+    /// Recursively union two nodes that sit at the same depth, starting `left_offset` and
+    /// `right_offset` bytes into their prefixes. Only nodes on paths shared by both sides are
+    /// rebuilt; everything else is shared with the inputs.
+    fn union_nodes<F>(
+        left: &NodePtr<KeyType::PartialType, ValueType, S>,
+        left_offset: usize,
+        right: &NodePtr<KeyType::PartialType, ValueType, S>,
+        right_offset: usize,
+        key_buf: &mut Vec<u8>,
+        version: u64,
+        resolver: &mut F,
+    ) -> NodePtr<KeyType::PartialType, ValueType, S>
+    where
+        F: FnMut(KeyType, &ValueType, &ValueType) -> ValueType,
+    {
+        let left_rest = &left.prefix.as_ref()[left_offset..];
+        let right_rest = &right.prefix.as_ref()[right_offset..];
+        let common = prefix_length_bytes(left_rest, right_rest);
+        let prefix = left.prefix.partial_from(left_offset, common);
+
+        // The prefixes diverge, so the two subtrees share no keys and hang side by side
+        // below a new parent.
+        if common < left_rest.len() && common < right_rest.len() {
+            let mut parent = VersionedNode::new_inner(prefix, version);
+            parent.add_child(
+                left_rest[common],
+                Self::share_subtree_from(left, left_offset + common, version),
             );
-            key_buf.truncate(restore_len);
-            return;
+            parent.add_child(
+                right_rest[common],
+                Self::share_subtree_from(right, right_offset + common, version),
+            );
+            return S::new(parent);
         }
 
-        if right_offset < right_prefix.len() {
-            if !left.is_inner() {
-                key_buf.truncate(restore_len);
-                return;
-            }
+        let restore_len = key_buf.len();
+        key_buf.extend_from_slice(&left_rest[..common]);
 
-            let edge = right_prefix[right_offset];
-            let Some(left_child) = left.seek_child(edge) else {
-                key_buf.truncate(restore_len);
-                return;
+        let (left_value, left_children) = Self::paired_side(left, left_offset + common);
+        let (right_value, right_children) = Self::paired_side(right, right_offset + common);
+        let value = match (left_value, right_value) {
+            (Some(left_value), Some(right_value)) => Some(resolver(
+                KeyType::new_from_slice(key_buf.as_slice()),
+                left_value,
+                right_value,
+            )),
+            (left_value, right_value) => left_value.or(right_value).cloned(),
+        };
+
+        let mut children = Vec::with_capacity(left_children.len() + right_children.len());
+        let mut left_children = left_children.into_iter().peekable();
+        let mut right_children = right_children.into_iter().peekable();
+        loop {
+            let next = match (left_children.peek(), right_children.peek()) {
+                (None, None) => break,
+                (Some(l), Some(r)) if l.0 == r.0 => {
+                    let (edge, left_child, left_child_offset) = left_children.next().unwrap();
+                    let (_, right_child, right_child_offset) = right_children.next().unwrap();
+                    let child = Self::union_nodes(
+                        left_child,
+                        left_child_offset,
+                        right_child,
+                        right_child_offset,
+                        key_buf,
+                        version,
+                        resolver,
+                    );
+                    (edge, child)
+                }
+                (Some(l), r) if r.is_none_or(|r| l.0 < r.0) => {
+                    let (edge, child, offset) = left_children.next().unwrap();
+                    (edge, Self::share_subtree_from(child, offset, version))
+                }
+                _ => {
+                    let (edge, child, offset) = right_children.next().unwrap();
+                    (edge, Self::share_subtree_from(child, offset, version))
+                }
             };
+            children.push(next);
+        }
 
-            key_buf.push(edge);
-            Self::intersect_nodes(
-                left_child.as_ref(),
-                1,
-                right,
-                right_offset + 1,
-                key_buf,
-                on_match,
+        key_buf.truncate(restore_len);
+        S::new(VersionedNode::from_sorted_children(
+            prefix, value, children, version,
+        ))
+    }
+
+    /// The owned counterpart of [`Self::union_nodes`], for [`merge_from`](Self::merge_from).
+    /// Values are moved out of nodes that no other tree shares.
+    fn merge_owned_nodes<F>(
+        left: NodePtr<KeyType::PartialType, ValueType, S>,
+        left_offset: usize,
+        right: NodePtr<KeyType::PartialType, ValueType, S>,
+        right_offset: usize,
+        key_buf: &mut Vec<u8>,
+        version: u64,
+        resolver: &mut F,
+    ) -> NodePtr<KeyType::PartialType, ValueType, S>
+    where
+        F: FnMut(KeyType, ValueType, ValueType) -> ValueType,
+    {
+        let left_rest = &left.prefix.as_ref()[left_offset..];
+        let right_rest = &right.prefix.as_ref()[right_offset..];
+        let common = prefix_length_bytes(left_rest, right_rest);
+        let prefix = left.prefix.partial_from(left_offset, common);
+
+        // The prefixes diverge, so the two subtrees share no keys and hang side by side
+        // below a new parent.
+        if common < left_rest.len() && common < right_rest.len() {
+            let mut parent = VersionedNode::new_inner(prefix, version);
+            parent.add_child(
+                left_rest[common],
+                Self::take_subtree_from(left, left_offset + common, version),
             );
-            key_buf.truncate(restore_len);
-            return;
+            parent.add_child(
+                right_rest[common],
+                Self::take_subtree_from(right, right_offset + common, version),
+            );
+            return S::new(parent);
         }
 
-        if let (Some(left_value), Some(right_value)) = (left.value(), right.value()) {
-            on_match(
+        let restore_len = key_buf.len();
+        key_buf.extend_from_slice(prefix.as_ref());
+
+        let (left_value, left_children) = Self::owned_side(left, left_offset + common);
+        let (right_value, right_children) = Self::owned_side(right, right_offset + common);
+        let value = match (left_value, right_value) {
+            (Some(left_value), Some(right_value)) => Some(resolver(
                 KeyType::new_from_slice(key_buf.as_slice()),
                 left_value,
                 right_value,
-            );
-        }
+            )),
+            (left_value, right_value) => left_value.or(right_value),
+        };
 
-        if left.is_inner() && right.is_inner() {
-            if left.num_children() <= right.num_children() {
-                for (edge, left_child) in left.iter() {
-                    if let Some(right_child) = right.seek_child(edge) {
-                        Self::intersect_nodes(
-                            left_child,
-                            0,
-                            right_child.as_ref(),
-                            0,
-                            key_buf,
-                            on_match,
-                        );
-                    }
+        let mut children = Vec::with_capacity(left_children.len() + right_children.len());
+        let mut left_children = left_children.into_iter().peekable();
+        let mut right_children = right_children.into_iter().peekable();
+        loop {
+            let next = match (left_children.peek(), right_children.peek()) {
+                (None, None) => break,
+                (Some(l), Some(r)) if l.0 == r.0 => {
+                    let (edge, left_child, left_child_offset) = left_children.next().unwrap();
+                    let (_, right_child, right_child_offset) = right_children.next().unwrap();
+                    let child = Self::merge_owned_nodes(
+                        left_child,
+                        left_child_offset,
+                        right_child,
+                        right_child_offset,
+                        key_buf,
+                        version,
+                        resolver,
+                    );
+                    (edge, child)
                 }
-            } else {
-                for (edge, right_child) in right.iter() {
-                    if let Some(left_child) = left.seek_child(edge) {
-                        Self::intersect_nodes(
-                            left_child.as_ref(),
-                            0,
-                            right_child,
-                            0,
-                            key_buf,
-                            on_match,
-                        );
-                    }
+                (Some(l), r) if r.is_none_or(|r| l.0 < r.0) => {
+                    let (edge, child, offset) = left_children.next().unwrap();
+                    (edge, Self::take_subtree_from(child, offset, version))
                 }
-            }
+                _ => {
+                    let (edge, child, offset) = right_children.next().unwrap();
+                    (edge, Self::take_subtree_from(child, offset, version))
+                }
+            };
+            children.push(next);
         }
 
         key_buf.truncate(restore_len);
-    }
-
-    fn intersect_nodes_lending<'a, F>(
-        left: &'a VersionedNode<KeyType::PartialType, ValueType>,
-        mut left_offset: usize,
-        right: &'a VersionedNode<KeyType::PartialType, ValueType>,
-        mut right_offset: usize,
-        key_segments: &mut Vec<&'a [u8]>,
-        key_len: &mut usize,
-        on_match: &mut F,
-    ) where
-        F: for<'view> FnMut(LendingKeyView<'a, 'view>, &'a ValueType, &'a ValueType),
+        S::new(VersionedNode::from_sorted_children(
+            prefix, value, children, version,
+        ))
+    }
+
+    /// Recursively three-way merge the subtrees at `base`, `ours`, and `theirs`, each a node
+    /// and an offset into its prefix, all at the same depth. A side that is still the same
+    /// shared node as the base (or as the other side) lets the other side's subtree be taken
+    /// whole; only paths changed on both sides are rebuilt.
+    fn merge3_nodes<F>(
+        base: Merge3Cursor<'_, KeyType::PartialType, ValueType, S>,
+        ours: Merge3Cursor<'_, KeyType::PartialType, ValueType, S>,
+        theirs: Merge3Cursor<'_, KeyType::PartialType, ValueType, S>,
+        key_buf: &mut Vec<u8>,
+        version: u64,
+        resolver: &mut F,
+    ) -> Option<NodePtr<KeyType::PartialType, ValueType, S>>
+    where
+        ValueType: PartialEq,
+        F: FnMut(
+            KeyType,
+            Option<&ValueType>,
+            Option<&ValueType>,
+            Option<&ValueType>,
+        ) -> Option<ValueType>,
     {
-        let restore_segments = key_segments.len();
-        let restore_len = *key_len;
-        let left_prefix = left.prefix.as_ref();
-        let right_prefix = right.prefix.as_ref();
-        let matched_left_start = left_offset;
-
-        while left_offset < left_prefix.len() && right_offset < right_prefix.len() {
-            if left_prefix[left_offset] != right_prefix[right_offset] {
-                key_segments.truncate(restore_segments);
-                *key_len = restore_len;
-                return;
-            }
-            left_offset += 1;
-            right_offset += 1;
+        let same = |a: Merge3Cursor<'_, _, _, S>, b: Merge3Cursor<'_, _, _, S>| match (a, b) {
+            (None, None) => true,
+            (Some((a, a_offset)), Some((b, b_offset))) => a_offset == b_offset && S::ptr_eq(a, b),
+            _ => false,
+        };
+        let take = |side: Merge3Cursor<'_, _, _, S>| {
+            side.map(|(node, offset)| Self::share_subtree_from(node, offset, version))
+        };
+        if same(ours, base) {
+            return take(theirs);
         }
-
-        if left_offset > matched_left_start {
-            let matched = &left_prefix[matched_left_start..left_offset];
-            key_segments.push(matched);
-            *key_len += matched.len();
+        if same(theirs, base) || same(ours, theirs) {
+            return take(ours);
         }
 
-        if left_offset < left_prefix.len() {
-            if !right.is_inner() {
-                key_segments.truncate(restore_segments);
-                *key_len = restore_len;
-                return;
-            }
-
-            let edge = left_prefix[left_offset];
-            let Some(right_child) = right.seek_child(edge) else {
-                key_segments.truncate(restore_segments);
-                *key_len = restore_len;
-                return;
-            };
+        let sides = [base, ours, theirs];
+        let (lead, lead_offset) = sides
+            .iter()
+            .flatten()
+            .copied()
+            .next()
+            .expect("ours and theirs cannot both be empty here");
+        let lead_rest = &lead.prefix.as_ref()[lead_offset..];
+        let common = sides
+            .iter()
+            .flatten()
+            .map(|(node, offset)| prefix_length_bytes(lead_rest, &node.prefix.as_ref()[*offset..]))
+            .min()
+            .unwrap_or(0);
+        let prefix = lead.prefix.partial_from(lead_offset, common);
 
-            let edge_segment = &left_prefix[left_offset..left_offset + 1];
-            key_segments.push(edge_segment);
-            *key_len += 1;
-            Self::intersect_nodes_lending(
-                left,
-                left_offset + 1,
-                right_child.as_ref(),
-                1,
-                key_segments,
-                key_len,
-                on_match,
-            );
-            key_segments.truncate(restore_segments);
-            *key_len = restore_len;
-            return;
-        }
+        let restore_len = key_buf.len();
+        key_buf.extend_from_slice(&lead_rest[..common]);
 
-        if right_offset < right_prefix.len() {
-            if !left.is_inner() {
-                key_segments.truncate(restore_segments);
-                *key_len = restore_len;
-                return;
-            }
+        let [base_side, our_side, their_side] = sides.map(|side| match side {
+            Some((node, offset)) => Self::paired_side(node, offset + common),
+            None => (None, Vec::new()),
+        });
+        let value = if our_side.0 == base_side.0 {
+            their_side.0.cloned()
+        } else if their_side.0 == base_side.0 || our_side.0 == their_side.0 {
+            our_side.0.cloned()
+        } else {
+            resolver(
+                KeyType::new_from_slice(key_buf.as_slice()),
+                base_side.0,
+                our_side.0,
+                their_side.0,
+            )
+        };
 
-            let edge = right_prefix[right_offset];
-            let Some(left_child) = left.seek_child(edge) else {
-                key_segments.truncate(restore_segments);
-                *key_len = restore_len;
-                return;
+        let mut children = Vec::new();
+        let mut base_children = base_side.1.into_iter().peekable();
+        let mut our_children = our_side.1.into_iter().peekable();
+        let mut their_children = their_side.1.into_iter().peekable();
+        while let Some(edge) = [
+            base_children.peek(),
+            our_children.peek(),
+            their_children.peek(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|(edge, _, _)| *edge)
+        .min()
+        {
+            let next_at_edge = |children: &mut std::iter::Peekable<_>| {
+                children
+                    .next_if(|(child_edge, _, _)| *child_edge == edge)
+                    .map(|(_, child, offset)| (child, offset))
             };
-
-            let edge_segment = &right_prefix[right_offset..right_offset + 1];
-            key_segments.push(edge_segment);
-            *key_len += 1;
-            Self::intersect_nodes_lending(
-                left_child.as_ref(),
-                1,
-                right,
-                right_offset + 1,
-                key_segments,
-                key_len,
-                on_match,
-            );
-            key_segments.truncate(restore_segments);
-            *key_len = restore_len;
-            return;
-        }
-
-        if let (Some(left_value), Some(right_value)) = (left.value(), right.value()) {
-            on_match(
-                LendingKeyView::new(key_segments, *key_len),
-                left_value,
-                right_value,
-            );
-        }
-
-        if left.is_inner() && right.is_inner() {
-            if left.num_children() <= right.num_children() {
-                for (edge, left_child) in left.iter() {
-                    if let Some(right_child) = right.seek_child(edge) {
-                        Self::intersect_nodes_lending(
-                            left_child,
-                            0,
-                            right_child.as_ref(),
-                            0,
-                            key_segments,
-                            key_len,
-                            on_match,
-                        );
-                    }
-                }
-            } else {
-                for (edge, right_child) in right.iter() {
-                    if let Some(left_child) = left.seek_child(edge) {
-                        Self::intersect_nodes_lending(
-                            left_child.as_ref(),
-                            0,
-                            right_child,
-                            0,
-                            key_segments,
-                            key_len,
-                            on_match,
-                        );
-                    }
-                }
+            let base_child = next_at_edge(&mut base_children);
+            let our_child = next_at_edge(&mut our_children);
+            let their_child = next_at_edge(&mut their_children);
+            if let Some(child) = Self::merge3_nodes(
+                base_child,
+                our_child,
+                their_child,
+                key_buf,
+                version,
+                resolver,
+            ) {
+                children.push((edge, child));
             }
         }
 
-        key_segments.truncate(restore_segments);
-        *key_len = restore_len;
+        key_buf.truncate(restore_len);
+        if value.is_none() && children.is_empty() {
+            return None;
+        }
+        Some(S::new(VersionedNode::from_sorted_children(
+            prefix, value, children, version,
+        )))
     }
 
-    /// Recursively intersect two nodes and emit only value pairs (no key reconstruction).
-    fn intersect_nodes_values<'a, F>(
-        left: &'a VersionedNode<KeyType::PartialType, ValueType>,
-        mut left_offset: usize,
-        right: &'a VersionedNode<KeyType::PartialType, ValueType>,
-        mut right_offset: usize,
-        on_match: &mut F,
-    ) where
-        F: FnMut(&'a ValueType, &'a ValueType),
+    /// The value and children of `node` as seen from `offset` bytes into its prefix. A node
+    /// whose prefix continues past `offset` has no value there and is its own only child.
+    fn paired_side<'a>(
+        node: &'a NodePtr<KeyType::PartialType, ValueType, S>,
+        offset: usize,
+    ) -> VersionedPairedSide<'a, KeyType::PartialType, ValueType, S>
+    where
+        KeyType::PartialType: 'a,
     {
-        let left_prefix = left.prefix.as_ref();
-        let right_prefix = right.prefix.as_ref();
-
-        while left_offset < left_prefix.len() && right_offset < right_prefix.len() {
-            if left_prefix[left_offset] != right_prefix[right_offset] {
-                return;
-            }
-            left_offset += 1;
-            right_offset += 1;
+        if offset < node.prefix.len() {
+            return (None, vec![(node.prefix.at(offset), node, offset)]);
         }
-
-        if left_offset < left_prefix.len() {
-            if !right.is_inner() {
-                return;
+        let children = node
+            .iter_arcs()
+            .map(|(edge, child)| (edge, child, 0))
+            .collect();
+        (node.value(), children)
+    }
+
+    /// The value and children of `node` as seen from `offset` bytes into its prefix, the owned
+    /// counterpart of [`Self::paired_side`]. A node no other tree shares is taken apart and its
+    /// value moved out; a shared one has its value cloned and its children shared.
+    fn owned_side(
+        node: NodePtr<KeyType::PartialType, ValueType, S>,
+        offset: usize,
+    ) -> VersionedOwnedSide<KeyType::PartialType, ValueType, S> {
+        if offset < node.prefix.len() {
+            let edge = node.prefix.at(offset);
+            return (None, vec![(edge, node, offset)]);
+        }
+        match S::try_unwrap(node) {
+            Ok(node) => {
+                let (_, value, children) = node.into_parts();
+                let children = children
+                    .into_iter()
+                    .map(|(edge, child)| (edge, child, 0))
+                    .collect();
+                (value, children)
             }
-            let edge = left_prefix[left_offset];
-            let Some(right_child) = right.seek_child(edge) else {
-                return;
-            };
-            Self::intersect_nodes_values(left, left_offset + 1, right_child.as_ref(), 1, on_match);
-            return;
-        }
-
-        if right_offset < right_prefix.len() {
-            if !left.is_inner() {
-                return;
+            Err(node) => {
+                let children = node
+                    .iter_arcs()
+                    .map(|(edge, child)| (edge, Clone::clone(child), 0))
+                    .collect();
+                (node.value().cloned(), children)
             }
-            let edge = right_prefix[right_offset];
-            let Some(left_child) = left.seek_child(edge) else {
-                return;
-            };
-            Self::intersect_nodes_values(left_child.as_ref(), 1, right, right_offset + 1, on_match);
-            return;
         }
+    }
 
-        if let (Some(left_value), Some(right_value)) = (left.value(), right.value()) {
-            on_match(left_value, right_value);
+    /// Take the subtree at `node`, dropping the first `offset` bytes of its prefix, as
+    /// [`Self::share_subtree_from`] does for a borrowed node.
+    fn take_subtree_from(
+        node: NodePtr<KeyType::PartialType, ValueType, S>,
+        offset: usize,
+        version: u64,
+    ) -> NodePtr<KeyType::PartialType, ValueType, S> {
+        if offset == 0 {
+            return node;
         }
+        Self::share_subtree_from(&node, offset, version)
+    }
 
-        if left.is_inner() && right.is_inner() {
-            if left.num_children() <= right.num_children() {
-                for (edge, left_child) in left.iter() {
-                    if let Some(right_child) = right.seek_child(edge) {
-                        Self::intersect_nodes_values(
-                            left_child,
-                            0,
-                            right_child.as_ref(),
-                            0,
-                            on_match,
-                        );
-                    }
-                }
-            } else {
-                for (edge, right_child) in right.iter() {
-                    if let Some(left_child) = left.seek_child(edge) {
-                        Self::intersect_nodes_values(
-                            left_child.as_ref(),
-                            0,
-                            right_child,
-                            0,
-                            on_match,
-                        );
-                    }
-                }
-            }
+    /// Share the subtree at `node`, dropping the first `offset` bytes of its prefix. Only the
+    /// top node is copied when its prefix has to change; its children stay shared.
+    fn share_subtree_from(
+        node: &NodePtr<KeyType::PartialType, ValueType, S>,
+        offset: usize,
+        version: u64,
+    ) -> NodePtr<KeyType::PartialType, ValueType, S> {
+        if offset == 0 {
+            return Clone::clone(node);
         }
+        let mut copy = node.cow_clone_inner(version);
+        copy.prefix = node.prefix.partial_after(offset);
+        S::new(copy)
     }
 
     fn find_prefix_subtree<'a>(
-        cur_node: &'a VersionedNode<KeyType::PartialType, ValueType>,
+        cur_node: &'a VersionedNode<KeyType::PartialType, ValueType, S>,
         prefix: &KeyType,
-    ) -> Option<(&'a VersionedNode<KeyType::PartialType, ValueType>, KeyType)> {
+    ) -> Option<VersionedSubtreeMatch<'a, KeyType, ValueType, S>> {
         let mut cur_node = cur_node;
         let mut cur_key = cur_node.prefix.as_ref().to_vec();
         let mut depth = 0;
@@ -2536,9 +4192,49 @@ where
     }
 
     fn find_prefix_subtree_node<'a>(
-        cur_node: &'a VersionedNode<KeyType::PartialType, ValueType>,
+        cur_node: &'a VersionedNode<KeyType::PartialType, ValueType, S>,
+        prefix: &KeyType,
+    ) -> Option<&'a VersionedNode<KeyType::PartialType, ValueType, S>> {
+        Self::find_prefix_subtree_offset(cur_node, prefix).map(|(node, _)| node)
+    }
+
+    /// Like `find_prefix_subtree_node`, but also returns how many bytes of the node's own
+    /// prefix the search prefix covers, so the node can seed an offset-based paired descent.
+    /// Whether any entry inside `span` may differ between `older` and this tree, judged by
+    /// node identity. Both trees are first descended to `seek`, which every key in the span
+    /// starts with.
+    pub(crate) fn changed_within<Span: KeySpan>(
+        &self,
+        older: &Self,
+        seek: &[u8],
+        span: &Span,
+    ) -> bool {
+        let seek_key = KeyType::new_from_slice(seek);
+        let mut key_buf = Vec::with_capacity(KeyType::MAXIMUM_SIZE.unwrap_or(64));
+        key_buf.extend_from_slice(seek);
+        join::changed_within(
+            older.seek_subtree(&seek_key),
+            self.seek_subtree(&seek_key),
+            span,
+            &mut key_buf,
+        )
+    }
+
+    fn seek_subtree(
+        &self,
+        prefix: &KeyType,
+    ) -> Option<VersionedNodeCursor<'_, KeyType::PartialType, ValueType, S>> {
+        let root = self.root.as_deref()?;
+        if prefix.length_at(0) == 0 {
+            return Some((root, 0));
+        }
+        Self::find_prefix_subtree_offset(root, prefix)
+    }
+
+    fn find_prefix_subtree_offset<'a>(
+        cur_node: &'a VersionedNode<KeyType::PartialType, ValueType, S>,
         prefix: &KeyType,
-    ) -> Option<&'a VersionedNode<KeyType::PartialType, ValueType>> {
+    ) -> Option<VersionedNodeCursor<'a, KeyType::PartialType, ValueType, S>> {
         let mut cur_node = cur_node;
         let mut depth = 0;
 
@@ -2546,13 +4242,13 @@ where
             let prefix_common_match = cur_node.prefix.prefix_length_key(prefix, depth);
             if prefix_common_match != cur_node.prefix.len() {
                 if prefix_common_match == prefix.length_at(depth) {
-                    return Some(cur_node);
+                    return Some((cur_node, prefix_common_match));
                 }
                 return None;
             }
 
             if cur_node.prefix.len() == prefix.length_at(depth) {
-                return Some(cur_node);
+                return Some((cur_node, cur_node.prefix.len()));
             }
 
             let key = prefix.at(depth + cur_node.prefix.len());
@@ -2563,9 +4259,9 @@ where
     }
 
     fn find_prefix_subtree_view<'a>(
-        cur_node: &'a VersionedNode<KeyType::PartialType, ValueType>,
+        cur_node: &'a VersionedNode<KeyType::PartialType, ValueType, S>,
         prefix: &KeyType,
-    ) -> Option<VersionedPrefixSubtreeView<'a, KeyType::PartialType, ValueType>> {
+    ) -> Option<VersionedPrefixSubtreeView<'a, KeyType::PartialType, ValueType, S>> {
         let mut cur_node = cur_node;
         let mut cur_segments = if cur_node.prefix.is_empty() {
             Vec::new()
@@ -2601,25 +4297,25 @@ where
     }
 
     fn cow_path_to_key(
-        cur_node: Arc<VersionedNode<KeyType::PartialType, ValueType>>,
+        cur_node: NodePtr<KeyType::PartialType, ValueType, S>,
         key: &KeyType,
         depth: usize,
         version: u64,
-    ) -> Option<Arc<VersionedNode<KeyType::PartialType, ValueType>>> {
+    ) -> Option<NodePtr<KeyType::PartialType, ValueType, S>> {
         let prefix_common_match = cur_node.prefix.prefix_length_key(key, depth);
         if prefix_common_match != cur_node.prefix.len() {
             return None;
         }
 
         let new_node = Self::ensure_cow_node(cur_node, version);
-        let mut new_node = match Arc::try_unwrap(new_node) {
+        let mut new_node = match S::try_unwrap(new_node) {
             Ok(owned) => owned,
             Err(_) => panic!("ensure_cow_node should have given us exclusive ownership"),
         };
 
         if new_node.prefix.len() == key.length_at(depth) {
             new_node.value.as_ref()?;
-            return Some(Arc::new(new_node));
+            return Some(S::new(new_node));
         }
 
         if new_node.is_leaf() {
@@ -2631,29 +4327,209 @@ where
         let child = new_node.delete_child(key_byte)?;
         let new_child = Self::cow_path_to_key(child, key, depth + prefix_len, version)?;
         new_node.add_child(key_byte, new_child);
-        Some(Arc::new(new_node))
+        Some(S::new(new_node))
+    }
+
+    /// Apply the sorted `ops`, whose keys all pass through the position of `node` at `depth`, to
+    /// the subtree at `node`. Returns the new subtree and whether anything changed; an
+    /// unchanged subtree comes back as the same node.
+    ///
+    /// A node no other tree shares is changed in place and hands its children down by value.
+    /// A shared node lends out clones of its children and is copied the first time one of them,
+    /// or its own value, changes. A node copied before an update fails may be left partly
+    /// rebuilt, which is fine since `apply_batch` then restores its held original root.
+    fn batch_node<E>(
+        mut node: NodePtr<KeyType::PartialType, ValueType, S>,
+        depth: usize,
+        ops: &mut [BatchEntry<'_, KeyType, ValueType, E>],
+        version: u64,
+    ) -> BatchResult<KeyType::PartialType, ValueType, S, E> {
+        let prefix_len = node.prefix.len();
+        let child_depth = depth + prefix_len;
+
+        // Keys running through the whole prefix are contiguous in key order; the others leave
+        // the prefix, so are absent from the subtree, and sort before or after them.
+        let (start, end) = {
+            let below = |key: &KeyType| {
+                key.length_at(depth) >= prefix_len
+                    && node.prefix.prefix_length_key(key, depth) == prefix_len
+            };
+            let start = ops
+                .iter()
+                .position(|(key, _)| below(key))
+                .unwrap_or(ops.len());
+            let end = start
+                + ops[start..]
+                    .iter()
+                    .take_while(|(key, _)| below(key))
+                    .count();
+            (start, end)
+        };
+        let (outside_before, rest) = ops.split_at_mut(start);
+        let (inside, outside_after) = rest.split_at_mut(end - start);
+
+        // Ops on the node's own value sort before those on its children.
+        let own = inside
+            .iter()
+            .take_while(|(key, _)| key.length_at(depth) == prefix_len)
+            .count();
+        let (own_ops, child_ops) = inside.split_at_mut(own);
+        let mut changed = false;
+        if !own_ops.is_empty() {
+            if node.value.is_some() {
+                let node_mut = Self::make_mut(&mut node, version);
+                changed |= Self::apply_key_ops(&mut node_mut.value, own_ops)?;
+            } else {
+                let mut value = None;
+                Self::apply_key_ops(&mut value, own_ops)?;
+                if value.is_some() {
+                    Self::make_mut(&mut node, version).value = value;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut i = 0;
+        while i < child_ops.len() {
+            let edge = child_ops[i].0.at(child_depth);
+            let group_len = child_ops[i..]
+                .iter()
+                .take_while(|(key, _)| key.at(child_depth) == edge)
+                .count();
+            let group = &mut child_ops[i..i + group_len];
+            i += group_len;
+
+            let unique = S::get_mut(&mut node).is_some();
+            let child = match S::get_mut(&mut node) {
+                Some(node_mut) => node_mut.delete_child(edge),
+                None => node.seek_child(edge).cloned(),
+            };
+            let (new_child, child_changed) = match child {
+                Some(child) => Self::batch_node(child, child_depth, group, version)?,
+                None => Self::batch_insert_absent(None, child_depth, group, version)?,
+            };
+            if child_changed {
+                let node_mut = Self::make_mut(&mut node, version);
+                node_mut.delete_child(edge);
+                if let Some(new_child) = new_child {
+                    node_mut.add_child(edge, new_child);
+                }
+                changed = true;
+            } else if let (true, Some(child)) = (unique, new_child) {
+                S::get_mut(&mut node)
+                    .expect("the node is still unshared")
+                    .add_child(edge, child);
+            }
+        }
+
+        let mut node =
+            (!changed || node.value.is_some() || node.num_children() > 0).then_some(node);
+        for outside in [outside_before, outside_after] {
+            let (new_node, inserted) = Self::batch_insert_absent(node, depth, outside, version)?;
+            node = new_node;
+            changed |= inserted;
+        }
+        Ok((node, changed))
+    }
+
+    /// Insert the keys of the sorted `ops`, none of which is in the subtree at `node`, into it.
+    fn batch_insert_absent<E>(
+        mut node: Option<NodePtr<KeyType::PartialType, ValueType, S>>,
+        depth: usize,
+        ops: &mut [BatchEntry<'_, KeyType, ValueType, E>],
+        version: u64,
+    ) -> BatchResult<KeyType::PartialType, ValueType, S, E> {
+        let mut changed = false;
+        let mut i = 0;
+        while i < ops.len() {
+            let run = ops[i..]
+                .iter()
+                .take_while(|(key, _)| *key == ops[i].0)
+                .count();
+            let mut value = None;
+            Self::apply_key_ops(&mut value, &mut ops[i..i + run])?;
+            if let Some(value) = value {
+                let key = &ops[i].0;
+                node = Some(match node {
+                    None => S::new(VersionedNode::new_leaf(
+                        key.to_partial(depth),
+                        value,
+                        version,
+                    )),
+                    Some(node) => Self::insert_recurse(node, key, value, depth, version, None).0,
+                });
+                changed = true;
+            }
+            i += run;
+        }
+        Ok((node, changed))
+    }
+
+    /// Run the ops of one key, in order, against its value slot. Returns whether the slot may
+    /// have changed; an update that keeps an occupied value counts, since it may have modified
+    /// the value in place.
+    fn apply_key_ops<E>(
+        value: &mut Option<ValueType>,
+        ops: &mut [BatchEntry<'_, KeyType, ValueType, E>],
+    ) -> Result<bool, E> {
+        let mut changed = false;
+        for (_, op) in ops {
+            let action = match op.take().expect("each batch op is applied once") {
+                BatchOp::Insert(new_value) => SlotUpdate::Insert(new_value),
+                BatchOp::Remove => SlotUpdate::Remove,
+                BatchOp::Update(update) => match value.as_mut() {
+                    Some(current) => update(Slot::Occupied(current))?,
+                    None => update(Slot::Vacant)?,
+                },
+            };
+            match action {
+                SlotUpdate::Keep => changed |= value.is_some(),
+                SlotUpdate::Insert(new_value) => {
+                    *value = Some(new_value);
+                    changed = true;
+                }
+                SlotUpdate::Remove => changed |= value.take().is_some(),
+            }
+        }
+        Ok(changed)
+    }
+
+    /// A mutable reference to `node` for a write at `version`: in place if no other tree
+    /// shares it, and otherwise on a copy that takes its place.
+    fn make_mut(
+        node: &mut NodePtr<KeyType::PartialType, ValueType, S>,
+        version: u64,
+    ) -> &mut VersionedNode<KeyType::PartialType, ValueType, S> {
+        if S::get_mut(node).is_none() {
+            *node = S::new(node.cow_clone_inner(version));
+        }
+        let node = S::get_mut(node).expect("a fresh copy is not shared");
+        node.version = version;
+        node
     }
 
     /// Copy-on-write helper: returns the node if it's already the right version,
     /// or creates a new copy if it needs to be modified.
     fn ensure_cow_node(
-        node: Arc<VersionedNode<KeyType::PartialType, ValueType>>,
+        mut node: NodePtr<KeyType::PartialType, ValueType, S>,
         target_version: u64,
-    ) -> Arc<VersionedNode<KeyType::PartialType, ValueType>> {
-        if node.version == target_version {
+    ) -> NodePtr<KeyType::PartialType, ValueType, S> {
+        // A node written by this same mutation can still be briefly referenced twice, e.g.
+        // while a batch update holds a clone of it, so the version alone is not enough.
+        if node.version == target_version && S::get_mut(&mut node).is_some() {
             // Already at target version, no work needed
             node
         } else {
             // Check if we have exclusive ownership
-            match Arc::try_unwrap(node) {
+            match S::try_unwrap(node) {
                 Ok(mut owned_node) => {
                     // We have exclusive ownership - just update version in place
                     owned_node.version = target_version;
-                    Arc::new(owned_node)
+                    S::new(owned_node)
                 }
                 Err(shared_node) => {
                     // Node is shared - need actual CoW
-                    Arc::new(shared_node.cow_clone_inner(target_version))
+                    S::new(shared_node.cow_clone_inner(target_version))
                 }
             }
         }
@@ -2663,20 +4539,20 @@ where
     /// Returns (new_root, was_replaced).
     /// If old_value_out is Some, captures the replaced value (cloning if necessary).
     fn insert_recurse(
-        cur_node: Arc<VersionedNode<KeyType::PartialType, ValueType>>,
+        cur_node: NodePtr<KeyType::PartialType, ValueType, S>,
         key: &KeyType,
         value: ValueType,
         depth: usize,
         version: u64,
         old_value_out: Option<&mut Option<ValueType>>,
-    ) -> (Arc<VersionedNode<KeyType::PartialType, ValueType>>, bool) {
+    ) -> (NodePtr<KeyType::PartialType, ValueType, S>, bool) {
         let longest_common_prefix = cur_node.prefix.prefix_length_key(key, depth);
         let is_prefix_match =
             min(cur_node.prefix.len(), key.length_at(depth)) == longest_common_prefix;
 
         if is_prefix_match && cur_node.prefix.len() == key.length_at(depth) {
             let new_node = Self::ensure_cow_node(cur_node, version);
-            let mut new_node = match Arc::try_unwrap(new_node) {
+            let mut new_node = match S::try_unwrap(new_node) {
                 Ok(owned) => owned,
                 Err(_) => panic!("ensure_cow_node should have given us exclusive ownership"),
             };
@@ -2685,7 +4561,7 @@ where
             if let (Some(old_value_out), Some(old_value)) = (old_value_out, old_value) {
                 *old_value_out = Some(old_value);
             }
-            return (Arc::new(new_node), was_replaced);
+            return (S::new(new_node), was_replaced);
         }
 
         if is_prefix_match && cur_node.prefix.len() > key.length_at(depth) {
@@ -2697,8 +4573,8 @@ where
                 VersionedNode::new_inner(old_prefix.partial_before(longest_common_prefix), version);
             new_parent.value = Some(value);
             let edge = old_prefix.at(longest_common_prefix);
-            new_parent.add_child(edge, Arc::new(existing_node));
-            return (Arc::new(new_parent), false);
+            new_parent.add_child(edge, S::new(existing_node));
+            return (S::new(new_parent), false);
         }
 
         if !is_prefix_match {
@@ -2713,10 +4589,10 @@ where
             // Create the existing node with truncated prefix
             let mut existing_node_clone = cur_node.cow_clone_inner(version);
             existing_node_clone.prefix = cur_node.prefix.partial_after(longest_common_prefix);
-            let existing_arc = Arc::new(existing_node_clone);
+            let existing_arc = S::new(existing_node_clone);
 
             // Create new leaf
-            let new_leaf = Arc::new(VersionedNode::new_leaf(
+            let new_leaf = S::new(VersionedNode::new_leaf(
                 key.to_partial(depth + longest_common_prefix),
                 value,
                 version,
@@ -2731,23 +4607,23 @@ where
                 _ => unreachable!(),
             }
 
-            return (Arc::new(new_inner), false);
+            return (S::new(new_inner), false);
         }
 
         if cur_node.is_leaf() {
             let edge = key.at(depth + longest_common_prefix);
-            let new_leaf = Arc::new(VersionedNode::new_leaf(
+            let new_leaf = S::new(VersionedNode::new_leaf(
                 key.to_partial(depth + longest_common_prefix),
                 value,
                 version,
             ));
             let new_node = Self::ensure_cow_node(cur_node, version);
-            let mut new_node = match Arc::try_unwrap(new_node) {
+            let mut new_node = match S::try_unwrap(new_node) {
                 Ok(owned) => owned,
                 Err(_) => panic!("ensure_cow_node should have given us exclusive ownership"),
             };
             new_node.add_child(edge, new_leaf);
-            return (Arc::new(new_node), false);
+            return (S::new(new_node), false);
         }
 
         // Case 3: Need to recurse deeper
@@ -2755,7 +4631,7 @@ where
         let prefix_len = cur_node.prefix.len();
         let new_node = Self::ensure_cow_node(cur_node, version);
 
-        let mut new_node_mut = match Arc::try_unwrap(new_node) {
+        let mut new_node_mut = match S::try_unwrap(new_node) {
             Ok(owned) => owned,
             Err(_) => panic!("ensure_cow_node should have given us exclusive ownership"),
         };
@@ -2773,10 +4649,10 @@ where
 
             new_node_mut.add_child(k, new_child);
 
-            (Arc::new(new_node_mut), was_replaced)
+            (S::new(new_node_mut), was_replaced)
         } else {
             // Add new child - check if node needs to grow first
-            let new_leaf = Arc::new(VersionedNode::new_leaf(
+            let new_leaf = S::new(VersionedNode::new_leaf(
                 key.to_partial(depth + prefix_len),
                 value,
                 version,
@@ -2784,18 +4660,129 @@ where
 
             new_node_mut.add_child(k, new_leaf);
 
-            (Arc::new(new_node_mut), false)
+            (S::new(new_node_mut), false)
+        }
+    }
+
+    fn retain_by_presence<W: Clone>(
+        &mut self,
+        other: &VersionedAdaptiveRadixTree<KeyType, W, S>,
+        keep_present: bool,
+    ) {
+        let Some(root) = self.root.as_ref() else {
+            return;
+        };
+        let version = self.fresh_version();
+        let retained = match other.root.as_deref() {
+            Some(other_root) => Self::retain_against(root, 0, other_root, 0, keep_present, version),
+            None => Self::retain_unmatched(keep_present),
+        };
+        match retained {
+            Retained::Unchanged => return,
+            Retained::Emptied => self.root = None,
+            Retained::Rebuilt(root) => self.root = Some(root),
+        }
+        self.advance_to(version);
+    }
+
+    /// Filter the entries below `node`, matched `offset` bytes into its prefix against
+    /// `other`, keeping those whose presence in `other` equals `keep_present`. Only nodes on
+    /// changed paths are rebuilt, at `version`; everything else stays shared.
+    fn retain_against<R: NodeView>(
+        node: &NodePtr<KeyType::PartialType, ValueType, S>,
+        mut offset: usize,
+        other: &R,
+        mut other_offset: usize,
+        keep_present: bool,
+        version: u64,
+    ) -> Retained<KeyType::PartialType, ValueType, S> {
+        let prefix = node.prefix.as_ref();
+        let other_prefix = other.view_prefix();
+        while offset < prefix.len() && other_offset < other_prefix.len() {
+            if prefix[offset] != other_prefix[other_offset] {
+                return Self::retain_unmatched(keep_present);
+            }
+            offset += 1;
+            other_offset += 1;
+        }
+
+        if offset < prefix.len() {
+            return match other.view_child(prefix[offset]) {
+                Some(other_child) => {
+                    Self::retain_against(node, offset, other_child, 0, keep_present, version)
+                }
+                None => Self::retain_unmatched(keep_present),
+            };
+        }
+
+        let other_has_value = other_offset == other_prefix.len() && other.view_value().is_some();
+        let drop_value = node.value.is_some() && other_has_value != keep_present;
+        let mut changed = drop_value;
+        let outcomes: Vec<_> = node
+            .iter_arcs()
+            .map(|(edge, child)| {
+                let other_next = if other_offset < other_prefix.len() {
+                    (other_prefix[other_offset] == edge).then_some((other, other_offset))
+                } else {
+                    other.view_child(edge).map(|other_child| (other_child, 0))
+                };
+                let outcome = match other_next {
+                    Some((other_next, other_next_offset)) => Self::retain_against(
+                        child,
+                        0,
+                        other_next,
+                        other_next_offset,
+                        keep_present,
+                        version,
+                    ),
+                    None => Self::retain_unmatched(keep_present),
+                };
+                changed |= !matches!(outcome, Retained::Unchanged);
+                (edge, child, outcome)
+            })
+            .collect();
+        if !changed {
+            return Retained::Unchanged;
+        }
+
+        let value = if drop_value { None } else { node.value.clone() };
+        let children: Vec<_> = outcomes
+            .into_iter()
+            .filter_map(|(edge, child, outcome)| match outcome {
+                Retained::Unchanged => Some((edge, Clone::clone(child))),
+                Retained::Emptied => None,
+                Retained::Rebuilt(child) => Some((edge, child)),
+            })
+            .collect();
+        if value.is_none() && children.is_empty() {
+            return Retained::Emptied;
+        }
+        Retained::Rebuilt(S::new(VersionedNode::from_sorted_children(
+            node.prefix.partial_before(node.prefix.len()),
+            value,
+            children,
+            version,
+        )))
+    }
+
+    /// Apply a retain to a subtree `other` has no keys in: drop it whole when keeping only
+    /// present keys, otherwise leave it shared as is.
+    fn retain_unmatched(keep_present: bool) -> Retained<KeyType::PartialType, ValueType, S> {
+        if keep_present {
+            Retained::Emptied
+        } else {
+            Retained::Unchanged
         }
     }
 
     /// Remove with copy-on-write semantics.
     /// Returns (new_root_option, removed_value).
     fn remove_recurse(
-        cur_node: Arc<VersionedNode<KeyType::PartialType, ValueType>>,
+        cur_node: NodePtr<KeyType::PartialType, ValueType, S>,
         key: &KeyType,
         depth: usize,
         version: u64,
-    ) -> Option<RemoveResult<KeyType::PartialType, ValueType>> {
+    ) -> Option<RemoveResult<KeyType::PartialType, ValueType, S>> {
         // Check prefix match
         let prefix_common_match = cur_node.prefix.prefix_length_key(key, depth);
         if prefix_common_match != cur_node.prefix.len() {
@@ -2809,7 +4796,7 @@ where
             }
 
             let new_node = Self::ensure_cow_node(cur_node, version);
-            let mut new_node = match Arc::try_unwrap(new_node) {
+            let mut new_node = match S::try_unwrap(new_node) {
                 Ok(owned) => owned,
                 Err(_) => panic!("ensure_cow_node should have given us exclusive ownership"),
             };
@@ -2817,7 +4804,7 @@ where
             if new_node.num_children() == 0 && new_node.value.is_none() {
                 return Some((None, removed_value));
             }
-            return Some((Some(Arc::new(new_node)), removed_value));
+            return Some((Some(S::new(new_node)), removed_value));
         }
 
         if cur_node.is_leaf() {
@@ -2830,7 +4817,7 @@ where
 
         let new_node = Self::ensure_cow_node(cur_node, version);
 
-        let mut new_node_mut = match Arc::try_unwrap(new_node) {
+        let mut new_node_mut = match S::try_unwrap(new_node) {
             Ok(owned) => owned,
             Err(_) => panic!("ensure_cow_node should have given us exclusive ownership"),
         };
@@ -2848,16 +4835,16 @@ where
             return Some((None, removed_value));
         }
 
-        Some((Some(Arc::new(new_node_mut)), removed_value))
+        Some((Some(S::new(new_node_mut)), removed_value))
     }
 
     /// Delete with copy-on-write semantics without returning the removed value.
     fn delete_recurse(
-        cur_node: Arc<VersionedNode<KeyType::PartialType, ValueType>>,
+        cur_node: NodePtr<KeyType::PartialType, ValueType, S>,
         key: &KeyType,
         depth: usize,
         version: u64,
-    ) -> Option<DeleteResult<KeyType::PartialType, ValueType>> {
+    ) -> Option<DeleteResult<KeyType::PartialType, ValueType, S>> {
         let prefix_common_match = cur_node.prefix.prefix_length_key(key, depth);
         if prefix_common_match != cur_node.prefix.len() {
             return None;
@@ -2869,7 +4856,7 @@ where
             }
 
             let new_node = Self::ensure_cow_node(cur_node, version);
-            let mut new_node = match Arc::try_unwrap(new_node) {
+            let mut new_node = match S::try_unwrap(new_node) {
                 Ok(owned) => owned,
                 Err(_) => panic!("ensure_cow_node should have given us exclusive ownership"),
             };
@@ -2878,7 +4865,7 @@ where
             if new_node.num_children() == 0 {
                 return Some(None);
             }
-            return Some(Some(Arc::new(new_node)));
+            return Some(Some(S::new(new_node)));
         }
 
         if cur_node.is_leaf() {
@@ -2889,7 +4876,7 @@ where
         let prefix_len = cur_node.prefix.len();
 
         let new_node = Self::ensure_cow_node(cur_node, version);
-        let mut new_node_mut = match Arc::try_unwrap(new_node) {
+        let mut new_node_mut = match S::try_unwrap(new_node) {
             Ok(owned) => owned,
             Err(_) => panic!("ensure_cow_node should have given us exclusive ownership"),
         };
@@ -2906,7 +4893,7 @@ where
             return Some(None);
         }
 
-        Some(Some(Arc::new(new_node_mut)))
+        Some(Some(S::new(new_node_mut)))
     }
 
     fn versioned_vacant_update<F>(update: &mut Option<F>) -> Option<ValueType>
@@ -2925,12 +4912,12 @@ where
     /// Update with copy-on-write semantics.
     /// Returns (new_node, changed).
     fn update_recurse<F>(
-        cur_node: Arc<VersionedNode<KeyType::PartialType, ValueType>>,
+        cur_node: NodePtr<KeyType::PartialType, ValueType, S>,
         key: &KeyType,
         depth: usize,
         version: u64,
         update: &mut Option<F>,
-    ) -> UpdateResult<KeyType::PartialType, ValueType>
+    ) -> UpdateResult<KeyType::PartialType, ValueType, S>
     where
         F: FnOnce(Slot<'_, ValueType>) -> SlotUpdate<ValueType>,
     {
@@ -2944,16 +4931,16 @@ where
                     return (Some(cur_node), false);
                 };
                 let new_node = Self::ensure_cow_node(cur_node, version);
-                let mut new_node = match Arc::try_unwrap(new_node) {
+                let mut new_node = match S::try_unwrap(new_node) {
                     Ok(owned) => owned,
                     Err(_) => panic!("ensure_cow_node should have given us exclusive ownership"),
                 };
                 new_node.value = Some(value);
-                return (Some(Arc::new(new_node)), true);
+                return (Some(S::new(new_node)), true);
             }
 
             let new_node = Self::ensure_cow_node(cur_node, version);
-            let mut new_node = match Arc::try_unwrap(new_node) {
+            let mut new_node = match S::try_unwrap(new_node) {
                 Ok(owned) => owned,
                 Err(_) => panic!("ensure_cow_node should have given us exclusive ownership"),
             };
@@ -2970,17 +4957,17 @@ where
             };
 
             match action {
-                SlotUpdate::Keep => (Some(Arc::new(new_node)), true),
+                SlotUpdate::Keep => (Some(S::new(new_node)), true),
                 SlotUpdate::Insert(value) => {
                     new_node.value = Some(value);
-                    (Some(Arc::new(new_node)), true)
+                    (Some(S::new(new_node)), true)
                 }
                 SlotUpdate::Remove => {
                     new_node.value = None;
                     if new_node.num_children() == 0 {
                         (None, true)
                     } else {
-                        (Some(Arc::new(new_node)), true)
+                        (Some(S::new(new_node)), true)
                     }
                 }
             }
@@ -2996,8 +4983,8 @@ where
                 VersionedNode::new_inner(old_prefix.partial_before(longest_common_prefix), version);
             new_parent.value = Some(value);
             let edge = old_prefix.at(longest_common_prefix);
-            new_parent.add_child(edge, Arc::new(existing_node));
-            (Some(Arc::new(new_parent)), true)
+            new_parent.add_child(edge, S::new(existing_node));
+            (Some(S::new(new_parent)), true)
         } else if !is_prefix_match {
             let Some(value) = Self::versioned_vacant_update(update) else {
                 return (Some(cur_node), false);
@@ -3012,9 +4999,9 @@ where
 
             let mut existing_node_clone = cur_node.cow_clone_inner(version);
             existing_node_clone.prefix = cur_node.prefix.partial_after(longest_common_prefix);
-            let existing_arc = Arc::new(existing_node_clone);
+            let existing_arc = S::new(existing_node_clone);
 
-            let new_leaf = Arc::new(VersionedNode::new_leaf(
+            let new_leaf = S::new(VersionedNode::new_leaf(
                 key.to_partial(depth + longest_common_prefix),
                 value,
                 version,
@@ -3028,24 +5015,24 @@ where
                 _ => unreachable!(),
             }
 
-            (Some(Arc::new(new_inner)), true)
+            (Some(S::new(new_inner)), true)
         } else if cur_node.is_leaf() {
             let Some(value) = Self::versioned_vacant_update(update) else {
                 return (Some(cur_node), false);
             };
             let edge = key.at(depth + longest_common_prefix);
-            let new_leaf = Arc::new(VersionedNode::new_leaf(
+            let new_leaf = S::new(VersionedNode::new_leaf(
                 key.to_partial(depth + longest_common_prefix),
                 value,
                 version,
             ));
             let new_node = Self::ensure_cow_node(cur_node, version);
-            let mut new_node = match Arc::try_unwrap(new_node) {
+            let mut new_node = match S::try_unwrap(new_node) {
                 Ok(owned) => owned,
                 Err(_) => panic!("ensure_cow_node should have given us exclusive ownership"),
             };
             new_node.add_child(edge, new_leaf);
-            (Some(Arc::new(new_node)), true)
+            (Some(S::new(new_node)), true)
         } else {
             let k = key.at(depth + cur_node.prefix.len());
             let prefix_len = cur_node.prefix.len();
@@ -3054,18 +5041,18 @@ where
                 let Some(value) = Self::versioned_vacant_update(update) else {
                     return (Some(cur_node), false);
                 };
-                let new_leaf = Arc::new(VersionedNode::new_leaf(
+                let new_leaf = S::new(VersionedNode::new_leaf(
                     key.to_partial(depth + prefix_len),
                     value,
                     version,
                 ));
                 let new_node = Self::ensure_cow_node(cur_node, version);
-                let mut new_node = match Arc::try_unwrap(new_node) {
+                let mut new_node = match S::try_unwrap(new_node) {
                     Ok(owned) => owned,
                     Err(_) => panic!("ensure_cow_node should have given us exclusive ownership"),
                 };
                 new_node.add_child(k, new_leaf);
-                return (Some(Arc::new(new_node)), true);
+                return (Some(S::new(new_node)), true);
             };
 
             let (new_child_opt, changed) =
@@ -3075,7 +5062,7 @@ where
             }
 
             let new_node = Self::ensure_cow_node(cur_node, version);
-            let mut new_node_mut = match Arc::try_unwrap(new_node) {
+            let mut new_node_mut = match S::try_unwrap(new_node) {
                 Ok(owned) => owned,
                 Err(_) => panic!("ensure_cow_node should have given us exclusive ownership"),
             };
@@ -3087,7 +5074,7 @@ where
             if new_node_mut.num_children() == 0 && new_node_mut.value.is_none() {
                 (None, true)
             } else {
-                (Some(Arc::new(new_node_mut)), true)
+                (Some(S::new(new_node_mut)), true)
             }
         }
     }
@@ -3096,7 +5083,8 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::keys::{array_key::ArrayKey, overflow_key::OverflowKey};
+    use crate::keys::{array_key::ArrayKey, overflow_key::OverflowKey, vector_key::VectorKey};
+    use crate::transaction::Transaction;
     use crate::{Slot, SlotUpdate, VisitControl};
     use proptest::prelude::*;
     use std::collections::BTreeSet;
@@ -3114,7 +5102,7 @@ mod tests {
     #[test]
     fn boxed_versioned_content_keeps_node_header_small() {
         type P = <ArrayKey<16> as crate::keys::KeyTrait>::PartialType;
-        let content_size = size_of::<VersionedContent<P, u64>>();
+        let content_size = size_of::<VersionedContent<P, u64, DefaultPtr>>();
         let node_size = size_of::<VersionedNode<P, u64>>();
 
         println!("VersionedContent<P, u64> = {content_size} bytes");
@@ -3305,6 +5293,459 @@ mod tests {
                 assert_versioned_tree_matches_map(snapshot, snapshot_map);
             }
         }
+
+        #[test]
+        fn prop_persistent_tree_matches_arc_tree(
+            ops in proptest::collection::vec(versioned_op_strategy(), 0..96)
+        ) {
+            let mut arc_tree = VersionedAdaptiveRadixTree::<ArrayKey<16>, u16>::new();
+            let mut rc_tree = PersistentAdaptiveRadixTree::<ArrayKey<16>, u16>::new();
+            let mut arc_snapshots = Vec::new();
+            let mut rc_snapshots = Vec::new();
+
+            for op in ops {
+                match op {
+                    VersionedOp::Get { key } => {
+                        prop_assert_eq!(rc_tree.get(key), arc_tree.get(key));
+                    }
+                    VersionedOp::Insert { key, value } => {
+                        prop_assert_eq!(rc_tree.insert(key, value), arc_tree.insert(key, value));
+                    }
+                    VersionedOp::Remove { key } => {
+                        prop_assert_eq!(rc_tree.remove(key), arc_tree.remove(key));
+                    }
+                    VersionedOp::Snapshot => {
+                        arc_snapshots.push(arc_tree.snapshot());
+                        rc_snapshots.push(rc_tree.snapshot());
+                    }
+                    VersionedOp::SnapshotInsert {
+                        snapshot_idx,
+                        key,
+                        value,
+                    } => {
+                        if !arc_snapshots.is_empty() {
+                            let idx = snapshot_idx as usize % arc_snapshots.len();
+                            prop_assert_eq!(
+                                rc_snapshots[idx].insert(key, value),
+                                arc_snapshots[idx].insert(key, value)
+                            );
+                        }
+                    }
+                    VersionedOp::SnapshotRemove { snapshot_idx, key } => {
+                        if !arc_snapshots.is_empty() {
+                            let idx = snapshot_idx as usize % arc_snapshots.len();
+                            prop_assert_eq!(
+                                rc_snapshots[idx].remove(key),
+                                arc_snapshots[idx].remove(key)
+                            );
+                        }
+                    }
+                }
+            }
+
+            arc_snapshots.push(arc_tree);
+            rc_snapshots.push(rc_tree);
+            for (arc_tree, rc_tree) in arc_snapshots.iter().zip(rc_snapshots.iter()) {
+                prop_assert!(rc_tree.iter().eq(arc_tree.iter()));
+                prop_assert_eq!(rc_tree.snapshot_stats(), arc_tree.snapshot_stats());
+            }
+        }
+
+        #[test]
+        fn prop_union_matches_reference_model_and_keeps_inputs_isolated(
+            left_entries in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'd', 1..=6), any::<u8>()),
+                0..64
+            ),
+            right_entries in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'd', 1..=6), any::<u8>()),
+                0..64
+            )
+        ) {
+            let mut left = VersionedAdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut right = VersionedAdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut left_map = std::collections::BTreeMap::<Vec<u8>, u8>::new();
+            let mut right_map = std::collections::BTreeMap::<Vec<u8>, u8>::new();
+            for (key, value) in left_entries {
+                left.insert_k(&ArrayKey::new_from_slice(&key), value);
+                left_map.insert(key, value);
+            }
+            for (key, value) in right_entries {
+                right.insert_k(&ArrayKey::new_from_slice(&key), value);
+                right_map.insert(key, value);
+            }
+
+            let mut expected = left_map.clone();
+            for (key, right_value) in &right_map {
+                expected
+                    .entry(key.clone())
+                    .and_modify(|left_value| *left_value = left_value.wrapping_sub(*right_value))
+                    .or_insert(*right_value);
+            }
+
+            let mut union = left.union_with(&right, |_key, l, r| l.wrapping_sub(*r));
+            prop_assert!(union.version() > left.version());
+            prop_assert!(union.version() > right.version());
+            let collect = |tree: &VersionedAdaptiveRadixTree<ArrayKey<8>, u8>| -> Vec<_> {
+                tree.iter()
+                    .map(|(key, value)| {
+                        let bytes = key.as_ref();
+                        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+                        (bytes[..end].to_vec(), *value)
+                    })
+                    .collect()
+            };
+            prop_assert_eq!(collect(&union), expected.clone().into_iter().collect::<Vec<_>>());
+
+            // Mutating the union must not leak into the subtrees it shares with its inputs.
+            for key in expected.keys() {
+                union.insert_k(&ArrayKey::new_from_slice(key), 0);
+            }
+            prop_assert_eq!(collect(&left), left_map.into_iter().collect::<Vec<_>>());
+            prop_assert_eq!(collect(&right), right_map.into_iter().collect::<Vec<_>>());
+
+            let mut merged = left.clone();
+            merged.merge_from_ref(&right, |_key, l, r| l.wrapping_sub(*r));
+            prop_assert_eq!(collect(&merged), expected.clone().into_iter().collect::<Vec<_>>());
+
+            let mut merged = left.clone();
+            merged.merge_from(right, |_key, l, r| l.wrapping_sub(r));
+            prop_assert_eq!(collect(&merged), expected.into_iter().collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn prop_diff_matches_reference_model(
+            base_entries in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'd', 1..=6), any::<u8>()),
+                0..64
+            ),
+            edits in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'd', 1..=6), proptest::option::of(0u8..4)),
+                0..32
+            ),
+            share_base in any::<bool>()
+        ) {
+            let mut older = VersionedAdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut older_map = std::collections::BTreeMap::<Vec<u8>, u8>::new();
+            for (key, value) in base_entries {
+                older.insert_k(&ArrayKey::new_from_slice(&key), value);
+                older_map.insert(key, value);
+            }
+            // Without sharing, the newer tree is built from scratch and no subtree can be
+            // skipped by pointer equality.
+            let mut newer = if share_base {
+                older.snapshot()
+            } else {
+                let mut tree = VersionedAdaptiveRadixTree::new();
+                for (key, value) in &older_map {
+                    tree.insert_k(&ArrayKey::new_from_slice(key), *value);
+                }
+                tree
+            };
+            let mut newer_map = older_map.clone();
+            for (key, value) in edits {
+                match value {
+                    Some(value) => {
+                        newer.insert_k(&ArrayKey::new_from_slice(&key), value);
+                        newer_map.insert(key, value);
+                    }
+                    None => {
+                        newer.remove_k(&ArrayKey::new_from_slice(&key));
+                        newer_map.remove(&key);
+                    }
+                }
+            }
+
+            let keys: BTreeSet<&Vec<u8>> = older_map.keys().chain(newer_map.keys()).collect();
+            let expected: Vec<_> = keys
+                .into_iter()
+                .filter_map(|key| match (newer_map.get(key), older_map.get(key)) {
+                    (Some(new), Some(old)) if new != old => {
+                        Some(Change::Modified { key: key.clone(), old: *old, new: *new })
+                    }
+                    (Some(value), None) => Some(Change::Added { key: key.clone(), value: *value }),
+                    (None, Some(value)) => {
+                        Some(Change::Removed { key: key.clone(), value: *value })
+                    }
+                    _ => None,
+                })
+                .collect();
+            let got: Vec<_> = newer
+                .diff(&older)
+                .map(|change| match change {
+                    Change::Added { key, value } => Change::Added {
+                        key: key.as_ref().to_vec(),
+                        value: *value,
+                    },
+                    Change::Removed { key, value } => Change::Removed {
+                        key: key.as_ref().to_vec(),
+                        value: *value,
+                    },
+                    Change::Modified { key, old, new } => Change::Modified {
+                        key: key.as_ref().to_vec(),
+                        old: *old,
+                        new: *new,
+                    },
+                })
+                .collect();
+            prop_assert_eq!(got, expected);
+            prop_assert_eq!(older.diff(&older).count(), 0);
+        }
+
+        #[test]
+        fn prop_changed_since_covers_every_write(
+            base_entries in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'd', 1..=6), any::<u8>()),
+                0..64
+            ),
+            edits in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'd', 1..=6), proptest::option::of(0u8..4)),
+                0..16
+            ),
+            from_snapshot in any::<bool>()
+        ) {
+            let mut base = VersionedAdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut base_map = std::collections::BTreeMap::<Vec<u8>, u8>::new();
+            for (key, value) in base_entries {
+                base.insert_k(&ArrayKey::new_from_slice(&key), value);
+                base_map.insert(key, value);
+            }
+            let checkpoint = base.version();
+            let mut tree = if from_snapshot { base.snapshot() } else { base.clone() };
+            let mut map = base_map.clone();
+            for (key, value) in edits {
+                match value {
+                    Some(value) => {
+                        tree.insert_k(&ArrayKey::new_from_slice(&key), value);
+                        map.insert(key, value);
+                    }
+                    None => {
+                        tree.remove_k(&ArrayKey::new_from_slice(&key));
+                        map.remove(&key);
+                    }
+                }
+            }
+
+            let changed: Vec<(Vec<u8>, u8)> = tree
+                .changed_since(checkpoint)
+                .map(|(key, value)| (key.as_ref().to_vec(), *value))
+                .collect();
+            prop_assert!(changed.windows(2).all(|pair| pair[0].0 < pair[1].0));
+            for (key, value) in &changed {
+                prop_assert_eq!(map.get(key), Some(value));
+            }
+            let changed_keys: BTreeSet<&Vec<u8>> = changed.iter().map(|(key, _)| key).collect();
+            for (key, value) in &map {
+                if base_map.get(key) != Some(value) {
+                    prop_assert!(changed_keys.contains(key), "missing write to {:?}", key);
+                }
+            }
+            prop_assert_eq!(base.changed_since(checkpoint).count(), 0);
+            prop_assert_eq!(tree.changed_since(tree.version()).count(), 0);
+        }
+
+        #[test]
+        fn prop_write_batch_matches_sequential_writes(
+            base_entries in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'd', 1..=6), any::<u8>()),
+                0..64
+            ),
+            ops in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'd', 1..=6), 0u8..3, any::<u8>()),
+                0..32
+            ),
+            fail_at in proptest::option::of(0usize..32)
+        ) {
+            let mut tree = VersionedAdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut map = std::collections::BTreeMap::<Vec<u8>, u8>::new();
+            for (key, value) in base_entries {
+                tree.insert_k(&ArrayKey::new_from_slice(&key), value);
+                map.insert(key, value);
+            }
+            let snapshot = tree.snapshot();
+            let before = tree.version();
+
+            // Ops are applied in key order, so a batch that fails anywhere fails as a whole.
+            let fail_at = fail_at.filter(|at| *at < ops.len());
+            let mut batch = WriteBatch::new();
+            let mut expected = map.clone();
+            for (i, (key, kind, value)) in ops.into_iter().enumerate() {
+                let k = ArrayKey::new_from_slice(&key);
+                if Some(i) == fail_at {
+                    batch.try_update_k(&k, move |_| Err(i));
+                    continue;
+                }
+                match kind {
+                    0 => {
+                        batch.insert_k(&k, value);
+                        expected.insert(key, value);
+                    }
+                    1 => {
+                        batch.remove_k(&k);
+                        expected.remove(&key);
+                    }
+                    _ => {
+                        batch.update_k(&k, move |slot| match slot {
+                            Slot::Occupied(current) => {
+                                *current = current.wrapping_add(value);
+                                SlotUpdate::Keep
+                            }
+                            Slot::Vacant => SlotUpdate::Insert(value),
+                        });
+                        let entry = expected.entry(key).or_insert(0);
+                        *entry = entry.wrapping_add(value);
+                    }
+                }
+            }
+
+            let result = tree.apply_batch(batch);
+            if let Some(at) = fail_at {
+                prop_assert_eq!(result, Err(at));
+                expected = map.clone();
+                prop_assert_eq!(tree.version(), before);
+            } else {
+                prop_assert_eq!(result, Ok(()));
+            }
+            let got: Vec<(Vec<u8>, u8)> =
+                tree.iter().map(|(key, value)| (key.as_ref().to_vec(), *value)).collect();
+            prop_assert_eq!(got, expected.into_iter().collect::<Vec<_>>());
+            let snapshot_entries: Vec<(Vec<u8>, u8)> =
+                snapshot.iter().map(|(key, value)| (key.as_ref().to_vec(), *value)).collect();
+            prop_assert_eq!(snapshot_entries, map.into_iter().collect::<Vec<_>>());
+            if tree.version() != before {
+                prop_assert_eq!(tree.parent_version(), Some(before));
+            }
+        }
+
+        #[test]
+        fn prop_merge3_matches_reference_model(
+            base_entries in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'd', 1..=6), any::<u8>()),
+                0..64
+            ),
+            our_edits in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'd', 1..=6), proptest::option::of(0u8..4)),
+                0..16
+            ),
+            their_edits in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'd', 1..=6), proptest::option::of(0u8..4)),
+                0..16
+            )
+        ) {
+            let mut base = VersionedAdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut base_map = std::collections::BTreeMap::<Vec<u8>, u8>::new();
+            for (key, value) in base_entries {
+                base.insert_k(&ArrayKey::new_from_slice(&key), value);
+                base_map.insert(key, value);
+            }
+            let fork = |edits: Vec<(Vec<u8>, Option<u8>)>| {
+                let mut tree = base.snapshot();
+                let mut map = base_map.clone();
+                for (key, value) in edits {
+                    match value {
+                        Some(value) => {
+                            tree.insert_k(&ArrayKey::new_from_slice(&key), value);
+                            map.insert(key, value);
+                        }
+                        None => {
+                            tree.remove_k(&ArrayKey::new_from_slice(&key));
+                            map.remove(&key);
+                        }
+                    }
+                }
+                (tree, map)
+            };
+            let (ours, our_map) = fork(our_edits);
+            let (theirs, their_map) = fork(their_edits);
+
+            // Conflicts keep the larger value; a conflict where either side deleted the key
+            // deletes it.
+            let resolve = |b: Option<&u8>, o: Option<&u8>, t: Option<&u8>| {
+                if o == b {
+                    t.copied()
+                } else if t == b || o == t {
+                    o.copied()
+                } else {
+                    Some(*o?.max(t?))
+                }
+            };
+            let keys: BTreeSet<&Vec<u8>> =
+                base_map.keys().chain(our_map.keys()).chain(their_map.keys()).collect();
+            let expected: Vec<(Vec<u8>, u8)> = keys
+                .into_iter()
+                .filter_map(|key| {
+                    resolve(base_map.get(key), our_map.get(key), their_map.get(key))
+                        .map(|value| (key.clone(), value))
+                })
+                .collect();
+
+            let merged = VersionedAdaptiveRadixTree::merge3(&base, &ours, &theirs, |key, b, o, t| {
+                assert!(o != b && t != b && o != t, "resolver called for {key:?} without a conflict");
+                Some(*o?.max(t?))
+            });
+            let got: Vec<(Vec<u8>, u8)> =
+                merged.iter().map(|(key, value)| (key.as_ref().to_vec(), *value)).collect();
+            prop_assert_eq!(got, expected);
+            prop_assert!(merged.version() > ours.version().max(theirs.version()));
+
+            // Merging against an untouched side hands back the other side's nodes.
+            let unchanged = VersionedAdaptiveRadixTree::merge3(&base, &ours, &base, |_, _, _, _| {
+                unreachable!("no conflicts against an untouched side")
+            });
+            match (&unchanged.root, &ours.root) {
+                (Some(merged_root), Some(our_root)) => {
+                    prop_assert!(DefaultPtr::ptr_eq(merged_root, our_root))
+                }
+                (merged_root, our_root) => prop_assert_eq!(merged_root.is_none(), our_root.is_none()),
+            }
+        }
+
+        #[test]
+        fn prop_semi_and_anti_joins_match_reference_model_and_keep_snapshots(
+            left_entries in proptest::collection::vec(
+                (proptest::collection::vec(b'a'..=b'd', 1..=6), any::<u8>()),
+                0..64
+            ),
+            right_keys in proptest::collection::vec(
+                proptest::collection::vec(b'a'..=b'd', 1..=6),
+                0..64
+            )
+        ) {
+            let mut left = VersionedAdaptiveRadixTree::<ArrayKey<8>, u8>::new();
+            let mut right = VersionedAdaptiveRadixTree::<ArrayKey<8>, bool>::new();
+            let mut left_map = std::collections::BTreeMap::<Vec<u8>, u8>::new();
+            for (key, value) in left_entries {
+                left.insert_k(&ArrayKey::new_from_slice(&key), value);
+                left_map.insert(key, value);
+            }
+            for key in &right_keys {
+                right.insert_k(&ArrayKey::new_from_slice(key), true);
+            }
+
+            let (present, absent): (Vec<_>, Vec<_>) = left_map
+                .clone()
+                .into_iter()
+                .partition(|(key, _)| right_keys.contains(key));
+            let collect = |iter: &mut dyn Iterator<Item = (ArrayKey<8>, &u8)>| -> Vec<_> {
+                iter.map(|(key, value)| (key.as_ref().to_vec(), *value)).collect()
+            };
+            prop_assert_eq!(&collect(&mut left.semi_join_iter(&right)), &present);
+            prop_assert_eq!(&collect(&mut left.anti_join_iter(&right)), &absent);
+
+            let mut semi = left.snapshot();
+            semi.retain_intersecting(&right);
+            prop_assert_eq!(&collect(&mut semi.iter()), &present);
+            let mut anti = left.snapshot();
+            anti.retain_not_in(&right);
+            prop_assert_eq!(&collect(&mut anti.iter()), &absent);
+            for (key, _) in &absent {
+                anti.insert_k(&ArrayKey::new_from_slice(key), 0);
+            }
+            prop_assert_eq!(
+                collect(&mut left.iter()),
+                left_map.into_iter().collect::<Vec<_>>()
+            );
+        }
     }
 
     proptest! {
@@ -3401,7 +5842,289 @@ mod tests {
         // Take snapshot
         let snapshot = tree.snapshot();
         assert_eq!(snapshot.get("key1"), Some(&1));
-        assert_eq!(snapshot.version(), tree.version() + 1);
+        assert!(snapshot.version() > tree.version());
+        assert_eq!(snapshot.parent_version(), Some(tree.version()));
+    }
+
+    #[test]
+    fn versions_are_unique_across_siblings_and_track_parents() {
+        let mut tree = VersionedAdaptiveRadixTree::<ArrayKey<16>, i32>::new();
+        assert_eq!(tree.parent_version(), None);
+        let created = tree.version();
+        tree.insert("key1", 1);
+        assert!(tree.version() > created);
+        assert_eq!(tree.parent_version(), Some(created));
+
+        let mut left = tree.snapshot();
+        let mut right = tree.snapshot();
+        assert_ne!(left.version(), right.version());
+        assert_eq!(left.parent_version(), Some(tree.version()));
+        assert_eq!(right.parent_version(), Some(tree.version()));
+
+        // Sibling forks write nodes under different versions.
+        let (left_before, right_before) = (left.version(), right.version());
+        left.insert("key2", 2);
+        right.insert("key2", 2);
+        assert_ne!(left.version(), right.version());
+        assert_eq!(left.parent_version(), Some(left_before));
+        assert_eq!(right.parent_version(), Some(right_before));
+        let left_root = left.root.as_ref().unwrap();
+        let right_root = right.root.as_ref().unwrap();
+        assert_eq!(left_root.version, left.version());
+        assert_eq!(right_root.version, right.version());
+
+        // A no-op update keeps the version; a clone shares it.
+        let version = left.version();
+        assert!(!left.update("missing", |_| SlotUpdate::Keep));
+        assert_eq!(left.version(), version);
+        assert_eq!(left.clone().version(), version);
+
+        let merged = left.union_with(&right, |_, a, _| *a);
+        assert!(merged.version() > left.version().max(right.version()));
+        assert_eq!(merged.parent_version(), Some(left.version()));
+    }
+
+    #[test]
+    fn each_tree_family_counts_its_own_versions() {
+        let mut busy = VersionedAdaptiveRadixTree::<ArrayKey<16>, i32>::new();
+        for i in 0..10 {
+            busy.insert(i, i);
+        }
+        let mut quiet = VersionedAdaptiveRadixTree::<ArrayKey<16>, i32>::new();
+        assert_eq!(quiet.version(), 1);
+        quiet.insert(100, 100);
+        assert_eq!(quiet.version(), 2);
+        let snapshot = quiet.snapshot();
+        assert_eq!(snapshot.version(), 3);
+
+        // The union adopts `busy`'s nodes, so its family jumps past `busy`'s versions.
+        let mut union = quiet.union_with(&busy, |_, a, _| *a);
+        assert!(union.version() > busy.version());
+        let before = union.version();
+        union.insert(200, 200);
+        assert!(union.version() > before);
+        assert!(quiet.snapshot().version() > union.version());
+        assert_eq!(union.changed_since(before).count(), 1);
+    }
+
+    #[test]
+    fn persistent_tree_shares_structure_through_rc() {
+        let mut base = PersistentAdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+        for i in 0..500u32 {
+            base.insert(i, i);
+        }
+        let mut ours = base.snapshot();
+        let mut theirs = base.snapshot();
+        ours.insert(1u32, 10);
+        theirs.insert(400u32, 4000);
+        theirs.remove(2u32);
+
+        let sharing = ours.shared_with(&base);
+        assert!(sharing.shared_nodes > 0);
+        assert!(sharing.shared_nodes < base.snapshot_stats().total_nodes);
+        assert_eq!(
+            ours.diff(&base).collect::<Vec<_>>(),
+            vec![Change::Modified {
+                key: ArrayKey::from(1u32),
+                old: &1,
+                new: &10
+            }]
+        );
+
+        let merged =
+            PersistentAdaptiveRadixTree::merge3(&base, &ours, &theirs, |_, _, o, _| o.copied());
+        assert_eq!(merged.get(1u32), Some(&10));
+        assert_eq!(merged.get(2u32), None);
+        assert_eq!(merged.get(400u32), Some(&4000));
+        assert_eq!(merged.iter().count(), 499);
+
+        let mut transaction = Transaction::begin(&merged);
+        transaction.insert(3u32, 30);
+        let mut head = merged.clone();
+        transaction.commit(&mut head).unwrap();
+        assert_eq!(head.get(3u32), Some(&30));
+        assert_eq!(merged.get(3u32), Some(&3));
+    }
+
+    #[test]
+    fn snapshot_stats_track_sharing_and_freed_bytes() {
+        let mut tree = VersionedAdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+        assert_eq!(tree.snapshot_stats(), SnapshotStats::default());
+        for i in 0..1000u32 {
+            tree.insert(i, i);
+        }
+
+        let stats = tree.snapshot_stats();
+        assert_eq!(stats.unique_nodes, stats.total_nodes);
+        assert_eq!(stats.shared_nodes, 0);
+        assert_eq!(stats.freed_on_drop_nodes, stats.total_nodes);
+        assert_eq!(stats.freed_on_drop_bytes, stats.total_bytes);
+
+        // A clone shares the root, so dropping either handle frees nothing.
+        let clone = tree.clone();
+        let stats = tree.snapshot_stats();
+        assert_eq!(stats.shared_nodes, 1);
+        assert_eq!(stats.freed_on_drop_nodes, 0);
+        assert_eq!(stats.freed_on_drop_bytes, 0);
+        drop(clone);
+
+        let snapshot = tree.snapshot();
+        tree.insert(5u32, 50);
+        let stats = tree.snapshot_stats();
+        let snapshot_stats = snapshot.snapshot_stats();
+        let sharing = tree.shared_with(&snapshot);
+        assert_eq!(sharing, snapshot.shared_with(&tree));
+        assert_eq!(
+            stats.freed_on_drop_nodes,
+            stats.total_nodes - sharing.shared_nodes
+        );
+        assert_eq!(
+            stats.freed_on_drop_bytes,
+            stats.total_bytes - sharing.shared_bytes
+        );
+        assert_eq!(
+            snapshot_stats.freed_on_drop_bytes,
+            snapshot_stats.total_bytes - sharing.shared_bytes
+        );
+        assert!(stats.shared_nodes > 0);
+
+        drop(tree);
+        let stats = snapshot.snapshot_stats();
+        assert_eq!(stats.freed_on_drop_bytes, stats.total_bytes);
+        assert_eq!(
+            snapshot.shared_with(&VersionedAdaptiveRadixTree::new()),
+            SharingStats::default()
+        );
+    }
+
+    #[test]
+    fn shared_with_matches_pointer_set_after_splits_and_merges() {
+        type Tree = VersionedAdaptiveRadixTree<ArrayKey<16>, u32>;
+        fn collect(
+            node: &NodePtr<crate::partials::array_partial::ArrPartial<16>, u32, DefaultPtr>,
+            out: &mut Vec<usize>,
+        ) {
+            out.push(DefaultPtr::as_ptr(node) as usize);
+            for (_, child) in node.iter_arcs() {
+                collect(child, out);
+            }
+        }
+        fn reference(left: &Tree, right: &Tree) -> usize {
+            let (mut left_nodes, mut right_nodes) = (Vec::new(), Vec::new());
+            collect(left.root.as_ref().unwrap(), &mut left_nodes);
+            collect(right.root.as_ref().unwrap(), &mut right_nodes);
+            let right_nodes: std::collections::HashSet<_> = right_nodes.into_iter().collect();
+            left_nodes
+                .iter()
+                .filter(|node| right_nodes.contains(node))
+                .count()
+        }
+
+        let mut tree = Tree::new();
+        for (i, word) in ["apple", "apricot", "banana", "band", "bandana", "cherry"]
+            .into_iter()
+            .enumerate()
+        {
+            tree.insert(word, i as u32);
+        }
+        let snapshot = tree.snapshot();
+        // Split a compressed prefix, merge a node into its lone child, and add a branch.
+        tree.insert("ap", 10);
+        tree.remove("banana");
+        tree.insert("cheese", 11);
+
+        let sharing = tree.shared_with(&snapshot);
+        assert!(sharing.shared_nodes > 0);
+        assert_eq!(sharing.shared_nodes, reference(&tree, &snapshot));
+        assert_eq!(sharing, snapshot.shared_with(&tree));
+    }
+
+    #[test]
+    fn tree_stats_match_unversioned_tree() {
+        let mut tree = AdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+        let mut versioned = VersionedAdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+        for i in (0..5000u32).step_by(3) {
+            tree.insert(i, i);
+            versioned.insert(i, i);
+        }
+
+        let expected = tree.get_tree_stats();
+        let stats = versioned.get_tree_stats();
+        assert_eq!(stats.num_values, expected.num_values);
+        assert_eq!(stats.num_leaves, expected.num_leaves);
+        assert_eq!(stats.num_inner_nodes, expected.num_inner_nodes);
+        assert_eq!(stats.max_height, expected.max_height);
+        assert_eq!(stats.total_density, expected.total_density);
+        assert_eq!(
+            versioned.snapshot_stats().total_nodes,
+            stats.num_leaves + stats.num_inner_nodes
+        );
+    }
+
+    #[test]
+    fn write_batch_copies_each_shared_node_once() {
+        let mut tree = VersionedAdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+        for i in 0..200u32 {
+            tree.insert(i, i);
+        }
+        let snapshot = tree.snapshot();
+
+        let mut batch = WriteBatch::new();
+        for i in (0..200u32).step_by(3) {
+            batch.insert(i, i + 1);
+        }
+        batch.remove(1u32);
+        tree.apply_batch::<std::convert::Infallible>(batch).unwrap();
+
+        // Every node is either still shared with the snapshot or was written by the batch, and
+        // each one the batch wrote is referenced only from the new tree.
+        fn check(
+            node: &NodePtr<<ArrayKey<16> as KeyTrait>::PartialType, u32, DefaultPtr>,
+            version: u64,
+        ) {
+            if node.version == version {
+                assert_eq!(DefaultPtr::strong_count(node), 1);
+                for (_, child) in node.iter_arcs() {
+                    check(child, version);
+                }
+            } else {
+                assert!(DefaultPtr::strong_count(node) > 1);
+            }
+        }
+        check(tree.root.as_ref().unwrap(), tree.version());
+        assert_eq!(tree.get(3u32), Some(&4));
+        assert_eq!(tree.get(1u32), None);
+        assert_eq!(snapshot.get(3u32), Some(&3));
+        assert_eq!(snapshot.get(1u32), Some(&1));
+    }
+
+    #[test]
+    fn write_batch_changes_unshared_nodes_in_place() {
+        let mut tree = VersionedAdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+        for i in 0..200u32 {
+            tree.insert(i, i);
+        }
+        let root = |tree: &VersionedAdaptiveRadixTree<ArrayKey<16>, u32>| {
+            &**tree.root.as_ref().unwrap() as *const VersionedNode<_, u32, DefaultPtr>
+        };
+        let before = root(&tree);
+
+        let mut batch = WriteBatch::new();
+        for i in (0..200u32).step_by(7) {
+            batch.insert(i, i * 2);
+        }
+        batch.remove(5u32);
+        tree.apply_batch::<std::convert::Infallible>(batch).unwrap();
+        assert_eq!(root(&tree), before);
+        assert_eq!(tree.get(7u32), Some(&14));
+        assert_eq!(tree.get(5u32), None);
+
+        // A batch that changes nothing leaves the version alone.
+        let version = tree.version();
+        let mut batch = WriteBatch::new();
+        batch.remove(5u32);
+        tree.apply_batch::<std::convert::Infallible>(batch).unwrap();
+        assert_eq!(tree.version(), version);
     }
 
     #[test]
@@ -3551,7 +6274,7 @@ mod tests {
         // Verify that shared nodes have high reference counts
         // The root should be referenced by: tree + snapshot1 + snapshot2 + snapshot3 = 4 references
         if let Some(root) = &tree.root {
-            let strong_count = Arc::strong_count(root);
+            let strong_count = DefaultPtr::strong_count(root);
             assert_eq!(
                 strong_count, 4,
                 "Root should be shared between original and 3 snapshots"
@@ -3567,16 +6290,16 @@ mod tests {
         {
             // All three snapshots should point to the same root node
             assert!(
-                Arc::ptr_eq(s1_root, s2_root),
+                DefaultPtr::ptr_eq(s1_root, s2_root),
                 "Snapshot1 and Snapshot2 should share root"
             );
             assert!(
-                Arc::ptr_eq(s2_root, s3_root),
+                DefaultPtr::ptr_eq(s2_root, s3_root),
                 "Snapshot2 and Snapshot3 should share root"
             );
 
             // The shared root should have exactly 3 references (from the 3 snapshots)
-            let shared_count = Arc::strong_count(s1_root);
+            let shared_count = DefaultPtr::strong_count(s1_root);
             assert_eq!(
                 shared_count, 3,
                 "Shared root should have 3 references after original tree CoW"
@@ -3585,7 +6308,7 @@ mod tests {
 
         // Verify that the original tree has its own root now
         if let Some(orig_root) = &tree.root {
-            let orig_count = Arc::strong_count(orig_root);
+            let orig_count = DefaultPtr::strong_count(orig_root);
             assert_eq!(
                 orig_count, 1,
                 "Original tree should have exclusive ownership of new root"
@@ -3620,7 +6343,7 @@ mod tests {
         }
 
         let initial_root_refs = if let Some(root) = &tree.root {
-            Arc::strong_count(root)
+            DefaultPtr::strong_count(root)
         } else {
             panic!("Tree should have a root");
         };
@@ -3634,7 +6357,7 @@ mod tests {
 
         // Root should now have more references
         let with_snapshots_refs = if let Some(root) = &tree.root {
-            Arc::strong_count(root)
+            DefaultPtr::strong_count(root)
         } else {
             panic!("Tree should have a root");
         };
@@ -3649,7 +6372,7 @@ mod tests {
 
         // Root should have fewer references now
         let after_drops_refs = if let Some(root) = &tree.root {
-            Arc::strong_count(root)
+            DefaultPtr::strong_count(root)
         } else {
             panic!("Tree should have a root");
         };
@@ -3675,7 +6398,7 @@ mod tests {
 
         // Now tree should have exclusive ownership
         let final_refs = if let Some(root) = &tree.root {
-            Arc::strong_count(root)
+            DefaultPtr::strong_count(root)
         } else {
             panic!("Tree should have a root");
         };
@@ -4490,6 +7213,197 @@ mod tests {
         assert_eq!(left.intersect_count(&right), 2);
     }
 
+    #[test]
+    fn intersect_prefix_and_range_only_visit_matching_keys() {
+        let mut left = VersionedAdaptiveRadixTree::<VectorKey, u32>::new();
+        for (i, key) in [&b"a/1"[..], b"a/2", b"a/3", b"b/1", b"b/2", b"c"]
+            .into_iter()
+            .enumerate()
+        {
+            left.insert_k(&VectorKey::new_from_slice(key), i as u32);
+        }
+        let mut right = left.snapshot();
+        right.remove_k(&VectorKey::new_from_slice(b"a/2"));
+        right.insert_k(&VectorKey::new_from_slice(b"b/1"), 100);
+
+        let mut seen = Vec::new();
+        left.intersect_prefix_with(&right, &VectorKey::new_from_slice(b"a/"), |key, l, r| {
+            seen.push((key.as_ref().to_vec(), *l, *r));
+        });
+        assert_eq!(seen, vec![(b"a/1".to_vec(), 0, 0), (b"a/3".to_vec(), 2, 2)]);
+        assert_eq!(
+            left.intersect_prefix_count(&right, &VectorKey::new_from_slice(b"b")),
+            2
+        );
+        assert_eq!(
+            left.intersect_prefix_count(&right, &VectorKey::new_from_slice(b"d")),
+            0
+        );
+
+        let start = VectorKey::new_from_slice(b"a/3");
+        let end = VectorKey::new_from_slice(b"b/2");
+        let mut seen = Vec::new();
+        left.intersect_range_with(&right, start.clone()..end.clone(), |key, l, r| {
+            seen.push((key.as_ref().to_vec(), *l, *r));
+        });
+        assert_eq!(
+            seen,
+            vec![(b"a/3".to_vec(), 2, 2), (b"b/1".to_vec(), 3, 100)]
+        );
+        let mut values = Vec::new();
+        left.intersect_range_values_with(&right, start..=end, |l, r| values.push((*l, *r)));
+        assert_eq!(values, vec![(2, 2), (3, 100), (4, 4)]);
+        assert_eq!(left.intersect_range_count(&right, ..), 5);
+    }
+
+    #[test]
+    fn intersect_many_matches_pairwise_intersection() {
+        let mut base = VersionedAdaptiveRadixTree::<ArrayKey<16>, u32>::new();
+        for i in 0..200u32 {
+            base.insert(i, i);
+        }
+        let mut evens = base.snapshot();
+        let mut thirds = base.snapshot();
+        for i in 0..200u32 {
+            if i % 2 != 0 {
+                evens.remove(i);
+            }
+            if i % 3 != 0 {
+                thirds.remove(i);
+            }
+        }
+        thirds.insert(6u32, 600);
+
+        let mut seen = Vec::new();
+        VersionedAdaptiveRadixTree::intersect_many(&[&base, &evens, &thirds], |key, values| {
+            let key: ArrayKey<16> = key.to_key();
+            seen.push((key, values.iter().map(|v| **v).collect::<Vec<_>>()));
+        });
+
+        let mut expected = Vec::new();
+        evens.intersect_with(&thirds, |key, even, third| {
+            expected.push((key, vec![*even, *even, *third]));
+        });
+        assert_eq!(seen.len(), 34);
+        assert!(seen == expected);
+        assert!(seen.iter().any(|(_, values)| values == &vec![6, 6, 600]));
+    }
+
+    #[test]
+    fn difference_and_symmetric_difference() {
+        let mut left = VersionedAdaptiveRadixTree::<ArrayKey<16>, i32>::new();
+        for (key, value) in [("a", 1), ("ab", 2), ("abc", 3), ("abd", 4), ("cat", 6)] {
+            left.insert(key, value);
+        }
+        let mut right = left.snapshot();
+        right.remove("abd");
+        right.insert("abcdef", 60);
+        right.insert("dog", 70);
+
+        let trim = |key: ArrayKey<16>| {
+            let bytes = key.as_ref();
+            let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+            bytes[..end].to_vec()
+        };
+
+        let mut only_left = Vec::new();
+        left.difference_with(&right, |key, value| only_left.push((trim(key), *value)));
+        assert_eq!(only_left, vec![(b"abd".to_vec(), 4)]);
+        assert_eq!(left.difference_count(&right), 1);
+        assert_eq!(right.difference_count(&left), 2);
+
+        let mut either = Vec::new();
+        left.symmetric_difference_with(&right, |key, side| {
+            let side = match side {
+                Side::Left(value) => Side::Left(*value),
+                Side::Right(value) => Side::Right(*value),
+            };
+            either.push((trim(key), side));
+        });
+        assert_eq!(
+            either,
+            vec![
+                (b"abcdef".to_vec(), Side::Right(60)),
+                (b"abd".to_vec(), Side::Left(4)),
+                (b"dog".to_vec(), Side::Right(70)),
+            ]
+        );
+
+        let empty = VersionedAdaptiveRadixTree::<ArrayKey<16>, i32>::new();
+        assert_eq!(left.difference_count(&empty), 5);
+        assert_eq!(empty.difference_count(&left), 0);
+    }
+
+    #[test]
+    fn union_with_shares_one_sided_subtrees() {
+        let mut left = VersionedAdaptiveRadixTree::<ArrayKey<16>, i32>::new();
+        let mut right = VersionedAdaptiveRadixTree::<ArrayKey<16>, i32>::new();
+        left.insert("apple", 1);
+        left.insert("apricot", 2);
+        left.insert("zebra", 3);
+        right.insert("zebra", 30);
+        for (i, word) in ["mango", "melon", "mulberry"].into_iter().enumerate() {
+            right.insert(word, i as i32);
+        }
+
+        let union = left.union_with(&right, |_key, l, r| l + r);
+        assert_eq!(union.get("zebra"), Some(&33));
+        assert_eq!(union.get("apricot"), Some(&2));
+        assert_eq!(union.get("melon"), Some(&1));
+
+        let root = union.root.as_ref().unwrap();
+        let left_a = left.root.as_ref().unwrap().seek_child(b'a').unwrap();
+        let right_m = right.root.as_ref().unwrap().seek_child(b'm').unwrap();
+        assert!(DefaultPtr::ptr_eq(root.seek_child(b'a').unwrap(), left_a));
+        assert!(DefaultPtr::ptr_eq(root.seek_child(b'm').unwrap(), right_m));
+        assert_eq!(root.version, union.version());
+
+        let mut merged = left.snapshot();
+        merged.merge_from_ref(&right, |_key, l, _r| *l);
+        assert_eq!(merged.get("zebra"), Some(&3));
+        assert_eq!(left.get("mango"), None);
+
+        let mut merged = left.snapshot();
+        merged.merge_from(right, |_key, _l, r| r);
+        assert_eq!(merged.get("zebra"), Some(&30));
+        assert_eq!(merged.get("mango"), Some(&0));
+    }
+
+    #[test]
+    fn merge_from_moves_values_out_of_unshared_nodes() {
+        static CLONES: AtomicU64 = AtomicU64::new(0);
+
+        #[derive(Debug, PartialEq)]
+        struct Counted(u32);
+
+        impl Clone for Counted {
+            fn clone(&self) -> Self {
+                CLONES.fetch_add(1, Ordering::Relaxed);
+                Counted(self.0)
+            }
+        }
+
+        let mut left = VersionedAdaptiveRadixTree::<ArrayKey<16>, Counted>::new();
+        let mut right = VersionedAdaptiveRadixTree::<ArrayKey<16>, Counted>::new();
+        for i in 0..100u32 {
+            left.insert(i, Counted(i));
+            right.insert(i * 2, Counted(i * 1000));
+        }
+
+        let before = CLONES.load(Ordering::Relaxed);
+        left.merge_from(right, |_key, l, r| Counted(l.0 + r.0));
+        assert_eq!(CLONES.load(Ordering::Relaxed), before);
+        assert_eq!(left.get(10u32), Some(&Counted(5010)));
+        assert_eq!(left.get(150u32), Some(&Counted(75000)));
+        assert_eq!(left.iter().count(), 150);
+
+        // A snapshot still shares the root, so its values are cloned rather than taken.
+        let snapshot = left.snapshot();
+        let other = VersionedAdaptiveRadixTree::<ArrayKey<16>, Counted>::new();
+        left.merge_from(other, |_key, l, _r| l);
+        assert_eq!(snapshot.get(10u32), Some(&Counted(5010)));
+    }
+
     #[test]
     fn intersect_with_empty_tree_visits_nothing() {
         let mut left = VersionedAdaptiveRadixTree::<ArrayKey<16>, i32>::new();
@@ -0,0 +1,164 @@
+//! Batched writes for [`VersionedAdaptiveRadixTree`](crate::VersionedAdaptiveRadixTree).
+//!
+//! A [`WriteBatch`] collects inserts, removals, and updates and is applied with
+//! [`VersionedAdaptiveRadixTree::apply_batch`](crate::VersionedAdaptiveRadixTree::apply_batch). The batch runs as a single mutation: its
+//! operations are sorted by key and applied under one new version in a single descent that
+//! visits each node on their paths once, so a node shared with another snapshot is copied at
+//! most once.
+//! If a fallible update fails, the tree is left exactly as it was before the batch.
+
+use std::convert::Infallible;
+
+use crate::keys::KeyTrait;
+use crate::{Slot, SlotUpdate};
+
+type BatchUpdate<'f, V, E> = Box<dyn FnOnce(Slot<'_, V>) -> Result<SlotUpdate<V>, E> + 'f>;
+
+/// One operation of a [`WriteBatch`].
+pub(crate) enum BatchOp<'f, V, E> {
+    Insert(V),
+    Remove,
+    Update(BatchUpdate<'f, V, E>),
+}
+
+/// A set of writes applied to a [`VersionedAdaptiveRadixTree`](crate::VersionedAdaptiveRadixTree)
+/// all at once.
+///
+/// `E` is the error type of [`try_update`](Self::try_update) operations; batches without
+/// fallible updates can leave it as [`Infallible`]. Operations on the same key apply in the
+/// order they were added.
+///
+/// ```rust
+/// use rart::{ArrayKey, SlotUpdate, VersionedAdaptiveRadixTree};
+/// use rart::write_batch::WriteBatch;
+///
+/// let mut tree = VersionedAdaptiveRadixTree::<ArrayKey<16>, i32>::new();
+/// tree.insert("a", 1);
+/// tree.insert("b", 2);
+///
+/// let mut batch: WriteBatch<ArrayKey<16>, i32> = WriteBatch::new();
+/// batch.insert("c", 3);
+/// batch.remove("a");
+/// batch.update("b", |_| SlotUpdate::Insert(20));
+/// tree.apply_batch(batch).unwrap();
+///
+/// assert_eq!(tree.get("a"), None);
+/// assert_eq!(tree.get("b"), Some(&20));
+/// assert_eq!(tree.get("c"), Some(&3));
+///
+/// // A failing update rolls back the whole batch.
+/// let mut batch = WriteBatch::new();
+/// batch.insert("d", 4);
+/// batch.try_update("b", |_| Err("rejected"));
+/// assert_eq!(tree.apply_batch(batch), Err("rejected"));
+/// assert_eq!(tree.get("d"), None);
+/// ```
+pub struct WriteBatch<'f, KeyType, ValueType, E = Infallible>
+where
+    KeyType: KeyTrait,
+{
+    pub(crate) ops: Vec<(KeyType, BatchOp<'f, ValueType, E>)>,
+}
+
+impl<'f, KeyType, ValueType, E> WriteBatch<'f, KeyType, ValueType, E>
+where
+    KeyType: KeyTrait,
+{
+    /// Create an empty batch.
+    pub fn new() -> Self {
+        Self { ops: Vec::new() }
+    }
+
+    /// Create an empty batch with room for `capacity` operations.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            ops: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// The number of operations in the batch.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Whether the batch has no operations.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Add an insert (generic version).
+    #[inline]
+    pub fn insert<Key>(&mut self, key: Key, value: ValueType)
+    where
+        Key: Into<KeyType>,
+    {
+        self.ops.push((key.into(), BatchOp::Insert(value)));
+    }
+
+    /// Add an insert.
+    pub fn insert_k(&mut self, key: &KeyType, value: ValueType) {
+        self.ops.push((key.clone(), BatchOp::Insert(value)));
+    }
+
+    /// Add a removal (generic version).
+    #[inline]
+    pub fn remove<Key>(&mut self, key: Key)
+    where
+        Key: Into<KeyType>,
+    {
+        self.ops.push((key.into(), BatchOp::Remove));
+    }
+
+    /// Add a removal. Removing a missing key is not an error.
+    pub fn remove_k(&mut self, key: &KeyType) {
+        self.ops.push((key.clone(), BatchOp::Remove));
+    }
+
+    /// Add a callback update of a value slot (generic version).
+    #[inline]
+    pub fn update<Key, F>(&mut self, key: Key, update: F)
+    where
+        Key: Into<KeyType>,
+        F: FnOnce(Slot<'_, ValueType>) -> SlotUpdate<ValueType> + 'f,
+    {
+        self.update_k(&key.into(), update)
+    }
+
+    /// Add a callback update of a value slot, as with
+    /// [`VersionedAdaptiveRadixTree::update_k`](crate::VersionedAdaptiveRadixTree::update_k).
+    pub fn update_k<F>(&mut self, key: &KeyType, update: F)
+    where
+        F: FnOnce(Slot<'_, ValueType>) -> SlotUpdate<ValueType> + 'f,
+    {
+        self.try_update_k(key, move |slot| Ok(update(slot)))
+    }
+
+    /// Add a fallible callback update of a value slot (generic version).
+    #[inline]
+    pub fn try_update<Key, F>(&mut self, key: Key, update: F)
+    where
+        Key: Into<KeyType>,
+        F: FnOnce(Slot<'_, ValueType>) -> Result<SlotUpdate<ValueType>, E> + 'f,
+    {
+        self.try_update_k(&key.into(), update)
+    }
+
+    /// Add a fallible callback update of a value slot. If it returns an error, the batch
+    /// stops and none of its operations take effect.
+    pub fn try_update_k<F>(&mut self, key: &KeyType, update: F)
+    where
+        F: FnOnce(Slot<'_, ValueType>) -> Result<SlotUpdate<ValueType>, E> + 'f,
+    {
+        self.ops
+            .push((key.clone(), BatchOp::Update(Box::new(update))));
+    }
+}
+
+impl<KeyType, ValueType, E> Default for WriteBatch<'_, KeyType, ValueType, E>
+where
+    KeyType: KeyTrait,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}